@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use super::{ActionType, Command};
-use crate::excel::Cell;
-use anyhow::Result;
+use crate::excel::{Cell, SheetResult};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CellAction {
     pub sheet_index: usize,
     pub sheet_name: String,
@@ -37,11 +38,42 @@ impl CellAction {
 }
 
 impl Command for CellAction {
-    fn execute(&self) -> Result<()> {
+    fn execute(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn undo(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn action_type(&self) -> ActionType {
+        self.action_type.clone()
+    }
+}
+
+// A single undo step covering several cells of one sheet, e.g. a substitution
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MultiCellAction {
+    pub sheet_index: usize,
+    pub sheet_name: String,
+    pub cells: Vec<CellChange>,
+    pub action_type: ActionType,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CellChange {
+    pub row: usize,
+    pub col: usize,
+    pub old_value: Cell,
+    pub new_value: Cell,
+}
+
+impl Command for MultiCellAction {
+    fn execute(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
-    fn undo(&self) -> Result<()> {
+    fn undo(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use super::{ActionType, Command};
-use crate::excel::Cell;
-use anyhow::Result;
+use crate::excel::{Cell, SheetResult};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ColumnAction {
     pub sheet_index: usize,
     pub sheet_name: String,
@@ -12,11 +13,11 @@ pub struct ColumnAction {
 }
 
 impl Command for ColumnAction {
-    fn execute(&self) -> Result<()> {
+    fn execute(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
-    fn undo(&self) -> Result<()> {
+    fn undo(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
@@ -25,7 +26,7 @@ impl Command for ColumnAction {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MultiColumnAction {
     pub sheet_index: usize,
     pub sheet_name: String,
@@ -36,11 +37,11 @@ pub struct MultiColumnAction {
 }
 
 impl Command for MultiColumnAction {
-    fn execute(&self) -> Result<()> {
+    fn execute(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
-    fn undo(&self) -> Result<()> {
+    fn undo(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
@@ -48,3 +49,36 @@ impl Command for MultiColumnAction {
         ActionType::DeleteMultiColumns
     }
 }
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReorderColumnsAction {
+    pub sheet_index: usize,
+    pub sheet_name: String,
+    pub order: Vec<usize>, // Column moved to each position, 1-based
+}
+
+impl ReorderColumnsAction {
+    /// The order that moves every column back to where it was
+    #[must_use]
+    pub fn inverse_order(&self) -> Vec<usize> {
+        let mut inverse = vec![0; self.order.len()];
+        for (position, &col) in self.order.iter().enumerate() {
+            inverse[col - 1] = position + 1;
+        }
+        inverse
+    }
+}
+
+impl Command for ReorderColumnsAction {
+    fn execute(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn undo(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::ReorderColumns
+    }
+}
@@ -1,4 +1,70 @@
+use std::mem::size_of;
+
+use unicode_width::UnicodeWidthChar;
+
 use super::types::{ActionCommand, ActionType};
+use super::CellChange;
+use crate::excel::{Cell, StyleId, DEFAULT_STYLE};
+use crate::utils::{cell_reference, index_to_col_name};
+
+// Longest value shown in a change summary, in columns
+const SUMMARY_VALUE_WIDTH: usize = 20;
+
+fn cells_bytes<'a>(cells: impl IntoIterator<Item = &'a Cell>) -> usize {
+    cells.into_iter().map(Cell::memory_size).sum()
+}
+
+impl ActionType {
+    /// Verb shown for the action in the undo list
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActionType::Edit => "Edit",
+            ActionType::Cut => "Cut",
+            ActionType::Paste => "Paste",
+            ActionType::DeleteRow | ActionType::DeleteMultiRows => "Delete rows",
+            ActionType::DeleteColumn | ActionType::DeleteMultiColumns => "Delete columns",
+            ActionType::DeleteSheet => "Delete sheet",
+            ActionType::AddSheet => "Add sheet",
+            ActionType::MoveSheet => "Move sheet",
+            ActionType::ReorderColumns => "Reorder columns",
+            ActionType::InsertRows => "Insert rows",
+            ActionType::Replace => "Replace",
+            ActionType::Group => "Changes",
+        }
+    }
+}
+
+// A cell value quoted and cut to fit a summary line, formulas as written
+fn short_value(cell: &Cell) -> String {
+    if cell.value.is_empty() {
+        return "empty".to_string();
+    }
+
+    let mut width = 0;
+    let mut value = String::new();
+    for c in cell
+        .value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+    {
+        width += c.width().unwrap_or(0);
+        if width > SUMMARY_VALUE_WIDTH {
+            value.push('…');
+            break;
+        }
+        value.push(c);
+    }
+    format!("\"{value}\"")
+}
+
+fn change_summary(change: &CellChange) -> String {
+    format!(
+        "{} → {}",
+        short_value(&change.old_value),
+        short_value(&change.new_value)
+    )
+}
 
 impl ActionCommand {
     // Returns the action type of this command
@@ -13,7 +79,201 @@ impl ActionCommand {
             ActionCommand::MultiRow(_) => ActionType::DeleteMultiRows,
             ActionCommand::Column(_) => ActionType::DeleteColumn,
             ActionCommand::MultiColumn(_) => ActionType::DeleteMultiColumns,
+            ActionCommand::ReorderColumns(_) => ActionType::ReorderColumns,
             ActionCommand::Sheet(_) => ActionType::DeleteSheet,
+            ActionCommand::AddSheet(_) => ActionType::AddSheet,
+            ActionCommand::MoveSheet(_) => ActionType::MoveSheet,
+            ActionCommand::InsertRows(_) => ActionType::InsertRows,
+            ActionCommand::MultiCell(action) => action.action_type.clone(),
+            ActionCommand::Group(_) => ActionType::Group,
+        }
+    }
+
+    // Points the action, or each action of a group, at the new name of a renamed sheet
+    pub fn rename_sheet(&mut self, old_name: &str, new_name: &str) {
+        let sheet_name = match self {
+            ActionCommand::Cell(action) => &mut action.sheet_name,
+            ActionCommand::Row(action) => &mut action.sheet_name,
+            ActionCommand::MultiRow(action) => &mut action.sheet_name,
+            ActionCommand::Column(action) => &mut action.sheet_name,
+            ActionCommand::MultiColumn(action) => &mut action.sheet_name,
+            ActionCommand::ReorderColumns(action) => &mut action.sheet_name,
+            ActionCommand::Sheet(action) => &mut action.sheet_name,
+            ActionCommand::AddSheet(action) => &mut action.sheet_name,
+            ActionCommand::MoveSheet(action) => &mut action.sheet_name,
+            ActionCommand::InsertRows(action) => &mut action.sheet_name,
+            ActionCommand::MultiCell(action) => &mut action.sheet_name,
+            ActionCommand::Group(group) => {
+                for action in &mut group.actions {
+                    action.rename_sheet(old_name, new_name);
+                }
+                return;
+            }
+        };
+        if sheet_name == old_name {
+            *sheet_name = new_name.to_string();
+        }
+    }
+
+    // Points the style ids of the cells kept for `sheet_name` through `ids`, indexed by the
+    // old id, after the sheet's style table was rebuilt. Deleted sheets keep their own table
+    pub fn remap_styles(&mut self, sheet_name: &str, ids: &[StyleId]) {
+        let remap = |cell: &mut Cell| {
+            cell.style = ids
+                .get(cell.style as usize)
+                .copied()
+                .unwrap_or(DEFAULT_STYLE);
+        };
+
+        match self {
+            ActionCommand::Cell(action) if action.sheet_name == sheet_name => {
+                remap(&mut action.old_value);
+                remap(&mut action.new_value);
+            }
+            ActionCommand::MultiCell(action) if action.sheet_name == sheet_name => {
+                for change in &mut action.cells {
+                    remap(&mut change.old_value);
+                    remap(&mut change.new_value);
+                }
+            }
+            ActionCommand::Row(action) if action.sheet_name == sheet_name => {
+                action.row_data.iter_mut().for_each(remap);
+            }
+            ActionCommand::MultiRow(action) if action.sheet_name == sheet_name => {
+                action.rows_data.iter_mut().flatten().for_each(remap);
+            }
+            ActionCommand::InsertRows(action) if action.sheet_name == sheet_name => {
+                action.rows_data.iter_mut().flatten().for_each(remap);
+            }
+            ActionCommand::Column(action) if action.sheet_name == sheet_name => {
+                action.column_data.iter_mut().for_each(remap);
+            }
+            ActionCommand::MultiColumn(action) if action.sheet_name == sheet_name => {
+                action.columns_data.iter_mut().flatten().for_each(remap);
+            }
+            ActionCommand::Group(group) => {
+                for action in &mut group.actions {
+                    action.remap_styles(sheet_name, ids);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Approximate memory the action holds, for the undo history's accounting
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        let data = match self {
+            ActionCommand::Cell(action) => {
+                action.old_value.memory_size() + action.new_value.memory_size()
+            }
+            ActionCommand::MultiCell(action) => action
+                .cells
+                .iter()
+                .map(|change| {
+                    size_of::<CellChange>() - 2 * size_of::<Cell>()
+                        + change.old_value.memory_size()
+                        + change.new_value.memory_size()
+                })
+                .sum(),
+            ActionCommand::Row(action) => cells_bytes(&action.row_data),
+            ActionCommand::MultiRow(action) => cells_bytes(action.rows_data.iter().flatten()),
+            ActionCommand::InsertRows(action) => cells_bytes(action.rows_data.iter().flatten()),
+            ActionCommand::Column(action) => cells_bytes(&action.column_data),
+            ActionCommand::MultiColumn(action) => {
+                cells_bytes(action.columns_data.iter().flatten())
+                    + action.column_widths.len() * size_of::<usize>()
+            }
+            ActionCommand::ReorderColumns(action) => action.order.len() * size_of::<usize>(),
+            ActionCommand::Sheet(action) => {
+                cells_bytes(action.sheet_data.data.cells().map(|(_, _, cell)| cell))
+                    + action.column_widths.len() * size_of::<usize>()
+            }
+            ActionCommand::AddSheet(action) => {
+                cells_bytes(action.sheet_data.data.cells().map(|(_, _, cell)| cell))
+                    + action.column_widths.len() * size_of::<usize>()
+            }
+            ActionCommand::MoveSheet(_) => 0,
+            ActionCommand::Group(group) => group.actions.iter().map(Self::memory_size).sum(),
+        };
+        size_of::<Self>() + data
+    }
+
+    /// One line describing the change: its type, where it happened and, for a single cell,
+    /// the old and new value, e.g. `Edit Sheet1!B3 "12" → "15"`
+    #[must_use]
+    pub fn summary(&self) -> String {
+        match self {
+            ActionCommand::Cell(action) => format!(
+                "{} {}!{} {} → {}",
+                action.action_type.label(),
+                action.sheet_name,
+                cell_reference((action.row, action.col)),
+                short_value(&action.old_value),
+                short_value(&action.new_value)
+            ),
+            ActionCommand::MultiCell(action) => {
+                let label = action.action_type.label();
+                match action.cells.as_slice() {
+                    [change] => format!(
+                        "{label} {}!{} {}",
+                        action.sheet_name,
+                        cell_reference((change.row, change.col)),
+                        change_summary(change)
+                    ),
+                    cells => {
+                        let top = cells.iter().map(|c| c.row).min().unwrap_or(0);
+                        let bottom = cells.iter().map(|c| c.row).max().unwrap_or(0);
+                        let left = cells.iter().map(|c| c.col).min().unwrap_or(0);
+                        let right = cells.iter().map(|c| c.col).max().unwrap_or(0);
+                        format!(
+                            "{label} {}!{}:{} ({} cells)",
+                            action.sheet_name,
+                            cell_reference((top, left)),
+                            cell_reference((bottom, right)),
+                            cells.len()
+                        )
+                    }
+                }
+            }
+            ActionCommand::Row(action) => {
+                format!("Delete row {} of {}", action.row, action.sheet_name)
+            }
+            ActionCommand::MultiRow(action) => format!(
+                "Delete rows {}-{} of {}",
+                action.start_row, action.end_row, action.sheet_name
+            ),
+            ActionCommand::Column(action) => format!(
+                "Delete column {} of {}",
+                index_to_col_name(action.col),
+                action.sheet_name
+            ),
+            ActionCommand::MultiColumn(action) => format!(
+                "Delete columns {}-{} of {}",
+                index_to_col_name(action.start_col),
+                index_to_col_name(action.end_col),
+                action.sheet_name
+            ),
+            ActionCommand::ReorderColumns(action) => {
+                format!("Reorder columns of {}", action.sheet_name)
+            }
+            ActionCommand::InsertRows(action) => format!(
+                "Insert {} rows at row {} of {}",
+                action.rows_data.len(),
+                action.start_row,
+                action.sheet_name
+            ),
+            ActionCommand::Sheet(action) => format!("Delete sheet {}", action.sheet_name),
+            ActionCommand::AddSheet(action) => format!("Add sheet {}", action.sheet_name),
+            ActionCommand::MoveSheet(action) => format!(
+                "Move sheet {} from position {} to {}",
+                action.sheet_name,
+                action.from_index + 1,
+                action.to_index + 1
+            ),
+            ActionCommand::Group(group) => {
+                format!("{} ({} changes)", group.label, group.actions.len())
+            }
         }
     }
 }
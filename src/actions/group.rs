@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ActionCommand, ActionType, Command};
+use crate::excel::SheetResult;
+
+// Several actions undone and redone as a single step, e.g. an append that also adds headers
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GroupAction {
+    pub label: String, // What the group did as a whole, e.g. Append data.csv
+    pub actions: Vec<ActionCommand>, // In the order they were made, undone in reverse
+}
+
+impl Command for GroupAction {
+    fn execute(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn undo(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::Group
+    }
+}
@@ -1,9 +1,19 @@
-use super::ActionCommand;
+use super::{ActionCommand, CellChange, GroupAction, MultiCellAction};
 use std::rc::Rc;
 
+/// Changes kept for undo unless :set undolevels says otherwise, like Vim
+const DEFAULT_UNDO_LEVELS: usize = 1000;
+
 pub struct UndoHistory {
     undo_stack: Vec<Rc<ActionCommand>>,
     redo_stack: Vec<Rc<ActionCommand>>,
+    revision: u64, // Bumped on every push, undo, redo and clear
+    last_change: Option<Rc<ActionCommand>>, // Repeated by ., kept through undo and clear
+    group: Option<GroupAction>, // Collects pushes between begin_group and end_group
+    group_depth: usize, // Nested groups join the outermost one
+    saved_depth: Option<usize>, // Undo steps in effect when the file was last saved or opened
+    levels: usize, // Most changes kept for undo, older ones are dropped
+    memory: usize, // Approximate bytes held by both stacks
 }
 
 impl Default for UndoHistory {
@@ -18,18 +28,140 @@ impl UndoHistory {
         Self {
             undo_stack: Vec::with_capacity(100), // Pre-allocate capacity
             redo_stack: Vec::with_capacity(20),
+            revision: 0,
+            last_change: None,
+            group: None,
+            group_depth: 0,
+            saved_depth: Some(0),
+            levels: DEFAULT_UNDO_LEVELS,
+            memory: 0,
         }
     }
 
+    #[must_use]
+    pub fn levels(&self) -> usize {
+        self.levels
+    }
+
+    /// Keeps at most `levels` changes to undo, dropping the oldest ones beyond that.
+    /// Returns how many were dropped
+    pub fn set_levels(&mut self, levels: usize) -> usize {
+        self.levels = levels;
+        self.trim()
+    }
+
+    /// Approximate memory taken by the changes that can be undone and redone, in bytes
+    #[must_use]
+    pub fn memory_used(&self) -> usize {
+        self.memory
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.undo_stack.len() + self.redo_stack.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Drops the changes beyond the undo levels, the oldest ones to undo first and then
+    // the undone ones furthest from the current state
+    fn trim(&mut self) -> usize {
+        let excess = self.len().saturating_sub(self.levels);
+        if excess == 0 {
+            return 0;
+        }
+
+        let from_undo = excess.min(self.undo_stack.len());
+        let dropped: usize = self
+            .undo_stack
+            .drain(..from_undo)
+            .chain(self.redo_stack.drain(..excess - from_undo))
+            .map(|action| action.memory_size())
+            .sum();
+        self.memory = self.memory.saturating_sub(dropped);
+        // The saved state may have been among the dropped changes
+        self.saved_depth = self
+            .saved_depth
+            .and_then(|depth| depth.checked_sub(from_undo))
+            .filter(|&depth| depth <= self.undo_stack.len() + self.redo_stack.len());
+        self.revision += 1;
+        excess
+    }
+
+    // Adds up the memory of both stacks again after they were replaced or rewritten
+    fn recount_memory(&mut self) {
+        self.memory = self
+            .undo_stack
+            .iter()
+            .chain(&self.redo_stack)
+            .map(|action| action.memory_size())
+            .sum();
+    }
+
     pub fn push(&mut self, action: ActionCommand) {
+        if let Some(group) = &mut self.group {
+            group.actions.push(action);
+            return;
+        }
+
+        // The saved state was undone and can't be redone anymore
+        if self.saved_depth > Some(self.undo_stack.len()) {
+            self.saved_depth = None;
+        }
+
         // Use Rc to avoid deep cloning the entire action
-        self.undo_stack.push(Rc::new(action));
+        let action = Rc::new(action);
+        self.last_change = Some(Rc::clone(&action));
+        let redo_memory: usize = self.redo_stack.iter().map(|a| a.memory_size()).sum();
+        self.memory = self.memory.saturating_sub(redo_memory) + action.memory_size();
+        self.undo_stack.push(action);
         self.redo_stack.clear();
+        self.revision += 1;
+        self.trim();
+    }
+
+    /// Starts collecting the actions pushed until the matching `end_group` into one undo step
+    pub fn begin_group(&mut self, label: impl Into<String>) {
+        if self.group_depth == 0 {
+            self.group = Some(GroupAction {
+                label: label.into(),
+                actions: Vec::new(),
+            });
+        }
+        self.group_depth += 1;
+    }
+
+    /// Pushes the actions collected since `begin_group` as a single step. A group of one
+    /// action is pushed as that action, an empty group leaves the history as it was
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        if self.group_depth > 0 {
+            return;
+        }
+        let Some(mut group) = self.group.take() else {
+            return;
+        };
+
+        match group.actions.len() {
+            0 => {}
+            1 => self.push(group.actions.remove(0)),
+            _ => self.push(ActionCommand::Group(group)),
+        }
+    }
+
+    /// The newest change made, whether or not it was undone since
+    #[must_use]
+    pub fn last_change(&self) -> Option<Rc<ActionCommand>> {
+        self.last_change.clone()
     }
 
     pub fn undo(&mut self) -> Option<Rc<ActionCommand>> {
         if let Some(action) = self.undo_stack.pop() {
             self.redo_stack.push(Rc::clone(&action));
+            self.revision += 1;
             Some(action)
         } else {
             None
@@ -39,19 +171,242 @@ impl UndoHistory {
     pub fn redo(&mut self) -> Option<Rc<ActionCommand>> {
         if let Some(action) = self.redo_stack.pop() {
             self.undo_stack.push(Rc::clone(&action));
+            self.revision += 1;
             Some(action)
         } else {
             None
         }
     }
 
+    /// Changes that can be undone, oldest first
+    #[must_use]
+    pub fn undo_actions(&self) -> &[Rc<ActionCommand>] {
+        &self.undo_stack
+    }
+
+    /// Undone changes that can be redone, the next one to redo last
     #[must_use]
-    pub fn all_undone(&self) -> bool {
-        self.undo_stack.is_empty()
+    pub fn redo_actions(&self) -> &[Rc<ActionCommand>] {
+        &self.redo_stack
+    }
+
+    /// Whether undo and redo led back to the state the file was saved or opened in
+    #[must_use]
+    pub fn is_at_saved(&self) -> bool {
+        self.saved_depth == Some(self.undo_stack.len())
+    }
+
+    /// Records the current state as the one on disk
+    pub fn mark_saved(&mut self) {
+        self.saved_depth = Some(self.undo_stack.len());
     }
 
     pub fn clear(&mut self) {
         self.undo_stack.clear();
         self.redo_stack.clear();
+        self.saved_depth = Some(0);
+        self.memory = 0;
+        self.revision += 1;
+    }
+
+    /// Copies of the changes that can be undone and redone, in stack order, to be stored
+    #[must_use]
+    pub fn snapshot(&self) -> (Vec<ActionCommand>, Vec<ActionCommand>) {
+        let copy = |stack: &[Rc<ActionCommand>]| -> Vec<ActionCommand> {
+            stack.iter().map(|action| action.as_ref().clone()).collect()
+        };
+        (copy(&self.undo_stack), copy(&self.redo_stack))
+    }
+
+    /// Replaces the history with stored stacks, the current state counts as saved
+    pub fn restore(&mut self, undo: Vec<ActionCommand>, redo: Vec<ActionCommand>) {
+        self.undo_stack = undo.into_iter().map(Rc::new).collect();
+        self.redo_stack = redo.into_iter().map(Rc::new).collect();
+        self.last_change = None;
+        self.mark_saved();
+        self.recount_memory();
+        self.revision += 1;
+        self.trim();
+    }
+
+    /// Points the recorded actions of a renamed sheet at its new name
+    pub fn rename_sheet(&mut self, old_name: &str, new_name: &str) {
+        for action in self.undo_stack.iter_mut().chain(self.redo_stack.iter_mut()) {
+            Rc::make_mut(action).rename_sheet(old_name, new_name);
+        }
+        self.recount_memory();
+    }
+
+    /// Takes the cells in scope out of the newest edit of `sheet_name` that changed any.
+    /// Inserted or deleted rows and columns stop the search, older cells may have moved since.
+    /// Grouped actions are searched the same way, newest first
+    pub fn take_scoped_changes(
+        &mut self,
+        sheet_name: &str,
+        in_scope: impl Fn(usize, usize) -> bool,
+    ) -> Option<MultiCellAction> {
+        for index in (0..self.undo_stack.len()).rev() {
+            let (scoped, rest) = match take_scoped(&self.undo_stack[index], sheet_name, &in_scope) {
+                Scoped::Skip => continue,
+                Scoped::Stop => return None,
+                Scoped::Take(scoped, rest) => (scoped, rest),
+            };
+
+            self.memory = self
+                .memory
+                .saturating_sub(self.undo_stack[index].memory_size());
+            match rest {
+                Some(rest) => {
+                    self.memory += rest.memory_size();
+                    self.undo_stack[index] = Rc::new(*rest);
+                }
+                None => {
+                    self.undo_stack.remove(index);
+                }
+            }
+            // Undoing an older change out of order leaves no way back to the saved state
+            if self.saved_depth > Some(index) {
+                self.saved_depth = None;
+            }
+            self.revision += 1;
+            return Some(scoped);
+        }
+
+        None
+    }
+
+    /// Makes `action` the next one Ctrl+r redoes, keeping the rest of the redo stack
+    pub fn push_redo(&mut self, action: ActionCommand) {
+        self.memory += action.memory_size();
+        self.redo_stack.push(Rc::new(action));
+        self.revision += 1;
+    }
+
+    /// Changes whenever the history does, so views derived from the data know when to rebuild
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Cells of the sheet changed by edits that can still be undone
+    pub fn changed_cells<'a>(
+        &'a self,
+        sheet_name: &'a str,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.undo_stack
+            .iter()
+            .flat_map(move |action| cells_changed_by(action, sheet_name))
+    }
+}
+
+// What take_scoped_changes makes of one recorded action
+enum Scoped {
+    Skip, // Nothing in scope, older actions are searched
+    Stop, // Moves cells of the sheet around
+    Take(MultiCellAction, Option<Box<ActionCommand>>), // The cells in scope and what is left
+}
+
+fn take_scoped(
+    action: &ActionCommand,
+    sheet_name: &str,
+    in_scope: &impl Fn(usize, usize) -> bool,
+) -> Scoped {
+    match action {
+        ActionCommand::Cell(action) if action.sheet_name == sheet_name => {
+            if !in_scope(action.row, action.col) {
+                return Scoped::Skip;
+            }
+            let change = CellChange {
+                row: action.row,
+                col: action.col,
+                old_value: action.old_value.clone(),
+                new_value: action.new_value.clone(),
+            };
+            let scoped = MultiCellAction {
+                sheet_index: action.sheet_index,
+                sheet_name: action.sheet_name.clone(),
+                cells: vec![change],
+                action_type: action.action_type.clone(),
+            };
+            Scoped::Take(scoped, None)
+        }
+        ActionCommand::MultiCell(action) if action.sheet_name == sheet_name => {
+            let (cells, rest): (Vec<CellChange>, Vec<CellChange>) = action
+                .cells
+                .iter()
+                .cloned()
+                .partition(|change| in_scope(change.row, change.col));
+            if cells.is_empty() {
+                return Scoped::Skip;
+            }
+            let scoped = MultiCellAction {
+                cells,
+                ..action.clone()
+            };
+            let rest = (!rest.is_empty()).then(|| {
+                Box::new(ActionCommand::MultiCell(MultiCellAction {
+                    cells: rest,
+                    ..action.clone()
+                }))
+            });
+            Scoped::Take(scoped, rest)
+        }
+        ActionCommand::Group(group) => {
+            for (index, inner) in group.actions.iter().enumerate().rev() {
+                let (scoped, rest) = match take_scoped(inner, sheet_name, in_scope) {
+                    Scoped::Skip => continue,
+                    Scoped::Stop => return Scoped::Stop,
+                    Scoped::Take(scoped, rest) => (scoped, rest),
+                };
+                let mut actions = group.actions.clone();
+                match rest {
+                    Some(rest) => actions[index] = *rest,
+                    None => {
+                        actions.remove(index);
+                    }
+                }
+                // What is left is kept as end_group would have pushed it
+                let rest = match actions.len() {
+                    0 => None,
+                    1 => actions.pop().map(Box::new),
+                    _ => Some(Box::new(ActionCommand::Group(GroupAction {
+                        label: group.label.clone(),
+                        actions,
+                    }))),
+                };
+                return Scoped::Take(scoped, rest);
+            }
+            Scoped::Skip
+        }
+        ActionCommand::Cell(_) | ActionCommand::MultiCell(_) => Scoped::Skip,
+        ActionCommand::Row(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::Column(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::MultiRow(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::MultiColumn(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::ReorderColumns(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::InsertRows(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::Sheet(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::AddSheet(action) if action.sheet_name != sheet_name => Scoped::Skip,
+        ActionCommand::MoveSheet(_) => Scoped::Skip,
+        _ => Scoped::Stop,
+    }
+}
+
+fn cells_changed_by(action: &ActionCommand, sheet_name: &str) -> Vec<(usize, usize)> {
+    match action {
+        ActionCommand::Cell(action) if action.sheet_name == sheet_name => {
+            vec![(action.row, action.col)]
+        }
+        ActionCommand::MultiCell(action) if action.sheet_name == sheet_name => action
+            .cells
+            .iter()
+            .map(|change| (change.row, change.col))
+            .collect(),
+        ActionCommand::Group(group) => group
+            .actions
+            .iter()
+            .flat_map(|action| cells_changed_by(action, sheet_name))
+            .collect(),
+        _ => Vec::new(),
     }
 }
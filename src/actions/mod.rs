@@ -1,14 +1,16 @@
 mod cell;
 mod column;
 mod command;
+mod group;
 mod history;
 mod row;
 mod sheet;
 mod types;
 
-pub use cell::CellAction;
-pub use column::{ColumnAction, MultiColumnAction};
+pub use cell::{CellAction, CellChange, MultiCellAction};
+pub use column::{ColumnAction, MultiColumnAction, ReorderColumnsAction};
+pub use group::GroupAction;
 pub use history::UndoHistory;
-pub use row::{MultiRowAction, RowAction};
-pub use sheet::SheetAction;
+pub use row::{InsertRowsAction, MultiRowAction, RowAction};
+pub use sheet::{AddSheetAction, MoveSheetAction, SheetAction};
 pub use types::{ActionCommand, ActionExecutor, ActionType, Command};
@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use super::{ActionType, Command};
-use crate::excel::Cell;
-use anyhow::Result;
+use crate::excel::{Cell, SheetResult};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RowAction {
     pub sheet_index: usize,
     pub sheet_name: String,
@@ -11,11 +12,11 @@ pub struct RowAction {
 }
 
 impl Command for RowAction {
-    fn execute(&self) -> Result<()> {
+    fn execute(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
-    fn undo(&self) -> Result<()> {
+    fn undo(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
@@ -24,7 +25,7 @@ impl Command for RowAction {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MultiRowAction {
     pub sheet_index: usize,
     pub sheet_name: String,
@@ -34,11 +35,11 @@ pub struct MultiRowAction {
 }
 
 impl Command for MultiRowAction {
-    fn execute(&self) -> Result<()> {
+    fn execute(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
-    fn undo(&self) -> Result<()> {
+    fn undo(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
@@ -46,3 +47,25 @@ impl Command for MultiRowAction {
         ActionType::DeleteMultiRows
     }
 }
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InsertRowsAction {
+    pub sheet_index: usize,
+    pub sheet_name: String,
+    pub start_row: usize,
+    pub rows_data: Vec<Vec<Cell>>,
+}
+
+impl Command for InsertRowsAction {
+    fn execute(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn undo(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::InsertRows
+    }
+}
@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use super::{ActionType, Command};
-use crate::excel::Sheet;
-use anyhow::Result;
+use crate::excel::{Sheet, SheetResult};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SheetAction {
     pub sheet_index: usize,
     pub sheet_name: String,
@@ -11,11 +12,11 @@ pub struct SheetAction {
 }
 
 impl Command for SheetAction {
-    fn execute(&self) -> Result<()> {
+    fn execute(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
-    fn undo(&self) -> Result<()> {
+    fn undo(&self) -> SheetResult<()> {
         unimplemented!("Requires an ActionExecutor implementation")
     }
 
@@ -23,3 +24,46 @@ impl Command for SheetAction {
         ActionType::DeleteSheet
     }
 }
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AddSheetAction {
+    pub sheet_index: usize,
+    pub sheet_name: String,
+    pub sheet_data: Sheet, // The sheet as added, empty or a copy
+    pub column_widths: Vec<usize>,
+}
+
+impl Command for AddSheetAction {
+    fn execute(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn undo(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::AddSheet
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MoveSheetAction {
+    pub sheet_name: String,
+    pub from_index: usize,
+    pub to_index: usize,
+}
+
+impl Command for MoveSheetAction {
+    fn execute(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn undo(&self) -> SheetResult<()> {
+        unimplemented!("Requires an ActionExecutor implementation")
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::MoveSheet
+    }
+}
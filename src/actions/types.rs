@@ -1,4 +1,8 @@
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+use crate::excel::SheetResult;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ActionType {
     Edit,
     Cut,
@@ -6,53 +10,74 @@ pub enum ActionType {
     DeleteRow,
     DeleteColumn,
     DeleteSheet,
+    AddSheet,
+    MoveSheet,
     DeleteMultiRows,
     DeleteMultiColumns,
+    ReorderColumns,
+    InsertRows,
+    Replace,
+    Group,
 }
 
 // Executor for actions in the application
 pub trait ActionExecutor {
-    fn execute_action(&mut self, action: &ActionCommand) -> Result<(), anyhow::Error>;
-    fn execute_cell_action(
-        &mut self,
-        action: &crate::actions::CellAction,
-    ) -> Result<(), anyhow::Error>;
-    fn execute_row_action(
-        &mut self,
-        action: &crate::actions::RowAction,
-    ) -> Result<(), anyhow::Error>;
-    fn execute_column_action(
+    fn execute_action(&mut self, action: &ActionCommand) -> SheetResult<()>;
+    fn execute_cell_action(&mut self, action: &crate::actions::CellAction) -> SheetResult<()>;
+    fn execute_row_action(&mut self, action: &crate::actions::RowAction) -> SheetResult<()>;
+    fn execute_column_action(&mut self, action: &crate::actions::ColumnAction) -> SheetResult<()>;
+    fn execute_sheet_action(&mut self, action: &crate::actions::SheetAction) -> SheetResult<()>;
+    fn execute_add_sheet_action(
         &mut self,
-        action: &crate::actions::ColumnAction,
-    ) -> Result<(), anyhow::Error>;
-    fn execute_sheet_action(
+        action: &crate::actions::AddSheetAction,
+    ) -> SheetResult<()>;
+    fn execute_move_sheet_action(
         &mut self,
-        action: &crate::actions::SheetAction,
-    ) -> Result<(), anyhow::Error>;
+        action: &crate::actions::MoveSheetAction,
+    ) -> SheetResult<()>;
     fn execute_multi_row_action(
         &mut self,
         action: &crate::actions::MultiRowAction,
-    ) -> Result<(), anyhow::Error>;
+    ) -> SheetResult<()>;
     fn execute_multi_column_action(
         &mut self,
         action: &crate::actions::MultiColumnAction,
-    ) -> Result<(), anyhow::Error>;
+    ) -> SheetResult<()>;
+    fn execute_reorder_columns_action(
+        &mut self,
+        action: &crate::actions::ReorderColumnsAction,
+    ) -> SheetResult<()>;
+    fn execute_insert_rows_action(
+        &mut self,
+        action: &crate::actions::InsertRowsAction,
+    ) -> SheetResult<()>;
+    fn execute_multi_cell_action(
+        &mut self,
+        action: &crate::actions::MultiCellAction,
+    ) -> SheetResult<()>;
+    fn execute_group_action(&mut self, action: &crate::actions::GroupAction) -> SheetResult<()>;
 }
 
 // Command interface for actions that can be executed and undone
 pub trait Command {
-    fn execute(&self) -> anyhow::Result<()>;
-    fn undo(&self) -> anyhow::Result<()>;
+    fn execute(&self) -> SheetResult<()>;
+    fn undo(&self) -> SheetResult<()>;
     fn action_type(&self) -> ActionType;
 }
 
 // Unified action command enum for all action types
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum ActionCommand {
     Cell(crate::actions::CellAction),
     Row(crate::actions::RowAction),
     Column(crate::actions::ColumnAction),
     Sheet(crate::actions::SheetAction),
+    AddSheet(crate::actions::AddSheetAction),
+    MoveSheet(crate::actions::MoveSheetAction),
     MultiRow(crate::actions::MultiRowAction),
     MultiColumn(crate::actions::MultiColumnAction),
+    ReorderColumns(crate::actions::ReorderColumnsAction),
+    InsertRows(crate::actions::InsertRowsAction),
+    MultiCell(crate::actions::MultiCellAction),
+    Group(crate::actions::GroupAction),
 }
@@ -0,0 +1,423 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::actions::{ActionCommand, ActionType, CellChange, InsertRowsAction, MultiCellAction};
+use crate::app::AppState;
+use crate::app::InputMode;
+use crate::excel::{load_sheet_from_file, Cell, Sheet};
+use crate::utils::index_to_col_name;
+
+/// Where the values of a source column go when appending
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappingTarget {
+    Skip,
+    /// A new column after the last column of the sheet, headed by the source header
+    New,
+    Column(usize),
+}
+
+/// State of the column mapping screen shown when the headers of an appended
+/// file do not match the current sheet
+pub struct ColumnMapping {
+    pub path: PathBuf,
+    pub source: Sheet,
+    pub source_headers: Vec<String>,
+    pub target_headers: Vec<String>,
+    pub targets: Vec<MappingTarget>, // One per source column
+    pub selected: usize,
+}
+
+impl ColumnMapping {
+    pub fn target_label(&self, target: MappingTarget) -> String {
+        match target {
+            MappingTarget::Skip => "(skip)".to_string(),
+            MappingTarget::New => "(new column)".to_string(),
+            MappingTarget::Column(col) => {
+                match self.target_headers.get(col - 1).filter(|h| !h.is_empty()) {
+                    Some(header) => format!("{}: {}", index_to_col_name(col), header),
+                    None => index_to_col_name(col),
+                }
+            }
+        }
+    }
+
+    /// Target columns chosen by more than one source column
+    pub fn duplicate_targets(&self) -> Vec<usize> {
+        let mut duplicates: Vec<usize> = self
+            .targets
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, target)| match target {
+                MappingTarget::Column(col) if self.targets[..idx].contains(target) => Some(*col),
+                _ => None,
+            })
+            .collect();
+        duplicates.dedup();
+        duplicates
+    }
+
+    // Cycles through skip, new column and every existing column of the sheet
+    fn cycle_target(&mut self, delta: isize) {
+        let Some(target) = self.targets.get_mut(self.selected) else {
+            return;
+        };
+
+        let option_count = self.target_headers.len() as isize + 2;
+        let position = match *target {
+            MappingTarget::Skip => 0,
+            MappingTarget::New => 1,
+            MappingTarget::Column(col) => col as isize + 1,
+        };
+
+        *target = match (position + delta).rem_euclid(option_count) {
+            0 => MappingTarget::Skip,
+            1 => MappingTarget::New,
+            n => MappingTarget::Column(n as usize - 1),
+        };
+    }
+}
+
+impl AppState<'_> {
+    /// Appends the data rows of another file below the current sheet's data,
+    /// matching columns by header name. When some columns cannot be matched
+    /// the column mapping screen is opened instead of guessing.
+    pub fn append_rows_from_file(&mut self, path: &str) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+
+        let path = self.resolve_relative_path(path);
+        let source = load_sheet_from_file(&path)?;
+
+        if source.max_rows < 2 {
+            self.add_notification(format!("No data rows found in {}", path.display()));
+            return Ok(());
+        }
+
+        let target_headers = header_row(self.workbook.get_current_sheet());
+        let source_headers = header_row(&source);
+
+        // Without any header in the sheet there is nothing to match, append the columns in order
+        if target_headers.iter().all(String::is_empty) {
+            let targets: Vec<MappingTarget> = (1..=source_headers.len())
+                .map(MappingTarget::Column)
+                .collect();
+            return self.apply_append(&path, &source, &source_headers, &target_headers, &targets);
+        }
+
+        // Map each source column to a target column by its (case-insensitive) header
+        let mut targets: Vec<MappingTarget> = source_headers
+            .iter()
+            .map(|header| {
+                target_headers
+                    .iter()
+                    .position(|target| !header.is_empty() && target.eq_ignore_ascii_case(header))
+                    .map_or(MappingTarget::Skip, |idx| MappingTarget::Column(idx + 1))
+            })
+            .collect();
+
+        let fully_matched = source_headers
+            .iter()
+            .zip(&targets)
+            .all(|(header, target)| header.is_empty() || *target != MappingTarget::Skip);
+
+        if fully_matched {
+            return self.apply_append(&path, &source, &source_headers, &target_headers, &targets);
+        }
+
+        if targets.iter().all(|target| *target == MappingTarget::Skip) {
+            // No header in common, suggest appending the columns in order
+            targets = (1..=source_headers.len())
+                .map(|col| {
+                    if col <= target_headers.len() {
+                        MappingTarget::Column(col)
+                    } else {
+                        MappingTarget::New
+                    }
+                })
+                .collect();
+        }
+
+        let unmatched = source_headers
+            .iter()
+            .zip(&targets)
+            .filter(|(header, target)| !header.is_empty() && **target == MappingTarget::Skip)
+            .count();
+        self.add_notification(format!(
+            "Headers of {} do not match the sheet, choose where each column goes",
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default()
+        ));
+        if unmatched > 0 {
+            self.add_notification(format!(
+                "{unmatched} column(s) will be skipped unless mapped"
+            ));
+        }
+
+        self.column_mapping = Some(ColumnMapping {
+            path,
+            source,
+            source_headers,
+            target_headers,
+            targets,
+            selected: 0,
+        });
+        self.input_mode = InputMode::ColumnMapping;
+        Ok(())
+    }
+
+    pub fn move_mapping_selection(&mut self, delta: isize) {
+        if let Some(mapping) = &mut self.column_mapping {
+            let count = mapping.targets.len() as isize;
+            if count > 0 {
+                mapping.selected = (mapping.selected as isize + delta).clamp(0, count - 1) as usize;
+            }
+        }
+    }
+
+    pub fn cycle_mapping_target(&mut self, delta: isize) {
+        if let Some(mapping) = &mut self.column_mapping {
+            mapping.cycle_target(delta);
+        }
+    }
+
+    pub fn set_mapping_target(&mut self, target: MappingTarget) {
+        if let Some(mapping) = &mut self.column_mapping {
+            if let Some(current) = mapping.targets.get_mut(mapping.selected) {
+                *current = target;
+            }
+        }
+    }
+
+    pub fn cancel_column_mapping(&mut self) {
+        self.column_mapping = None;
+        self.input_mode = InputMode::Normal;
+        self.add_notification("Append cancelled".to_string());
+    }
+
+    pub fn confirm_column_mapping(&mut self) {
+        let Some(mapping) = self.column_mapping.as_ref() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+
+        // Two columns writing into the same target would silently overwrite each other
+        let duplicates = mapping.duplicate_targets();
+        if !duplicates.is_empty() {
+            let names: Vec<String> = duplicates.into_iter().map(index_to_col_name).collect();
+            self.add_notification(format!(
+                "Column(s) {} mapped more than once",
+                names.join(", ")
+            ));
+            return;
+        }
+
+        let Some(mapping) = self.column_mapping.take() else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+
+        if let Err(e) = self.apply_append(
+            &mapping.path,
+            &mapping.source,
+            &mapping.source_headers,
+            &mapping.target_headers,
+            &mapping.targets,
+        ) {
+            self.add_notification(format!("Append failed: {e}"));
+        }
+    }
+
+    // New header cells and the appended rows are undone together
+    fn apply_append(
+        &mut self,
+        path: &Path,
+        source: &Sheet,
+        source_headers: &[String],
+        target_headers: &[String],
+        targets: &[MappingTarget],
+    ) -> Result<()> {
+        let label = format!(
+            "Append {}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        self.grouped(label, |app| {
+            app.append_rows(path, source, source_headers, target_headers, targets)
+        })
+    }
+
+    fn append_rows(
+        &mut self,
+        path: &Path,
+        source: &Sheet,
+        source_headers: &[String],
+        target_headers: &[String],
+        targets: &[MappingTarget],
+    ) -> Result<()> {
+        let sheet_index = self.workbook.get_current_sheet_index();
+        let sheet_name = self.workbook.get_current_sheet_name();
+
+        // New columns are added after the last column, headed by the source header
+        let mut next_col = self.workbook.get_current_sheet().max_cols + 1;
+        let mut new_headers = Vec::new();
+        let mapping: Vec<Option<usize>> = targets
+            .iter()
+            .zip(source_headers)
+            .map(|(target, header)| match target {
+                MappingTarget::Skip => None,
+                MappingTarget::Column(col) => Some(*col),
+                MappingTarget::New => {
+                    let col = next_col;
+                    next_col += 1;
+                    new_headers.push((col, header.clone()));
+                    Some(col)
+                }
+            })
+            .collect();
+
+        let width = mapping.iter().flatten().copied().max().unwrap_or(0) + 1;
+        let mut rows_data = Vec::with_capacity(source.max_rows - 1);
+
+        for row in 2..=source.max_rows {
+            let mut row_data = vec![Cell::empty(); width];
+            let mut has_data = false;
+
+            for (src_col, target_col) in mapping.iter().enumerate() {
+                let Some(target_col) = target_col else {
+                    continue;
+                };
+                let cell = source.cell(row, src_col + 1);
+
+                if !cell.value.is_empty() {
+                    // Style ids are per sheet, so re-intern the style in the target sheet
+                    let mut cell = cell.clone();
+                    cell.style = self
+                        .workbook
+                        .get_current_sheet_mut()
+                        .styles
+                        .intern(source.cell_style(row, src_col + 1).clone());
+                    row_data[*target_col] = cell;
+                    has_data = true;
+                }
+            }
+
+            if has_data {
+                rows_data.push(row_data);
+            }
+        }
+
+        if rows_data.is_empty() {
+            self.add_notification(format!("No data rows found in {}", path.display()));
+            return Ok(());
+        }
+
+        if !new_headers.is_empty() {
+            let mut cells = Vec::with_capacity(new_headers.len());
+            for (col, header) in &new_headers {
+                let old_value = self.workbook.get_current_sheet().cell(1, *col).clone();
+                self.workbook.set_cell_value(1, *col, header.clone())?;
+                cells.push(CellChange {
+                    row: 1,
+                    col: *col,
+                    old_value,
+                    new_value: self.workbook.get_current_sheet().cell(1, *col).clone(),
+                });
+            }
+            self.undo_history
+                .push(ActionCommand::MultiCell(MultiCellAction {
+                    sheet_index,
+                    sheet_name: sheet_name.clone(),
+                    cells,
+                    action_type: ActionType::Edit,
+                }));
+        }
+
+        let start_row = self.workbook.get_current_sheet().max_rows + 1;
+        let row_count = rows_data.len();
+
+        self.workbook.insert_rows(start_row, &rows_data)?;
+        self.undo_history
+            .push(ActionCommand::InsertRows(InsertRowsAction {
+                sheet_index,
+                sheet_name,
+                start_row,
+                rows_data,
+            }));
+
+        self.ensure_column_widths();
+        self.search_results.clear();
+        self.current_search_idx = None;
+        self.selected_cell = (start_row, self.selected_cell.1);
+        self.handle_scrolling();
+
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        self.add_notification(format!(
+            "Appended {row_count} rows from {file_name} at row {start_row}"
+        ));
+
+        if target_headers.iter().all(String::is_empty) {
+            self.add_notification(
+                "No headers in the sheet, columns appended by position".to_string(),
+            );
+            return Ok(());
+        }
+
+        let skipped: Vec<&str> = source_headers
+            .iter()
+            .zip(&mapping)
+            .filter(|(header, target)| target.is_none() && !header.is_empty())
+            .map(|(header, _)| header.as_str())
+            .collect();
+        let missing: Vec<&str> = target_headers
+            .iter()
+            .enumerate()
+            .filter(|(idx, header)| !header.is_empty() && !mapping.contains(&Some(idx + 1)))
+            .map(|(_, header)| header.as_str())
+            .collect();
+
+        if !new_headers.is_empty() {
+            let added: Vec<&str> = new_headers.iter().map(|(_, h)| h.as_str()).collect();
+            self.add_notification(format!("Added columns: {}", added.join(", ")));
+        }
+        if !skipped.is_empty() {
+            self.add_notification(format!("Skipped columns: {}", skipped.join(", ")));
+        }
+        if !missing.is_empty() {
+            self.add_notification(format!(
+                "Columns missing from {file_name}: {}",
+                missing.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Resolves a path typed in command mode, falling back to the directory of
+    /// the open workbook when it does not exist relative to the working directory
+    pub fn resolve_relative_path(&self, path: &str) -> PathBuf {
+        let path = PathBuf::from(path);
+        if path.is_absolute() || path.exists() {
+            return path;
+        }
+
+        let workbook_dir = Path::new(self.workbook.get_file_path())
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let candidate = workbook_dir.join(&path);
+
+        if candidate.exists() {
+            candidate
+        } else {
+            path
+        }
+    }
+}
+
+// Trimmed header values of the first row, indexed from column 1
+fn header_row(sheet: &Sheet) -> Vec<String> {
+    (1..=sheet.max_cols)
+        .map(|col| sheet.cell(1, col).value.trim().to_string())
+        .collect()
+}
@@ -0,0 +1,183 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+
+use crate::app::AppState;
+use crate::excel::SheetResult;
+
+// Shortest interval :set autosave accepts
+const MIN_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Periodic copies of a modified workbook written on a background thread, set with :set autosave
+#[derive(Default)]
+pub struct Autosave {
+    interval: Option<Duration>,
+    next_due: Option<Instant>,
+    running: Option<Receiver<SheetResult<()>>>,
+    remove_when_done: bool, // The workbook was saved while a copy was being written
+    pub last_result: Option<AutosaveResult>,
+}
+
+/// Outcome of the last autosave, shown in the status bar
+pub enum AutosaveResult {
+    Saved(String), // Time of day it was written
+    Failed,
+}
+
+/// Parses intervals like `30s`, `5m` or `1h`, a bare number is minutes. Intervals too
+/// long to count from now are refused
+fn parse_interval(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, "m"),
+    };
+
+    let number: u64 = number.parse().ok()?;
+    let seconds = match unit.trim() {
+        "s" | "sec" => number,
+        "m" | "min" => number.checked_mul(60)?,
+        "h" => number.checked_mul(3600)?,
+        _ => return None,
+    };
+    let interval = Duration::from_secs(seconds);
+    Instant::now().checked_add(interval).map(|_| interval)
+}
+
+fn format_interval(interval: Duration) -> String {
+    let seconds = interval.as_secs();
+    if seconds % 3600 == 0 {
+        format!("{}h", seconds / 3600)
+    } else if seconds % 60 == 0 {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+impl AppState<'_> {
+    /// :set autosave 5m, :set autosave=30s, :set noautosave
+    pub fn set_autosave(&mut self, value: Option<&str>) {
+        let Some(value) = value else {
+            self.autosave.interval = None;
+            self.autosave.next_due = None;
+            self.add_notification("Autosave disabled".to_string());
+            return;
+        };
+
+        match parse_interval(value) {
+            Some(interval) if interval >= MIN_AUTOSAVE_INTERVAL => {
+                self.autosave.interval = Some(interval);
+                self.autosave.next_due = Some(Instant::now() + interval);
+                self.add_notification(format!(
+                    "Autosave every {}: modified workbooks are copied to {}",
+                    format_interval(interval),
+                    self.workbook.autosave_path().display()
+                ));
+            }
+            Some(_) => self.add_notification(format!(
+                "Autosave interval must be at least {}",
+                format_interval(MIN_AUTOSAVE_INTERVAL)
+            )),
+            None => self.add_notification(
+                "Usage: :set autosave 5m (s, m or h, a bare number is minutes), :set noautosave"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Collects a finished autosave and starts the next one when due, called between key presses
+    pub fn continue_autosave(&mut self) {
+        if let Some(receiver) = &self.autosave.running {
+            let result = match receiver.try_recv() {
+                Ok(result) => result.map_err(|e| e.to_string()),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    Err("the writer stopped unexpectedly".to_string())
+                }
+            };
+            self.autosave.running = None;
+
+            match result {
+                _ if std::mem::take(&mut self.autosave.remove_when_done) => {
+                    self.delete_autosave_file();
+                }
+                Ok(()) => {
+                    self.autosave.last_result = Some(AutosaveResult::Saved(
+                        Local::now().format("%H:%M").to_string(),
+                    ));
+                }
+                Err(e) => {
+                    self.autosave.last_result = Some(AutosaveResult::Failed);
+                    self.add_notification(format!("Autosave failed: {e}"));
+                }
+            }
+        }
+
+        let (Some(interval), Some(next_due)) = (self.autosave.interval, self.autosave.next_due)
+        else {
+            return;
+        };
+        if Instant::now() < next_due {
+            return;
+        }
+        self.autosave.next_due = Instant::now().checked_add(interval);
+
+        // Nothing new to write, the cells are still changing under a running replace, or
+        // a save is writing the file already
+        if !self.workbook.is_modified()
+            || self.replace_job.is_some()
+            || self.workbook.saving().is_some()
+        {
+            return;
+        }
+
+        // The copy is written from a snapshot so editing can go on meanwhile
+        self.store_current_column_widths();
+        let workbook = self.workbook.clone();
+        let column_widths = self.sheet_column_widths.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = workbook.save_autosave_copy(&column_widths).map(|_| ());
+            let _ = sender.send(result);
+        });
+        self.autosave.running = Some(receiver);
+    }
+
+    /// Drops the autosave copy once the workbook is saved for real
+    pub fn remove_autosave_copy(&mut self) {
+        self.autosave.last_result = None;
+        // A copy still being written would show up again after the removal, so it is
+        // removed once the writer is done
+        if self.autosave.running.is_some() {
+            self.autosave.remove_when_done = true;
+            return;
+        }
+        self.delete_autosave_file();
+    }
+
+    /// On quitting, waits for a copy still being written that was marked for removal, so
+    /// it isn't left behind
+    pub fn finish_autosave(&mut self) {
+        if let Some(receiver) = self.autosave.running.take() {
+            if self.autosave.remove_when_done {
+                let _ = receiver.recv();
+                self.delete_autosave_file();
+            }
+        }
+    }
+
+    fn delete_autosave_file(&mut self) {
+        let path = self.workbook.autosave_path();
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                self.add_notification(format!(
+                    "Could not remove autosave copy {}: {e}",
+                    path.display()
+                ));
+            }
+        }
+    }
+}
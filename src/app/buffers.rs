@@ -0,0 +1,371 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::actions::UndoHistory;
+use crate::app::{
+    AppState, CellPosition, ColumnFormat, DiffList, FileWatch, InputMode, JumpList, SplitPane,
+    ValidationRule, WorkbookDiff,
+};
+use crate::excel::{
+    import_profiles_path, load_import_profiles, open_workbook_with_delimiter, ImportProfile,
+    Workbook,
+};
+use crate::json_export::HeaderDirection;
+
+/// A workbook open beside the one shown, with the cursor, undo history and everything
+/// else that belongs to it. Switching buffers swaps these with the fields of `AppState`
+pub struct Buffer {
+    workbook: Workbook,
+    file_path: PathBuf,
+    profile: Option<ImportProfile>,
+    json_headers: Option<(HeaderDirection, usize)>,
+    selected_cell: (usize, usize),
+    start_row: usize,
+    start_col: usize,
+    frozen_rows: usize,
+    frozen_cols: usize,
+    column_widths: Vec<usize>,
+    sheet_column_widths: HashMap<String, Vec<usize>>,
+    sheet_cell_positions: HashMap<String, CellPosition>,
+    marks: HashMap<String, HashMap<char, (usize, usize)>>,
+    validation_rules: HashMap<String, BTreeMap<usize, ValidationRule>>,
+    column_formats: HashMap<String, BTreeMap<usize, ColumnFormat>>,
+    jump_list: JumpList,
+    undo_history: UndoHistory,
+    diff: Option<WorkbookDiff>,
+    diff_list: Option<DiffList>,
+    split: Option<SplitPane>,
+    file_watch: Option<FileWatch>,
+    file_changed: bool,
+    reload_prompt: bool,
+    search_results: Vec<(usize, usize)>,
+    current_search_idx: Option<usize>,
+}
+
+impl Buffer {
+    /// Opens a file the way the command line does, with the import profile matching its name
+    fn open(path: &Path, lazy_loading: bool) -> Result<(Self, Option<String>)> {
+        let profile = load_import_profiles(&import_profiles_path())
+            .ok()
+            .and_then(|profiles| profiles.into_iter().find(|p| p.matches(path)));
+        let delimiter = match profile.as_ref().map(ImportProfile::delimiter_byte) {
+            Some(delimiter) => delimiter.map_err(anyhow::Error::msg)?,
+            None => None,
+        };
+        let mut workbook = open_workbook_with_delimiter(path, lazy_loading, delimiter)?;
+
+        let mut message = None;
+        if let Some(profile) = profile.as_ref().filter(|p| !p.columns.is_empty()) {
+            let changed = workbook.set_import_profile(profile.clone());
+            let later = if workbook.is_lazy_loading() {
+                ", other sheets as they load"
+            } else {
+                ""
+            };
+            message = Some(format!(
+                "Import profile '{}' changed {changed} cell(s){later}",
+                profile.display_name()
+            ));
+        }
+
+        let column_widths = vec![15; workbook.get_current_sheet().max_cols + 1];
+        let sheet_column_widths =
+            HashMap::from([(workbook.get_current_sheet_name(), column_widths.clone())]);
+        let buffer = Self {
+            workbook,
+            file_path: path.to_path_buf(),
+            profile,
+            json_headers: None,
+            selected_cell: (1, 1),
+            start_row: 1,
+            start_col: 1,
+            frozen_rows: 0,
+            frozen_cols: 0,
+            column_widths,
+            sheet_column_widths,
+            sheet_cell_positions: HashMap::new(),
+            marks: HashMap::new(),
+            validation_rules: HashMap::new(),
+            column_formats: HashMap::new(),
+            jump_list: JumpList::default(),
+            undo_history: UndoHistory::new(),
+            diff: None,
+            diff_list: None,
+            split: None,
+            file_watch: FileWatch::new(path).ok(),
+            file_changed: false,
+            reload_prompt: false,
+            search_results: Vec::new(),
+            current_search_idx: None,
+        };
+        Ok((buffer, message))
+    }
+}
+
+// Name shown for a workbook in the title and the buffer list
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().to_string(),
+    )
+}
+
+// The same file reached through another relative path is still the same file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+impl AppState<'_> {
+    // Position of the shown workbook in the buffer list
+    fn current_buffer(&self) -> usize {
+        self.buffers
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or_default()
+    }
+
+    /// Number of the shown workbook and how many are open, for the title
+    #[must_use]
+    pub fn buffer_position(&self) -> (usize, usize) {
+        (self.current_buffer() + 1, self.buffers.len())
+    }
+
+    /// :open file: opens another workbook and shows it, the open ones stay as they are.
+    /// A file that is already open is just shown
+    pub fn open_buffer(&mut self, path: &str) {
+        let path = path.trim();
+        if path.is_empty() {
+            self.add_notification("Usage: :open file".to_string());
+            return;
+        }
+        let path = Path::new(path);
+
+        if same_file(path, &self.file_path) {
+            self.add_notification(format!("{} is already open", file_name(path)));
+            return;
+        }
+        let open = self.buffers.iter().position(|buffer| {
+            buffer
+                .as_ref()
+                .is_some_and(|buffer| same_file(path, &buffer.file_path))
+        });
+        if let Some(index) = open {
+            self.switch_buffer(index);
+            return;
+        }
+
+        let (buffer, message) = match Buffer::open(path, self.workbook.is_lazy_loading()) {
+            Ok(opened) => opened,
+            Err(e) => {
+                self.add_notification(format!("Cannot open {}: {e}", path.display()));
+                return;
+            }
+        };
+        self.buffers.push(Some(buffer));
+        if !self.switch_buffer(self.buffers.len() - 1) {
+            self.buffers.pop();
+            return;
+        }
+        if let Some(message) = message {
+            self.add_notification(message);
+        }
+        if !self.workbook.is_lazy_loading() {
+            self.restore_undo_file();
+        }
+    }
+
+    /// Shows the workbook at `index` of the buffer list, keeping the state of the one
+    /// shown so far for when it comes back. Returns whether it switched
+    pub fn switch_buffer(&mut self, index: usize) -> bool {
+        let current = self.current_buffer();
+        if index == current || index >= self.buffers.len() {
+            return false;
+        }
+        if self.report_running_save() {
+            return false;
+        }
+        // The replace works through the shown workbook, it would go on in the other one
+        if self.replace_job.is_some() {
+            self.add_notification("Wait for the replace to finish before switching".to_string());
+            return false;
+        }
+        let Some(mut buffer) = self.buffers[index].take() else {
+            return false;
+        };
+
+        self.store_current_column_widths();
+        self.sheet_cell_positions.insert(
+            self.workbook.get_current_sheet_name(),
+            CellPosition {
+                selected: self.selected_cell,
+                view: (self.start_row, self.start_col),
+            },
+        );
+        self.swap_buffer(&mut buffer);
+        self.buffers[current] = Some(buffer);
+
+        self.clear_selection();
+        self.search_job = None;
+        self.flash = None;
+        self.selection_stats = None;
+        if self.minimap.is_some() {
+            self.minimap = None;
+            self.refresh_minimap();
+        }
+        self.ensure_column_widths();
+        self.handle_scrolling();
+        self.update_row_number_width();
+
+        let sheet_index = self.workbook.get_current_sheet_index();
+        if self.workbook.is_lazy_loading() && !self.workbook.is_sheet_loaded(sheet_index) {
+            self.input_mode = InputMode::LazyLoading;
+        } else if matches!(self.input_mode, InputMode::LazyLoading) {
+            self.input_mode = InputMode::Normal;
+        }
+        self.add_notification(format!(
+            "Buffer {} of {}: {}",
+            index + 1,
+            self.buffers.len(),
+            file_name(&self.file_path)
+        ));
+        true
+    }
+
+    fn swap_buffer(&mut self, buffer: &mut Buffer) {
+        mem::swap(&mut self.workbook, &mut buffer.workbook);
+        mem::swap(&mut self.file_path, &mut buffer.file_path);
+        mem::swap(&mut self.profile, &mut buffer.profile);
+        mem::swap(&mut self.json_headers, &mut buffer.json_headers);
+        mem::swap(&mut self.selected_cell, &mut buffer.selected_cell);
+        mem::swap(&mut self.start_row, &mut buffer.start_row);
+        mem::swap(&mut self.start_col, &mut buffer.start_col);
+        mem::swap(&mut self.frozen_rows, &mut buffer.frozen_rows);
+        mem::swap(&mut self.frozen_cols, &mut buffer.frozen_cols);
+        mem::swap(&mut self.column_widths, &mut buffer.column_widths);
+        mem::swap(
+            &mut self.sheet_column_widths,
+            &mut buffer.sheet_column_widths,
+        );
+        mem::swap(
+            &mut self.sheet_cell_positions,
+            &mut buffer.sheet_cell_positions,
+        );
+        mem::swap(&mut self.marks, &mut buffer.marks);
+        mem::swap(&mut self.validation_rules, &mut buffer.validation_rules);
+        mem::swap(&mut self.column_formats, &mut buffer.column_formats);
+        mem::swap(&mut self.jump_list, &mut buffer.jump_list);
+        mem::swap(&mut self.undo_history, &mut buffer.undo_history);
+        mem::swap(&mut self.diff, &mut buffer.diff);
+        mem::swap(&mut self.diff_list, &mut buffer.diff_list);
+        mem::swap(&mut self.split, &mut buffer.split);
+        mem::swap(&mut self.file_watch, &mut buffer.file_watch);
+        mem::swap(&mut self.file_changed, &mut buffer.file_changed);
+        mem::swap(&mut self.reload_prompt, &mut buffer.reload_prompt);
+        mem::swap(&mut self.search_results, &mut buffer.search_results);
+        mem::swap(&mut self.current_search_idx, &mut buffer.current_search_idx);
+    }
+
+    /// :bn and :bp: shows the next or previous workbook, wrapping around
+    pub fn cycle_buffer(&mut self, forward: bool) {
+        let count = self.buffers.len();
+        if count < 2 {
+            self.add_notification("Only one workbook is open, :open file adds another".to_string());
+            return;
+        }
+        let current = self.current_buffer();
+        let index = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        self.switch_buffer(index);
+    }
+
+    /// :b N: shows the workbook numbered N in :ls
+    pub fn goto_buffer(&mut self, number: &str) {
+        match number.trim().parse::<usize>() {
+            Ok(number) if (1..=self.buffers.len()).contains(&number) => {
+                if number - 1 == self.current_buffer() {
+                    self.add_notification(format!("Already in buffer {number}"));
+                } else {
+                    self.switch_buffer(number - 1);
+                }
+            }
+            _ => self.add_notification(format!(
+                "Usage: :b N with N from 1 to {}, :ls lists them",
+                self.buffers.len()
+            )),
+        }
+    }
+
+    /// :ls: the open workbooks, % marks the shown one and [+] unsaved changes
+    pub fn list_buffers(&mut self) {
+        let list: Vec<String> = self
+            .buffers
+            .iter()
+            .enumerate()
+            .map(|(index, buffer)| {
+                let (path, modified, current) = match buffer {
+                    Some(buffer) => (&buffer.file_path, buffer.workbook.is_modified(), ""),
+                    None => (&self.file_path, self.workbook.is_modified(), "%"),
+                };
+                let modified = if modified { " [+]" } else { "" };
+                format!("{current}{} {}{modified}", index + 1, file_name(path))
+            })
+            .collect();
+        self.add_notification(format!("Buffers: {}", list.join(", ")));
+    }
+
+    /// :bd: closes the shown workbook and shows the next one. Unsaved changes keep it
+    /// open unless forced with :bd!
+    pub fn close_buffer(&mut self, force: bool) {
+        if self.buffers.len() < 2 {
+            self.add_notification("Only one workbook is open, use :q to quit".to_string());
+            return;
+        }
+        if !force && self.workbook.is_modified() {
+            self.add_notification(
+                "File has unsaved changes. Use :bd! to close it anyway or :w to save them."
+                    .to_string(),
+            );
+            return;
+        }
+
+        let current = self.current_buffer();
+        let next = if current + 1 < self.buffers.len() {
+            current + 1
+        } else {
+            current - 1
+        };
+        let name = file_name(&self.file_path);
+        if self.switch_buffer(next) {
+            self.buffers.remove(current);
+            self.add_notification(format!("Closed {name}"));
+        }
+    }
+
+    /// Returns true when quitting was held back because a workbook in the background has
+    /// unsaved changes
+    pub fn warn_modified_buffer(&mut self, force_command: &str) -> bool {
+        let modified = self.buffers.iter().enumerate().find_map(|(index, buffer)| {
+            buffer
+                .as_ref()
+                .filter(|buffer| buffer.workbook.is_modified())
+                .map(|buffer| (index + 1, file_name(&buffer.file_path)))
+        });
+        let Some((number, name)) = modified else {
+            return false;
+        };
+        self.add_notification(format!(
+            "{name} has unsaved changes. Use :b {number} to go to it or :{force_command} to quit anyway."
+        ));
+        true
+    }
+}
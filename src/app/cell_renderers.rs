@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::Deserialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::AppState;
+use crate::excel::{import_profiles_path, Cell, CellType};
+
+/// Kind of content a renderer presents, detected from the cell's type and value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Url,
+    Timestamp,
+    Boolean,
+    Json,
+}
+
+impl ContentKind {
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentKind::Url => "url",
+            ContentKind::Timestamp => "timestamp",
+            ContentKind::Boolean => "boolean",
+            ContentKind::Json => "json",
+        }
+    }
+
+    #[must_use]
+    pub fn of(cell: &Cell) -> Option<Self> {
+        let value = cell.value.trim();
+        if cell.is_formula || value.is_empty() {
+            return None;
+        }
+
+        if cell.cell_type == CellType::Boolean {
+            Some(ContentKind::Boolean)
+        } else if (value.starts_with("http://") || value.starts_with("https://"))
+            && !value.contains(char::is_whitespace)
+        {
+            Some(ContentKind::Url)
+        } else if (value.starts_with('{') && value.ends_with('}'))
+            || (value.starts_with('[') && value.ends_with(']'))
+        {
+            Some(ContentKind::Json)
+        } else if parse_timestamp(value).is_some() {
+            Some(ContentKind::Timestamp)
+        } else {
+            None
+        }
+    }
+}
+
+/// Presents one kind of content in the grid instead of its raw value. The formula bar
+/// and the K popup keep showing what is stored
+pub trait CellRenderer {
+    /// Name used in the renderer config
+    fn name(&self) -> &'static str;
+
+    fn kind(&self) -> ContentKind;
+
+    /// Text shown in a column `width` wide, `None` leaves the raw value
+    fn render(&self, value: &str, width: usize) -> Option<String>;
+}
+
+/// `https://www.example.com/a/b` as `🔗 example.com/a/b`, cut to the host when it doesn't fit
+pub struct LinkRenderer;
+
+impl CellRenderer for LinkRenderer {
+    fn name(&self) -> &'static str {
+        "link"
+    }
+
+    fn kind(&self) -> ContentKind {
+        ContentKind::Url
+    }
+
+    fn render(&self, value: &str, width: usize) -> Option<String> {
+        let address = value
+            .trim()
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("www.")
+            .trim_end_matches('/');
+
+        let full = format!("🔗 {address}");
+        if full.width() <= width {
+            return Some(full);
+        }
+        match address.split_once('/') {
+            Some((host, _)) => Some(format!("🔗 {host}/…")),
+            None => Some(full),
+        }
+    }
+}
+
+/// ISO timestamps as `2h ago` or `in 3d`, shown once :set relativetime is on
+pub struct RelativeTimeRenderer;
+
+impl CellRenderer for RelativeTimeRenderer {
+    fn name(&self) -> &'static str {
+        "relative"
+    }
+
+    fn kind(&self) -> ContentKind {
+        ContentKind::Timestamp
+    }
+
+    fn render(&self, value: &str, _width: usize) -> Option<String> {
+        let time = parse_timestamp(value.trim())?;
+        let seconds = Local::now()
+            .naive_local()
+            .signed_duration_since(time)
+            .num_seconds();
+        Some(humanize_seconds(seconds))
+    }
+}
+
+/// true and false as ✓ and ✗
+pub struct CheckmarkRenderer;
+
+impl CellRenderer for CheckmarkRenderer {
+    fn name(&self) -> &'static str {
+        "checkmark"
+    }
+
+    fn kind(&self) -> ContentKind {
+        ContentKind::Boolean
+    }
+
+    fn render(&self, value: &str, _width: usize) -> Option<String> {
+        match value.trim() {
+            v if v.eq_ignore_ascii_case("true") => Some("✓".to_string()),
+            v if v.eq_ignore_ascii_case("false") => Some("✗".to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// JSON too long for its column as `{…} 3 keys` or `[…] 12 items`, K shows it in full
+pub struct CollapsedJsonRenderer;
+
+impl CellRenderer for CollapsedJsonRenderer {
+    fn name(&self) -> &'static str {
+        "collapsed"
+    }
+
+    fn kind(&self) -> ContentKind {
+        ContentKind::Json
+    }
+
+    fn render(&self, value: &str, width: usize) -> Option<String> {
+        if value.width() <= width {
+            return None;
+        }
+        match serde_json::from_str(value).ok()? {
+            serde_json::Value::Object(map) => Some(format!("{{…}} {} keys", map.len())),
+            serde_json::Value::Array(items) => Some(format!("[…] {} items", items.len())),
+            _ => None,
+        }
+    }
+}
+
+fn builtin_renderers() -> Vec<Box<dyn CellRenderer>> {
+    vec![
+        Box::new(LinkRenderer),
+        Box::new(RelativeTimeRenderer),
+        Box::new(CheckmarkRenderer),
+        Box::new(CollapsedJsonRenderer),
+    ]
+}
+
+// ISO 8601 timestamps with a time of day, with or without an offset
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    if !value.contains(':') {
+        return None;
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local).naive_local());
+    }
+
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+}
+
+fn humanize_seconds(seconds: i64) -> String {
+    let (amount, unit) = match seconds.unsigned_abs() {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "m"),
+        s if s < 86_400 => (s / 3600, "h"),
+        s if s < 30 * 86_400 => (s / 86_400, "d"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "mo"),
+        s => (s / (365 * 86_400), "y"),
+    };
+
+    if seconds >= 0 {
+        format!("{amount}{unit} ago")
+    } else {
+        format!("in {amount}{unit}")
+    }
+}
+
+/// JSON objects and arrays indented over several lines, for the K popup
+#[must_use]
+pub fn pretty_json(value: &str) -> Option<String> {
+    match serde_json::from_str(value.trim()).ok()? {
+        json @ (serde_json::Value::Object(_) | serde_json::Value::Array(_)) => {
+            serde_json::to_string_pretty(&json).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Location of the renderer config, next to the import profiles,
+/// e.g. ~/.config/excel-cli/cell_renderers.json
+#[must_use]
+pub fn cell_renderers_path() -> PathBuf {
+    import_profiles_path().with_file_name("cell_renderers.json")
+}
+
+/// The renderer used for each kind of content, set up from the config file
+pub struct CellRenderers {
+    renderers: HashMap<ContentKind, Box<dyn CellRenderer>>,
+    pub enabled: bool,       // :set norenderers shows every value as stored
+    pub relative_time: bool, // Timestamps stay as written until :set relativetime
+}
+
+impl Default for CellRenderers {
+    fn default() -> Self {
+        Self {
+            renderers: builtin_renderers()
+                .into_iter()
+                .map(|renderer| (renderer.kind(), renderer))
+                .collect(),
+            enabled: true,
+            relative_time: false,
+        }
+    }
+}
+
+impl CellRenderers {
+    /// Reads a config like `{"url": "link", "json": "plain"}`, kinds it leaves out keep their
+    /// default renderer and `plain` turns a kind off. A missing file means the defaults
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut renderers = Self::default();
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(renderers),
+            Err(e) => return Err(e).with_context(|| format!("Unable to read {}", path.display())),
+        };
+
+        let config: HashMap<ContentKind, String> = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid renderer config {}", path.display()))?;
+        for (kind, name) in config {
+            renderers.set(kind, &name)?;
+        }
+        Ok(renderers)
+    }
+
+    /// Presents `kind` with the renderer called `name`, `plain` shows the raw value
+    pub fn set(&mut self, kind: ContentKind, name: &str) -> Result<()> {
+        if name == "plain" {
+            self.renderers.remove(&kind);
+            return Ok(());
+        }
+
+        let available = builtin_renderers();
+        let names: Vec<&str> = available
+            .iter()
+            .filter(|renderer| renderer.kind() == kind)
+            .map(|renderer| renderer.name())
+            .collect();
+        let renderer = available
+            .into_iter()
+            .find(|renderer| renderer.kind() == kind && renderer.name() == name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown renderer '{name}' for {} content, expected {} or plain",
+                    kind.name(),
+                    names.join(", ")
+                )
+            })?;
+        self.renderers.insert(kind, renderer);
+        Ok(())
+    }
+
+    /// What the grid shows for `cell` in a column `width` wide, `None` for the raw value
+    #[must_use]
+    pub fn render(&self, cell: &Cell, width: usize) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        let kind = ContentKind::of(cell)?;
+        if kind == ContentKind::Timestamp && !self.relative_time {
+            return None;
+        }
+        self.renderers.get(&kind)?.render(&cell.value, width)
+    }
+}
+
+impl AppState<'_> {
+    /// Content of a cell as the grid shows it, in its number format or presented by its
+    /// renderer if it has one
+    pub fn get_display_content(&self, row: usize, col: usize, width: usize) -> String {
+        let sheet = self.workbook.get_current_sheet();
+        sheet
+            .data
+            .get(row, col)
+            .and_then(|cell| {
+                self.formatted_number(cell, row, col)
+                    .or_else(|| self.cell_renderers.render(cell, width))
+            })
+            .unwrap_or_else(|| self.get_cell_content(row, col))
+    }
+}
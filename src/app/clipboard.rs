@@ -0,0 +1,384 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+use crate::actions::{ActionCommand, ActionType, CellChange, MultiCellAction};
+use crate::app::AppState;
+use crate::excel::{Cell, CellStyle, Sheet};
+use crate::utils::{cell_reference, index_to_col_name, parse_smart_number, MAX_COLS, MAX_ROWS};
+
+// Commands that print the system clipboard, tried in order until one is installed
+const SYSTEM_CLIPBOARD_COMMANDS: [&[&str]; 4] = [
+    &["pbpaste"],
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+/// What y and d put aside for p
+#[derive(Clone)]
+pub enum Clipboard {
+    Text(String),
+    Range(CellRange),
+    Row(CellRange),    // From yy, pasted over the cursor's row
+    Column(CellRange), // From yc, pasted over the cursor's column
+}
+
+/// A rectangle of cells yanked from a visual selection. Styles are kept by value
+/// since style ids only mean something in the sheet they came from
+#[derive(Clone)]
+pub struct CellRange {
+    pub source: String, // e.g. Sheet1!A1:C4
+    pub cells: Vec<Vec<(Cell, CellStyle)>>,
+}
+
+impl CellRange {
+    #[must_use]
+    pub fn size_label(&self) -> String {
+        let rows = self.cells.len();
+        let cols = self.cells.first().map_or(0, Vec::len);
+        format!("{rows}x{cols}")
+    }
+}
+
+// Commands that set the system clipboard from their input, in the same order
+const SYSTEM_COPY_COMMANDS: [&[&str]; 4] = [
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+/// Text on the system clipboard, `None` when it is empty or no clipboard tool is available
+#[must_use]
+pub fn read_system_clipboard() -> Option<String> {
+    SYSTEM_CLIPBOARD_COMMANDS.iter().find_map(|command| {
+        let output = Command::new(command[0]).args(&command[1..]).output().ok()?;
+        let text = String::from_utf8(output.stdout).ok()?;
+        (output.status.success() && !text.is_empty()).then_some(text)
+    })
+}
+
+/// Puts `text` on the system clipboard with the first clipboard tool that is installed,
+/// or asks the terminal to with an OSC 52 escape sequence. Returns how it was copied
+pub fn write_system_clipboard(text: &str) -> Result<&'static str> {
+    for command in SYSTEM_COPY_COMMANDS {
+        let Ok(mut child) = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .with_context(|| format!("Unable to write to {}", command[0]))?;
+        }
+        if child.wait()?.success() {
+            return Ok(command[0]);
+        }
+    }
+
+    // Terminals supporting OSC 52 set the clipboard themselves, also over SSH
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+    stdout.flush()?;
+    Ok("OSC 52")
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (idx, &byte)| {
+            n | u32::from(byte) << (16 - 8 * idx)
+        });
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * idx) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Splits a block copied from a spreadsheet or web table into rows of values. Tabs separate
+/// cells when there are any, commas when every line has the same number of them, otherwise
+/// each line is one cell. Text on a single line without tabs gives `None`
+#[must_use]
+pub fn parse_text_block(text: &str) -> Option<Vec<Vec<String>>> {
+    let text = text.trim_end_matches(['\r', '\n']);
+    if !text.contains(['\t', '\n']) {
+        return None;
+    }
+
+    let read = |delimiter: u8| -> Option<Vec<Vec<String>>> {
+        csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes())
+            .records()
+            .map(|record| Some(record.ok()?.iter().map(str::to_string).collect()))
+            .collect()
+    };
+
+    if text.contains('\t') {
+        return read(b'\t');
+    }
+    if let Some(rows) = read(b',') {
+        let cols = rows.first().map_or(0, Vec::len);
+        if cols > 1 && rows.iter().all(|row| row.len() == cols) {
+            return Some(rows);
+        }
+    }
+    Some(text.lines().map(|line| vec![line.to_string()]).collect())
+}
+
+// A cell with its style by value, empty where the sheet has no data yet
+fn cell_with_style(sheet: &Sheet, row: usize, col: usize) -> (Cell, CellStyle) {
+    let cell = sheet.cell(row, col).clone();
+    let style = sheet.styles.get(cell.style).clone();
+    (cell, style)
+}
+
+impl AppState<'_> {
+    /// Pastes text from outside the app at the cursor, a block of rows and columns
+    /// fills a range of cells in one undo step
+    pub fn paste_text(&mut self, text: &str, source: &str) -> Result<()> {
+        let Some(rows) = parse_text_block(text) else {
+            return self.paste_into_cell(text.trim_end_matches(['\r', '\n']).to_string());
+        };
+        self.workbook.ensure_current_sheet_editable()?;
+
+        // Pasted values take the style of the cells they land on, like a single pasted value
+        let (top, left) = self.selected_cell;
+        let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let sheet = self.workbook.get_current_sheet();
+        let cells = rows
+            .into_iter()
+            .enumerate()
+            .map(|(row_offset, values)| {
+                let mut values = values.into_iter();
+                (0..cols)
+                    .map(|col_offset| {
+                        let value = values.next().unwrap_or_default();
+                        let target = sheet.cell(top + row_offset, left + col_offset);
+                        let mut style = sheet.styles.get(target.style).clone();
+
+                        let number = parse_smart_number(&value).filter(|_| self.smart_numbers);
+                        let value = match number {
+                            Some(number) => {
+                                style.number_format = Some(number.number_format);
+                                style.number_format_code = None;
+                                number.value
+                            }
+                            None => value,
+                        };
+                        let is_formula = value.starts_with('=');
+                        (Cell::new(value, is_formula), style)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.paste_range(&CellRange {
+            source: source.to_string(),
+            cells,
+        })
+    }
+
+    /// y with a visual selection: copies the selected cells with their types and styles
+    pub fn yank_selection(&mut self) {
+        if let Some(range) = self.selected_range() {
+            self.add_notification(format!(
+                "Copied {} range {}",
+                range.size_label(),
+                range.source
+            ));
+            self.clipboard = Some(Clipboard::Range(range));
+        }
+        self.clear_selection();
+    }
+
+    /// yy: copies the current row up to the last used column
+    pub fn yank_row(&mut self) {
+        let row = self.selected_cell.0;
+        let sheet = self.workbook.get_current_sheet();
+        let cells = (1..=sheet.max_cols.max(1))
+            .map(|col| cell_with_style(sheet, row, col))
+            .collect();
+        let range = CellRange {
+            source: format!("{}!{row}", sheet.name),
+            cells: vec![cells],
+        };
+
+        self.add_notification(format!("Copied row {}", range.source));
+        self.clipboard = Some(Clipboard::Row(range));
+    }
+
+    /// yc: copies the current column down to the last used row
+    pub fn yank_column(&mut self) {
+        let col = self.selected_cell.1;
+        let sheet = self.workbook.get_current_sheet();
+        let cells = (1..=sheet.max_rows.max(1))
+            .map(|row| vec![cell_with_style(sheet, row, col)])
+            .collect();
+        let range = CellRange {
+            source: format!("{}!{}", sheet.name, index_to_col_name(col)),
+            cells,
+        };
+
+        self.add_notification(format!("Copied column {}", range.source));
+        self.clipboard = Some(Clipboard::Column(range));
+    }
+
+    /// d with a visual selection: copies the selected cells and empties them, one undo step
+    pub fn cut_selection(&mut self) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+        let (Some(range), Some(((top, left), (bottom, right)))) =
+            (self.selected_range(), self.selection_range())
+        else {
+            return Ok(());
+        };
+        self.clear_selection();
+
+        let sheet = self.workbook.get_current_sheet();
+        let mut cells = Vec::with_capacity((bottom - top + 1) * (right - left + 1));
+        for row in top..=bottom.min(sheet.data.len().saturating_sub(1)) {
+            for col in left..=right.min(sheet.data.width().saturating_sub(1)) {
+                let old_value = sheet.data[row][col].clone();
+                if !old_value.value.is_empty() {
+                    cells.push(CellChange {
+                        row,
+                        col,
+                        old_value,
+                        new_value: Cell::empty(),
+                    });
+                }
+            }
+        }
+
+        for change in &cells {
+            self.workbook.get_current_sheet_mut().data.set(
+                change.row,
+                change.col,
+                change.new_value.clone(),
+            );
+        }
+        if !cells.is_empty() {
+            self.workbook.set_modified(true);
+            self.workbook.recalculate_max_rows();
+            self.workbook.recalculate_max_cols();
+            self.undo_history
+                .push(ActionCommand::MultiCell(MultiCellAction {
+                    sheet_index: self.workbook.get_current_sheet_index(),
+                    sheet_name: self.workbook.get_current_sheet_name(),
+                    cells,
+                    action_type: ActionType::Cut,
+                }));
+        }
+
+        self.add_notification(format!("Cut {} range {}", range.size_label(), range.source));
+        self.clipboard = Some(Clipboard::Range(range));
+        Ok(())
+    }
+
+    /// p after yanking a range: pastes it with the cursor as top-left corner, on any sheet
+    pub fn paste_range(&mut self, range: &CellRange) -> Result<()> {
+        self.paste_range_at(range, self.selected_cell)
+    }
+
+    /// Pastes `range` with its top-left corner at `(top, left)`, as one undo step
+    pub fn paste_range_at(&mut self, range: &CellRange, (top, left): (usize, usize)) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+
+        let rows = range.cells.len();
+        let cols = range.cells.first().map_or(0, Vec::len);
+        if rows == 0 || cols == 0 {
+            return Ok(());
+        }
+        if top + rows - 1 > MAX_ROWS || left + cols - 1 > MAX_COLS {
+            bail!(
+                "{rows}x{cols} cells at {} go beyond XFD{MAX_ROWS}, sheets have {MAX_ROWS} rows and {MAX_COLS} columns",
+                cell_reference((top, left))
+            );
+        }
+        self.workbook
+            .ensure_cell_exists(top + rows - 1, left + cols - 1);
+
+        let sheet = self.workbook.get_current_sheet_mut();
+        let mut cells = Vec::with_capacity(rows * cols);
+        for (row_offset, source_row) in range.cells.iter().enumerate() {
+            for (col_offset, (cell, style)) in source_row.iter().enumerate() {
+                let (row, col) = (top + row_offset, left + col_offset);
+                let mut new_value = cell.clone();
+                new_value.style = sheet.styles.intern(style.clone());
+
+                let old_value = sheet.data.set(row, col, new_value.clone());
+                cells.push(CellChange {
+                    row,
+                    col,
+                    old_value,
+                    new_value,
+                });
+            }
+        }
+
+        self.workbook.set_modified(true);
+        self.workbook.recalculate_max_rows();
+        self.workbook.recalculate_max_cols();
+        self.ensure_column_widths();
+
+        let target = format!(
+            "{}!{}",
+            self.workbook.get_current_sheet_name(),
+            cell_reference((top, left))
+        );
+        let action = ActionCommand::MultiCell(MultiCellAction {
+            sheet_index: self.workbook.get_current_sheet_index(),
+            sheet_name: self.workbook.get_current_sheet_name(),
+            cells,
+            action_type: ActionType::Paste,
+        });
+        self.flash_action(&action);
+        self.undo_history.push(action);
+
+        self.add_notification(format!(
+            "Pasted {} range from {} at {target}",
+            range.size_label(),
+            range.source
+        ));
+        Ok(())
+    }
+
+    // The cells of the visual selection, empty where the sheet has no data yet
+    fn selected_range(&self) -> Option<CellRange> {
+        let ((top, left), (bottom, right)) = self.selection_range()?;
+        let sheet = self.workbook.get_current_sheet();
+
+        let cells = (top..=bottom)
+            .map(|row| {
+                (left..=right)
+                    .map(|col| cell_with_style(sheet, row, col))
+                    .collect()
+            })
+            .collect();
+
+        Some(CellRange {
+            source: format!(
+                "{}!{}",
+                sheet.name,
+                self.selection_label().unwrap_or_default()
+            ),
+            cells,
+        })
+    }
+}
@@ -0,0 +1,306 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::actions::{ActionCommand, ActionType, CellChange, MultiCellAction};
+use crate::app::query::find_column;
+use crate::app::AppState;
+use crate::excel::{Cell, CellStyle, CellType, DataTypeInfo};
+use crate::utils::{index_to_col_name, serial_to_datetime, NumberFormat};
+
+/// How the cells of a column are shown, set with :format. Takes precedence over
+/// :set numfmt and the number formats of the file
+pub enum ColumnFormat {
+    Number(NumberFormat),
+    Date(String), // A chrono format such as %d/%m/%Y
+}
+
+// `2dp` as 0.00, anything else is taken as an Excel number format
+fn number_code(spec: &str, suffix: &str) -> String {
+    match spec
+        .strip_suffix("dp")
+        .and_then(|places| places.parse::<usize>().ok())
+    {
+        Some(0) => format!("0{suffix}"),
+        Some(places) => format!("0.{}{suffix}", "0".repeat(places)),
+        None => spec.to_string(),
+    }
+}
+
+// A date written in the text of a cell, as ISO dates and times are stored
+fn parse_text_date(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+}
+
+// The Excel date format of a chrono format, `None` if it uses something Excel can't show
+fn excel_date_code(format: &str) -> Option<String> {
+    let mut code = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            match c {
+                '"' => code.push_str("\\\""),
+                c if c.is_alphabetic() => code.push_str(&format!("\"{c}\"")),
+                c => code.push(c),
+            }
+            continue;
+        }
+        let mut specifier = chars.next()?;
+        let unpadded = specifier == '-';
+        if unpadded {
+            specifier = chars.next()?;
+        }
+        code.push_str(match (specifier, unpadded) {
+            ('Y', _) => "yyyy",
+            ('y', _) => "yy",
+            ('m', false) => "mm",
+            ('m', true) => "m",
+            ('d', false) => "dd",
+            ('d' | 'e', _) => "d",
+            ('b' | 'h', _) => "mmm",
+            ('B', _) => "mmmm",
+            ('a', _) => "ddd",
+            ('A', _) => "dddd",
+            ('H' | 'I', false) => "hh",
+            ('H' | 'I', true) => "h",
+            ('M', _) => "mm",
+            ('S', _) => "ss",
+            ('p', _) => "AM/PM",
+            ('F', _) => "yyyy-mm-dd",
+            ('T', _) => "hh:mm:ss",
+            ('R', _) => "hh:mm",
+            ('%', _) => "%",
+            _ => return None,
+        });
+    }
+    Some(code)
+}
+
+impl ColumnFormat {
+    /// The format after the column in :format, e.g. `number:2dp`, `number:#,##0.00`,
+    /// `percent:1dp` or `date:%d/%m/%Y`
+    fn parse(spec: &str) -> Result<Self> {
+        let (kind, spec) = spec.split_once(':').unwrap_or((spec, ""));
+        let spec = spec.trim();
+        match kind.to_lowercase().as_str() {
+            "number" | "num" if !spec.is_empty() => Ok(Self::Number(
+                NumberFormat::parse(&number_code(spec, "")).map_err(anyhow::Error::msg)?,
+            )),
+            "percent" | "pct" => {
+                let spec = if spec.is_empty() { "0dp" } else { spec };
+                Ok(Self::Number(
+                    NumberFormat::parse(&number_code(spec, "%")).map_err(anyhow::Error::msg)?,
+                ))
+            }
+            "date" => {
+                let format = if spec.is_empty() { "%Y-%m-%d" } else { spec };
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    bail!("{format} isn't a valid date format");
+                }
+                Ok(Self::Date(format.to_string()))
+            }
+            _ => bail!("Usage: :format col number:2dp|percent:1dp|date:%Y-%m-%d, or off"),
+        }
+    }
+
+    /// The text of a cell in this format, `None` if it doesn't apply to the value
+    #[must_use]
+    pub fn format(&self, cell: &Cell) -> Option<String> {
+        if cell.is_formula {
+            return None;
+        }
+        match self {
+            Self::Number(format) if cell.cell_type == CellType::Number => {
+                Some(format.format(cell.value.trim().parse::<f64>().ok()?))
+            }
+            Self::Number(_) => None,
+            Self::Date(format) => {
+                let datetime = match (&cell.cell_type, &cell.original_type) {
+                    (CellType::Date, Some(DataTypeInfo::DateTime(serial))) => {
+                        serial_to_datetime(*serial)?
+                    }
+                    (CellType::Number, _) => {
+                        serial_to_datetime(cell.value.trim().parse::<f64>().ok()?)?
+                    }
+                    _ => parse_text_date(&cell.value)?,
+                };
+                // Time zones such as %Z can't be shown for a date without one
+                let mut text = String::new();
+                write!(text, "{}", datetime.format(format)).ok()?;
+                Some(text)
+            }
+        }
+    }
+
+    // The Excel number format written to the file by :format!
+    fn excel_code(&self) -> Option<String> {
+        match self {
+            Self::Number(format) => Some(format.code().to_string()),
+            Self::Date(format) => excel_date_code(format),
+        }
+    }
+
+    /// e.g. `number 0.00` or `date %d/%m/%Y`, for the notifications
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Number(format) => format!("number {}", format.code()),
+            Self::Date(format) => format!("date {format}"),
+        }
+    }
+}
+
+impl AppState<'_> {
+    /// The :format of column `col` of the current sheet, if any
+    #[must_use]
+    pub fn column_format(&self, col: usize) -> Option<&ColumnFormat> {
+        self.column_formats
+            .get(&self.workbook.get_current_sheet_name())?
+            .get(&col)
+    }
+
+    /// :format col spec: shows the cells of a column of the current sheet as numbers with
+    /// set decimals, percentages or dates. `:format! col spec` also gives the cells that
+    /// number format, so saving writes it to the file. `:format col off` drops the format,
+    /// `:format off` all of the sheet's and a bare :format lists them
+    pub fn set_column_format(&mut self, input: &str, write_to_cells: bool) {
+        let input = input.trim();
+        let sheet_name = self.workbook.get_current_sheet_name();
+        if input.is_empty() {
+            self.list_column_formats();
+            return;
+        }
+        if input.eq_ignore_ascii_case("off") {
+            self.column_formats.remove(&sheet_name);
+            self.add_notification("Column formats of this sheet removed".to_string());
+            return;
+        }
+
+        let (column, spec) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let spec = spec.trim();
+        let sheet = self.workbook.get_current_sheet();
+        let headers: Vec<String> = (1..=sheet.max_cols)
+            .map(|col| sheet.cell(1, col).value.trim().to_string())
+            .collect();
+        let col = match find_column(&headers, column) {
+            Ok(col) => col,
+            Err(e) => {
+                self.add_notification(format!("Format: {e}"));
+                return;
+            }
+        };
+        let name = index_to_col_name(col);
+
+        if spec.eq_ignore_ascii_case("off") {
+            let removed = self
+                .column_formats
+                .get_mut(&sheet_name)
+                .and_then(|formats| formats.remove(&col));
+            self.add_notification(match removed {
+                Some(_) => format!("Column {name} shown as stored"),
+                None => format!("Column {name} has no format"),
+            });
+            return;
+        }
+        let format = match ColumnFormat::parse(spec) {
+            Ok(format) => format,
+            Err(e) => {
+                self.add_notification(format!("Format: {e}"));
+                return;
+            }
+        };
+
+        let description = format.describe();
+        let code = format.excel_code();
+        self.column_formats
+            .entry(sheet_name)
+            .or_default()
+            .insert(col, format);
+        self.add_notification(format!("Column {name} shown as {description}"));
+        if write_to_cells {
+            match code {
+                Some(code) => self.write_column_format(col, &code),
+                None => self.add_notification(format!(
+                    "Format: {description} can't be written as an Excel number format"
+                )),
+            }
+        }
+    }
+
+    // Gives the data cells of a column the number format `code` as one undo step
+    fn write_column_format(&mut self, col: usize, code: &str) {
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Format: {e}"));
+            return;
+        }
+
+        let mut cells = Vec::new();
+        for row in self.first_data_row()..=self.workbook.get_current_sheet().max_rows {
+            let sheet = self.workbook.get_current_sheet_mut();
+            let old_value = sheet.cell(row, col).clone();
+            if old_value.value.is_empty() {
+                continue;
+            }
+            let style = CellStyle {
+                number_format: None,
+                number_format_code: Some(code.to_string()),
+                ..sheet.styles.get(old_value.style).clone()
+            };
+            let style = sheet.styles.intern(style);
+            if style == old_value.style {
+                continue;
+            }
+            if let Err(e) = self.workbook.set_cell_style(row, col, style) {
+                self.add_notification(format!("Format: {e}"));
+                break;
+            }
+            cells.push(CellChange {
+                row,
+                col,
+                old_value,
+                new_value: self.workbook.get_current_sheet().cell(row, col).clone(),
+            });
+        }
+        if cells.is_empty() {
+            return;
+        }
+
+        let count = cells.len();
+        self.undo_history
+            .push(ActionCommand::MultiCell(MultiCellAction {
+                sheet_index: self.workbook.get_current_sheet_index(),
+                sheet_name: self.workbook.get_current_sheet_name(),
+                cells,
+                action_type: ActionType::Edit,
+            }));
+        self.add_notification(format!("{count} cell(s) saved with the format {code}"));
+    }
+
+    fn list_column_formats(&mut self) {
+        let Some(formats) = self
+            .column_formats
+            .get(&self.workbook.get_current_sheet_name())
+            .filter(|formats| !formats.is_empty())
+        else {
+            self.add_notification(
+                "No column formats, e.g. :format B number:2dp adds one".to_string(),
+            );
+            return;
+        };
+        let list: Vec<String> = formats
+            .iter()
+            .map(|(&col, format)| format!("{} {}", index_to_col_name(col), format.describe()))
+            .collect();
+        self.add_notification(format!("Formats: {}", list.join("; ")));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::app::AppState;
+use crate::excel::{Cell, CellType, Sheet};
+use crate::utils::{cell_reference, index_to_col_name};
+
+/// Dominant type of a column's data cells and how many of them have it, for :set typerow
+pub struct ColumnTypeSummary {
+    pub dominant: &'static str,
+    pub conforming: usize,
+    pub filled: usize,
+}
+
+impl ColumnTypeSummary {
+    #[must_use]
+    pub fn percent(&self) -> usize {
+        self.conforming * 100 / self.filled.max(1)
+    }
+
+    /// No type covers more than half of the cells
+    #[must_use]
+    pub fn is_mixed(&self) -> bool {
+        self.conforming * 2 <= self.filled && self.filled > 1
+    }
+
+    /// e.g. `Int 98%`, or `Mixed` when no type has the upper hand
+    #[must_use]
+    pub fn label(&self) -> String {
+        if self.is_mixed() {
+            return "Mixed".to_string();
+        }
+        format!("{} {}%", self.dominant, self.percent())
+    }
+}
+
+// Formula results aren't known, so formulas and empty cells have no type to check
+fn type_name(cell: &Cell) -> Option<&'static str> {
+    if cell.is_formula {
+        return None;
+    }
+    match cell.cell_type {
+        CellType::Text => Some("Text"),
+        CellType::Number if cell.value.trim().parse::<i64>().is_ok() => Some("Int"),
+        CellType::Number => Some("Float"),
+        CellType::Date => Some("Date"),
+        CellType::Boolean => Some("Boolean"),
+        CellType::Empty => None,
+    }
+}
+
+// Whole numbers are fine in a Float column
+fn conforms(name: &str, dominant: &str) -> bool {
+    name == dominant || (name == "Int" && dominant == "Float")
+}
+
+fn summarize(sheet: &Sheet, col: usize, first_row: usize) -> Option<ColumnTypeSummary> {
+    let mut counts: Vec<(&'static str, usize)> = Vec::new();
+    for row in first_row..=sheet.max_rows {
+        let Some(name) = type_name(sheet.cell(row, col)) else {
+            continue;
+        };
+        match counts.iter_mut().find(|(counted, _)| *counted == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+
+    // Ints and floats count together, as Float once there is one
+    let count_of = |name| {
+        counts
+            .iter()
+            .find(|(counted, _)| *counted == name)
+            .map(|&(_, count)| count)
+    };
+    if let Some(floats) = count_of("Float") {
+        let ints = count_of("Int").unwrap_or_default();
+        counts.retain(|(name, _)| !matches!(*name, "Int" | "Float"));
+        counts.push(("Float", floats + ints));
+    }
+
+    let filled = counts.iter().map(|(_, count)| count).sum();
+    let (dominant, conforming) = counts.into_iter().max_by_key(|&(_, count)| count)?;
+    Some(ColumnTypeSummary {
+        dominant,
+        conforming,
+        filled,
+    })
+}
+
+// Cells named per column by :typecheck, the rest are only counted
+const LISTED_CELLS: usize = 5;
+
+impl AppState<'_> {
+    /// First row counted as data, below the header rows (the frozen rows, at least 1)
+    #[must_use]
+    pub fn first_data_row(&self) -> usize {
+        self.frozen_rows.max(1) + 1
+    }
+
+    #[must_use]
+    pub fn column_type_summary(&self, col: usize) -> Option<ColumnTypeSummary> {
+        summarize(
+            self.workbook.get_current_sheet(),
+            col,
+            self.first_data_row(),
+        )
+    }
+
+    /// ]t and [t: moves to the next or previous cell of the current column whose type
+    /// differs from the column's dominant type, wrapping around
+    pub fn jump_to_nonconforming(&mut self, forward: bool) {
+        let (current_row, col) = self.selected_cell;
+        let first_row = self.first_data_row();
+        let sheet = self.workbook.get_current_sheet();
+
+        let Some(summary) = summarize(sheet, col, first_row) else {
+            self.add_notification(format!(
+                "Column {} has no data to check",
+                index_to_col_name(col)
+            ));
+            return;
+        };
+
+        let nonconforming: Vec<usize> = (first_row..=sheet.max_rows)
+            .filter(|&row| {
+                type_name(sheet.cell(row, col))
+                    .is_some_and(|name| !conforms(name, summary.dominant))
+            })
+            .collect();
+
+        let (Some(&first), Some(&last)) = (nonconforming.first(), nonconforming.last()) else {
+            self.add_notification(format!(
+                "Every cell of column {} is {}",
+                index_to_col_name(col),
+                summary.dominant
+            ));
+            return;
+        };
+
+        let next = if forward {
+            nonconforming.iter().find(|&&row| row > current_row)
+        } else {
+            nonconforming.iter().rev().find(|&&row| row < current_row)
+        };
+        let row = match next {
+            Some(&row) => row,
+            None if forward => first,
+            None => last,
+        };
+        let found = type_name(sheet.cell(row, col)).unwrap_or_default();
+
+        self.record_jump();
+        self.selected_cell = (row, col);
+        self.handle_scrolling();
+        self.add_notification(format!(
+            "{}: {found} in a {} column ({} of {} cells don't conform)",
+            cell_reference((row, col)),
+            summary.dominant,
+            nonconforming.len(),
+            summary.filled
+        ));
+    }
+
+    /// :typecheck: lists the cells of each column of the sheet whose type differs from the
+    /// column's dominant type, a few per column
+    pub fn check_column_types(&mut self) {
+        let first_row = self.first_data_row();
+        let sheet = self.workbook.get_current_sheet();
+
+        let mut columns = Vec::new();
+        let mut total = 0;
+        for col in 1..=sheet.max_cols {
+            let Some(summary) = summarize(sheet, col, first_row) else {
+                continue;
+            };
+            let nonconforming: Vec<(usize, &str)> = (first_row..=sheet.max_rows)
+                .filter_map(|row| {
+                    type_name(sheet.cell(row, col))
+                        .filter(|name| !conforms(name, summary.dominant))
+                        .map(|name| (row, name))
+                })
+                .collect();
+            if nonconforming.is_empty() {
+                continue;
+            }
+            total += nonconforming.len();
+
+            let mut cells: Vec<String> = nonconforming
+                .iter()
+                .take(LISTED_CELLS)
+                .map(|&(row, name)| format!("{} {name}", cell_reference((row, col))))
+                .collect();
+            if nonconforming.len() > LISTED_CELLS {
+                cells.push(format!("{} more", nonconforming.len() - LISTED_CELLS));
+            }
+            let kind = if summary.is_mixed() {
+                format!("Mixed, mostly {}", summary.dominant)
+            } else {
+                summary.dominant.to_string()
+            };
+            columns.push(format!(
+                "{} ({kind}): {}",
+                index_to_col_name(col),
+                cells.join(", ")
+            ));
+        }
+
+        if columns.is_empty() {
+            self.add_notification("Every column has cells of a single type".to_string());
+        } else {
+            self.add_notification(format!(
+                "{total} cell(s) don't match their column's type. {}. ]t/[t jump to them",
+                columns.join("; ")
+            ));
+        }
+    }
+}
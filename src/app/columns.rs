@@ -0,0 +1,184 @@
+use crate::actions::{ActionCommand, ReorderColumnsAction};
+use crate::app::{AppState, InputMode};
+
+/// A column as listed on the column manager
+pub struct ManagedColumn {
+    pub col: usize, // Position in the sheet when the manager was opened
+    pub header: String,
+    pub width: usize, // Width to use while visible
+    pub hidden: bool,
+}
+
+/// State of the :columns screen, changes are applied together with Enter
+pub struct ColumnManager {
+    pub columns: Vec<ManagedColumn>,
+    pub selected: usize,
+    pub pinned: usize, // Leading columns frozen on the left
+}
+
+impl ColumnManager {
+    fn is_reordered(&self) -> bool {
+        self.columns
+            .iter()
+            .enumerate()
+            .any(|(idx, column)| column.col != idx + 1)
+    }
+}
+
+impl AppState<'_> {
+    /// :columns: lists every column of the sheet with its header, width, visibility and pin
+    pub fn open_column_manager(&mut self) {
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Cannot manage columns: {e}"));
+            return;
+        }
+
+        let sheet = self.workbook.get_current_sheet();
+        if sheet.max_cols == 0 {
+            self.add_notification("No columns to manage".to_string());
+            return;
+        }
+
+        let columns = (1..=sheet.max_cols)
+            .map(|col| {
+                let width = self.get_column_width(col);
+                ManagedColumn {
+                    col,
+                    header: sheet.cell(1, col).value.clone(),
+                    width: if width == 0 { 15 } else { width },
+                    hidden: width == 0,
+                }
+            })
+            .collect();
+
+        self.column_manager = Some(ColumnManager {
+            columns,
+            selected: self.selected_cell.1.clamp(1, sheet.max_cols) - 1,
+            pinned: self.frozen_cols.min(sheet.max_cols),
+        });
+        self.input_mode = InputMode::ColumnManager;
+    }
+
+    pub fn move_column_manager_selection(&mut self, delta: isize) {
+        if let Some(manager) = &mut self.column_manager {
+            let last = manager.columns.len().saturating_sub(1);
+            manager.selected = manager.selected.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Moves the selected column up or down the list, the pinned count stays the same
+    pub fn move_managed_column(&mut self, delta: isize) {
+        let Some(manager) = &mut self.column_manager else {
+            return;
+        };
+
+        let from = manager.selected;
+        let to = from
+            .saturating_add_signed(delta)
+            .min(manager.columns.len().saturating_sub(1));
+        if from != to {
+            manager.columns.swap(from, to);
+            manager.selected = to;
+        }
+    }
+
+    pub fn toggle_managed_column_hidden(&mut self) {
+        if let Some(manager) = &mut self.column_manager {
+            if let Some(column) = manager.columns.get_mut(manager.selected) {
+                column.hidden = !column.hidden;
+            }
+        }
+    }
+
+    /// Freezes the columns up to the selected one, or one fewer if that's already the case
+    pub fn toggle_managed_column_pin(&mut self) {
+        if let Some(manager) = &mut self.column_manager {
+            let through_selected = manager.selected + 1;
+            manager.pinned = if manager.pinned == through_selected {
+                manager.selected
+            } else {
+                through_selected
+            };
+        }
+    }
+
+    pub fn adjust_managed_column_width(&mut self, delta: isize) {
+        if let Some(manager) = &mut self.column_manager {
+            if let Some(column) = manager.columns.get_mut(manager.selected) {
+                column.width = column.width.saturating_add_signed(delta).clamp(5, 50);
+            }
+        }
+    }
+
+    /// Applies the new order, widths, hidden columns and pin in one go, the reorder
+    /// is undone as a single step
+    pub fn apply_column_manager(&mut self) {
+        let Some(manager) = self.column_manager.take() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+
+        if manager.columns.iter().all(|column| column.hidden) {
+            self.add_notification("At least one column must stay visible".to_string());
+            self.column_manager = Some(manager);
+            return;
+        }
+        self.input_mode = InputMode::Normal;
+
+        let order: Vec<usize> = manager.columns.iter().map(|column| column.col).collect();
+        let moved = manager.is_reordered();
+        if moved {
+            if let Err(e) = self.workbook.reorder_columns(&order) {
+                self.add_notification(format!("Failed to reorder columns: {e}"));
+                return;
+            }
+            self.undo_history
+                .push(ActionCommand::ReorderColumns(ReorderColumnsAction {
+                    sheet_index: self.workbook.get_current_sheet_index(),
+                    sheet_name: self.workbook.get_current_sheet_name(),
+                    order: order.clone(),
+                }));
+
+            // Keep the cursor on the column it was on
+            if let Some(position) = order.iter().position(|&col| col == self.selected_cell.1) {
+                self.selected_cell.1 = position + 1;
+            }
+        }
+
+        self.ensure_column_widths();
+        for (idx, column) in manager.columns.iter().enumerate() {
+            self.column_widths[idx + 1] = if column.hidden { 0 } else { column.width };
+        }
+        self.store_current_column_widths();
+
+        // The cursor moves off a column that was just hidden
+        let current = self.selected_cell.1;
+        if self.is_column_hidden(current) {
+            if let Some(col) = (1..=manager.columns.len())
+                .filter(|&col| !self.is_column_hidden(col))
+                .min_by_key(|col| col.abs_diff(current))
+            {
+                self.selected_cell.1 = col;
+            }
+        }
+
+        self.frozen_cols = manager.pinned;
+        self.handle_scrolling();
+
+        let hidden = manager
+            .columns
+            .iter()
+            .filter(|column| column.hidden)
+            .count();
+        self.add_notification(format!(
+            "Columns updated: {}, {hidden} hidden, {} pinned",
+            if moved { "reordered" } else { "same order" },
+            manager.pinned
+        ));
+    }
+
+    pub fn cancel_column_manager(&mut self) {
+        self.column_manager = None;
+        self.input_mode = InputMode::Normal;
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use crate::app::query::find_column;
+use crate::app::AppState;
+use crate::utils::{index_to_col_name, split_arguments};
+
+impl AppState<'_> {
+    /// :combine A B [sep]: fills a new column after the last one with the values of the
+    /// given columns joined by the separator, a space by default. Empty values are left
+    /// out rather than leaving the separator dangling
+    pub fn combine_columns(&mut self, input: &str) {
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Combine: {e}"));
+            return;
+        }
+
+        let sheet = self.workbook.get_current_sheet();
+        let headers: Vec<String> = (1..=sheet.max_cols)
+            .map(|col| sheet.cell(1, col).value.trim().to_string())
+            .collect();
+        let mut arguments = split_arguments(input);
+        // The last argument is the separator unless it names a column
+        let separator = match arguments.last() {
+            Some(last) if arguments.len() > 2 && find_column(&headers, last).is_err() => {
+                arguments.pop().unwrap_or_default()
+            }
+            _ => " ".to_string(),
+        };
+        if arguments.len() < 2 {
+            self.add_notification("Usage: :combine col col... [separator]".to_string());
+            return;
+        }
+        let columns = match arguments
+            .iter()
+            .map(|name| find_column(&headers, name))
+            .collect::<anyhow::Result<Vec<usize>>>()
+        {
+            Ok(columns) => columns,
+            Err(e) => {
+                self.add_notification(format!("Combine: {e}"));
+                return;
+            }
+        };
+
+        let target = sheet.max_cols + 1;
+        let mut changes = BTreeMap::new();
+        for row in 1..=sheet.max_rows {
+            let values: Vec<&str> = columns
+                .iter()
+                .map(|&col| sheet.cell(row, col).value.trim())
+                .filter(|value| !value.is_empty())
+                .collect();
+            if !values.is_empty() {
+                changes.insert((row, target), values.join(&separator));
+            }
+        }
+        if changes.is_empty() {
+            self.add_notification("Combine: the columns are empty".to_string());
+            return;
+        }
+
+        if self.apply_script_changes("Combine", changes) > 0 {
+            self.selected_cell.1 = target;
+            self.handle_scrolling();
+            let names: Vec<String> = columns.iter().map(|&col| index_to_col_name(col)).collect();
+            self.add_notification(format!(
+                "Column {} joins {}",
+                index_to_col_name(target),
+                names.join(", ")
+            ));
+        }
+    }
+}
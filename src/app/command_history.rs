@@ -0,0 +1,160 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::app::AppState;
+
+// Oldest commands are dropped beyond this many entries
+const MAX_COMMAND_HISTORY: usize = 200;
+
+/// Commands run with :, recalled with Up and Down in command mode and kept between
+/// sessions in a history file, like Vim's viminfo
+#[derive(Default)]
+pub struct CommandHistory {
+    entries: Vec<String>, // Oldest first
+    added: Vec<String>,   // Commands of this session, merged into the file when saving
+    index: usize,         // entries.len() when not walking the history
+    draft: String,        // What was typed before Up, it also filters the recalled commands
+}
+
+/// Directory of the files kept between sessions, e.g. ~/.local/state/excel-cli. None when
+/// there is no state directory to put it in, then nothing is kept
+#[must_use]
+pub fn state_dir() -> Option<PathBuf> {
+    let state_dir = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+
+    Some(state_dir.join("excel-cli"))
+}
+
+/// Location of the command history, e.g. ~/.local/state/excel-cli/command_history
+#[must_use]
+pub fn command_history_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("command_history"))
+}
+
+// One command per line, oldest first. A missing file is an empty history
+fn read_history(path: &Path) -> io::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+// Moves `command` to the newest end, dropping the oldest commands beyond the limit
+fn push_entry(entries: &mut Vec<String>, command: &str) {
+    entries.retain(|entry| entry != command);
+    entries.push(command.to_string());
+    if entries.len() > MAX_COMMAND_HISTORY {
+        entries.drain(..entries.len() - MAX_COMMAND_HISTORY);
+    }
+}
+
+impl CommandHistory {
+    /// Reads the history file, an unreadable file starts an empty history
+    #[must_use]
+    pub fn load(path: &Path) -> Self {
+        let entries = read_history(path).unwrap_or_default();
+        Self {
+            index: entries.len(),
+            entries,
+            ..Self::default()
+        }
+    }
+
+    pub fn push(&mut self, command: &str) {
+        // Multi-line input can't be stored one command per line
+        if command.trim().is_empty() || command.contains('\n') {
+            return;
+        }
+        push_entry(&mut self.entries, command);
+        push_entry(&mut self.added, command);
+        self.reset();
+    }
+
+    /// Stops walking the history, the next Up starts from the newest command again
+    pub fn reset(&mut self) {
+        self.index = self.entries.len();
+        self.draft.clear();
+    }
+
+    /// The previous command starting with what was typed before the first Up
+    pub fn older(&mut self, input: &str) -> Option<&str> {
+        if self.index == self.entries.len() {
+            self.draft = input.to_string();
+        }
+
+        let index = self.entries[..self.index]
+            .iter()
+            .rposition(|entry| entry.starts_with(&self.draft))?;
+        self.index = index;
+        Some(&self.entries[index])
+    }
+
+    /// The next command starting with the typed text, or the typed text itself past the newest
+    pub fn newer(&mut self) -> Option<&str> {
+        if self.index == self.entries.len() {
+            return None;
+        }
+
+        match self.entries[self.index + 1..]
+            .iter()
+            .position(|entry| entry.starts_with(&self.draft))
+        {
+            Some(offset) => {
+                self.index += 1 + offset;
+                Some(&self.entries[self.index])
+            }
+            None => {
+                self.index = self.entries.len();
+                Some(&self.draft)
+            }
+        }
+    }
+
+    /// Adds this session's commands to the history file, keeping the ones other
+    /// sessions saved in the meantime
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if self.added.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = read_history(path)?;
+        for command in &self.added {
+            push_entry(&mut entries, command);
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = entries.join("\n");
+        content.push('\n');
+        fs::write(path, content)
+    }
+}
+
+impl AppState<'_> {
+    /// Up in command mode: recalls the previous command starting with the typed text
+    pub fn recall_previous_command(&mut self) {
+        let input = self.input_buffer.clone();
+        if let Some(command) = self.command_history.older(&input) {
+            self.input_buffer = command.to_string();
+        }
+    }
+
+    /// Down in command mode: goes forward again, back to the typed text past the newest command
+    pub fn recall_next_command(&mut self) {
+        if let Some(command) = self.command_history.newer() {
+            self.input_buffer = command.to_string();
+        }
+    }
+}
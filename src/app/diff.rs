@@ -0,0 +1,452 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::app::{AppState, InputMode};
+use crate::excel::{open_workbook, Sheet, Workbook};
+use crate::utils::{cell_reference, split_arguments};
+
+/// How a cell differs from the same cell in the workbook it is compared with
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CellDiff {
+    Added,   // Empty before, filled now
+    Removed, // Filled before, empty now
+    Changed,
+}
+
+/// The workbook given to :diff or `excel-cli diff`, or the sheets given to :sheetdiff.
+/// Sheets are matched by name and cells by position, and each cell is compared as it is
+/// drawn, so edits show up right away
+pub struct WorkbookDiff {
+    pub file_name: String, // What the workbook is compared with, shown in the status bar
+    other: Vec<Sheet>,
+    pairs: Vec<(String, usize)>, // Sheets compared with a given one of `other` whatever its name
+    by_name: bool,               // Sheets without a pair are compared with the one of their name
+}
+
+impl WorkbookDiff {
+    pub fn open(path: &Path, workbook: &Workbook) -> Result<Self> {
+        let other = open_workbook(path, false)?;
+        let file_name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        // CSV files are a single sheet named after the file, a.csv and b.csv still match
+        let names = workbook.get_sheet_names();
+        let pairs = match names.as_slice() {
+            [name] if other.get_sheet_names().len() == 1 => vec![(name.clone(), 0)],
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            file_name,
+            other: other.into_sheets(),
+            pairs,
+            by_name: true,
+        })
+    }
+
+    /// Compares two sheets of the same workbook with each other, both ways. Each is
+    /// compared with the other one as it is now
+    #[must_use]
+    pub fn sheets(first: &Sheet, second: &Sheet) -> Self {
+        Self {
+            file_name: format!("{} and {}", first.name, second.name),
+            other: vec![second.clone(), first.clone()],
+            pairs: vec![(first.name.clone(), 0), (second.name.clone(), 1)],
+            by_name: false,
+        }
+    }
+
+    /// The sheet of the other workbook with the same name, ignoring case
+    #[must_use]
+    pub fn other_sheet(&self, name: &str) -> Option<&Sheet> {
+        if let Some((_, index)) = self.pairs.iter().find(|(own, _)| own == name) {
+            return self.other.get(*index);
+        }
+        if !self.by_name {
+            return None;
+        }
+        self.other
+            .iter()
+            .find(|sheet| sheet.name.eq_ignore_ascii_case(name))
+    }
+
+    /// What the value of the other side is called in the status bar
+    #[must_use]
+    pub fn other_label(&self, sheet: &Sheet) -> &str {
+        match self.other_sheet(&sheet.name) {
+            Some(other) if !self.by_name => &other.name,
+            _ => "was",
+        }
+    }
+
+    #[must_use]
+    pub fn cell_diff(&self, sheet: &Sheet, row: usize, col: usize) -> Option<CellDiff> {
+        if !sheet.is_loaded {
+            return None;
+        }
+        // Only the two sheets of :sheetdiff are compared, the others have nothing to match
+        let other = self.other_sheet(&sheet.name);
+        if other.is_none() && !self.by_name {
+            return None;
+        }
+        let new = sheet.cell(row, col).value.as_str();
+        let old = other.map_or("", |other| other.cell(row, col).value.as_str());
+        match (old.is_empty(), new.is_empty()) {
+            _ if old == new => None,
+            (true, _) => Some(CellDiff::Added),
+            (_, true) => Some(CellDiff::Removed),
+            _ => Some(CellDiff::Changed),
+        }
+    }
+
+    /// The value a changed or removed cell had in the other workbook
+    #[must_use]
+    pub fn old_value(&self, sheet: &Sheet, row: usize, col: usize) -> Option<&str> {
+        match self.cell_diff(sheet, row, col)? {
+            CellDiff::Added => None,
+            CellDiff::Changed | CellDiff::Removed => self
+                .other_sheet(&sheet.name)
+                .map(|other| other.cell(row, col).value.as_str()),
+        }
+    }
+
+    /// Every differing cell of a sheet in reading order
+    #[must_use]
+    pub fn differences(&self, sheet: &Sheet) -> Vec<((usize, usize), CellDiff)> {
+        let mut cells: Vec<(usize, usize)> = sheet
+            .rows()
+            .flat_map(|row| row.cells())
+            .filter(|cell| !cell.is_empty())
+            .map(|cell| (cell.row, cell.col))
+            .collect();
+        if let Some(other) = self.other_sheet(&sheet.name) {
+            cells.extend(
+                other
+                    .rows()
+                    .flat_map(|row| row.cells())
+                    .filter(|cell| !cell.is_empty())
+                    .map(|cell| (cell.row, cell.col)),
+            );
+        }
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+            .into_iter()
+            .filter_map(|(row, col)| Some(((row, col), self.cell_diff(sheet, row, col)?)))
+            .collect()
+    }
+}
+
+impl AppState<'_> {
+    /// :diff file: compares the open workbook with another one, highlighting the cells that
+    /// differ until :diffoff
+    pub fn start_diff(&mut self, path: &str) {
+        let path = path.trim();
+        if path.is_empty() {
+            match &self.diff {
+                Some(diff) => {
+                    self.add_notification(format!("Comparing with {}", diff.file_name));
+                }
+                None => self.add_notification("Usage: :diff file".to_string()),
+            }
+            return;
+        }
+
+        match WorkbookDiff::open(Path::new(path), &self.workbook) {
+            Ok(diff) => {
+                self.diff = Some(diff);
+                self.diff_list = None;
+                self.show_diff_summary();
+            }
+            Err(e) => self.add_notification(format!("Cannot compare with {path}: {e}")),
+        }
+    }
+
+    /// :diffoff
+    pub fn stop_diff(&mut self) {
+        self.diff_list = None;
+        match self.diff.take() {
+            Some(diff) => {
+                self.add_notification(format!("Stopped comparing with {}", diff.file_name))
+            }
+            None => self.add_notification("No comparison to stop".to_string()),
+        }
+    }
+
+    // How many cells differ in each loaded sheet, and the sheets only one side has
+    fn show_diff_summary(&mut self) {
+        let Some(diff) = &self.diff else {
+            return;
+        };
+        let mut messages = Vec::new();
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        let mut sheets = 0;
+        for sheet in self.workbook.sheets().filter(|sheet| sheet.is_loaded) {
+            let differences = diff.differences(sheet);
+            if differences.is_empty() {
+                continue;
+            }
+            sheets += 1;
+            for (_, kind) in differences {
+                match kind {
+                    CellDiff::Added => added += 1,
+                    CellDiff::Removed => removed += 1,
+                    CellDiff::Changed => changed += 1,
+                }
+            }
+            if diff.other_sheet(&sheet.name).is_none() {
+                messages.push(format!("Sheet {} is new", sheet.name));
+            }
+        }
+        let names = self.workbook.get_sheet_names();
+        for sheet in &diff.other {
+            if diff.by_name
+                && diff.pairs.is_empty()
+                && !names.iter().any(|n| n.eq_ignore_ascii_case(&sheet.name))
+            {
+                messages.push(format!(
+                    "Sheet {} is only in {}",
+                    sheet.name, diff.file_name
+                ));
+            }
+        }
+
+        let summary = if sheets == 0 {
+            format!("No differences from {}", diff.file_name)
+        } else {
+            format!(
+                "Compared with {}: {changed} changed, {added} added, {removed} removed cell(s) in {sheets} sheet(s), ]c/[c to jump",
+                diff.file_name
+            )
+        };
+        self.add_notification(summary);
+        for message in messages {
+            self.add_notification(message);
+        }
+    }
+
+    /// ]c and [c: moves to the next or previous differing cell in reading order, wrapping around
+    pub fn jump_to_difference(&mut self, forward: bool) {
+        let Some(diff) = &self.diff else {
+            self.add_notification("Not comparing with another file, use :diff file".to_string());
+            return;
+        };
+        let current = self.selected_cell;
+        let differences = diff.differences(self.workbook.get_current_sheet());
+
+        let (Some(&first), Some(&last)) = (differences.first(), differences.last()) else {
+            self.add_notification(format!(
+                "No differences in this sheet from {}",
+                diff.file_name
+            ));
+            return;
+        };
+        let next = if forward {
+            differences.iter().find(|(pos, _)| *pos > current)
+        } else {
+            differences.iter().rev().find(|(pos, _)| *pos < current)
+        };
+        let (target, kind) = match next {
+            Some(&found) => found,
+            None if forward => first,
+            None => last,
+        };
+        let index = differences
+            .iter()
+            .position(|(pos, _)| *pos == target)
+            .unwrap_or(0);
+        let count = differences.len();
+
+        self.record_jump();
+        self.selected_cell = target;
+        self.handle_scrolling();
+        let kind = match kind {
+            CellDiff::Added => "added",
+            CellDiff::Removed => "removed",
+            CellDiff::Changed => "changed",
+        };
+        self.add_notification(format!(
+            "{} {kind} ({} of {count})",
+            cell_reference(target),
+            index + 1
+        ));
+    }
+}
+
+/// One differing cell in the :sheetdiff panel
+pub struct DiffListEntry {
+    pub cell: (usize, usize),
+    pub kind: CellDiff,
+    pub first: String, // Value in the first sheet
+    pub second: String,
+}
+
+/// State of the panel :sheetdiff opens, listing the cells that differ as they did then
+pub struct DiffList {
+    pub first: String, // Names of the sheets compared
+    pub second: String,
+    pub entries: Vec<DiffListEntry>,
+    pub size_note: Option<String>, // Set when the sheets are not the same shape
+    pub selected: usize,
+}
+
+impl AppState<'_> {
+    // Index of a sheet given by name, ignoring case, or by its 1-based number
+    fn find_sheet(&self, name: &str) -> Option<usize> {
+        let names = self.workbook.get_sheet_names();
+        names
+            .iter()
+            .position(|own| own.eq_ignore_ascii_case(name))
+            .or_else(|| {
+                let number = name.parse::<usize>().ok()?;
+                (1..=names.len()).contains(&number).then(|| number - 1)
+            })
+    }
+
+    /// :sheetdiff first second: highlights the cells that differ between two sheets of the
+    /// workbook and lists them in a panel. With one sheet it is compared with the current
+    /// one, without any the panel of the last comparison opens again
+    pub fn start_sheet_diff(&mut self, args: &str) {
+        let names = split_arguments(args);
+        let (first, second) = match names.as_slice() {
+            [] => {
+                if self.diff_list.is_some() {
+                    self.input_mode = InputMode::DiffList;
+                } else {
+                    self.add_notification("Usage: :sheetdiff sheet1 [sheet2]".to_string());
+                }
+                return;
+            }
+            [other] => (self.workbook.get_current_sheet_name(), other.clone()),
+            [first, second] => (first.clone(), second.clone()),
+            _ => {
+                self.add_notification(
+                    "Usage: :sheetdiff sheet1 [sheet2], quote names with spaces".to_string(),
+                );
+                return;
+            }
+        };
+
+        let mut indexes = Vec::with_capacity(2);
+        for name in [&first, &second] {
+            match self.find_sheet(name) {
+                Some(index) => indexes.push(index),
+                None => {
+                    self.add_notification(format!("Sheet '{name}' not found"));
+                    return;
+                }
+            }
+        }
+        if indexes[0] == indexes[1] {
+            self.add_notification("Give two different sheets to compare".to_string());
+            return;
+        }
+        let names = self.workbook.get_sheet_names();
+        for &index in &indexes {
+            if let Err(e) = self.workbook.ensure_sheet_loaded(index, &names[index]) {
+                self.add_notification(format!("Cannot compare {}: {e}", names[index]));
+                return;
+            }
+        }
+
+        let (Some(first), Some(second)) = (
+            self.workbook.get_sheet_by_index(indexes[0]),
+            self.workbook.get_sheet_by_index(indexes[1]),
+        ) else {
+            return;
+        };
+        let diff = WorkbookDiff::sheets(first, second);
+        let entries: Vec<DiffListEntry> = diff
+            .differences(first)
+            .into_iter()
+            .map(|((row, col), kind)| DiffListEntry {
+                cell: (row, col),
+                kind,
+                first: first.cell(row, col).value.clone(),
+                second: second.cell(row, col).value.clone(),
+            })
+            .collect();
+        let size_note = ((first.max_rows, first.max_cols) != (second.max_rows, second.max_cols))
+            .then(|| {
+                format!(
+                    "{} has {}x{} cells, {} has {}x{}",
+                    first.name,
+                    first.max_rows,
+                    first.max_cols,
+                    second.name,
+                    second.max_rows,
+                    second.max_cols
+                )
+            });
+        let list = DiffList {
+            first: first.name.clone(),
+            second: second.name.clone(),
+            entries,
+            size_note,
+            selected: 0,
+        };
+
+        if list.entries.is_empty() {
+            self.add_notification(format!(
+                "No differences between {} and {}",
+                list.first, list.second
+            ));
+        } else {
+            self.add_notification(format!(
+                "{} cell(s) differ between {} and {}, ]c/[c to jump",
+                list.entries.len(),
+                list.first,
+                list.second
+            ));
+            self.input_mode = InputMode::DiffList;
+        }
+        if let Some(note) = &list.size_note {
+            self.add_notification(note.clone());
+        }
+        self.diff = Some(diff);
+        self.diff_list = Some(list);
+    }
+
+    pub fn move_diff_list_selection(&mut self, delta: isize) {
+        if let Some(list) = &mut self.diff_list {
+            list.selected = list
+                .selected
+                .saturating_add_signed(delta)
+                .min(list.entries.len().saturating_sub(1));
+        }
+    }
+
+    pub fn close_diff_list(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Enter in the :sheetdiff panel: goes to the selected cell in the first sheet
+    pub fn confirm_diff_list(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let Some((sheet, cell)) = self.diff_list.as_ref().and_then(|list| {
+            let entry = list.entries.get(list.selected)?;
+            Some((list.first.clone(), entry.cell))
+        }) else {
+            return;
+        };
+
+        self.record_jump();
+        if self.workbook.get_current_sheet_name() != sheet {
+            match self.find_sheet(&sheet) {
+                Some(index) => {
+                    if let Err(e) = self.switch_sheet_by_index(index) {
+                        self.add_notification(format!("Cannot go to {sheet}: {e}"));
+                        return;
+                    }
+                }
+                None => {
+                    self.add_notification(format!("Sheet '{sheet}' no longer exists"));
+                    return;
+                }
+            }
+        }
+        self.selected_cell = cell;
+        self.handle_scrolling();
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::app::AppState;
+use crate::app::InputMode;
+use crate::utils::index_to_col_name;
+
+/// State of the distinct-values picker for a single column
+pub struct DistinctPicker {
+    pub col: usize,
+    pub values: Vec<(String, usize)>, // (value, occurrence count), most frequent first
+    pub query: String,
+    pub selected: usize,
+}
+
+impl DistinctPicker {
+    /// Values matching the current query, keeping the original order
+    pub fn filtered(&self) -> Vec<&(String, usize)> {
+        let query = self.query.to_lowercase();
+        self.values
+            .iter()
+            .filter(|(value, _)| query.is_empty() || value.to_lowercase().contains(&query))
+            .collect()
+    }
+}
+
+impl AppState<'_> {
+    pub fn open_distinct_picker(&mut self, col: usize) {
+        let sheet = self.workbook.get_current_sheet();
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for cell in sheet.column(col).filter(|cell| !cell.is_empty()) {
+            *counts.entry(cell.value()).or_insert(0) += 1;
+        }
+
+        if counts.is_empty() {
+            self.add_notification(format!("Column {} has no values", index_to_col_name(col)));
+            return;
+        }
+
+        let mut values: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(value, count)| (value.to_string(), count))
+            .collect();
+        values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        self.add_notification(format!(
+            "{} distinct values in column {}",
+            values.len(),
+            index_to_col_name(col)
+        ));
+
+        self.distinct_picker = Some(DistinctPicker {
+            col,
+            values,
+            query: String::new(),
+            selected: 0,
+        });
+        self.input_mode = InputMode::DistinctPicker;
+    }
+
+    pub fn move_distinct_selection(&mut self, delta: isize) {
+        if let Some(picker) = &mut self.distinct_picker {
+            let count = picker.filtered().len();
+            if count == 0 {
+                picker.selected = 0;
+                return;
+            }
+            let selected = (picker.selected as isize + delta).clamp(0, count as isize - 1);
+            picker.selected = selected as usize;
+        }
+    }
+
+    pub fn update_distinct_query(&mut self, c: Option<char>) {
+        if let Some(picker) = &mut self.distinct_picker {
+            match c {
+                Some(c) => picker.query.push(c),
+                None => {
+                    picker.query.pop();
+                }
+            }
+            picker.selected = 0;
+        }
+    }
+
+    pub fn close_distinct_picker(&mut self) {
+        self.distinct_picker = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Jumps to the first occurrence of the selected value and highlights every
+    /// occurrence in the column so that n/N walk through them
+    pub fn confirm_distinct_selection(&mut self) {
+        let Some(picker) = self.distinct_picker.take() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+
+        let Some(value) = picker
+            .filtered()
+            .get(picker.selected)
+            .map(|(value, _)| value.clone())
+        else {
+            return;
+        };
+
+        let col = picker.col;
+        let sheet = self.workbook.get_current_sheet();
+        let matches: Vec<(usize, usize)> = sheet
+            .column(col)
+            .filter(|cell| cell.value() == value)
+            .map(|cell| (cell.row, cell.col))
+            .collect();
+
+        if let Some(&first) = matches.first() {
+            self.record_jump();
+            self.selected_cell = first;
+            self.handle_scrolling();
+
+            self.search_query.clone_from(&value);
+            self.search_direction = true;
+            self.search_results = matches;
+            self.current_search_idx = Some(0);
+            self.highlight_enabled = true;
+
+            self.add_notification(format!(
+                "{} occurrences of \"{}\" in column {}",
+                self.search_results.len(),
+                value,
+                index_to_col_name(col)
+            ));
+        }
+    }
+}
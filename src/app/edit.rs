@@ -1,26 +1,49 @@
 use crate::actions::{ActionCommand, ActionType, CellAction};
-use crate::app::AppState;
 use crate::app::InputMode;
+use crate::app::{read_system_clipboard, AppState, Clipboard};
 use crate::app::{Transition, VimMode, VimState};
+use crate::excel::{CellStyle, StyleId};
+use crate::utils::parse_smart_number;
 use anyhow::Result;
 use ratatui::style::{Modifier, Style};
-use tui_textarea::Input;
+use tui_textarea::{Input, TextArea};
 
 impl AppState<'_> {
+    // With :set smartnumbers, `$1,234.56` is stored as 1234.56 and the cell style gets
+    // a matching number format. Anything else is kept as typed with the old style
+    fn smart_number_entry(&mut self, content: String, style: StyleId) -> (String, StyleId) {
+        if !self.smart_numbers {
+            return (content, style);
+        }
+        let Some(number) = parse_smart_number(&content) else {
+            return (content, style);
+        };
+
+        let styles = &mut self.workbook.get_current_sheet_mut().styles;
+        let number_style = CellStyle {
+            number_format: Some(number.number_format),
+            number_format_code: None,
+            ..styles.get(style).clone()
+        };
+        (number.value, styles.intern(number_style))
+    }
+
     pub fn start_editing(&mut self) {
         self.input_mode = InputMode::Editing;
         let content = self.get_cell_content(self.selected_cell.0, self.selected_cell.1);
         self.input_buffer.clone_from(&content);
+        self.text_area = Self::edit_box(&content);
+        self.vim_state = Some(VimState::new(VimMode::Normal));
+    }
 
-        // Initialize TextArea with content and settings
-        let mut text_area = tui_textarea::TextArea::default();
-        text_area.insert_str(&content);
+    // The text area a cell is edited in, holding `content`
+    pub(super) fn edit_box(content: &str) -> TextArea<'static> {
+        let mut text_area = TextArea::default();
+        text_area.insert_str(content);
         text_area.set_tab_length(4);
         text_area.set_cursor_line_style(Style::default());
         text_area.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED));
-
-        self.text_area = text_area;
-        self.vim_state = Some(VimState::new(VimMode::Normal));
+        text_area
     }
 
     pub fn handle_vim_input(&mut self, input: Input) -> Result<()> {
@@ -44,6 +67,8 @@ impl AppState<'_> {
 
     pub fn confirm_edit(&mut self) -> Result<()> {
         if let InputMode::Editing = self.input_mode {
+            self.workbook.ensure_current_sheet_editable()?;
+
             // Get content from TextArea
             let content = self.text_area.lines().join("\n");
             let (row, col) = self.selected_cell;
@@ -55,9 +80,15 @@ impl AppState<'_> {
             let sheet_name = self.workbook.get_current_sheet_name();
 
             let old_cell = self.workbook.get_current_sheet().data[row][col].clone();
+            // A value left as it was is never held back, even when it breaks the rule
+            if content != old_cell.value && !self.check_edit(row, col, &content) {
+                return Ok(());
+            }
 
+            let (content, style) = self.smart_number_entry(content, old_cell.style);
             let mut new_cell = old_cell.clone();
             new_cell.value.clone_from(&content);
+            new_cell.style = style;
 
             let cell_action = CellAction::new(
                 sheet_index,
@@ -72,6 +103,7 @@ impl AppState<'_> {
             self.undo_history.push(ActionCommand::Cell(cell_action));
 
             self.workbook.set_cell_value(row, col, content)?;
+            self.workbook.set_cell_style(row, col, style)?;
             self.input_mode = InputMode::Normal;
             self.input_buffer = String::new();
             self.text_area = tui_textarea::TextArea::default();
@@ -81,12 +113,28 @@ impl AppState<'_> {
     }
 
     pub fn copy_cell(&mut self) {
+        if self.selection_anchor.is_some() {
+            self.yank_selection();
+            return;
+        }
+
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Copy failed: {e}"));
+            return;
+        }
+
         let content = self.get_cell_content_mut(self.selected_cell.0, self.selected_cell.1);
-        self.clipboard = Some(content);
+        self.clipboard = Some(Clipboard::Text(content));
         self.add_notification("Cell content copied".to_string());
     }
 
     pub fn cut_cell(&mut self) -> Result<()> {
+        if self.selection_anchor.is_some() {
+            return self.cut_selection();
+        }
+
+        self.workbook.ensure_current_sheet_editable()?;
+
         let (row, col) = self.selected_cell;
 
         self.workbook.ensure_cell_exists(row, col);
@@ -94,7 +142,7 @@ impl AppState<'_> {
         self.ensure_column_widths();
 
         let content = self.get_cell_content(row, col);
-        self.clipboard = Some(content);
+        self.clipboard = Some(Clipboard::Text(content));
 
         let sheet_index = self.workbook.get_current_sheet_index();
         let sheet_name = self.workbook.get_current_sheet_name();
@@ -122,36 +170,72 @@ impl AppState<'_> {
     }
 
     pub fn paste_cell(&mut self) -> Result<()> {
-        if let Some(content) = self.clipboard.clone() {
-            let (row, col) = self.selected_cell;
+        self.workbook.ensure_current_sheet_editable()?;
 
-            self.workbook.ensure_cell_exists(row, col);
-            self.ensure_column_widths();
+        match self.clipboard.clone() {
+            Some(Clipboard::Range(range)) => self.paste_range(&range),
+            Some(Clipboard::Row(range)) => self.paste_range_at(&range, (self.selected_cell.0, 1)),
+            Some(Clipboard::Column(range)) => {
+                self.paste_range_at(&range, (1, self.selected_cell.1))
+            }
+            Some(Clipboard::Text(content)) => self.paste_into_cell(content),
+            // Nothing yanked in the app yet, so what was copied elsewhere
+            None => match read_system_clipboard() {
+                Some(text) => self.paste_text(&text, "system clipboard"),
+                None => {
+                    self.add_notification("Clipboard is empty".to_string());
+                    Ok(())
+                }
+            },
+        }
+    }
 
-            let sheet_index = self.workbook.get_current_sheet_index();
-            let sheet_name = self.workbook.get_current_sheet_name();
+    /// Replaces the value of the current cell with `content`, one undo step
+    pub fn paste_into_cell(&mut self, content: String) -> Result<()> {
+        self.set_current_cell(content, ActionType::Paste)?;
+        self.add_notification("Content pasted".to_string());
+        Ok(())
+    }
 
-            let old_cell = self.workbook.get_current_sheet().data[row][col].clone();
+    // Writes the current cell as one undoable change, numbers entered the smart way
+    // with :set smartnumbers
+    pub(super) fn set_current_cell(
+        &mut self,
+        content: String,
+        action_type: ActionType,
+    ) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
 
-            let mut new_cell = old_cell.clone();
-            new_cell.value.clone_from(&content);
+        let (row, col) = self.selected_cell;
 
-            let cell_action = CellAction::new(
-                sheet_index,
-                sheet_name,
-                row,
-                col,
-                old_cell,
-                new_cell,
-                ActionType::Paste,
-            );
+        self.workbook.ensure_cell_exists(row, col);
+        self.ensure_column_widths();
 
-            self.undo_history.push(ActionCommand::Cell(cell_action));
-            self.workbook.set_cell_value(row, col, content)?;
-            self.add_notification("Content pasted".to_string());
-        } else {
-            self.add_notification("Clipboard is empty".to_string());
-        }
+        let sheet_index = self.workbook.get_current_sheet_index();
+        let sheet_name = self.workbook.get_current_sheet_name();
+
+        let old_cell = self.workbook.get_current_sheet().data[row][col].clone();
+
+        let (content, style) = self.smart_number_entry(content, old_cell.style);
+        let mut new_cell = old_cell.clone();
+        new_cell.value.clone_from(&content);
+        new_cell.style = style;
+
+        let cell_action = CellAction::new(
+            sheet_index,
+            sheet_name,
+            row,
+            col,
+            old_cell,
+            new_cell,
+            action_type,
+        );
+
+        let action = ActionCommand::Cell(cell_action);
+        self.flash_action(&action);
+        self.undo_history.push(action);
+        self.workbook.set_cell_value(row, col, content)?;
+        self.workbook.set_cell_style(row, col, style)?;
         Ok(())
     }
 }
@@ -0,0 +1,137 @@
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::hash::BuildHasher;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{self, Command};
+
+use anyhow::{bail, Context, Result};
+
+use crate::actions::ActionType;
+use crate::app::{AppState, InputMode};
+use crate::utils::cell_reference;
+
+/// Where the text edited in $EDITOR goes once the editor exits
+#[derive(Clone, Copy)]
+pub enum ExternalEdit {
+    Cell,    // ge in normal mode, the result is written to the cell
+    EditBox, // Ctrl-e while editing, the result replaces the text being edited
+}
+
+// $VISUAL or $EDITOR, with the arguments they may carry, e.g. "code --wait"
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string())
+}
+
+// Creates a file only this user can read in the temp directory. The name ends in a random
+// suffix and an existing file or symlink of that name is never opened, so another user
+// can't guess the name to read the cell or redirect the write elsewhere
+fn create_temp_file(name: &str) -> io::Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    loop {
+        let suffix = RandomState::new().hash_one(process::id());
+        let path = env::temp_dir().join(format!("excel-cli-{name}-{suffix:016x}.txt"));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Opens `text` in the user's editor through a temporary file and returns it as saved.
+/// The terminal has to be handed over to the editor before calling this
+pub fn edit_in_external_editor(text: &str, name: &str) -> Result<String> {
+    let (path, mut file) = create_temp_file(name).context("Cannot create a temporary file")?;
+    let written = file.write_all(text.as_bytes());
+    drop(file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&path);
+        return Err(e).with_context(|| format!("Cannot write {}", path.display()));
+    }
+
+    let editor = editor_command();
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program).args(parts).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    let status = status.with_context(|| format!("Cannot run {editor}"))?;
+    if !status.success() {
+        bail!("{editor} exited with {status}, the cell is left as it was");
+    }
+    let mut edited = edited.with_context(|| format!("Cannot read {}", path.display()))?;
+
+    // Editors end the file with a newline the cell didn't have
+    if edited.ends_with('\n') && !text.ends_with('\n') {
+        edited.pop();
+        if edited.ends_with('\r') {
+            edited.pop();
+        }
+    }
+    Ok(edited)
+}
+
+impl AppState<'_> {
+    /// ge: edit the current cell in $EDITOR, run_app opens it before the next frame
+    pub fn start_external_edit(&mut self) {
+        match self.workbook.ensure_current_sheet_editable() {
+            Ok(()) => self.external_edit = Some(ExternalEdit::Cell),
+            Err(e) => self.add_notification(format!("Cannot edit: {e}")),
+        }
+    }
+
+    /// Ctrl-e while editing: continue with the text in $EDITOR
+    pub fn start_external_edit_of_edit_box(&mut self) {
+        self.external_edit = Some(ExternalEdit::EditBox);
+    }
+
+    /// The text to open in the editor and a name for its file
+    #[must_use]
+    pub fn external_edit_text(&self, target: ExternalEdit) -> (String, String) {
+        let (row, col) = self.selected_cell;
+        let text = match target {
+            ExternalEdit::Cell => self.get_cell_content(row, col),
+            ExternalEdit::EditBox => self.text_area.lines().join("\n"),
+        };
+        (text, cell_reference((row, col)))
+    }
+
+    pub fn finish_external_edit(&mut self, target: ExternalEdit, edited: Result<String>) {
+        let edited = match edited {
+            Ok(edited) => edited,
+            Err(e) => {
+                self.add_notification(format!("External editor failed: {e:#}"));
+                return;
+            }
+        };
+
+        match target {
+            ExternalEdit::Cell => {
+                let (row, col) = self.selected_cell;
+                if edited == self.get_cell_content(row, col) {
+                    self.add_notification("Cell unchanged".to_string());
+                } else if let Err(e) = self.set_current_cell(edited, ActionType::Edit) {
+                    self.add_notification(format!("Edit failed: {e}"));
+                } else {
+                    self.add_notification("Cell updated from the external editor".to_string());
+                }
+            }
+            ExternalEdit::EditBox => {
+                if let InputMode::Editing = self.input_mode {
+                    self.text_area = Self::edit_box(&edited);
+                }
+            }
+        }
+    }
+}
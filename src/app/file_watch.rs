@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant, SystemTime};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::app::{AppState, CellPosition, InputMode};
+use crate::excel::{open_workbook_with_delimiter, ImportProfile};
+
+// A change is reported once the file has been quiet this long, not halfway through a write
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+// What tells one version of the file from the next
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Notices changes other programs make to the opened file. Its directory is watched
+/// rather than the file itself, so a file replaced through a rename, the way most
+/// programs save, is still followed
+pub struct FileWatch {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    path: PathBuf,
+    stamp: Option<(SystemTime, u64)>, // Modification time and size of the version read
+    touched: Option<Instant>,         // Last event for the file not reported yet
+}
+
+impl FileWatch {
+    pub fn new(path: &Path) -> notify::Result<Self> {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let directory = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            stamp: file_stamp(&path),
+            path,
+            touched: None,
+        })
+    }
+
+    /// Whether the file is different from the version read, reported once per change
+    pub fn changed(&mut self) -> bool {
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else { continue };
+            if event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == self.path.file_name())
+            {
+                self.touched = Some(Instant::now());
+            }
+        }
+        if self.touched.map_or(true, |at| at.elapsed() < SETTLE_TIME) {
+            return false;
+        }
+        self.touched = None;
+
+        // Reading the file or writing the same bytes back doesn't count, nor does a file
+        // that is gone for now
+        let stamp = file_stamp(&self.path);
+        if stamp.is_none() || stamp == self.stamp {
+            return false;
+        }
+        self.stamp = stamp;
+        true
+    }
+
+    /// Takes the file as it is now as the version read
+    pub fn reset(&mut self) {
+        self.stamp = file_stamp(&self.path);
+        self.touched = None;
+    }
+}
+
+impl AppState<'_> {
+    /// Asks whether to reload once the file changed on disk, called between key presses.
+    /// The question waits while a command or an edit is under way
+    pub fn check_file_changed(&mut self) {
+        if self.file_watch.as_mut().is_some_and(FileWatch::changed) {
+            self.file_changed = true;
+            self.reload_prompt = true;
+        }
+        if self.reload_prompt && matches!(self.input_mode, InputMode::Normal) {
+            self.reload_prompt = false;
+            self.input_mode = InputMode::ConfirmReload;
+        }
+    }
+
+    pub fn confirm_reload(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.reload_workbook();
+    }
+
+    pub fn keep_local_workbook(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.add_notification(
+            "Kept the workbook as it is, :e! reads the changed file later".to_string(),
+        );
+    }
+
+    /// :e! and :reload: reads the file again, dropping the changes made since it was opened.
+    /// The current sheet and cursor stay where they were as far as the new version allows
+    pub fn reload_workbook(&mut self) {
+        if self.report_running_save() {
+            return;
+        }
+
+        let delimiter = match self.profile.as_ref().map(ImportProfile::delimiter_byte) {
+            Some(Ok(delimiter)) => delimiter,
+            _ => None,
+        };
+        let mut workbook = match open_workbook_with_delimiter(
+            &self.file_path,
+            self.workbook.is_lazy_loading(),
+            delimiter,
+        ) {
+            Ok(workbook) => workbook,
+            Err(e) => {
+                self.add_notification(format!("Reload failed: {e}"));
+                return;
+            }
+        };
+        if let Some(profile) = self.profile.as_ref().filter(|p| !p.columns.is_empty()) {
+            workbook.set_import_profile(profile.clone());
+        }
+
+        let sheet_name = self.workbook.get_current_sheet_name();
+        self.store_current_column_widths();
+        self.sheet_cell_positions.insert(
+            sheet_name.clone(),
+            CellPosition {
+                selected: self.selected_cell,
+                view: (self.start_row, self.start_col),
+            },
+        );
+
+        let index = workbook
+            .get_sheet_names()
+            .iter()
+            .position(|name| *name == sheet_name)
+            .unwrap_or(0);
+        // Switching can only fail for an index out of range, and this one comes from the list
+        let _ = workbook.switch_sheet(index);
+        self.workbook = workbook;
+        self.undo_history.clear();
+        self.search_job = None;
+        self.replace_job = None;
+        self.search_results.clear();
+        self.current_search_idx = None;
+        self.selection_anchor = None;
+        self.selection_stats = None;
+        self.file_changed = false;
+        self.reload_prompt = false;
+        if let Some(watch) = &mut self.file_watch {
+            watch.reset();
+        }
+
+        let name = self.workbook.get_current_sheet_name();
+        self.column_widths = self
+            .sheet_column_widths
+            .get(&name)
+            .cloned()
+            .unwrap_or_else(|| vec![15; 1]);
+        self.ensure_column_widths();
+        let sheet = self.workbook.get_current_sheet();
+        let position = self.sheet_cell_positions.get(&name).copied();
+        let (selected, view) = position.map_or(((1, 1), (1, 1)), |p| (p.selected, p.view));
+        self.selected_cell = (
+            selected.0.min(sheet.max_rows.max(1)),
+            selected.1.min(sheet.max_cols.max(1)),
+        );
+        (self.start_row, self.start_col) = view;
+        self.handle_scrolling();
+        self.update_row_number_width();
+
+        if self.workbook.is_lazy_loading() && !self.workbook.is_sheet_loaded(index) {
+            self.input_mode = InputMode::LazyLoading;
+        }
+        let file_name = self
+            .file_path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+        self.add_notification(format!("Reloaded {file_name} from disk"));
+    }
+}
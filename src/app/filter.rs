@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{bail, Context, Result};
+
+use crate::app::{AppState, TextFormat};
+
+// Runs `command` through the shell with `input` on its stdin and returns its stdout
+fn run_filter(command: &str, input: String) -> Result<String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let mut child = shell
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Cannot run {command}"))?;
+
+    // Written from another thread, a command printing as it reads would block otherwise
+    let mut stdin = child.stdin.take().context("No stdin for the command")?;
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    // A command that doesn't read all its input, like head, closes the pipe early
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.lines().find(|line| !line.trim().is_empty()) {
+            Some(line) => bail!("{command} exited with {}: {line}", output.status),
+            None => bail!("{command} exited with {}", output.status),
+        }
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Rows of the command's output split on tabs, the way the selection was written
+fn read_tsv(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_end_matches(['\r', '\n']);
+    if text.is_empty() {
+        return Vec::new();
+    }
+    csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .records()
+        .map(|record| match record {
+            Ok(record) => record.iter().map(str::to_string).collect(),
+            Err(_) => Vec::new(),
+        })
+        .collect()
+}
+
+impl AppState<'_> {
+    /// :!cmd: sends the visual selection, or the current cell, to a shell command as TSV
+    /// and replaces it with what the command prints, like Vim's filter. One undo step
+    pub fn filter_selection(&mut self, command: &str) {
+        let command = command.trim();
+        if command.is_empty() {
+            self.add_notification("Usage: :!command, e.g. :!sort -n".to_string());
+            return;
+        }
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Filter: {e}"));
+            return;
+        }
+
+        let ((top, left), (bottom, right)) = self
+            .selection_range()
+            .unwrap_or((self.selected_cell, self.selected_cell));
+        let sheet = self.workbook.get_current_sheet();
+        let rows: Vec<Vec<String>> = (top..=bottom)
+            .map(|row| {
+                (left..=right)
+                    .map(|col| sheet.cell(row, col).value.clone())
+                    .collect()
+            })
+            .collect();
+        let input = TextFormat::Tsv.format(&rows) + "\n";
+
+        let output = match run_filter(command, input) {
+            Ok(output) => read_tsv(&output),
+            Err(e) => {
+                self.add_notification(format!("Filter failed: {e:#}"));
+                return;
+            }
+        };
+
+        // The output replaces the selection from its top-left corner, selected cells it
+        // doesn't reach are emptied
+        let mut changes = BTreeMap::new();
+        for row in top..=bottom {
+            for col in left..=right {
+                changes.insert((row, col), String::new());
+            }
+        }
+        for (row_offset, values) in output.into_iter().enumerate() {
+            for (col_offset, value) in values.into_iter().enumerate() {
+                changes.insert((top + row_offset, left + col_offset), value);
+            }
+        }
+
+        self.clear_selection();
+        if self.apply_script_changes("Filter", changes) == 0 {
+            self.add_notification(format!("{command} left the cells as they were"));
+        }
+    }
+}
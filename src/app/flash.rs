@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::actions::ActionCommand;
+use crate::app::AppState;
+
+// How long changed cells stay highlighted unless :set flashtime says otherwise
+pub const DEFAULT_FLASH_TIME: Duration = Duration::from_millis(400);
+
+/// Cells changed by the last paste, undo or redo, highlighted for a moment
+pub struct Flash {
+    sheet_index: usize,
+    area: FlashArea,
+    until: Instant,
+}
+
+enum FlashArea {
+    Cells(HashSet<(usize, usize)>),
+    Rows(usize, usize),    // First and last row, inclusive
+    Columns(usize, usize), // First and last column, inclusive
+}
+
+impl FlashArea {
+    // Sheet-level actions have nothing to point at in the grid
+    fn of(action: &ActionCommand) -> Option<Self> {
+        let area = match action {
+            ActionCommand::Cell(action) => Self::Cells(HashSet::from([(action.row, action.col)])),
+            ActionCommand::MultiCell(action) => Self::Cells(
+                action
+                    .cells
+                    .iter()
+                    .map(|change| (change.row, change.col))
+                    .collect(),
+            ),
+            ActionCommand::Row(action) => Self::Rows(action.row, action.row),
+            ActionCommand::MultiRow(action) => Self::Rows(action.start_row, action.end_row),
+            ActionCommand::InsertRows(action) => Self::Rows(
+                action.start_row,
+                action.start_row + action.rows_data.len().saturating_sub(1),
+            ),
+            ActionCommand::Column(action) => Self::Columns(action.col, action.col),
+            ActionCommand::MultiColumn(action) => Self::Columns(action.start_col, action.end_col),
+            ActionCommand::ReorderColumns(action) => Self::Columns(1, action.order.len()),
+            ActionCommand::Sheet(_) | ActionCommand::AddSheet(_) | ActionCommand::MoveSheet(_) => {
+                return None
+            }
+            // The last change of the group that has something to point at
+            ActionCommand::Group(group) => return group.actions.iter().rev().find_map(Self::of),
+        };
+        Some(area)
+    }
+
+    fn contains(&self, row: usize, col: usize) -> bool {
+        match self {
+            Self::Cells(cells) => cells.contains(&(row, col)),
+            Self::Rows(first, last) => (*first..=*last).contains(&row),
+            Self::Columns(first, last) => (*first..=*last).contains(&col),
+        }
+    }
+}
+
+impl AppState<'_> {
+    /// Highlights the cells `action` changed on the current sheet for `flash_time`
+    pub fn flash_action(&mut self, action: &ActionCommand) {
+        if self.flash_time.is_zero() {
+            return;
+        }
+
+        self.flash = FlashArea::of(action).map(|area| Flash {
+            sheet_index: self.workbook.get_current_sheet_index(),
+            area,
+            until: Instant::now() + self.flash_time,
+        });
+    }
+
+    #[must_use]
+    pub fn is_flashing(&self, row: usize, col: usize) -> bool {
+        self.flash.as_ref().is_some_and(|flash| {
+            flash.sheet_index == self.workbook.get_current_sheet_index()
+                && Instant::now() < flash.until
+                && flash.area.contains(row, col)
+        })
+    }
+}
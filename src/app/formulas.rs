@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use crate::app::AppState;
+use crate::excel::Sheet;
+
+// How long [ and ] wait for an f before switching sheets, like Vim's 'timeoutlen'
+const BRACKET_TIMEOUT: Duration = Duration::from_millis(500);
+
+fn formula_cells(sheet: &Sheet) -> impl Iterator<Item = (usize, usize)> + '_ {
+    sheet
+        .rows()
+        .flat_map(|row| row.cells())
+        .filter(|cell| cell.cell.is_formula)
+        .map(|cell| (cell.row, cell.col))
+}
+
+impl AppState<'_> {
+    /// [ or ] was pressed, it becomes [f/]f or a sheet switch depending on the next key
+    pub fn start_bracket(&mut self, bracket: char) {
+        self.pending_bracket = Some((bracket, Instant::now()));
+    }
+
+    /// Switches sheets for a pending [ or ], right away with `force` or once the timeout passed
+    pub fn flush_pending_bracket(&mut self, force: bool) {
+        let Some((bracket, pressed_at)) = self.pending_bracket else {
+            return;
+        };
+        if !force && pressed_at.elapsed() < BRACKET_TIMEOUT {
+            return;
+        }
+        self.pending_bracket = None;
+
+        if bracket == '[' {
+            if let Err(e) = self.prev_sheet() {
+                self.add_notification(format!("Failed to switch to previous sheet: {e}"));
+            }
+        } else if let Err(e) = self.next_sheet() {
+            self.add_notification(format!("Failed to switch to next sheet: {e}"));
+        }
+    }
+
+    /// ]f and [f: moves to the next or previous formula cell in reading order, wrapping around
+    pub fn jump_to_formula(&mut self, forward: bool) {
+        let current = self.selected_cell;
+        let formulas: Vec<(usize, usize)> =
+            formula_cells(self.workbook.get_current_sheet()).collect();
+
+        let (Some(&first), Some(&last)) = (formulas.first(), formulas.last()) else {
+            self.add_notification("No formula cells in this sheet".to_string());
+            return;
+        };
+
+        let next = if forward {
+            formulas.iter().find(|&&pos| pos > current)
+        } else {
+            formulas.iter().rev().find(|&&pos| pos < current)
+        };
+        let target = match next {
+            Some(&pos) => pos,
+            None if forward => {
+                self.add_notification("Formula search wrapped to top".to_string());
+                first
+            }
+            None => {
+                self.add_notification("Formula search wrapped to bottom".to_string());
+                last
+            }
+        };
+
+        self.record_jump();
+        self.selected_cell = target;
+        self.handle_scrolling();
+    }
+
+    /// Lists how many formula cells each sheet has
+    pub fn show_formula_counts(&mut self) {
+        let counts: Vec<String> = self
+            .workbook
+            .sheets()
+            .map(|sheet| {
+                if sheet.is_loaded {
+                    format!("{}: {}", sheet.name, formula_cells(sheet).count())
+                } else {
+                    format!("{}: not loaded", sheet.name)
+                }
+            })
+            .collect();
+
+        self.add_notification(format!("Formulas: {}", counts.join(", ")));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::app::AppState;
+
+// Oldest jumps are dropped beyond this many entries, as in Vim
+const MAX_JUMPS: usize = 100;
+
+/// Positions the cursor jumped away from, walked with Ctrl-o and Ctrl-i
+#[derive(Default)]
+pub struct JumpList {
+    entries: Vec<(String, (usize, usize))>, // (sheet name, cell)
+    index: usize,                           // entries.len() when not walking the list
+}
+
+impl JumpList {
+    pub fn rename_sheet(&mut self, old_name: &str, new_name: &str) {
+        for (sheet_name, _) in &mut self.entries {
+            if sheet_name == old_name {
+                *sheet_name = new_name.to_string();
+            }
+        }
+    }
+}
+
+impl AppState<'_> {
+    /// Records the current position before a jump (gg, G, search, goto, marks)
+    pub fn record_jump(&mut self) {
+        let position = (self.workbook.get_current_sheet_name(), self.selected_cell);
+        let jumps = &mut self.jump_list;
+
+        // A new jump while walking back forgets the newer entries, like browser history
+        jumps.entries.truncate(jumps.index);
+        jumps.entries.retain(|entry| *entry != position);
+        jumps.entries.push(position);
+
+        if jumps.entries.len() > MAX_JUMPS {
+            jumps.entries.remove(0);
+        }
+        jumps.index = jumps.entries.len();
+    }
+
+    /// Ctrl-o: goes back to the previous position in the jump list
+    pub fn jump_back(&mut self) {
+        if self.jump_list.index == 0 {
+            self.add_notification("Already at the oldest jump".to_string());
+            return;
+        }
+
+        // Keep the current position so Ctrl-i can come back to it
+        if self.jump_list.index == self.jump_list.entries.len() {
+            self.record_jump();
+            self.jump_list.index = self.jump_list.entries.len() - 1;
+        }
+
+        self.jump_list.index -= 1;
+        self.go_to_jump(self.jump_list.index);
+    }
+
+    /// Ctrl-i (Tab): goes forward again after Ctrl-o
+    pub fn jump_forward(&mut self) {
+        if self.jump_list.index + 1 >= self.jump_list.entries.len() {
+            self.add_notification("Already at the newest jump".to_string());
+            return;
+        }
+
+        self.jump_list.index += 1;
+        self.go_to_jump(self.jump_list.index);
+    }
+
+    fn go_to_jump(&mut self, index: usize) {
+        let (sheet_name, (row, col)) = self.jump_list.entries[index].clone();
+
+        if sheet_name != self.workbook.get_current_sheet_name() {
+            // The sheet may have been deleted since the jump was recorded
+            let Some(sheet_index) = self
+                .workbook
+                .get_sheet_names()
+                .iter()
+                .position(|name| *name == sheet_name)
+            else {
+                self.jump_list.entries.remove(index);
+                self.jump_list.index = self.jump_list.index.min(self.jump_list.entries.len());
+                self.add_notification(format!("Sheet '{sheet_name}' no longer exists"));
+                return;
+            };
+
+            if let Err(e) = self.switch_sheet_by_index(sheet_index) {
+                self.add_notification(format!("Failed to switch to sheet '{sheet_name}': {e}"));
+                return;
+            }
+        }
+
+        let sheet = self.workbook.get_current_sheet();
+        self.selected_cell = (
+            row.min(sheet.max_rows.max(1)),
+            col.min(sheet.max_cols.max(1)),
+        );
+        self.handle_scrolling();
+    }
+}
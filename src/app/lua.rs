@@ -0,0 +1,305 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use mlua::{HookTriggers, Lua, MultiValue, Value, VmState};
+
+use crate::app::script::{number_text, ScriptEdits};
+use crate::app::AppState;
+use crate::excel::{import_profiles_path, Sheet};
+use crate::utils::{parse_cell_reference, MAX_COLS, MAX_ROWS};
+
+// A script running longer than this is stopped, it would otherwise hang the UI
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Functions written in Lua on top of the ones the app provides
+const PRELUDE: &str = r"
+function rows(first, last)
+    local row = (first or 1) - 1
+    last = last or max_row()
+    return function()
+        row = row + 1
+        if row <= last then
+            return row, row_values(row)
+        end
+    end
+end
+";
+
+// The sheet a script runs on and what it changed so far
+struct ScriptContext<'a> {
+    sheet: &'a Sheet,
+    edits: ScriptEdits,
+    commands: Vec<(String, String, String)>, // Name, help and code of commands to register
+}
+
+// A whole number such as a row or column, 3.0 counts as 3
+fn index_arg(value: &Value) -> Option<usize> {
+    match value {
+        Value::Integer(i) => usize::try_from(*i).ok(),
+        Value::Number(n) if n.fract() == 0.0 && *n >= 0.0 => Some(*n as usize),
+        _ => None,
+    }
+}
+
+// A cell given as "B3" or as row and column numbers, both 1-based and within XFD1048576
+fn cell_arg(cell: &Value, col: Option<&Value>) -> mlua::Result<(usize, usize)> {
+    let position = match (cell, col) {
+        (Value::String(reference), None) => parse_cell_reference(&reference.to_str()?),
+        (row, Some(col)) => index_arg(row).zip(index_arg(col)),
+        _ => None,
+    };
+    match position {
+        Some((row, col)) if row > MAX_ROWS || col > MAX_COLS => Err(mlua::Error::runtime(format!(
+            "cell beyond XFD{MAX_ROWS}, sheets have {MAX_ROWS} rows and {MAX_COLS} columns"
+        ))),
+        Some((row, col)) if row > 0 && col > 0 => Ok((row, col)),
+        _ => Err(mlua::Error::runtime(
+            "expected a cell like \"B3\" or a row and a column starting at 1",
+        )),
+    }
+}
+
+// How a value set from Lua reads in a cell, nil clears it
+fn cell_text(value: &Value) -> mlua::Result<String> {
+    Ok(match value {
+        Value::Nil => String::new(),
+        Value::Boolean(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Number(n) => number_text(*n),
+        Value::String(s) => s.to_str()?.to_string(),
+        other => {
+            return Err(mlua::Error::runtime(format!(
+                "a {} can't be stored in a cell",
+                other.type_name()
+            )))
+        }
+    })
+}
+
+// The message of an error without the traceback Lua adds, which won't fit a notification
+fn error_message(error: &mlua::Error) -> String {
+    match error {
+        mlua::Error::CallbackError { cause, .. } => error_message(cause),
+        mlua::Error::RuntimeError(message) | mlua::Error::SyntaxError { message, .. } => {
+            message.clone()
+        }
+        other => other.to_string(),
+    }
+}
+
+fn display_values(values: &MultiValue) -> mlua::Result<Vec<String>> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::String(s) => Ok(s.to_str()?.to_string()),
+            other => Ok(other.to_string()?),
+        })
+        .collect()
+}
+
+/// Script run when the app starts, next to the import profiles,
+/// e.g. ~/.config/excel-cli/init.lua
+#[must_use]
+pub fn lua_init_path() -> PathBuf {
+    import_profiles_path().with_file_name("init.lua")
+}
+
+impl AppState<'_> {
+    /// :lua [code]: runs Lua on the current sheet and shows what an expression returns
+    pub fn run_lua(&mut self, code: &str) {
+        if code.trim().is_empty() {
+            self.add_notification("Usage: :lua [code], e.g. :lua set(\"A1\", 42)".to_string());
+            return;
+        }
+        if self.lua_sheet_ready() {
+            self.run_script(code, "=lua", "");
+        }
+    }
+
+    /// :luafile [path]: runs a Lua script file on the current sheet
+    pub fn run_lua_file(&mut self, path: &str) {
+        if path.is_empty() {
+            self.add_notification("Usage: :luafile [path]".to_string());
+            return;
+        }
+        if !self.lua_sheet_ready() {
+            return;
+        }
+        match fs::read_to_string(path) {
+            Ok(code) => self.run_script(&code, &format!("@{path}"), ""),
+            Err(e) => self.add_notification(format!("Cannot read {path}: {e}")),
+        }
+    }
+
+    /// Runs the init script if there is one, mostly to register commands. The sheet
+    /// may not be loaded yet, it then reads as empty
+    pub fn run_lua_init(&mut self, path: &Path) {
+        match fs::read_to_string(path) {
+            Ok(code) => self.run_script(&code, &format!("@{}", path.display()), ""),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => self.add_notification(format!("Cannot read {}: {e}", path.display())),
+        }
+    }
+
+    fn lua_sheet_ready(&mut self) -> bool {
+        match self.workbook.ensure_current_sheet_editable() {
+            Ok(()) => true,
+            Err(e) => {
+                self.add_notification(format!("Lua: {e}"));
+                false
+            }
+        }
+    }
+
+    // Makes a command registered by a script run its code with `args` set to what was
+    // typed after the command name
+    fn register_lua_command(&mut self, name: &str, help: &str, code: String) {
+        let chunk_name = format!("={name}");
+        let registered = self.commands.register(name, help, move |app, args| {
+            if app.lua_sheet_ready() {
+                app.run_script(&code, &chunk_name, args);
+            }
+        });
+        if let Err(e) = registered {
+            self.add_notification(format!("Lua: {e}"));
+        }
+    }
+
+    // Runs the script against the current sheet, then applies what it changed as one
+    // undoable step. A script that fails changes nothing
+    fn run_script(&mut self, code: &str, chunk_name: &str, args: &str) {
+        let context = RefCell::new(ScriptContext {
+            sheet: self.workbook.get_current_sheet(),
+            edits: ScriptEdits::default(),
+            commands: Vec::new(),
+        });
+        let cursor = self.selected_cell;
+        let selection = self.selection_range().unwrap_or((cursor, cursor));
+        let sheet_name = self.workbook.get_current_sheet_name();
+
+        let lua = Lua::new();
+        let started = Instant::now();
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(10_000),
+            move |_, _| {
+                if started.elapsed() > SCRIPT_TIMEOUT {
+                    Err(mlua::Error::runtime("the script ran for more than 10s"))
+                } else {
+                    Ok(VmState::Continue)
+                }
+            },
+        );
+
+        let result = lua.scope(|scope| {
+            let globals = lua.globals();
+            globals.set(
+                "get",
+                scope.create_function(|_, (cell, col): (Value, Option<Value>)| {
+                    let (row, col) = cell_arg(&cell, col.as_ref())?;
+                    let context = context.borrow();
+                    Ok(context.edits.value(context.sheet, row, col).to_string())
+                })?,
+            )?;
+            globals.set(
+                "set",
+                scope.create_function(|_, args: MultiValue| {
+                    let args: Vec<Value> = args.into_iter().collect();
+                    let (position, value) = match args.as_slice() {
+                        [cell, value] => (cell_arg(cell, None)?, value),
+                        [row, col, value] => (cell_arg(row, Some(col))?, value),
+                        _ => {
+                            return Err(mlua::Error::runtime(
+                                "expected set(\"B3\", value) or set(row, col, value)",
+                            ))
+                        }
+                    };
+                    let text = cell_text(value)?;
+                    context.borrow_mut().edits.changes.insert(position, text);
+                    Ok(())
+                })?,
+            )?;
+            globals.set(
+                "row_values",
+                scope.create_function(|lua, row: usize| {
+                    let context = context.borrow();
+                    lua.create_table_from(context.edits.row_values(context.sheet, row))
+                })?,
+            )?;
+            globals.set(
+                "max_row",
+                scope.create_function(|_, ()| {
+                    let context = context.borrow();
+                    Ok(context.edits.max_row(context.sheet))
+                })?,
+            )?;
+            globals.set(
+                "max_col",
+                scope.create_function(|_, ()| {
+                    let context = context.borrow();
+                    Ok(context.edits.max_col(context.sheet))
+                })?,
+            )?;
+            globals.set("cursor", lua.create_function(move |_, ()| Ok(cursor))?)?;
+            globals.set(
+                "selection",
+                lua.create_function(move |_, ()| {
+                    let ((top, left), (bottom, right)) = selection;
+                    Ok((top, left, bottom, right))
+                })?,
+            )?;
+            globals.set(
+                "sheet_name",
+                lua.create_function(move |_, ()| Ok(sheet_name.clone()))?,
+            )?;
+            globals.set(
+                "add_sheet",
+                scope.create_function(|_, name: String| {
+                    context.borrow_mut().edits.new_sheets.push(name);
+                    Ok(())
+                })?,
+            )?;
+            globals.set(
+                "print",
+                scope.create_function(|_, values: MultiValue| {
+                    let line = display_values(&values)?.join("  ");
+                    context.borrow_mut().edits.output.push(line);
+                    Ok(())
+                })?,
+            )?;
+            globals.set(
+                "command",
+                scope.create_function(|_, (name, help, code): (String, String, String)| {
+                    context.borrow_mut().commands.push((name, help, code));
+                    Ok(())
+                })?,
+            )?;
+            globals.set("args", args)?;
+            lua.load(PRELUDE).set_name("=prelude").exec()?;
+
+            // An expression shows its value, like typing it at the Lua prompt
+            match lua
+                .load(format!("return {code}"))
+                .set_name(chunk_name)
+                .into_function()
+            {
+                Ok(expression) => expression.call::<MultiValue>(()),
+                Err(_) => lua.load(code).set_name(chunk_name).eval::<MultiValue>(),
+            }
+        });
+
+        let ScriptContext {
+            edits, commands, ..
+        } = context.into_inner();
+        let result = result
+            .and_then(|values| display_values(&values))
+            .map_err(|e| error_message(&e));
+        if result.is_ok() {
+            for (name, help, code) in commands {
+                self.register_lua_command(&name, &help, code);
+            }
+        }
+        self.finish_script("Lua", edits, result);
+    }
+}
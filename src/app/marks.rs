@@ -0,0 +1,70 @@
+use crate::app::AppState;
+use crate::utils::cell_reference;
+
+impl AppState<'_> {
+    /// Sets mark `name` (a-z) at the current cell of the current sheet
+    pub fn set_mark(&mut self, name: char) {
+        if !name.is_ascii_lowercase() {
+            self.add_notification(format!("Invalid mark: {name}, use a-z"));
+            return;
+        }
+
+        let sheet_name = self.workbook.get_current_sheet_name();
+        self.marks
+            .entry(sheet_name)
+            .or_default()
+            .insert(name, self.selected_cell);
+
+        self.add_notification(format!(
+            "Mark '{name}' set at {}",
+            cell_reference(self.selected_cell)
+        ));
+    }
+
+    /// Jumps to mark `name` of the current sheet, `'` goes back to where the last jump started
+    pub fn jump_to_mark(&mut self, name: char) {
+        let sheet_name = self.workbook.get_current_sheet_name();
+        let Some(&(row, col)) = self.marks.get(&sheet_name).and_then(|m| m.get(&name)) else {
+            self.add_notification(format!("Mark '{name}' not set"));
+            return;
+        };
+
+        self.record_jump();
+
+        // Remember where we came from, like Vim's '' mark
+        self.marks
+            .entry(sheet_name)
+            .or_default()
+            .insert('\'', self.selected_cell);
+
+        // Rows or columns may have been deleted since the mark was set
+        let sheet = self.workbook.get_current_sheet();
+        self.selected_cell = (
+            row.min(sheet.max_rows.max(1)),
+            col.min(sheet.max_cols.max(1)),
+        );
+        self.handle_scrolling();
+    }
+
+    /// Lists the marks of the current sheet
+    pub fn show_marks(&mut self) {
+        let sheet_name = self.workbook.get_current_sheet_name();
+        let mut marks: Vec<(char, (usize, usize))> = self
+            .marks
+            .get(&sheet_name)
+            .map(|m| m.iter().map(|(&name, &cell)| (name, cell)).collect())
+            .unwrap_or_default();
+
+        if marks.is_empty() {
+            self.add_notification("No marks set in this sheet".to_string());
+            return;
+        }
+
+        marks.sort_unstable();
+        let list: Vec<String> = marks
+            .into_iter()
+            .map(|(name, cell)| format!("{name}: {}", cell_reference(cell)))
+            .collect();
+        self.add_notification(format!("Marks: {}", list.join(", ")));
+    }
+}
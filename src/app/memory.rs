@@ -0,0 +1,89 @@
+use crate::app::AppState;
+use crate::utils::format_bytes;
+
+/// Memory taken by the data of each sheet and by the undo history, measured again
+/// once either changes
+pub struct MemoryUsage {
+    revision: u64,
+    loaded: usize,
+    pub sheets: Vec<(String, Option<usize>)>, // Bytes of each sheet, None when not loaded
+    pub undo: usize,
+}
+
+impl MemoryUsage {
+    fn measure(app_state: &AppState) -> Self {
+        let sheets: Vec<(String, Option<usize>)> = app_state
+            .workbook
+            .sheets()
+            .enumerate()
+            .map(|(index, sheet)| {
+                let bytes = app_state
+                    .workbook
+                    .is_sheet_loaded(index)
+                    .then(|| sheet.memory_size());
+                (sheet.name.clone(), bytes)
+            })
+            .collect();
+
+        Self {
+            revision: app_state.undo_history.revision(),
+            loaded: sheets.iter().filter(|(_, bytes)| bytes.is_some()).count(),
+            sheets,
+            undo: app_state.undo_history.memory_used(),
+        }
+    }
+
+    /// Bytes of the loaded sheets and the undo history together
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.sheets
+            .iter()
+            .filter_map(|(_, bytes)| *bytes)
+            .sum::<usize>()
+            + self.undo
+    }
+}
+
+impl AppState<'_> {
+    /// :mem: the memory each sheet's data takes, so the biggest can be left unloaded
+    pub fn show_memory_usage(&mut self) {
+        let usage = MemoryUsage::measure(self);
+
+        let sheets: Vec<String> = usage
+            .sheets
+            .iter()
+            .map(|(name, bytes)| match bytes {
+                Some(bytes) => format!("{name}: {}", format_bytes(*bytes)),
+                None => format!("{name}: not loaded"),
+            })
+            .collect();
+        self.add_notification(format!(
+            "Memory: {}, undo history: {}, total {}",
+            sheets.join(", "),
+            format_bytes(usage.undo),
+            format_bytes(usage.total())
+        ));
+    }
+
+    /// :set memstatus shows the total in the status bar, :set nomemstatus hides it
+    pub fn set_memory_status(&mut self, enabled: bool) {
+        if enabled {
+            self.refresh_memory_status();
+        } else {
+            self.memory_status = None;
+        }
+    }
+
+    /// Measures again when the data or the set of loaded sheets changed since the last time
+    pub fn refresh_memory_status(&mut self) {
+        if let Some(usage) = &self.memory_status {
+            let loaded = (0..self.workbook.sheets().count())
+                .filter(|&index| self.workbook.is_sheet_loaded(index))
+                .count();
+            if usage.revision == self.undo_history.revision() && usage.loaded == loaded {
+                return;
+            }
+        }
+        self.memory_status = Some(MemoryUsage::measure(self));
+    }
+}
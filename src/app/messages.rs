@@ -0,0 +1,113 @@
+use crate::app::{AppState, InputMode};
+
+// Oldest messages are dropped from :messages beyond this many
+const MAX_MESSAGE_HISTORY: usize = 500;
+
+// A batch with more messages than this is summarized in the notification area
+const MAX_BATCH_NOTIFICATIONS: usize = 3;
+
+/// Messages collected while a bulk operation runs, shown as one summary when it ends
+pub struct NotificationBatch {
+    label: String,
+    messages: Vec<String>,
+}
+
+// How often `message` was already shown if `last` is that message, e.g. "Saved (x3)"
+fn repeat_count(last: &str, message: &str) -> Option<usize> {
+    if last == message {
+        return Some(1);
+    }
+
+    last.strip_prefix(message)?
+        .strip_prefix(" (x")?
+        .strip_suffix(')')?
+        .parse()
+        .ok()
+}
+
+impl AppState<'_> {
+    /// Keeps `message` for :messages and shows it, unless a bulk operation collects it
+    pub fn add_notification(&mut self, message: String) {
+        self.message_history.push(message.clone());
+        if self.message_history.len() > MAX_MESSAGE_HISTORY {
+            self.message_history.remove(0);
+        }
+
+        if let Some(batch) = &mut self.notification_batch {
+            batch.messages.push(message);
+            return;
+        }
+        self.show_notification(message);
+    }
+
+    // The same message several times in a row is shown once with a count
+    fn show_notification(&mut self, message: String) {
+        if let Some(last) = self.notification_messages.last_mut() {
+            if let Some(count) = repeat_count(last, &message) {
+                *last = format!("{message} (x{})", count + 1);
+                return;
+            }
+        }
+
+        self.notification_messages.push(message);
+        if self.notification_messages.len() > self.max_notifications {
+            self.notification_messages.remove(0);
+        }
+    }
+
+    /// Runs a bulk operation, summarizing its notifications when there are too many to read
+    pub fn with_notification_batch<T>(
+        &mut self,
+        label: &str,
+        run: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        // Nested batches report into the outer one
+        if self.notification_batch.is_some() {
+            return run(self);
+        }
+
+        self.notification_batch = Some(NotificationBatch {
+            label: label.to_string(),
+            messages: Vec::new(),
+        });
+        let result = run(self);
+        self.end_notification_batch();
+        result
+    }
+
+    fn end_notification_batch(&mut self) {
+        let Some(NotificationBatch {
+            label,
+            mut messages,
+        }) = self.notification_batch.take()
+        else {
+            return;
+        };
+
+        if messages.len() > MAX_BATCH_NOTIFICATIONS {
+            // The last message is usually the outcome, keep that one in view
+            let last = messages.pop().unwrap_or_default();
+            self.show_notification(format!(
+                "{label}: {} more message(s), :messages shows them",
+                messages.len()
+            ));
+            self.show_notification(last);
+        } else {
+            for message in messages {
+                self.show_notification(message);
+            }
+        }
+    }
+
+    /// :messages: shows every recent notification in a scrollable popup, newest at the bottom
+    pub fn show_messages(&mut self) {
+        if self.message_history.is_empty() {
+            self.add_notification("No messages".to_string());
+            return;
+        }
+
+        // Start at the end, the popup clamps the scroll position when drawn
+        self.cell_popup_scroll = usize::MAX / 2;
+        self.input_mode = InputMode::Messages;
+    }
+}
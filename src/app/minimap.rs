@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+use crate::app::{AppState, InputMode};
+use crate::excel::Sheet;
+use crate::utils::cell_reference;
+
+/// Compressed overview of the current sheet drawn beside the grid, each
+/// character of its strips stands for a run of rows or columns
+pub struct Minimap {
+    sheet_index: usize,
+    revision: u64,
+    row_counts: Vec<usize>, // Non-empty cells per row, 1-based like the sheet
+    col_counts: Vec<usize>, // Non-empty cells per column
+    /// Rows and columns per character of the strips, updated when they are drawn
+    pub rows_per_cell: usize,
+    pub cols_per_cell: usize,
+    /// Cell picked with gm, Enter jumps to it
+    pub target: Option<(usize, usize)>,
+}
+
+impl Minimap {
+    fn build(sheet: &Sheet, sheet_index: usize, revision: u64) -> Self {
+        let mut row_counts = vec![0; sheet.max_rows + 1];
+        let mut col_counts = vec![0; sheet.max_cols + 1];
+
+        for row in sheet.rows() {
+            for cell in row.cells().filter(|cell| !cell.is_empty()) {
+                row_counts[cell.row] += 1;
+                col_counts[cell.col] += 1;
+            }
+        }
+
+        Self {
+            sheet_index,
+            revision,
+            row_counts,
+            col_counts,
+            rows_per_cell: 1,
+            cols_per_cell: 1,
+            target: None,
+        }
+    }
+
+    #[must_use]
+    pub fn max_rows(&self) -> usize {
+        self.row_counts.len().saturating_sub(1)
+    }
+
+    #[must_use]
+    pub fn max_cols(&self) -> usize {
+        self.col_counts.len().saturating_sub(1)
+    }
+
+    /// Share of non-empty cells in the given rows (1-based), between 0 and 1
+    #[must_use]
+    pub fn row_density(&self, rows: Range<usize>) -> f64 {
+        density(&self.row_counts, rows, self.max_cols())
+    }
+
+    /// Share of non-empty cells in the given columns (1-based), between 0 and 1
+    #[must_use]
+    pub fn col_density(&self, cols: Range<usize>) -> f64 {
+        density(&self.col_counts, cols, self.max_rows())
+    }
+}
+
+fn density(counts: &[usize], range: Range<usize>, cross_size: usize) -> f64 {
+    let range = range.start.max(1)..range.end.min(counts.len());
+    if range.is_empty() || cross_size == 0 {
+        return 0.0;
+    }
+
+    let filled: usize = counts[range.clone()].iter().sum();
+    filled as f64 / (range.len() * cross_size) as f64
+}
+
+impl AppState<'_> {
+    pub fn set_minimap(&mut self, enabled: bool) {
+        if enabled {
+            self.refresh_minimap();
+        } else {
+            self.minimap = None;
+        }
+    }
+
+    /// Rebuilds the overview when the sheet or its data changed since it was built
+    pub fn refresh_minimap(&mut self) {
+        let sheet_index = self.workbook.get_current_sheet_index();
+        let revision = self.undo_history.revision();
+        let sheet = self.workbook.get_current_sheet();
+
+        if let Some(map) = &self.minimap {
+            if map.sheet_index == sheet_index
+                && map.revision == revision
+                && map.max_rows() == sheet.max_rows
+                && map.max_cols() == sheet.max_cols
+            {
+                return;
+            }
+        }
+
+        let mut map = Minimap::build(sheet, sheet_index, revision);
+        if let Some(old) = self.minimap.take() {
+            map.rows_per_cell = old.rows_per_cell;
+            map.cols_per_cell = old.cols_per_cell;
+            map.target = old.target;
+        }
+        self.minimap = Some(map);
+    }
+
+    /// gm: picks a spot on the overview with the cursor keys, showing it if it's hidden
+    pub fn start_minimap_selection(&mut self) {
+        self.set_minimap(true);
+        if let Some(map) = &mut self.minimap {
+            map.target = Some(self.selected_cell);
+        }
+        self.input_mode = InputMode::Minimap;
+    }
+
+    /// Moves the picked spot by whole characters of the strips
+    pub fn move_minimap_target(&mut self, rows: isize, cols: isize) {
+        let Some(map) = &mut self.minimap else {
+            return;
+        };
+        let (row, col) = map.target.unwrap_or(self.selected_cell);
+
+        let step_row = rows * map.rows_per_cell as isize;
+        let step_col = cols * map.cols_per_cell as isize;
+        map.target = Some((
+            row.saturating_add_signed(step_row)
+                .clamp(1, map.max_rows().max(1)),
+            col.saturating_add_signed(step_col)
+                .clamp(1, map.max_cols().max(1)),
+        ));
+    }
+
+    pub fn confirm_minimap_selection(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let Some(target) = self.minimap.as_mut().and_then(|map| map.target.take()) else {
+            return;
+        };
+
+        self.record_jump();
+        self.selected_cell = target;
+        self.handle_scrolling();
+        self.add_notification(format!("Jumped to {}", cell_reference(target)));
+    }
+
+    pub fn cancel_minimap_selection(&mut self) {
+        if let Some(map) = &mut self.minimap {
+            map.target = None;
+        }
+        self.input_mode = InputMode::Normal;
+    }
+}
@@ -1,12 +1,93 @@
+mod append;
+mod autosave;
+mod buffers;
+mod cell_renderers;
+mod clipboard;
+mod column_format;
+mod column_types;
+mod columns;
+mod combine;
+mod command_history;
+mod diff;
+mod distinct;
 mod edit;
+mod external_editor;
+mod file_watch;
+mod filter;
+mod flash;
+mod formulas;
+mod jumplist;
+mod lua;
+mod marks;
+mod memory;
+mod messages;
+mod minimap;
+mod mouse;
 mod navigation;
+mod number_display;
+mod pivot;
+mod profile;
+mod progress;
+#[cfg(feature = "python")]
+mod python;
+mod query;
+mod repeat;
+mod replace;
+mod script;
 mod search;
+mod selection;
 mod sheet;
+mod sheet_picker;
+mod split;
+mod sql;
 mod state;
+mod stats;
+mod stream_viewer;
+mod text_transform;
 mod ui;
+mod undo_file;
+mod undo_list;
 mod undo_manager;
+mod validation;
 mod vim;
 mod word;
+mod yank_text;
 
+pub use append::{ColumnMapping, MappingTarget};
+pub use autosave::{Autosave, AutosaveResult};
+pub use buffers::Buffer;
+pub use cell_renderers::{
+    cell_renderers_path, pretty_json, CellRenderer, CellRenderers, CheckmarkRenderer,
+    CollapsedJsonRenderer, ContentKind, LinkRenderer, RelativeTimeRenderer,
+};
+pub use clipboard::{read_system_clipboard, write_system_clipboard, CellRange, Clipboard};
+pub use column_format::ColumnFormat;
+pub use column_types::ColumnTypeSummary;
+pub use columns::{ColumnManager, ManagedColumn};
+pub use command_history::{command_history_path, state_dir, CommandHistory};
+pub use diff::{CellDiff, DiffList, DiffListEntry, WorkbookDiff};
+pub use distinct::DistinctPicker;
+pub use external_editor::{edit_in_external_editor, ExternalEdit};
+pub use file_watch::FileWatch;
+pub use flash::Flash;
+pub use jumplist::JumpList;
+pub use lua::lua_init_path;
+pub use memory::MemoryUsage;
+pub use messages::NotificationBatch;
+pub use minimap::Minimap;
+pub use mouse::{MouseAreas, MouseDrag};
+pub use progress::{Activity, ActivityKind};
+pub use replace::ReplaceJob;
+pub use search::{SearchJob, SearchOrigin};
+pub use sheet_picker::SheetPicker;
+pub use split::SplitPane;
 pub use state::*;
+pub use stats::SelectionStats;
+pub use stream_viewer::{StreamMode, StreamSearch, StreamViewer};
+pub use text_transform::TextCase;
+pub use ui::PendingSave;
+pub use undo_file::undo_file_path;
+pub use undo_list::{UndoList, UndoListEntry};
+pub use validation::ValidationRule;
 pub use vim::*;
+pub use yank_text::{header_rows, TextFormat};
@@ -0,0 +1,182 @@
+use ratatui::layout::Rect;
+
+use crate::app::{AppState, InputMode};
+use crate::utils::index_to_col_name;
+
+// Lines or columns moved by one notch of the mouse wheel
+const WHEEL_STEP: isize = 3;
+
+/// Where the sheet tabs, columns and rows were drawn in the last frame, so mouse events
+/// can be matched to what is under the pointer
+#[derive(Clone, Debug, Default)]
+pub struct MouseAreas {
+    pub tabs: Vec<(Rect, usize)>, // Each sheet tab shown and the index of its sheet
+    pub grid: Rect,               // The grid with the focus, borders included
+    pub columns: Vec<(u16, u16, usize)>, // Start x, width and column of each column shown
+    pub rows: Vec<(u16, u16, usize)>, // Start y, height and row of each data row shown
+    pub content: Rect,            // The panel with the content of the current cell
+    pub header: u16,              // Line of the column letters of the grid
+}
+
+/// What moving the mouse with the left button held does
+#[derive(Clone, Copy, Debug)]
+pub enum MouseDrag {
+    Select,                        // Selects from the cell clicked to the one under the pointer
+    Resize { col: usize, x: u16 }, // Sets the width of `col`, which starts at `x`
+}
+
+fn contains(rect: Rect, x: u16, y: u16) -> bool {
+    (rect.x..rect.x + rect.width).contains(&x) && (rect.y..rect.y + rect.height).contains(&y)
+}
+
+// The item whose span of `start` and `length` holds `position`
+fn find_span(spans: &[(u16, u16, usize)], position: u16) -> Option<usize> {
+    spans
+        .iter()
+        .find(|&&(start, length, _)| (start..start + length).contains(&position))
+        .map(|&(_, _, item)| item)
+}
+
+impl MouseAreas {
+    /// The sheet whose tab is at the position
+    #[must_use]
+    pub fn tab_at(&self, x: u16, y: u16) -> Option<usize> {
+        self.tabs
+            .iter()
+            .find(|&&(rect, _)| contains(rect, x, y))
+            .map(|&(_, index)| index)
+    }
+
+    /// The cell of the grid at the position, as (row, col)
+    #[must_use]
+    pub fn cell_at(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        Some((find_span(&self.rows, y)?, find_span(&self.columns, x)?))
+    }
+
+    /// The column whose right border, the space after it on the header line, is at the
+    /// position, with where the column starts
+    #[must_use]
+    pub fn border_at(&self, x: u16, y: u16) -> Option<(usize, u16)> {
+        if y != self.header {
+            return None;
+        }
+        self.columns
+            .iter()
+            .find(|&&(start, width, _)| start + width == x)
+            .map(|&(start, _, col)| (col, start))
+    }
+
+    /// The cell a drag to the position reaches: the one under it, or past the edge of the
+    /// grid the one just beyond it, so dragging there scrolls
+    #[must_use]
+    pub fn drag_target(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        Some((
+            span_or_beyond(&self.rows, y)?,
+            span_or_beyond(&self.columns, x)?,
+        ))
+    }
+}
+
+// Like find_span, but before the first span the item before it and after the last the
+// item after it
+fn span_or_beyond(spans: &[(u16, u16, usize)], position: u16) -> Option<usize> {
+    let &(first_start, _, first) = spans.first()?;
+    let &(last_start, last_length, last) = spans.last()?;
+    if position < first_start {
+        Some(first.saturating_sub(1).max(1))
+    } else if position >= last_start + last_length {
+        Some(last + 1)
+    } else {
+        find_span(spans, position)
+    }
+}
+
+impl AppState<'_> {
+    /// A left click: on a sheet tab switches to that sheet, on a cell moves the cursor
+    /// there and ends the visual selection, on a column border starts resizing the column
+    pub fn click_at(&mut self, x: u16, y: u16) {
+        self.mouse_drag = None;
+        if let Some((col, x)) = self.mouse_areas.border_at(x, y) {
+            self.mouse_drag = Some(MouseDrag::Resize { col, x });
+            return;
+        }
+
+        if let Some(index) = self.mouse_areas.tab_at(x, y) {
+            if index != self.workbook.get_current_sheet_index() {
+                if let Err(e) = self.switch_sheet_by_index(index) {
+                    self.add_notification(format!("Failed to switch sheet: {e}"));
+                }
+            }
+            return;
+        }
+
+        if let Some(cell) = self.mouse_areas.cell_at(x, y) {
+            self.clear_selection();
+            self.selected_cell = cell;
+            self.handle_scrolling();
+            self.mouse_drag = Some(MouseDrag::Select);
+        }
+    }
+
+    /// Moving with the left button held. After a click on a cell it selects the range
+    /// from that cell to the one under the pointer, as v and moving the cursor would.
+    /// After a click on a column border the border follows the pointer
+    pub fn drag_to(&mut self, x: u16, y: u16) {
+        match self.mouse_drag {
+            Some(MouseDrag::Select) => self.drag_selection(x, y),
+            Some(MouseDrag::Resize { col, x: start }) => {
+                self.set_column_width(col, x.saturating_sub(start).into());
+            }
+            None => {}
+        }
+    }
+
+    /// Letting go of the left button, a resized column tells its new width
+    pub fn end_drag(&mut self) {
+        if let Some(MouseDrag::Resize { col, .. }) = self.mouse_drag.take() {
+            self.add_notification(format!(
+                "Column {} width: {}",
+                index_to_col_name(col),
+                self.get_column_width(col)
+            ));
+        }
+    }
+
+    fn drag_selection(&mut self, x: u16, y: u16) {
+        let Some(cell) = self.mouse_areas.drag_target(x, y) else {
+            return;
+        };
+        if cell == self.selected_cell {
+            return;
+        }
+        self.start_selection();
+        self.selected_cell = cell;
+        self.handle_scrolling();
+    }
+
+    /// A notch of the mouse wheel, `down` for down or right: scrolls the help or the cell
+    /// popup when open, else the content panel or the grid under the pointer
+    pub fn wheel_at(&mut self, x: u16, y: u16, down: bool, horizontal: bool) {
+        let delta = if down { WHEEL_STEP } else { -WHEEL_STEP };
+        match self.input_mode {
+            InputMode::Help if !horizontal => self.scroll_help(delta),
+            InputMode::CellPopup | InputMode::Messages if !horizontal => {
+                self.scroll_cell_popup(delta);
+            }
+            InputMode::Normal => {
+                if contains(self.mouse_areas.content, x, y) {
+                    if !horizontal {
+                        self.scroll_info_panel(delta);
+                    }
+                } else if contains(self.mouse_areas.grid, x, y) {
+                    if horizontal {
+                        self.scroll_columns(delta);
+                    } else {
+                        self.scroll_rows(delta);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
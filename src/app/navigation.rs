@@ -6,7 +6,18 @@ impl AppState<'_> {
     pub fn move_cursor(&mut self, delta_row: isize, delta_col: isize) {
         // Calculate new position
         let new_row = (self.selected_cell.0 as isize + delta_row).max(1) as usize;
-        let new_col = (self.selected_cell.1 as isize + delta_col).max(1) as usize;
+        let mut new_col = (self.selected_cell.1 as isize + delta_col).max(1) as usize;
+
+        // Step over hidden columns, staying put if only hidden ones are left of the cursor
+        while delta_col != 0 && self.is_column_hidden(new_col) {
+            match new_col.checked_add_signed(delta_col.signum()) {
+                Some(col) if col >= 1 => new_col = col,
+                _ => {
+                    new_col = self.selected_cell.1;
+                    break;
+                }
+            }
+        }
 
         // Update selected position
         self.selected_cell = (new_row, new_col);
@@ -16,16 +27,139 @@ impl AppState<'_> {
     }
 
     pub fn handle_scrolling(&mut self) {
-        if self.selected_cell.0 < self.start_row {
-            self.start_row = self.selected_cell.0;
-        } else if self.selected_cell.0 >= self.start_row + self.visible_rows {
-            self.start_row = self.selected_cell.0 - self.visible_rows + 1;
+        // Frozen rows are always drawn, so the scrollable area starts below them
+        if self.start_row <= self.frozen_rows {
+            self.start_row = self.frozen_rows + 1;
+        }
+
+        let row = self.selected_cell.0;
+        let scrollable_rows = self.scrollable_rows();
+
+        if row > self.frozen_rows {
+            if row < self.start_row {
+                self.start_row = row;
+            } else if row >= self.start_row + scrollable_rows {
+                self.start_row = row - scrollable_rows + 1;
+            }
         }
 
         self.handle_column_scrolling();
     }
 
+    /// Ctrl-f/Ctrl-b and PageDown/PageUp: scrolls a screen down or up
+    pub fn scroll_page(&mut self, forward: bool) {
+        let page = self.scrollable_rows() as isize;
+        self.scroll_rows(if forward { page } else { -page });
+    }
+
+    /// Ctrl-d/Ctrl-u: scrolls half a screen down or up
+    pub fn scroll_half_page(&mut self, forward: bool) {
+        let half = (self.scrollable_rows() as isize / 2).max(1);
+        self.scroll_rows(if forward { half } else { -half });
+    }
+
+    /// Moves the view and the cursor together, so the cursor keeps its place on the screen
+    pub fn scroll_rows(&mut self, delta: isize) {
+        let last_row = self.workbook.get_current_sheet().max_rows.max(1);
+        let (row, col) = self.selected_cell;
+        let new_row = row.saturating_add_signed(delta).clamp(1, last_row);
+        if new_row == row {
+            return;
+        }
+
+        // Stop scrolling once the last row is at the bottom of the screen
+        let first_scrollable = self.frozen_rows + 1;
+        let last_start = (last_row + 1)
+            .saturating_sub(self.scrollable_rows())
+            .max(first_scrollable);
+        self.start_row = self
+            .start_row
+            .saturating_add_signed(delta)
+            .clamp(first_scrollable, last_start);
+
+        self.selected_cell = (new_row, col);
+        self.handle_scrolling();
+    }
+
+    /// Moves the view and the cursor sideways together, stepping over hidden columns
+    pub fn scroll_columns(&mut self, delta: isize) {
+        let last_col = self.workbook.get_current_sheet().max_cols.max(1);
+        let (row, col) = self.selected_cell;
+        let mut new_col = col.saturating_add_signed(delta).clamp(1, last_col);
+        while self.is_column_hidden(new_col) {
+            match new_col.checked_add_signed(delta.signum()) {
+                Some(next) if (1..=last_col).contains(&next) => new_col = next,
+                _ => return,
+            }
+        }
+        if new_col == col {
+            return;
+        }
+
+        let first_scrollable = self.frozen_cols + 1;
+        let last_start = (last_col + 1)
+            .saturating_sub(self.visible_cols)
+            .max(first_scrollable);
+        self.start_col = self
+            .start_col
+            .saturating_add_signed(delta)
+            .clamp(first_scrollable, last_start);
+
+        self.selected_cell = (row, new_col);
+        self.handle_scrolling();
+    }
+
+    /// zt, zz and zb: scrolls so the cursor row is at the top, middle or bottom of the screen
+    pub fn scroll_cursor_row_to(&mut self, position: char) {
+        let row = self.selected_cell.0;
+        let first_scrollable = self.frozen_rows + 1;
+        if row < first_scrollable {
+            return;
+        }
+
+        let rows_above = match position {
+            't' => 0,
+            'z' => self.scrollable_rows() / 2,
+            _ => self.scrollable_rows() - 1,
+        };
+        self.start_row = row.saturating_sub(rows_above).max(first_scrollable);
+    }
+
+    /// Number of visible rows left for scrolling once frozen rows are drawn
+    pub fn scrollable_rows(&self) -> usize {
+        self.visible_rows.saturating_sub(self.frozen_rows).max(1)
+    }
+
+    pub fn freeze_panes(&mut self, rows: usize, cols: usize) {
+        if rows > 0 && rows >= self.visible_rows {
+            self.add_notification(format!(
+                "Cannot freeze {rows} rows: only {} rows are visible",
+                self.visible_rows
+            ));
+            return;
+        }
+        // visible_cols only counts the columns that scroll
+        let shown_cols = self.visible_cols + self.frozen_cols;
+        if cols > 0 && cols >= shown_cols {
+            self.add_notification(format!(
+                "Cannot freeze {cols} columns: only {shown_cols} columns are visible"
+            ));
+            return;
+        }
+
+        self.frozen_rows = rows;
+        self.frozen_cols = cols;
+        self.handle_scrolling();
+
+        if rows == 0 && cols == 0 {
+            self.add_notification("Panes unfrozen".to_string());
+        } else {
+            self.add_notification(format!("Froze {rows} rows and {cols} columns"));
+        }
+    }
+
     pub fn jump_to_first_row(&mut self) {
+        self.record_jump();
         let current_col = self.selected_cell.1;
         self.selected_cell = (1, current_col);
         self.handle_scrolling();
@@ -33,6 +167,7 @@ impl AppState<'_> {
     }
 
     pub fn jump_to_last_row(&mut self) {
+        self.record_jump();
         let sheet = self.workbook.get_current_sheet();
         let current_col = self.selected_cell.1;
 
@@ -56,14 +191,10 @@ impl AppState<'_> {
 
         let mut first_non_empty_col = 1; // Default to first column
 
-        if current_row < sheet.data.len() {
-            for col in 1..=sheet.max_cols {
-                if col < sheet.data[current_row].len()
-                    && !sheet.data[current_row][col].value.is_empty()
-                {
-                    first_non_empty_col = col;
-                    break;
-                }
+        for col in 1..=sheet.max_cols {
+            if !sheet.cell(current_row, col).value.is_empty() {
+                first_non_empty_col = col;
+                break;
             }
         }
 
@@ -103,14 +234,12 @@ impl AppState<'_> {
             let sheet = self.workbook.get_current_sheet();
 
             let (row, col) = self.selected_cell;
-            let is_cell_empty = row >= sheet.data.len()
-                || col >= sheet.data[row].len()
-                || sheet.data[row][col].value.is_empty();
+            let is_cell_empty = sheet.cell(row, col).value.is_empty();
 
             let message = if is_cell_empty {
-                format!("Jumped to first non-empty cell ({dir_name})")
+                format!("Jumped to the edge of the sheet ({dir_name})")
             } else {
-                format!("Jumped to last non-empty cell ({dir_name})")
+                format!("Jumped to the edge of the data ({dir_name})")
             };
 
             self.add_notification(message);
@@ -138,6 +267,15 @@ impl AppState<'_> {
     }
 
     pub fn ensure_column_visible(&mut self, column: usize) {
+        // Frozen columns are always drawn, so the scrollable area starts right of them
+        if self.start_col <= self.frozen_cols {
+            self.start_col = self.frozen_cols + 1;
+        }
+
+        if column <= self.frozen_cols {
+            return;
+        }
+
         // If column is to the left of visible area, adjust start_col
         if column < self.start_col {
             self.start_col = column;
@@ -148,7 +286,7 @@ impl AppState<'_> {
 
         // If column is to the right of visible area, adjust start_col to make it visible
         if column > last_visible_col {
-            self.start_col = (column - self.visible_cols + 1).max(1);
+            self.start_col = (column - self.visible_cols + 1).max(self.frozen_cols + 1);
             return;
         }
 
@@ -160,7 +298,7 @@ impl AppState<'_> {
         if column < max_col && column == last_visible_col && self.visible_cols > 1 {
             // Adjust start column to show more columns to the left
             // This creates a margin on the right
-            self.start_col = (column - (self.visible_cols - 2)).max(1);
+            self.start_col = (column - (self.visible_cols - 2)).max(self.frozen_cols + 1);
         }
     }
 }
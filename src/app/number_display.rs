@@ -0,0 +1,78 @@
+use crate::app::AppState;
+use crate::excel::{Cell, CellType, DataTypeInfo};
+use crate::utils::{format_duration, format_serial_date, NumberFormat};
+
+impl AppState<'_> {
+    /// :set numfmt=#,##0.00 shows numbers in the grid with that format, :set nonumfmt as
+    /// stored. The values themselves stay as they are
+    pub fn set_number_format(&mut self, code: &str) {
+        let code = code.trim();
+        if code.is_empty() {
+            self.number_format = None;
+            self.add_notification("Numbers shown as stored".to_string());
+            return;
+        }
+        match NumberFormat::parse(code) {
+            Ok(format) => {
+                let example = if format.is_date() {
+                    45000.75
+                } else {
+                    -1234.5678
+                };
+                self.add_notification(format!(
+                    "Numbers shown as {code}, e.g. {}",
+                    format.format(example)
+                ));
+                self.number_format = Some(format);
+            }
+            Err(e) => self.add_notification(format!("Invalid number format: {e}")),
+        }
+    }
+
+    // The number format a cell of the current sheet has in its file or got from
+    // :set smartnumbers, `None` for General and formats that can't be shown
+    fn source_format(&self, cell: &Cell) -> Option<NumberFormat> {
+        let style = self.workbook.get_current_sheet().styles.get(cell.style);
+        match &style.number_format_code {
+            Some(code) => NumberFormat::parse(code).ok(),
+            None => style.number_format.and_then(NumberFormat::builtin),
+        }
+    }
+
+    /// The text of a cell in its display format, `None` for anything else. A :format of
+    /// the column comes first, below the header rows. Numbers take :set numfmt, or else the format of the cell
+    /// such as `0.0%`. Dates and
+    /// durations read from Excel files are serial numbers, shown in the date format of
+    /// the cell or else as 2023-01-01 and 36:15:00
+    #[must_use]
+    pub fn formatted_number(&self, cell: &Cell, row: usize, col: usize) -> Option<String> {
+        if cell.is_formula {
+            return None;
+        }
+        let column_format = self
+            .column_format(col)
+            .filter(|_| row >= self.first_data_row());
+        if let Some(text) = column_format.and_then(|format| format.format(cell)) {
+            return Some(text);
+        }
+        let date_format = || self.source_format(cell).filter(NumberFormat::is_date);
+        match (&cell.cell_type, &cell.original_type) {
+            (CellType::Date, Some(DataTypeInfo::DateTime(serial))) => match date_format() {
+                Some(format) => Some(format.format(*serial)),
+                None => format_serial_date(*serial),
+            },
+            (CellType::Date, Some(DataTypeInfo::Duration(days))) => Some(match date_format() {
+                Some(format) => format.format(*days),
+                None => format_duration(*days),
+            }),
+            (CellType::Number, _) => {
+                let value = cell.value.trim().parse::<f64>().ok()?;
+                match &self.number_format {
+                    Some(format) => Some(format.format(value)),
+                    None => Some(self.source_format(cell)?.format(value)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+
+use crate::app::query::find_column;
+use crate::app::script::number_text;
+use crate::app::AppState;
+use crate::excel::{Cell, CellGrid, Sheet, StyleTable};
+use crate::utils::{index_to_col_name, split_arguments};
+
+/// How the values falling into one cell of the pivot table are combined
+#[derive(Clone, Copy)]
+enum Aggregate {
+    Sum,
+    Count,
+    Average,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "sum" => Some(Self::Sum),
+            "count" => Some(Self::Count),
+            "avg" | "average" | "mean" => Some(Self::Average),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sum => "sum",
+            Self::Count => "count",
+            Self::Average => "avg",
+            Self::Min => "min",
+            Self::Max => "max",
+        }
+    }
+}
+
+// The values of one cell of the table. Count takes every filled cell, the others only
+// numbers
+#[derive(Default)]
+struct Group {
+    count: usize,
+    numbers: Vec<f64>,
+}
+
+impl Group {
+    fn add(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        self.count += 1;
+        if let Ok(number) = value.parse::<f64>() {
+            self.numbers.push(number);
+        }
+    }
+
+    fn result(&self, aggregate: Aggregate) -> String {
+        let sum: f64 = self.numbers.iter().sum();
+        match aggregate {
+            Aggregate::Count => self.count.to_string(),
+            _ if self.numbers.is_empty() => String::new(),
+            Aggregate::Sum => number_text(sum),
+            Aggregate::Average => number_text(sum / self.numbers.len() as f64),
+            Aggregate::Min => {
+                number_text(self.numbers.iter().copied().fold(f64::INFINITY, f64::min))
+            }
+            Aggregate::Max => number_text(
+                self.numbers
+                    .iter()
+                    .copied()
+                    .fold(f64::NEG_INFINITY, f64::max),
+            ),
+        }
+    }
+}
+
+/// A row or column label, numbers sort by value before text and blanks come last
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key(String);
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0 == BLANK, other.0 == BLANK) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            _ => {}
+        }
+        match (self.0.parse::<f64>(), other.0.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.total_cmp(&b).then_with(|| self.0.cmp(&other.0)),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            _ => self.0.cmp(&other.0),
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Rows without a label are grouped under this one
+const BLANK: &str = "(blank)";
+
+fn key(value: &str) -> Key {
+    let value = value.trim();
+    Key(if value.is_empty() { BLANK } else { value }.to_string())
+}
+
+// A column is named by its header, or by its letter when the header is empty
+fn column_label(headers: &[String], col: usize) -> String {
+    match headers[col - 1].as_str() {
+        "" => index_to_col_name(col),
+        header => header.to_string(),
+    }
+}
+
+/// What :pivot was asked for, columns as 1-based indexes
+struct Pivot {
+    rows: usize,
+    columns: Option<usize>,
+    value: usize,
+    aggregate: Aggregate,
+}
+
+impl Pivot {
+    fn parse(input: &str, headers: &[String]) -> Result<Self> {
+        let arguments = split_arguments(input);
+        let (rows, columns, value, aggregate) = match arguments.as_slice() {
+            [rows, value] => (rows, "-", value, "sum"),
+            // rows value agg, unless a column is named like the aggregate
+            [rows, value, aggregate]
+                if Aggregate::parse(aggregate).is_some()
+                    && find_column(headers, aggregate).is_err() =>
+            {
+                (rows, "-", value, aggregate.as_str())
+            }
+            [rows, columns, value] => (rows, columns.as_str(), value, "sum"),
+            [rows, columns, value, aggregate] => {
+                (rows, columns.as_str(), value, aggregate.as_str())
+            }
+            _ => bail!("Usage: :pivot rows [cols|-] value [sum|count|avg|min|max]"),
+        };
+        let Some(aggregate) = Aggregate::parse(aggregate) else {
+            bail!("Unknown aggregate {aggregate}, use sum, count, avg, min or max")
+        };
+        Ok(Self {
+            rows: find_column(headers, rows)?,
+            columns: match columns {
+                "-" => None,
+                name => Some(find_column(headers, name)?),
+            },
+            value: find_column(headers, value)?,
+            aggregate,
+        })
+    }
+
+    // The table with a row per row label and a column per column label, totals last
+    fn build(&self, source: &Sheet, headers: &[String], name: &str) -> Sheet {
+        let mut cells: HashMap<(Key, Key), Group> = HashMap::new();
+        let mut row_totals: BTreeMap<Key, Group> = BTreeMap::new();
+        let mut column_totals: BTreeMap<Key, Group> = BTreeMap::new();
+        let mut total = Group::default();
+        let single = Key(format!(
+            "{} of {}",
+            self.aggregate.name(),
+            column_label(headers, self.value)
+        ));
+
+        for row in 2..=source.max_rows {
+            let row_key = key(&source.cell(row, self.rows).value);
+            let column_key = match self.columns {
+                Some(col) => key(&source.cell(row, col).value),
+                None => single.clone(),
+            };
+            let value = &source.cell(row, self.value).value;
+            cells
+                .entry((row_key.clone(), column_key.clone()))
+                .or_default()
+                .add(value);
+            row_totals.entry(row_key).or_default().add(value);
+            column_totals.entry(column_key).or_default().add(value);
+            total.add(value);
+        }
+
+        // Without a column grouping the only column is already the total
+        let with_totals = self.columns.is_some();
+        let column_keys: Vec<&Key> = column_totals.keys().collect();
+        let max_cols = 1 + column_keys.len() + usize::from(with_totals);
+        let max_rows = 1 + row_totals.len() + 1;
+        let mut data = CellGrid::with_size(max_rows + 1, max_cols + 1);
+        let mut set = |row: usize, col: usize, value: String| {
+            if !value.is_empty() {
+                data.set(row, col, Cell::new(value, false));
+            }
+        };
+
+        set(1, 1, column_label(headers, self.rows));
+        for (i, column_key) in column_keys.iter().enumerate() {
+            set(1, i + 2, column_key.0.clone());
+        }
+        if with_totals {
+            set(1, max_cols, "Total".to_string());
+        }
+
+        for (i, (row_key, row_total)) in row_totals.iter().enumerate() {
+            let row = i + 2;
+            set(row, 1, row_key.0.clone());
+            for (j, &column_key) in column_keys.iter().enumerate() {
+                if let Some(group) = cells.get(&(row_key.clone(), column_key.clone())) {
+                    set(row, j + 2, group.result(self.aggregate));
+                }
+            }
+            if with_totals {
+                set(row, max_cols, row_total.result(self.aggregate));
+            }
+        }
+
+        set(max_rows, 1, "Total".to_string());
+        for (j, group) in column_totals.values().enumerate() {
+            set(max_rows, j + 2, group.result(self.aggregate));
+        }
+        if with_totals {
+            set(max_rows, max_cols, total.result(self.aggregate));
+        }
+
+        Sheet {
+            name: name.to_string(),
+            data,
+            max_rows,
+            max_cols,
+            is_loaded: true,
+            styles: StyleTable::default(),
+        }
+    }
+}
+
+impl AppState<'_> {
+    /// :pivot rows [cols] value [agg]: groups the rows of the current sheet by the values of
+    /// one column, and optionally another one across, and combines a third column for each
+    /// group in a new sheet
+    pub fn create_pivot(&mut self, input: &str) {
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Pivot: {e}"));
+            return;
+        }
+
+        let source = self.workbook.get_current_sheet();
+        let headers: Vec<String> = (1..=source.max_cols)
+            .map(|col| source.cell(1, col).value.trim().to_string())
+            .collect();
+        let pivot = match Pivot::parse(input, &headers) {
+            Ok(pivot) => pivot,
+            Err(e) => {
+                self.add_notification(format!("Pivot: {e}"));
+                return;
+            }
+        };
+        let name = self.free_sheet_name("Pivot");
+        let sheet = pivot.build(self.workbook.get_current_sheet(), &headers, &name);
+
+        let groups = sheet.max_rows - 2;
+        let column_widths = vec![15; sheet.max_cols + 1];
+        let sheet_index = self.workbook.get_current_sheet_index() + 1;
+        match self.insert_new_sheet(sheet, sheet_index, column_widths) {
+            Ok(()) => self.add_notification(format!("{groups} group(s) in sheet {name}")),
+            Err(e) => self.add_notification(format!("Pivot: {e}")),
+        }
+    }
+}
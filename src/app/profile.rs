@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use crate::app::AppState;
+use crate::excel::{
+    import_profiles_path, load_import_profiles, save_import_profile, ImportProfile,
+};
+
+impl AppState<'_> {
+    /// Shows the profile the file was opened with
+    pub fn show_profile(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("Using profile '{}'", profile.display_name()),
+            None => "No profile in use, save one with :profile save [name]".to_string(),
+        }
+    }
+
+    /// Saves the current delimiter, headers and column transforms as a named profile
+    /// that can be reused with `--profile <name>`. The headers are the ones of the last
+    /// JSON export, else the ones of the profile the file was opened with
+    pub fn save_profile(&mut self, name: &str) {
+        if name.is_empty() {
+            self.add_notification("Usage: :profile save [name]".to_string());
+            return;
+        }
+
+        let path = import_profiles_path();
+        let existing = match load_import_profiles(&path) {
+            Ok(profiles) => profiles.into_iter().find(|p| p.is_named(name)),
+            Err(e) => {
+                self.add_notification(format!("Failed to save profile: {e}"));
+                return;
+            }
+        };
+
+        // Transforms come from the profile in use, settings the session can't
+        // change are kept from the profile being replaced
+        let columns = self
+            .profile
+            .as_ref()
+            .map(|p| p.columns.clone())
+            .or_else(|| existing.as_ref().map(|p| p.columns.clone()))
+            .unwrap_or_default();
+
+        let encoding = self
+            .profile
+            .as_ref()
+            .and_then(|p| p.encoding)
+            .or_else(|| existing.as_ref().and_then(|p| p.encoding));
+
+        let (header_direction, header_rows) = match self.json_headers {
+            Some((direction, rows)) => (Some(direction.as_str().to_string()), Some(rows)),
+            None => self.profile.as_ref().map_or((None, None), |p| {
+                (p.header_direction.clone(), p.header_rows)
+            }),
+        };
+
+        let profile = ImportProfile {
+            name: Some(name.to_string()),
+            pattern: existing.and_then(|p| p.pattern),
+            delimiter: self.current_delimiter(),
+            header_rows,
+            header_direction,
+            encoding,
+            columns,
+        };
+
+        match save_import_profile(&path, profile.clone()) {
+            Ok(()) => {
+                self.add_notification(format!("Profile '{name}' saved to {}", path.display()));
+                self.profile = Some(profile);
+            }
+            Err(e) => self.add_notification(format!("Failed to save profile: {e}")),
+        }
+    }
+
+    // Separator of the open CSV/TSV file, None for other formats
+    fn current_delimiter(&self) -> Option<char> {
+        if let Some(delimiter) = self.workbook.delimiter() {
+            return Some(char::from(delimiter));
+        }
+
+        let extension = Path::new(self.workbook.get_file_path())
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("csv") => Some(','),
+            Some("tsv" | "tab") => Some('\t'),
+            _ => None,
+        }
+    }
+}
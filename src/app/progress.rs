@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use crate::app::AppState;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    Replace,
+    Search,
+    SheetLoad,
+    Save,
+}
+
+/// A long operation still running, drawn as a progress bar in the status bar so a
+/// large sheet being loaded, saved, searched or replaced doesn't look frozen
+pub struct Activity {
+    pub kind: ActivityKind,
+    pub label: String,
+    pub ratio: Option<f64>, // Fraction done, None when it can't be told and a spinner is shown
+    pub elapsed: Duration,
+}
+
+impl AppState<'_> {
+    /// Operations running right now, the ones holding the keyboard first
+    #[must_use]
+    pub fn activities(&self) -> Vec<Activity> {
+        let mut activities = Vec::new();
+
+        if let Some(job) = &self.replace_job {
+            activities.push(Activity {
+                kind: ActivityKind::Replace,
+                label: format!(
+                    "Replacing \"{}\": row {}/{}, {} occurrence(s) in {} cell(s)",
+                    job.pattern(),
+                    job.rows_done(),
+                    job.total_rows(),
+                    job.replacements(),
+                    job.cells_changed()
+                ),
+                ratio: Some(job.progress()),
+                elapsed: job.elapsed(),
+            });
+        }
+
+        if let Some(job) = &self.search_job {
+            activities.push(Activity {
+                kind: ActivityKind::Search,
+                label: format!(
+                    "Searching \"{}\": row {}/{}, {} match(es)",
+                    job.query(),
+                    job.rows_done(),
+                    job.total_rows(),
+                    job.matches()
+                ),
+                ratio: Some(job.progress()),
+                elapsed: job.elapsed(),
+            });
+        }
+
+        if let Some((name, elapsed)) = self.workbook.loading_sheet() {
+            activities.push(Activity {
+                kind: ActivityKind::SheetLoad,
+                label: format!("Loading sheet {name}"),
+                ratio: self.workbook.sheet_load_progress(),
+                elapsed,
+            });
+        }
+
+        if let Some((path, elapsed)) = self.workbook.saving() {
+            let file_name = path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            activities.push(Activity {
+                kind: ActivityKind::Save,
+                label: format!("Saving {file_name}"),
+                ratio: self.workbook.save_progress(),
+                elapsed,
+            });
+        }
+
+        activities
+    }
+}
@@ -0,0 +1,306 @@
+use std::ffi::CStr;
+use std::mem;
+use std::time::Duration;
+
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyRuntimeError, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyString};
+
+use crate::app::script::{number_text, ScriptEdits};
+use crate::app::AppState;
+use crate::excel::Sheet;
+use crate::utils::{parse_cell_reference, MAX_COLS, MAX_ROWS};
+
+// A script running longer than this is stopped, it would otherwise hang the UI
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Runs a snippet with its output captured, showing the value of an expression like the
+// Python prompt does. A timer raises an exception in the script when it runs too long
+const RUNNER: &CStr = cr#"
+import ctypes
+import sys
+import threading
+
+
+class TimeoutError(TimeoutError):
+    pass
+
+
+def run(code, scope, output, limit):
+    try:
+        expression = compile(code, "<py>", "eval")
+    except SyntaxError:
+        expression = None
+        statements = compile(code, "<py>", "exec")
+
+    thread = ctypes.c_ulong(threading.get_ident())
+
+    def stop():
+        ctypes.pythonapi.PyThreadState_SetAsyncExc(thread, ctypes.py_object(TimeoutError))
+
+    timer = threading.Timer(limit, stop)
+    stdout, stderr = sys.stdout, sys.stderr
+    sys.stdout = sys.stderr = output
+    timer.start()
+    try:
+        if expression is None:
+            exec(statements, scope)
+            return None
+        return eval(expression, scope)
+    except TimeoutError:
+        raise TimeoutError(f"the script ran for more than {limit:g}s") from None
+    finally:
+        timer.cancel()
+        timer.join()
+        ctypes.pythonapi.PyThreadState_SetAsyncExc(thread, None)
+        sys.stdout, sys.stderr = stdout, stderr
+"#;
+
+/// The current sheet as the `sheet` object of a Python script. It owns the sheet while
+/// the script runs and hands it back afterwards
+#[pyclass(unsendable, name = "Sheet")]
+struct ScriptSheet {
+    sheet: Option<Sheet>, // None once the script is over
+    edits: ScriptEdits,
+    cursor: (usize, usize),
+    selection: ((usize, usize), (usize, usize)),
+}
+
+impl ScriptSheet {
+    fn sheet(&self) -> PyResult<&Sheet> {
+        self.sheet
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("the sheet is only there while the script runs"))
+    }
+
+    fn row_list(&self, row: usize) -> PyResult<Vec<String>> {
+        let sheet = self.sheet()?;
+        let mut values = vec![String::new(); self.edits.max_col(sheet)];
+        for (col, value) in self.edits.row_values(sheet, row) {
+            values[col - 1] = value.to_string();
+        }
+        Ok(values)
+    }
+}
+
+// A cell given as "B3" or as a (row, column) pair, both 1-based and within XFD1048576
+fn cell_key(key: &Bound<'_, PyAny>) -> PyResult<(usize, usize)> {
+    let position = match key.extract::<String>() {
+        Ok(reference) => parse_cell_reference(&reference),
+        Err(_) => key.extract::<(usize, usize)>().ok(),
+    };
+    match position {
+        Some((row, col)) if row > MAX_ROWS || col > MAX_COLS => {
+            Err(PyIndexError::new_err(format!(
+                "cell beyond XFD{MAX_ROWS}, sheets have {MAX_ROWS} rows and {MAX_COLS} columns"
+            )))
+        }
+        Some((row, col)) if row > 0 && col > 0 => Ok((row, col)),
+        _ => Err(PyKeyError::new_err(
+            "expected a cell like \"B3\" or a (row, column) pair starting at 1",
+        )),
+    }
+}
+
+// How a value set from Python reads in a cell, None clears it
+fn cell_text(value: &Bound<'_, PyAny>) -> PyResult<String> {
+    if value.is_none() {
+        Ok(String::new())
+    } else if let Ok(b) = value.downcast::<PyBool>() {
+        Ok(if b.is_true() { "TRUE" } else { "FALSE" }.to_string())
+    } else if value.is_instance_of::<PyInt>() {
+        Ok(value.str()?.to_string())
+    } else if let Ok(n) = value.downcast::<PyFloat>() {
+        Ok(number_text(n.value()))
+    } else if let Ok(s) = value.downcast::<PyString>() {
+        Ok(s.to_str()?.to_string())
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "a {} can't be stored in a cell",
+            value.get_type().name()?
+        )))
+    }
+}
+
+#[pymethods]
+impl ScriptSheet {
+    fn __getitem__(&self, key: &Bound<'_, PyAny>) -> PyResult<String> {
+        let (row, col) = cell_key(key)?;
+        Ok(self.edits.value(self.sheet()?, row, col).to_string())
+    }
+
+    fn __setitem__(&mut self, key: &Bound<'_, PyAny>, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.sheet()?;
+        let position = cell_key(key)?;
+        let text = cell_text(value)?;
+        self.edits.changes.insert(position, text);
+        Ok(())
+    }
+
+    fn __delitem__(&mut self, key: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.sheet()?;
+        let position = cell_key(key)?;
+        self.edits.changes.insert(position, String::new());
+        Ok(())
+    }
+
+    /// The values of a row as a list, column A first
+    fn row(&self, row: usize) -> PyResult<Vec<String>> {
+        self.row_list(row)
+    }
+
+    /// Iterates over the rows as (row, values) pairs
+    #[pyo3(signature = (first = 1, last = None))]
+    fn rows(slf: PyRef<'_, Self>, first: usize, last: Option<usize>) -> PyResult<RowIter> {
+        let last = match last {
+            Some(last) => last,
+            None => slf.edits.max_row(slf.sheet()?),
+        };
+        Ok(RowIter {
+            sheet: slf.into(),
+            row: first.max(1),
+            last,
+        })
+    }
+
+    #[getter]
+    fn name(&self) -> PyResult<String> {
+        Ok(self.sheet()?.name.clone())
+    }
+
+    #[getter]
+    fn max_row(&self) -> PyResult<usize> {
+        Ok(self.edits.max_row(self.sheet()?))
+    }
+
+    #[getter]
+    fn max_col(&self) -> PyResult<usize> {
+        Ok(self.edits.max_col(self.sheet()?))
+    }
+
+    /// Row and column of the selected cell
+    #[getter]
+    fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Top, left, bottom and right of the selected range
+    #[getter]
+    fn selection(&self) -> (usize, usize, usize, usize) {
+        let ((top, left), (bottom, right)) = self.selection;
+        (top, left, bottom, right)
+    }
+
+    /// Adds a sheet once the script is over
+    fn add_sheet(&mut self, name: String) -> PyResult<()> {
+        self.sheet()?;
+        self.edits.new_sheets.push(name);
+        Ok(())
+    }
+}
+
+#[pyclass(unsendable)]
+struct RowIter {
+    sheet: Py<ScriptSheet>,
+    row: usize,
+    last: usize,
+}
+
+#[pymethods]
+impl RowIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<(usize, Vec<String>)>> {
+        if slf.row > slf.last {
+            return Ok(None);
+        }
+        let row = slf.row;
+        let values = slf.sheet.borrow(slf.py()).row_list(row)?;
+        slf.row += 1;
+        Ok(Some((row, values)))
+    }
+}
+
+// Runs the code with `sheet` in scope, returning what it printed and the value of an
+// expression, None for statements
+fn run_python_code(
+    py: Python<'_>,
+    code: &str,
+    sheet: &Bound<'_, ScriptSheet>,
+) -> PyResult<(String, PyResult<Option<String>>)> {
+    let runner = PyModule::from_code(py, RUNNER, c"runner.py", c"runner")?;
+    let scope = PyDict::new(py);
+    scope.set_item("sheet", sheet)?;
+    let output = py.import("io")?.getattr("StringIO")?.call0()?;
+
+    let result = runner
+        .getattr("run")?
+        .call1((code, scope, &output, SCRIPT_TIMEOUT.as_secs_f64()))
+        .and_then(|value| {
+            if value.is_none() {
+                Ok(None)
+            } else {
+                Ok(Some(value.str()?.to_string()))
+            }
+        });
+    let printed = output.call_method0("getvalue")?.extract()?;
+    Ok((printed, result))
+}
+
+impl AppState<'_> {
+    /// :py [code]: runs Python on the current sheet, which scripts reach as `sheet`
+    pub fn run_python(&mut self, code: &str) {
+        let code = code.trim();
+        if code.is_empty() {
+            self.add_notification("Usage: :py [code], e.g. :py sheet[\"A1\"] = 42".to_string());
+            return;
+        }
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Python: {e}"));
+            return;
+        }
+
+        let cursor = self.selected_cell;
+        let selection = self.selection_range().unwrap_or((cursor, cursor));
+        let name = self.workbook.get_current_sheet_name();
+
+        let (edits, result) = Python::with_gil(|py| {
+            let script_sheet = match Bound::new(
+                py,
+                ScriptSheet {
+                    sheet: None,
+                    edits: ScriptEdits::default(),
+                    cursor,
+                    selection,
+                },
+            ) {
+                Ok(script_sheet) => script_sheet,
+                Err(e) => return (ScriptEdits::default(), Err(e.to_string())),
+            };
+
+            // The script gets the sheet itself rather than a copy, a giant sheet stays cheap
+            let sheet = mem::replace(self.workbook.get_current_sheet_mut(), Sheet::empty(&name));
+            script_sheet.borrow_mut().sheet = Some(sheet);
+            let run = run_python_code(py, code, &script_sheet);
+            let mut script_sheet = script_sheet.borrow_mut();
+            if let Some(sheet) = script_sheet.sheet.take() {
+                *self.workbook.get_current_sheet_mut() = sheet;
+            }
+            let mut edits = mem::take(&mut script_sheet.edits);
+
+            match run {
+                Ok((printed, result)) => {
+                    edits.output.extend(printed.lines().map(str::to_string));
+                    let result = result
+                        .map(|value| value.into_iter().collect())
+                        .map_err(|e| e.to_string());
+                    (edits, result)
+                }
+                Err(e) => (edits, Err(e.to_string())),
+            }
+        });
+        self.finish_script("Python", edits, result);
+    }
+}
@@ -0,0 +1,454 @@
+use std::cmp::Ordering;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::app::script::number_text;
+use crate::app::AppState;
+use crate::excel::{CellGrid, Sheet};
+use crate::utils::col_name_to_index;
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Name(String), // A column, `quoted` when its header has spaces
+    Op(&'static str),
+    Open,
+    Close,
+    Comma,
+    Pipe, // Starts the list of columns to keep
+}
+
+// Two-character operators first so "<=" isn't read as "<"
+const OPERATORS: [&str; 17] = [
+    "==", "!=", "<>", "<=", ">=", "&&", "||", "=", "<", ">", "!", "+", "-", "*", "/", "%", "~",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse()
+                .map_err(|_| anyhow!("{text} is not a number"))?;
+            tokens.push(Token::Number(number));
+        } else if c == '"' || c == '\'' || c == '`' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => bail!("{c} is never closed"),
+                    Some('\\') if chars.get(i + 1) == Some(&c) => {
+                        text.push(c);
+                        i += 2;
+                    }
+                    Some(&next) if next == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&next) => {
+                        text.push(next);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(if c == '`' {
+                Token::Name(text)
+            } else {
+                Token::Text(text)
+            });
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(match word.to_lowercase().as_str() {
+                "and" => Token::Op("&&"),
+                "or" => Token::Op("||"),
+                "not" => Token::Op("!"),
+                "contains" => Token::Op("~"),
+                _ => Token::Name(word),
+            });
+        } else if c == '(' {
+            tokens.push(Token::Open);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::Close);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else if c == '|' && chars.get(i + 1) != Some(&'|') {
+            tokens.push(Token::Pipe);
+            i += 1;
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| anyhow!("Unexpected {c}"))?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+enum Expr {
+    Column(usize),
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+}
+
+// The column a name refers to: a header, ignoring case, or else a column letter
+pub(super) fn find_column(headers: &[String], name: &str) -> Result<usize> {
+    if let Some(i) = headers.iter().position(|h| h.eq_ignore_ascii_case(name)) {
+        return Ok(i + 1);
+    }
+    match col_name_to_index(name) {
+        Some(col) if col > 0 && col <= headers.len() && name.len() <= 3 => Ok(col),
+        _ => bail!("No column named {name}"),
+    }
+}
+
+// Recursive descent, from the loosest binding operator: or, and, not, comparisons,
+// + and -, then * / and %
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    headers: &'a [String],
+}
+
+impl Parser<'_> {
+    fn peek_op(&self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[&'static str],
+        next: fn(&mut Self) -> Result<Expr>,
+    ) -> Result<Expr> {
+        let mut left = next(self)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            let right = next(self)?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        self.binary(&["||"], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        self.binary(&["&&"], Self::not)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.peek_op(&["!"]).is_some() {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        self.binary(
+            &["==", "=", "!=", "<>", "<", "<=", ">", ">=", "~"],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        self.binary(&["+", "-"], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        self.binary(&["*", "/", "%"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek_op(&["-"]).is_some() {
+            self.pos += 1;
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("The expression ends too early"))?;
+        self.pos += 1;
+        Ok(match token {
+            Token::Number(n) => Expr::Number(n),
+            Token::Text(text) => Expr::Text(text),
+            Token::Name(name) if name.eq_ignore_ascii_case("true") => Expr::Bool(true),
+            Token::Name(name) if name.eq_ignore_ascii_case("false") => Expr::Bool(false),
+            Token::Name(name) => Expr::Column(find_column(self.headers, &name)?),
+            Token::Open => {
+                let expr = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    bail!("( is never closed");
+                }
+                self.pos += 1;
+                expr
+            }
+            _ => bail!("Expected a value or a column"),
+        })
+    }
+}
+
+#[derive(Clone)]
+enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn from_cell(value: &str) -> Self {
+        match value.trim().parse::<f64>() {
+            Ok(n) if !value.trim().is_empty() => Value::Number(n),
+            _ => Value::Text(value.to_string()),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Value::Number(n) => number_text(*n),
+            Value::Text(text) => text.clone(),
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+
+    fn is_true(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.0,
+            Value::Text(text) => !text.is_empty(),
+            Value::Bool(b) => *b,
+        }
+    }
+
+    // Empty cells count as 0 in arithmetic, like in a formula
+    fn number(&self) -> Result<f64> {
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::Text(text) if text.trim().is_empty() => Ok(0.0),
+            _ => bail!("{} is not a number", self.text()),
+        }
+    }
+}
+
+// Numbers compare as numbers and text as text. A number and text don't order, so an
+// empty cell is never > 100
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn evaluate(expr: &Expr, row: &dyn Fn(usize) -> Value) -> Result<Value> {
+    Ok(match expr {
+        Expr::Column(col) => row(*col),
+        Expr::Number(n) => Value::Number(*n),
+        Expr::Text(text) => Value::Text(text.clone()),
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::Not(inner) => Value::Bool(!evaluate(inner, row)?.is_true()),
+        Expr::Negate(inner) => Value::Number(-evaluate(inner, row)?.number()?),
+        Expr::Binary(left, "&&", right) => {
+            Value::Bool(evaluate(left, row)?.is_true() && evaluate(right, row)?.is_true())
+        }
+        Expr::Binary(left, "||", right) => {
+            Value::Bool(evaluate(left, row)?.is_true() || evaluate(right, row)?.is_true())
+        }
+        Expr::Binary(left, op, right) => {
+            let left = evaluate(left, row)?;
+            let right = evaluate(right, row)?;
+            match *op {
+                "==" | "=" => Value::Bool(
+                    compare(&left, &right)
+                        .map_or_else(|| left.text() == right.text(), Ordering::is_eq),
+                ),
+                "!=" | "<>" => Value::Bool(
+                    compare(&left, &right)
+                        .map_or_else(|| left.text() != right.text(), Ordering::is_ne),
+                ),
+                "<" => Value::Bool(compare(&left, &right).is_some_and(Ordering::is_lt)),
+                "<=" => Value::Bool(compare(&left, &right).is_some_and(Ordering::is_le)),
+                ">" => Value::Bool(compare(&left, &right).is_some_and(Ordering::is_gt)),
+                ">=" => Value::Bool(compare(&left, &right).is_some_and(Ordering::is_ge)),
+                "~" => Value::Bool(left.text().contains(&right.text())),
+                "+" => Value::Number(left.number()? + right.number()?),
+                "-" => Value::Number(left.number()? - right.number()?),
+                "*" => Value::Number(left.number()? * right.number()?),
+                "/" | "%" => {
+                    let divisor = right.number()?;
+                    if divisor == 0.0 {
+                        bail!("Division by zero");
+                    }
+                    if *op == "/" {
+                        Value::Number(left.number()? / divisor)
+                    } else {
+                        Value::Number(left.number()? % divisor)
+                    }
+                }
+                _ => bail!("Unknown operator {op}"),
+            }
+        }
+    })
+}
+
+/// A parsed :q query, the filter and the columns to keep, all of them when empty
+struct RowQuery {
+    filter: Option<Expr>,
+    columns: Vec<usize>,
+}
+
+impl RowQuery {
+    fn parse(input: &str, headers: &[String]) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let (filter_tokens, column_tokens) = match tokens.iter().position(|t| *t == Token::Pipe) {
+            Some(pipe) => (&tokens[..pipe], &tokens[pipe + 1..]),
+            None => (&tokens[..], &[][..]),
+        };
+
+        let filter = if filter_tokens.is_empty() {
+            None
+        } else {
+            let mut parser = Parser {
+                tokens: filter_tokens,
+                pos: 0,
+                headers,
+            };
+            let expr = parser.or()?;
+            if parser.pos < filter_tokens.len() {
+                bail!("Unexpected text after the expression, join conditions with and/or");
+            }
+            Some(expr)
+        };
+
+        let mut columns = Vec::new();
+        for (i, token) in column_tokens.iter().enumerate() {
+            match token {
+                Token::Name(name) if i % 2 == 0 => columns.push(find_column(headers, name)?),
+                Token::Comma if i % 2 == 1 => {}
+                _ => bail!("List the columns to keep after | separated by commas"),
+            }
+        }
+        if column_tokens.len() % 2 == 0 && !column_tokens.is_empty() {
+            bail!("List the columns to keep after | separated by commas");
+        }
+        if filter.is_none() && columns.is_empty() {
+            bail!("Nothing to query, give a condition and/or | columns");
+        }
+        Ok(Self { filter, columns })
+    }
+}
+
+// The header and the rows matching the query, their cells kept as they are, styles included
+fn query_sheet(source: &Sheet, query: &RowQuery, columns: &[usize], name: &str) -> Result<Sheet> {
+    let mut matches = vec![1];
+    for row in 2..=source.max_rows {
+        let keep = match &query.filter {
+            Some(filter) => {
+                let value = |col| Value::from_cell(&source.cell(row, col).value);
+                evaluate(filter, &value)
+                    .map_err(|e| anyhow!("{e} in row {row}"))?
+                    .is_true()
+            }
+            None => true,
+        };
+        if keep {
+            matches.push(row);
+        }
+    }
+
+    let mut data = CellGrid::with_size(matches.len() + 1, columns.len() + 1);
+    for (new_row, &row) in matches.iter().enumerate() {
+        for (new_col, &col) in columns.iter().enumerate() {
+            let cell = source.cell(row, col);
+            if !cell.value.is_empty() {
+                data.set(new_row + 1, new_col + 1, cell.clone());
+            }
+        }
+    }
+    Ok(Sheet {
+        name: name.to_string(),
+        data,
+        max_rows: matches.len(),
+        max_cols: columns.len(),
+        is_loaded: true,
+        styles: source.styles.clone(),
+    })
+}
+
+impl AppState<'_> {
+    /// :q expr [| columns]: copies the rows of the current sheet matching an expression
+    /// over its columns, named by their header in row 1, into a new sheet
+    pub fn run_row_query(&mut self, input: &str) {
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Query: {e}"));
+            return;
+        }
+
+        let source = self.workbook.get_current_sheet();
+        let headers: Vec<String> = (1..=source.max_cols)
+            .map(|col| source.cell(1, col).value.trim().to_string())
+            .collect();
+        let name = self.free_sheet_name("Query");
+        let result = RowQuery::parse(input.trim(), &headers).and_then(|query| {
+            let columns = if query.columns.is_empty() {
+                (1..=source.max_cols).collect()
+            } else {
+                query.columns.clone()
+            };
+            let sheet = query_sheet(source, &query, &columns, &name)?;
+            Ok((sheet, columns))
+        });
+        let (sheet, columns) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.add_notification(format!("Query error: {e}"));
+                return;
+            }
+        };
+
+        let total = self.workbook.get_current_sheet().max_rows.saturating_sub(1);
+        let count = sheet.max_rows - 1;
+        let mut column_widths = vec![15];
+        column_widths.extend(columns.iter().map(|&col| self.get_column_width(col)));
+        let sheet_index = self.workbook.get_current_sheet_index() + 1;
+        match self.insert_new_sheet(sheet, sheet_index, column_widths) {
+            Ok(()) => self.add_notification(format!("{count} of {total} row(s) in sheet {name}")),
+            Err(e) => self.add_notification(format!("Query: {e}")),
+        }
+    }
+}
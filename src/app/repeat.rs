@@ -0,0 +1,83 @@
+use anyhow::Result;
+
+use crate::actions::{ActionCommand, ActionType, CellAction};
+use crate::app::AppState;
+
+impl AppState<'_> {
+    /// .: repeats the last edit, paste, cut or row/column deletion at the cursor, like Vim
+    pub fn repeat_last_change(&mut self) -> Result<()> {
+        let Some(change) = self.undo_history.last_change() else {
+            self.add_notification("No change to repeat".to_string());
+            return Ok(());
+        };
+        let (row, col) = self.selected_cell;
+
+        match change.as_ref() {
+            ActionCommand::Cell(action) => match action.action_type {
+                ActionType::Edit => self.repeat_edit(action),
+                ActionType::Paste => self.paste_cell(),
+                ActionType::Cut => self.cut_cell(),
+                _ => self.cannot_repeat(),
+            },
+            // The range in the clipboard, pasted again at the cursor
+            ActionCommand::MultiCell(action) if matches!(action.action_type, ActionType::Paste) => {
+                self.paste_cell()
+            }
+            ActionCommand::Row(_) => self.delete_current_row(),
+            ActionCommand::Column(_) => self.delete_current_column(),
+            ActionCommand::MultiRow(action) => {
+                self.delete_rows(row, row + action.end_row - action.start_row)
+            }
+            ActionCommand::MultiColumn(action) => {
+                self.delete_columns(col, col + action.end_col - action.start_col)
+            }
+            _ => self.cannot_repeat(),
+        }
+    }
+
+    // Writes the value of a repeated edit into the current cell as a new edit
+    fn repeat_edit(&mut self, action: &CellAction) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+
+        let (row, col) = self.selected_cell;
+        self.workbook.ensure_cell_exists(row, col);
+        self.ensure_column_widths();
+
+        let sheet_index = self.workbook.get_current_sheet_index();
+        let sheet_name = self.workbook.get_current_sheet_name();
+
+        let old_cell = self.workbook.get_current_sheet().data[row][col].clone();
+        let mut new_cell = old_cell.clone();
+        new_cell.value.clone_from(&action.new_value.value);
+        // Style ids only mean something in their own sheet, e.g. a :set smartnumbers format
+        if action.sheet_name == sheet_name {
+            new_cell.style = action.new_value.style;
+        }
+
+        let content = new_cell.value.clone();
+        let style = new_cell.style;
+        let action = ActionCommand::Cell(CellAction::new(
+            sheet_index,
+            sheet_name,
+            row,
+            col,
+            old_cell,
+            new_cell,
+            ActionType::Edit,
+        ));
+        self.flash_action(&action);
+        self.undo_history.push(action);
+
+        self.workbook.set_cell_value(row, col, content)?;
+        self.workbook.set_cell_style(row, col, style)?;
+        Ok(())
+    }
+
+    fn cannot_repeat(&mut self) -> Result<()> {
+        self.add_notification(
+            "Only edits, pastes, cuts and row or column deletions can be repeated with ."
+                .to_string(),
+        );
+        Ok(())
+    }
+}
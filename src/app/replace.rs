@@ -0,0 +1,356 @@
+use std::time::{Duration, Instant};
+
+use crate::actions::{ActionCommand, ActionType, CellChange, MultiCellAction};
+use crate::app::AppState;
+use crate::app::InputMode;
+use crate::utils::cell_reference;
+
+// A parsed `:s/pattern/replacement/flags` command
+struct Substitution {
+    pattern: String,
+    replacement: String,
+    global: bool,
+    ignore_case: bool,
+}
+
+// How long a replace may hold the UI before the next redraw and key check
+const REPLACE_SLICE: Duration = Duration::from_millis(30);
+
+/// A substitution running over the current sheet or the visual selection a slice of
+/// rows at a time, so large sheets keep redrawing and the replace can be cancelled with Esc
+pub struct ReplaceJob {
+    pattern: String,
+    substitution: Substitution,
+    first_row: usize,
+    next_row: usize,
+    last_row: usize,
+    cols: (usize, usize),   // First and last column replaced in
+    scope: String,          // "the sheet" or the range of the selection, for the report
+    cells: Vec<CellChange>, // Cells already changed, in scan order
+    replacements: usize,
+    was_modified: bool,
+    started: Instant,
+}
+
+impl ReplaceJob {
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn rows_done(&self) -> usize {
+        self.next_row - self.first_row
+    }
+
+    pub fn total_rows(&self) -> usize {
+        (self.last_row + 1).saturating_sub(self.first_row)
+    }
+
+    pub fn cells_changed(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn replacements(&self) -> usize {
+        self.replacements
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Fraction of rows scanned, between 0 and 1
+    pub fn progress(&self) -> f64 {
+        if self.total_rows() == 0 {
+            1.0
+        } else {
+            self.rows_done() as f64 / self.total_rows() as f64
+        }
+    }
+}
+
+impl AppState<'_> {
+    // Handles `:s/pattern/replacement/[g][i]` on the visual selection, or the current
+    // sheet without one, and `:%s/...` on the whole sheet. Without `g` only the first
+    // match in each cell is replaced, like Vim does per line.
+    pub fn substitute(&mut self, command: &str) {
+        let substitution = match parse_substitution(command) {
+            Ok(substitution) => substitution,
+            Err(e) => {
+                self.add_notification(e);
+                return;
+            }
+        };
+
+        // An empty pattern reuses the last search query
+        let pattern = if substitution.pattern.is_empty() {
+            self.search_query.clone()
+        } else {
+            substitution.pattern.clone()
+        };
+
+        if pattern.is_empty() {
+            self.add_notification("No previous search pattern".to_string());
+            return;
+        }
+
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Replace failed: {e}"));
+            return;
+        }
+
+        let sheet = self.workbook.get_current_sheet();
+        let selection = self.selection_range().filter(|_| !command.starts_with('%'));
+        let ((top, left), (bottom, right), scope) = match selection {
+            Some((start, end)) => (
+                start,
+                end,
+                format!("{}:{}", cell_reference(start), cell_reference(end)),
+            ),
+            None => (
+                (1, 1),
+                (sheet.max_rows, sheet.max_cols),
+                "the sheet".to_string(),
+            ),
+        };
+        // The cursor goes to the first replaced cell, which would reshape the selection
+        self.clear_selection();
+
+        self.replace_job = Some(ReplaceJob {
+            pattern,
+            substitution,
+            first_row: top,
+            next_row: top,
+            last_row: bottom.min(self.workbook.get_current_sheet().max_rows),
+            cols: (left, right.min(self.workbook.get_current_sheet().max_cols)),
+            scope,
+            cells: Vec::new(),
+            replacements: 0,
+            was_modified: self.workbook.is_modified(),
+            started: Instant::now(),
+        });
+        self.input_mode = InputMode::Replacing;
+
+        // Small sheets finish within the first slice
+        self.continue_replace();
+    }
+
+    /// Runs the pending replace for one time slice, finishing it once every row is scanned
+    pub fn continue_replace(&mut self) {
+        let Some(mut job) = self.replace_job.take() else {
+            return;
+        };
+
+        let deadline = Instant::now() + REPLACE_SLICE;
+        while job.next_row <= job.last_row && Instant::now() < deadline {
+            let row = job.next_row;
+            let sheet = self.workbook.get_current_sheet();
+            let changes: Vec<(usize, String, usize)> = sheet
+                .data
+                .row(row)
+                .into_iter()
+                .flat_map(|cells| cells.iter())
+                .filter(|&(col, cell)| {
+                    (job.cols.0..=job.cols.1).contains(&col) && !cell.value.is_empty()
+                })
+                .filter_map(|(col, cell)| {
+                    let (new_value, count) = replace_in(
+                        &cell.value,
+                        &job.pattern,
+                        &job.substitution.replacement,
+                        job.substitution.global,
+                        job.substitution.ignore_case,
+                    );
+                    (count > 0).then_some((col, new_value, count))
+                })
+                .collect();
+
+            for (col, new_value, count) in changes {
+                let old_value = self.workbook.get_current_sheet().data[row][col].clone();
+                if let Err(e) = self.workbook.set_cell_value(row, col, new_value) {
+                    self.add_notification(format!("Replace failed: {e}"));
+                    self.replace_job = Some(job);
+                    self.cancel_replace();
+                    return;
+                }
+
+                job.replacements += count;
+                job.cells.push(CellChange {
+                    row,
+                    col,
+                    old_value,
+                    new_value: self.workbook.get_current_sheet().data[row][col].clone(),
+                });
+            }
+
+            job.next_row += 1;
+        }
+
+        if job.next_row > job.last_row {
+            self.finish_replace(job);
+        } else {
+            self.replace_job = Some(job);
+        }
+    }
+
+    /// Stops the running replace and restores the cells it already changed
+    pub fn cancel_replace(&mut self) {
+        let Some(job) = self.replace_job.take() else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+
+        let sheet = self.workbook.get_current_sheet_mut();
+        for change in job.cells.iter().rev() {
+            sheet
+                .data
+                .set(change.row, change.col, change.old_value.clone());
+        }
+        self.workbook.set_modified(job.was_modified);
+
+        self.add_notification(format!(
+            "Replace cancelled after {} of {} rows, no cells were changed",
+            job.rows_done(),
+            job.total_rows()
+        ));
+    }
+
+    fn finish_replace(&mut self, job: ReplaceJob) {
+        self.input_mode = InputMode::Normal;
+
+        if job.cells.is_empty() {
+            self.add_notification(format!(
+                "Pattern not found in {}: {}",
+                job.scope, job.pattern
+            ));
+            return;
+        }
+
+        let cell_count = job.cells.len();
+        let first = (job.cells[0].row, job.cells[0].col);
+        self.selected_cell = first;
+        self.handle_scrolling();
+
+        self.undo_history
+            .push(ActionCommand::MultiCell(MultiCellAction {
+                sheet_index: self.workbook.get_current_sheet_index(),
+                sheet_name: self.workbook.get_current_sheet_name(),
+                cells: job.cells,
+                action_type: ActionType::Replace,
+            }));
+
+        self.search_results.clear();
+        self.current_search_idx = None;
+
+        self.add_notification(format!(
+            "Replaced {} occurrence(s) in {cell_count} cell(s) of {}",
+            job.replacements, job.scope
+        ));
+    }
+}
+
+fn parse_substitution(command: &str) -> Result<Substitution, String> {
+    let body = command
+        .strip_prefix("%s")
+        .or_else(|| command.strip_prefix('s'))
+        .ok_or_else(|| format!("Unknown command: {command}"))?;
+
+    let mut chars = body.chars();
+    let delimiter = chars
+        .next()
+        .ok_or_else(|| "Usage: :s/pattern/replacement/[g][i]".to_string())?;
+
+    // Split on the delimiter, allowing it to be escaped with a backslash
+    let mut parts = vec![String::new()];
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                Some(next) => {
+                    let part = parts.last_mut().unwrap();
+                    part.push(c);
+                    part.push(next);
+                }
+                None => parts.last_mut().unwrap().push(c),
+            }
+        } else if c == delimiter && parts.len() < 3 {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+
+    if parts.len() < 2 {
+        return Err("Usage: :s/pattern/replacement/[g][i]".to_string());
+    }
+
+    let flags = parts.get(2).cloned().unwrap_or_default();
+    let mut global = false;
+    let mut ignore_case = false;
+    for flag in flags.chars() {
+        match flag {
+            'g' => global = true,
+            'i' => ignore_case = true,
+            _ => return Err(format!("Invalid substitute flag: {flag}")),
+        }
+    }
+
+    Ok(Substitution {
+        pattern: parts[0].clone(),
+        replacement: parts[1].clone(),
+        global,
+        ignore_case,
+    })
+}
+
+// Returns the new value and the number of replacements made
+fn replace_in(
+    value: &str,
+    pattern: &str,
+    replacement: &str,
+    global: bool,
+    ignore_case: bool,
+) -> (String, usize) {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut count = 0;
+
+    while let Some((start, end)) = find_match(rest, pattern, ignore_case) {
+        result.push_str(&rest[..start]);
+        result.push_str(replacement);
+        rest = &rest[end..];
+        count += 1;
+
+        if !global {
+            break;
+        }
+    }
+
+    result.push_str(rest);
+    (result, count)
+}
+
+// Byte range of the first match of `pattern` in `haystack`
+fn find_match(haystack: &str, pattern: &str, ignore_case: bool) -> Option<(usize, usize)> {
+    if !ignore_case {
+        return haystack
+            .find(pattern)
+            .map(|start| (start, start + pattern.len()));
+    }
+
+    for (start, _) in haystack.char_indices() {
+        let mut candidate = haystack[start..].char_indices();
+        let mut end = start;
+        let matched = pattern.chars().all(|p| match candidate.next() {
+            Some((offset, c)) if c.to_lowercase().eq(p.to_lowercase()) => {
+                end = start + offset + c.len_utf8();
+                true
+            }
+            _ => false,
+        });
+
+        if matched {
+            return Some((start, end));
+        }
+    }
+
+    None
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use crate::actions::{ActionCommand, ActionType, CellChange, MultiCellAction};
+use crate::app::AppState;
+use crate::excel::Sheet;
+
+/// What a script changed, applied to the sheet only once it ran without error. Scripts
+/// read the sheet through it so they see their own changes
+#[derive(Default)]
+pub struct ScriptEdits {
+    pub changes: BTreeMap<(usize, usize), String>, // New values by (row, col), in sheet order
+    pub new_sheets: Vec<String>,
+    pub output: Vec<String>, // Lines the script printed
+}
+
+impl ScriptEdits {
+    pub fn value<'a>(&'a self, sheet: &'a Sheet, row: usize, col: usize) -> &'a str {
+        self.changes
+            .get(&(row, col))
+            .map_or_else(|| sheet.cell(row, col).value.as_str(), String::as_str)
+    }
+
+    pub fn max_row(&self, sheet: &Sheet) -> usize {
+        let changed = self.changes.keys().map(|&(row, _)| row).max();
+        sheet.max_rows.max(changed.unwrap_or(0))
+    }
+
+    pub fn max_col(&self, sheet: &Sheet) -> usize {
+        let changed = self.changes.keys().map(|&(_, col)| col).max();
+        sheet.max_cols.max(changed.unwrap_or(0))
+    }
+
+    /// The values of a row with their column, empty cells left out
+    pub fn row_values<'a>(&'a self, sheet: &'a Sheet, row: usize) -> BTreeMap<usize, &'a str> {
+        let mut values = BTreeMap::new();
+        if let Some(cells) = sheet.data.row(row) {
+            for (col, cell) in cells.iter() {
+                if (1..=sheet.max_cols).contains(&col) {
+                    values.insert(col, cell.value.as_str());
+                }
+            }
+        }
+        for (&(_, col), value) in self.changes.range((row, 0)..(row + 1, 0)) {
+            values.insert(col, value.as_str());
+        }
+        values.retain(|_, value| !value.is_empty());
+        values
+    }
+}
+
+/// How a number set from a script reads in a cell, whole numbers without a fraction
+pub fn number_text(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        (n as i64).to_string()
+    } else {
+        n.to_string()
+    }
+}
+
+impl AppState<'_> {
+    /// Shows what a script printed and returned, then applies its changes as one
+    /// undoable step. A script that failed changes nothing
+    pub(super) fn finish_script(
+        &mut self,
+        language: &str,
+        edits: ScriptEdits,
+        result: Result<Vec<String>, String>,
+    ) {
+        let ScriptEdits {
+            changes,
+            new_sheets,
+            output,
+        } = edits;
+        for line in output {
+            self.add_notification(line);
+        }
+        match result {
+            Ok(returned) if !returned.is_empty() => self.add_notification(returned.join("  ")),
+            Ok(_) => {}
+            Err(message) => {
+                self.add_notification(format!("{language} error: {message}"));
+                return;
+            }
+        }
+
+        self.apply_script_changes(language, changes);
+        for name in new_sheets {
+            self.add_sheet(&name, None);
+        }
+    }
+
+    // Writes the changed values as one undo step, returning how many cells changed
+    pub(super) fn apply_script_changes(
+        &mut self,
+        language: &str,
+        changes: BTreeMap<(usize, usize), String>,
+    ) -> usize {
+        let mut cells = Vec::new();
+        for ((row, col), value) in changes {
+            let old_value = self.workbook.get_current_sheet().cell(row, col).clone();
+            if old_value.value == value {
+                continue;
+            }
+            if let Err(e) = self.workbook.set_cell_value(row, col, value) {
+                self.add_notification(format!("{language}: {e}"));
+                break;
+            }
+            cells.push(CellChange {
+                row,
+                col,
+                old_value,
+                new_value: self.workbook.get_current_sheet().cell(row, col).clone(),
+            });
+        }
+        if cells.is_empty() {
+            return 0;
+        }
+
+        self.ensure_column_widths();
+        self.update_row_number_width();
+        let count = cells.len();
+        self.undo_history
+            .push(ActionCommand::MultiCell(MultiCellAction {
+                sheet_index: self.workbook.get_current_sheet_index(),
+                sheet_name: self.workbook.get_current_sheet_name(),
+                cells,
+                action_type: ActionType::Edit,
+            }));
+        self.add_notification(format!("{language} changed {count} cell(s)"));
+        count
+    }
+
+    /// :py without the python feature
+    #[cfg(not(feature = "python"))]
+    pub fn run_python(&mut self, _code: &str) {
+        self.add_notification(
+            "Python scripting isn't built in, rebuild with --features python".to_string(),
+        );
+    }
+}
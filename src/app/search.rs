@@ -1,7 +1,135 @@
+use std::num::NonZeroUsize;
+use std::thread;
+use std::time::{Duration, Instant};
+
 use crate::app::AppState;
 use crate::app::InputMode;
+use crate::excel::Sheet;
 use ratatui::style::{Modifier, Style};
 
+// How long a search may hold the UI before the next redraw and key check
+const SEARCH_SLICE: Duration = Duration::from_millis(30);
+
+// Rows scanned together, split over the available cores
+const SEARCH_BATCH: usize = 8192;
+
+// Fewer rows than this are scanned on the calling thread
+const PARALLEL_ROWS: usize = 2048;
+
+/// Cursor and search state saved when a search prompt opens, so cancelling an
+/// incremental search puts everything back
+pub struct SearchOrigin {
+    cell: (usize, usize),
+    start_row: usize,
+    start_col: usize,
+    results: Vec<(usize, usize)>,
+    current_idx: Option<usize>,
+    column: Option<usize>,
+}
+
+// What a search looks for, fixed when it starts
+struct SearchPattern {
+    query: String,
+    query_lower: String,
+    ignore_case: bool,
+    column: Option<usize>, // Only this column, for :csearch
+}
+
+impl SearchPattern {
+    // Columns of the row with a value containing the query, in column order
+    fn matches_in<'a>(&'a self, sheet: &'a Sheet, row: usize) -> impl Iterator<Item = usize> + 'a {
+        sheet
+            .data
+            .row(row)
+            .into_iter()
+            .flat_map(|cells| cells.iter())
+            .filter(move |&(col, cell)| {
+                (1..=sheet.max_cols).contains(&col)
+                    && self.column.map_or(true, |column| column == col)
+                    && !cell.value.is_empty()
+                    && if self.ignore_case {
+                        AppState::case_insensitive_contains(&cell.value, &self.query_lower)
+                    } else {
+                        cell.value.contains(&self.query)
+                    }
+            })
+            .map(|(col, _)| col)
+    }
+
+    // Matches in rows `first` to `last` in row-first, column-second order. Large ranges
+    // are cut into one chunk per core, scanned at the same time
+    fn matches_in_rows(&self, sheet: &Sheet, first: usize, last: usize) -> Vec<(usize, usize)> {
+        let scan = |first: usize, last: usize| -> Vec<(usize, usize)> {
+            (first..=last)
+                .flat_map(|row| self.matches_in(sheet, row).map(move |col| (row, col)))
+                .collect()
+        };
+
+        let rows = (last + 1).saturating_sub(first);
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        if threads == 1 || rows < PARALLEL_ROWS {
+            return scan(first, last);
+        }
+
+        let chunk = rows.div_ceil(threads);
+        thread::scope(|scope| {
+            let handles: Vec<_> = (first..=last)
+                .step_by(chunk)
+                .map(|start| scope.spawn(move || scan(start, (start + chunk - 1).min(last))))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        })
+    }
+}
+
+/// A search running over the current sheet a slice of rows at a time, so searching
+/// a large sheet shows its progress and can be cancelled with Esc
+pub struct SearchJob {
+    pattern: SearchPattern,
+    next_row: usize,
+    total_rows: usize,
+    results: Vec<(usize, usize)>,
+    started: Instant,
+}
+
+impl SearchJob {
+    pub fn query(&self) -> &str {
+        &self.pattern.query
+    }
+
+    pub fn rows_done(&self) -> usize {
+        self.next_row - 1
+    }
+
+    pub fn total_rows(&self) -> usize {
+        self.total_rows
+    }
+
+    pub fn matches(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Fraction of rows scanned, between 0 and 1
+    pub fn progress(&self) -> f64 {
+        if self.total_rows == 0 {
+            1.0
+        } else {
+            self.rows_done() as f64 / self.total_rows as f64
+        }
+    }
+}
+
 impl AppState<'_> {
     pub fn start_search_forward(&mut self) {
         self.input_mode = InputMode::SearchForward;
@@ -15,6 +143,7 @@ impl AppState<'_> {
 
         self.add_notification("Search forward mode".to_string());
         self.highlight_enabled = true;
+        self.save_search_origin();
     }
 
     pub fn start_search_backward(&mut self) {
@@ -29,6 +158,7 @@ impl AppState<'_> {
 
         self.add_notification("Search backward mode".to_string());
         self.highlight_enabled = true;
+        self.save_search_origin();
     }
 
     pub fn execute_search(&mut self) {
@@ -36,10 +166,15 @@ impl AppState<'_> {
         self.input_buffer.clone_from(&query);
 
         if query.is_empty() {
-            self.input_mode = InputMode::Normal;
+            self.cancel_search();
             return;
         }
 
+        // The incremental preview moved the cursor, search again from where it started
+        if let Some(origin) = self.search_origin.take() {
+            self.selected_cell = origin.cell;
+        }
+
         // Save the query for n/N commands
         self.search_query.clone_from(&query);
 
@@ -50,8 +185,139 @@ impl AppState<'_> {
             _ => {}
         }
 
-        self.search_results = self.find_all_matches(&query);
+        self.input_mode = InputMode::Normal;
+        self.input_buffer = String::new();
+        self.text_area = tui_textarea::TextArea::default();
+
+        self.search_column = None;
+        self.run_search(&query);
+    }
+
+    fn save_search_origin(&mut self) {
+        self.search_origin = Some(SearchOrigin {
+            cell: self.selected_cell,
+            start_row: self.start_row,
+            start_col: self.start_col,
+            results: self.search_results.clone(),
+            current_idx: self.current_search_idx,
+            column: self.search_column,
+        });
+    }
+
+    /// Leaves the search prompt, moving the cursor back to where the search started
+    pub fn cancel_search(&mut self) {
+        if let Some(origin) = self.search_origin.take() {
+            self.selected_cell = origin.cell;
+            self.start_row = origin.start_row;
+            self.start_col = origin.start_col;
+            self.search_results = origin.results;
+            self.current_search_idx = origin.current_idx;
+            self.search_column = origin.column;
+        }
+
+        self.input_mode = InputMode::Normal;
+        self.input_buffer = String::new();
+        self.text_area = tui_textarea::TextArea::default();
+    }
+
+    /// Like Vim's 'incsearch': highlights the matches of the query typed so far
+    /// and moves the cursor to the match the search would jump to
+    pub fn preview_search(&mut self) {
+        if !self.inc_search {
+            return;
+        }
+        let Some(origin) = &self.search_origin else {
+            return;
+        };
+        let (origin_cell, start_row, start_col) = (origin.cell, origin.start_row, origin.start_col);
+
+        let query = self.text_area.lines().join("\n");
+        self.search_column = None;
+        self.search_results = if query.is_empty() {
+            Vec::new()
+        } else {
+            self.find_all_matches(&query)
+        };
+
+        let forward = matches!(self.input_mode, InputMode::SearchForward);
+        let preview_idx = if forward {
+            self.search_results
+                .iter()
+                .position(|&pos| pos > origin_cell)
+                .or((!self.search_results.is_empty()).then_some(0))
+        } else {
+            self.search_results
+                .iter()
+                .rposition(|&pos| pos < origin_cell)
+                .or(self.search_results.len().checked_sub(1))
+        };
+
+        self.current_search_idx = preview_idx;
+        match preview_idx {
+            Some(idx) => {
+                self.selected_cell = self.search_results[idx];
+                self.handle_scrolling();
+            }
+            None => {
+                self.selected_cell = origin_cell;
+                self.start_row = start_row;
+                self.start_col = start_col;
+            }
+        }
+    }
+
+    // Search forward for the query in the current column only, n/N stay in that column
+    pub fn search_in_column(&mut self, query: &str) {
+        if query.is_empty() {
+            self.add_notification("Usage: :csearch [text]".to_string());
+            return;
+        }
+
+        self.search_query = query.to_string();
+        self.search_direction = true;
+        self.search_column = Some(self.selected_cell.1);
+        self.highlight_enabled = true;
+        self.run_search(query);
+    }
+
+    // Searches the sheet as a job, large sheets take more than one slice
+    fn run_search(&mut self, query: &str) {
+        self.search_job = Some(SearchJob {
+            pattern: self.search_pattern(query),
+            next_row: 1,
+            total_rows: self.workbook.get_current_sheet().max_rows,
+            results: Vec::new(),
+            started: Instant::now(),
+        });
+        self.input_mode = InputMode::Searching;
+
+        // Small sheets finish within the first slice
+        self.continue_search();
+    }
+
+    /// Runs the pending search for one time slice, jumping to a match once every row is scanned
+    pub fn continue_search(&mut self) {
+        let Some(mut job) = self.search_job.take() else {
+            return;
+        };
 
+        let sheet = self.workbook.get_current_sheet();
+        let deadline = Instant::now() + SEARCH_SLICE;
+        while job.next_row <= job.total_rows && Instant::now() < deadline {
+            let last = (job.next_row + SEARCH_BATCH - 1).min(job.total_rows);
+            job.results
+                .extend(job.pattern.matches_in_rows(sheet, job.next_row, last));
+            job.next_row = last + 1;
+        }
+
+        if job.next_row <= job.total_rows {
+            self.search_job = Some(job);
+            return;
+        }
+
+        self.input_mode = InputMode::Normal;
+        self.search_results = job.results;
+        let query = job.pattern.query;
         if self.search_results.is_empty() {
             self.add_notification(format!("Pattern not found: {query}"));
             self.current_search_idx = None;
@@ -64,37 +330,43 @@ impl AppState<'_> {
                 query
             ));
         }
-
-        self.input_mode = InputMode::Normal;
-        self.input_buffer = String::new();
-        self.text_area = tui_textarea::TextArea::default();
     }
 
-    pub fn find_all_matches(&self, query: &str) -> Vec<(usize, usize)> {
-        let sheet = self.workbook.get_current_sheet();
-        let query_lower = query.to_lowercase();
-
-        // Pre-allocate with reasonable capacity
-        let mut results = Vec::with_capacity(32);
+    /// Stops the running search, leaving the cursor where it was
+    pub fn cancel_running_search(&mut self) {
+        let Some(job) = self.search_job.take() else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+        self.search_results.clear();
+        self.current_search_idx = None;
 
-        // row-first, column-second order
-        for row in 1..=sheet.max_rows {
-            for col in 1..=sheet.max_cols {
-                if row < sheet.data.len() && col < sheet.data[row].len() {
-                    let cell_content = &sheet.data[row][col].value;
+        self.add_notification(format!(
+            "Search cancelled after {} of {} rows",
+            job.rows_done(),
+            job.total_rows
+        ));
+    }
 
-                    if cell_content.is_empty() {
-                        continue;
-                    }
+    // Mirrors Vim: 'smartcase' only takes effect together with 'ignorecase'
+    fn search_ignores_case(&self, query: &str) -> bool {
+        self.ignore_case && !(self.smart_case && query.chars().any(char::is_uppercase))
+    }
 
-                    if Self::case_insensitive_contains(cell_content, &query_lower) {
-                        results.push((row, col));
-                    }
-                }
-            }
+    fn search_pattern(&self, query: &str) -> SearchPattern {
+        SearchPattern {
+            query: query.to_string(),
+            query_lower: query.to_lowercase(),
+            ignore_case: self.search_ignores_case(query),
+            column: self.search_column,
         }
+    }
+
+    pub fn find_all_matches(&self, query: &str) -> Vec<(usize, usize)> {
+        let sheet = self.workbook.get_current_sheet();
+        let pattern = self.search_pattern(query);
 
-        results
+        pattern.matches_in_rows(sheet, 1, sheet.max_rows)
     }
 
     fn case_insensitive_contains(haystack: &str, needle: &str) -> bool {
@@ -114,6 +386,7 @@ impl AppState<'_> {
         }
 
         self.highlight_enabled = true;
+        self.record_jump();
 
         let current_pos = self.selected_cell;
 
@@ -0,0 +1,51 @@
+use crate::app::AppState;
+use crate::utils::cell_reference;
+
+impl AppState<'_> {
+    /// v: starts a rectangular selection at the cursor, or ends the current one
+    pub fn toggle_selection(&mut self) {
+        if self.selection_anchor.is_some() {
+            self.clear_selection();
+        } else {
+            self.start_selection();
+            self.add_notification("Visual selection started, move to extend it".to_string());
+        }
+    }
+
+    /// Anchors a selection at the cursor unless one is already active
+    pub fn start_selection(&mut self) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.selected_cell);
+        }
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection_anchor = None;
+    }
+
+    /// Top-left and bottom-right corners of the selection between the anchor and the cursor
+    #[must_use]
+    pub fn selection_range(&self) -> Option<((usize, usize), (usize, usize))> {
+        let (anchor_row, anchor_col) = self.selection_anchor?;
+        let (row, col) = self.selected_cell;
+        Some((
+            (anchor_row.min(row), anchor_col.min(col)),
+            (anchor_row.max(row), anchor_col.max(col)),
+        ))
+    }
+
+    #[must_use]
+    pub fn is_selected(&self, row: usize, col: usize) -> bool {
+        self.selection_range()
+            .is_some_and(|((top, left), (bottom, right))| {
+                (top..=bottom).contains(&row) && (left..=right).contains(&col)
+            })
+    }
+
+    /// The selection as written in Excel, e.g. A1:C4
+    #[must_use]
+    pub fn selection_label(&self) -> Option<String> {
+        let (start, end) = self.selection_range()?;
+        Some(format!("{}:{}", cell_reference(start), cell_reference(end)))
+    }
+}
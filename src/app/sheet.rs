@@ -1,9 +1,15 @@
 use crate::actions::{
-    ActionCommand, ColumnAction, MultiColumnAction, MultiRowAction, RowAction, SheetAction,
+    ActionCommand, AddSheetAction, ColumnAction, MoveSheetAction, MultiColumnAction,
+    MultiRowAction, RowAction, SheetAction,
 };
 use crate::app::AppState;
+use crate::excel::{Sheet, SheetError, SheetResult};
 use crate::utils::index_to_col_name;
 use anyhow::Result;
+use unicode_width::UnicodeWidthStr;
+
+// Upper bound for automatically fitted column widths
+const MAX_AUTO_FIT_WIDTH: usize = 50;
 
 impl AppState<'_> {
     pub fn next_sheet(&mut self) -> Result<()> {
@@ -15,7 +21,8 @@ impl AppState<'_> {
             return Ok(());
         }
 
-        self.switch_sheet_by_index(current_index + 1)
+        self.switch_sheet_by_index(current_index + 1)?;
+        Ok(())
     }
 
     pub fn prev_sheet(&mut self) -> Result<()> {
@@ -26,10 +33,12 @@ impl AppState<'_> {
             return Ok(());
         }
 
-        self.switch_sheet_by_index(current_index - 1)
+        self.switch_sheet_by_index(current_index - 1)?;
+        Ok(())
     }
 
-    pub fn switch_sheet_by_index(&mut self, index: usize) -> Result<()> {
+    /// Stores the column widths of the current sheet so they survive sheet switches and saves
+    pub fn store_current_column_widths(&mut self) {
         let current_sheet_name = self.workbook.get_current_sheet_name();
 
         // Save current column widths if they've changed
@@ -37,8 +46,13 @@ impl AppState<'_> {
             || self.sheet_column_widths[&current_sheet_name] != self.column_widths
         {
             self.sheet_column_widths
-                .insert(current_sheet_name.clone(), self.column_widths.clone());
+                .insert(current_sheet_name, self.column_widths.clone());
         }
+    }
+
+    pub fn switch_sheet_by_index(&mut self, index: usize) -> SheetResult<()> {
+        self.store_current_column_widths();
+        let current_sheet_name = self.workbook.get_current_sheet_name();
 
         // Save current cell position and view position
         let current_position = crate::app::CellPosition {
@@ -49,6 +63,7 @@ impl AppState<'_> {
             .insert(current_sheet_name, current_position);
 
         self.workbook.switch_sheet(index)?;
+        self.clear_selection();
 
         let new_sheet_name = self.workbook.get_current_sheet_name();
 
@@ -101,9 +116,11 @@ impl AppState<'_> {
         if is_lazy_loading && !is_sheet_loaded {
             // If the sheet is not loaded, switch to LazyLoading mode
             self.input_mode = crate::app::InputMode::LazyLoading;
-            self.add_notification(format!(
-                "Switched to sheet: {new_sheet_name} (press Enter to load)"
-            ));
+            let hint = match self.workbook.loading_sheet() {
+                Some((loading, _)) if loading == new_sheet_name => "still loading",
+                _ => "press Enter to load",
+            };
+            self.add_notification(format!("Switched to sheet: {new_sheet_name} ({hint})"));
         } else {
             self.add_notification(format!("Switched to sheet: {new_sheet_name}"));
         }
@@ -111,6 +128,83 @@ impl AppState<'_> {
         Ok(())
     }
 
+    /// Starts loading the current sheet in the background, other sheets stay usable meanwhile
+    pub fn load_current_sheet(&mut self) {
+        let index = self.workbook.get_current_sheet_index();
+        let sheet_name = self.workbook.get_current_sheet_name();
+
+        if self.workbook.is_sheet_loaded(index) {
+            self.add_notification(format!("Sheet {sheet_name} is already loaded"));
+            return;
+        }
+
+        if !self.workbook.start_sheet_load(index) {
+            if let Some((loading, _)) = self.workbook.loading_sheet() {
+                self.add_notification(format!(
+                    "Sheet {loading} is still loading, load {sheet_name} once it is done"
+                ));
+            }
+        }
+    }
+
+    /// Takes in a sheet finished loading in the background, called between key presses
+    pub fn continue_sheet_load(&mut self) {
+        let Some((sheet_name, result)) = self.workbook.poll_sheet_load() else {
+            return;
+        };
+
+        match result {
+            Ok((elapsed, changed)) => {
+                if self.workbook.get_current_sheet_name() == sheet_name {
+                    self.ensure_column_widths();
+                    self.update_row_number_width();
+                }
+                self.add_notification(format!(
+                    "Loaded sheet: {sheet_name} in {:.1}s",
+                    elapsed.as_secs_f64()
+                ));
+                if let Some(profile) = self.workbook.import_profile().filter(|_| changed > 0) {
+                    self.add_notification(format!(
+                        "Import profile '{}' changed {changed} cell(s) in {sheet_name}",
+                        profile.display_name()
+                    ));
+                }
+            }
+            Err(SheetError::Cancelled) => {
+                self.add_notification(format!("Loading sheet {sheet_name} cancelled"));
+            }
+            Err(e) => self.report_load_error(&e),
+        }
+    }
+
+    /// Esc while the current sheet loads in the background: stops reading it, the
+    /// sheet stays unloaded until the next :load
+    pub fn cancel_sheet_load(&mut self) {
+        match self.workbook.loading_sheet() {
+            Some((name, _)) if name == self.workbook.get_current_sheet_name() => {
+                self.workbook.cancel_sheet_load();
+            }
+            _ => self.add_notification(
+                "Press Enter to load the sheet data, or use [ and ] to switch sheets".to_string(),
+            ),
+        }
+    }
+
+    // Explain how to recover from a sheet that failed to load
+    pub fn report_load_error(&mut self, error: &SheetError) {
+        self.add_notification(format!("Failed to load sheet: {error}"));
+
+        match error {
+            SheetError::Parse { .. } => self.add_notification(
+                "Retry with :load, or use [ and ] to continue with another sheet".to_string(),
+            ),
+            SheetError::Io { .. } => self.add_notification(
+                "Check that the file is still readable, then retry with :load".to_string(),
+            ),
+            _ => {}
+        }
+    }
+
     pub fn switch_to_sheet(&mut self, name_or_index: &str) {
         // Get all sheet names
         let sheet_names = self.workbook.get_sheet_names();
@@ -150,6 +244,30 @@ impl AppState<'_> {
         self.add_notification(format!("Sheet '{name_or_index}' not found"));
     }
 
+    /// :sheetdelete: asks for confirmation before deleting the current sheet
+    pub fn request_sheet_delete(&mut self) {
+        if self.workbook.get_sheet_names().len() <= 1 {
+            self.add_notification(format!("Failed to delete sheet: {}", SheetError::LastSheet));
+            return;
+        }
+        self.input_mode = crate::app::InputMode::ConfirmDeleteSheet;
+    }
+
+    pub fn confirm_sheet_delete(&mut self) {
+        self.input_mode = crate::app::InputMode::Normal;
+        self.delete_current_sheet();
+    }
+
+    pub fn cancel_sheet_delete(&mut self) {
+        let current_index = self.workbook.get_current_sheet_index();
+        self.input_mode = if self.workbook.is_sheet_loaded(current_index) {
+            crate::app::InputMode::Normal
+        } else {
+            crate::app::InputMode::LazyLoading
+        };
+        self.add_notification("Sheet not deleted".to_string());
+    }
+
     pub fn delete_current_sheet(&mut self) {
         let current_sheet_name = self.workbook.get_current_sheet_name();
         let sheet_index = self.workbook.get_current_sheet_index();
@@ -171,6 +289,9 @@ impl AppState<'_> {
                 self.undo_history.push(ActionCommand::Sheet(sheet_action));
                 self.sheet_column_widths.remove(&current_sheet_name);
                 self.sheet_cell_positions.remove(&current_sheet_name);
+                self.marks.remove(&current_sheet_name);
+                self.validation_rules.remove(&current_sheet_name);
+                self.column_formats.remove(&current_sheet_name);
 
                 let new_sheet_name = self.workbook.get_current_sheet_name();
                 let new_sheet_index = self.workbook.get_current_sheet_index();
@@ -228,7 +349,157 @@ impl AppState<'_> {
         }
     }
 
+    /// :sheetnew and :sheetins: adds an empty sheet at the 0-based `index`, or after the
+    /// last sheet, and switches to it
+    pub fn add_sheet(&mut self, name: &str, index: Option<usize>) {
+        let sheet_index = index.unwrap_or(self.workbook.get_sheet_names().len());
+
+        match self.insert_new_sheet(Sheet::empty(name), sheet_index, vec![15; 1]) {
+            Ok(()) => self.add_notification(format!("Created sheet {name}")),
+            Err(e) => self.add_notification(format!("Failed to create sheet: {e}")),
+        }
+    }
+
+    /// :sheetcopy: adds a copy of the current sheet after it, named like Excel's
+    /// "Sheet1 (2)" unless a name is given
+    pub fn copy_current_sheet(&mut self, name: Option<&str>) {
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("Failed to copy sheet: {e}"));
+            return;
+        }
+
+        let source_name = self.workbook.get_current_sheet_name();
+        let copy_name = name.map_or_else(|| self.copy_sheet_name(&source_name), str::to_string);
+        let mut sheet = self.workbook.get_current_sheet().clone();
+        sheet.name.clone_from(&copy_name);
+
+        let sheet_index = self.workbook.get_current_sheet_index() + 1;
+        match self.insert_new_sheet(sheet, sheet_index, self.column_widths.clone()) {
+            Ok(()) => {
+                self.add_notification(format!("Copied sheet {source_name} to {copy_name}"));
+            }
+            Err(e) => self.add_notification(format!("Failed to copy sheet: {e}")),
+        }
+    }
+
+    // The first free "name (n)", shortening the name to stay within Excel's 31 characters
+    fn copy_sheet_name(&self, name: &str) -> String {
+        let names = self.workbook.get_sheet_names();
+        (2..)
+            .map(|n| {
+                let suffix = format!(" ({n})");
+                let base: String = name.chars().take(31 - suffix.len()).collect();
+                format!("{base}{suffix}")
+            })
+            .find(|candidate| !names.iter().any(|n| n.eq_ignore_ascii_case(candidate)))
+            .unwrap_or_default()
+    }
+
+    // `name`, or the first free "name (n)" when a sheet already has it
+    pub(super) fn free_sheet_name(&self, name: &str) -> String {
+        let names = self.workbook.get_sheet_names();
+        if names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            self.copy_sheet_name(name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    // Adds the sheet with its column widths as one undoable step and switches to it
+    pub(super) fn insert_new_sheet(
+        &mut self,
+        sheet: Sheet,
+        sheet_index: usize,
+        column_widths: Vec<usize>,
+    ) -> SheetResult<()> {
+        let sheet_name = sheet.name.clone();
+        let sheet_data = sheet.clone();
+        self.workbook.add_sheet(sheet, sheet_index)?;
+
+        self.sheet_column_widths
+            .insert(sheet_name.clone(), column_widths.clone());
+        self.undo_history
+            .push(ActionCommand::AddSheet(AddSheetAction {
+                sheet_index,
+                sheet_name,
+                sheet_data,
+                column_widths,
+            }));
+
+        self.switch_sheet_by_index(sheet_index)
+    }
+
+    /// :sheetmove: moves the current sheet to the 1-based `position` among the sheets
+    pub fn move_current_sheet(&mut self, position: usize) {
+        let sheet_count = self.workbook.get_sheet_names().len();
+        if position == 0 || position > sheet_count {
+            self.add_notification(format!(
+                "Invalid sheet position {position}, expected 1 to {sheet_count}"
+            ));
+            return;
+        }
+
+        let sheet_name = self.workbook.get_current_sheet_name();
+        let from_index = self.workbook.get_current_sheet_index();
+        let to_index = position - 1;
+        if from_index == to_index {
+            self.add_notification(format!(
+                "Sheet {sheet_name} is already at position {position}"
+            ));
+            return;
+        }
+
+        if let Err(e) = self.workbook.move_sheet(from_index, to_index) {
+            self.add_notification(format!("Failed to move sheet: {e}"));
+            return;
+        }
+        self.undo_history
+            .push(ActionCommand::MoveSheet(MoveSheetAction {
+                sheet_name: sheet_name.clone(),
+                from_index,
+                to_index,
+            }));
+        self.add_notification(format!("Moved sheet {sheet_name} to position {position}"));
+    }
+
+    /// :sheetrename: renames the current sheet, keeping its marks, widths and undo history
+    pub fn rename_current_sheet(&mut self, new_name: &str) {
+        let old_name = self.workbook.get_current_sheet_name();
+
+        if let Err(e) = self.workbook.rename_current_sheet(new_name) {
+            self.add_notification(format!("Failed to rename sheet: {e}"));
+            return;
+        }
+
+        if let Some(widths) = self.sheet_column_widths.remove(&old_name) {
+            self.sheet_column_widths
+                .insert(new_name.to_string(), widths);
+        }
+        if let Some(position) = self.sheet_cell_positions.remove(&old_name) {
+            self.sheet_cell_positions
+                .insert(new_name.to_string(), position);
+        }
+        if let Some(marks) = self.marks.remove(&old_name) {
+            self.marks.insert(new_name.to_string(), marks);
+        }
+        if let Some(rules) = self.validation_rules.remove(&old_name) {
+            self.validation_rules.insert(new_name.to_string(), rules);
+        }
+        if let Some(formats) = self.column_formats.remove(&old_name) {
+            self.column_formats.insert(new_name.to_string(), formats);
+        }
+        self.jump_list.rename_sheet(&old_name, new_name);
+        if let Some(split) = &mut self.split {
+            split.rename_sheet(&old_name, new_name);
+        }
+        self.undo_history.rename_sheet(&old_name, new_name);
+
+        self.add_notification(format!("Renamed sheet {old_name} to {new_name}"));
+    }
+
     pub fn delete_current_row(&mut self) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+
         let row = self.selected_cell.0;
         let sheet = self.workbook.get_current_sheet();
 
@@ -242,11 +513,10 @@ impl AppState<'_> {
         let sheet_name = self.workbook.get_current_sheet_name();
 
         // Create a copy of the row data before deletion
-        let row_data = if row < sheet.data.len() {
-            sheet.data[row].clone()
-        } else {
-            Vec::new()
-        };
+        let row_data = sheet
+            .data
+            .row(row)
+            .map_or_else(Vec::new, |cells| cells.to_cells(sheet.data.width()));
 
         // Create and add undo action
         let row_action = RowAction {
@@ -276,6 +546,8 @@ impl AppState<'_> {
     }
 
     pub fn delete_row(&mut self, row: usize) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+
         let sheet = self.workbook.get_current_sheet();
 
         // If row is outside the valid range, return success
@@ -288,11 +560,10 @@ impl AppState<'_> {
         let sheet_name = self.workbook.get_current_sheet_name();
 
         // Create a copy of the row data before deletion
-        let row_data = if row < sheet.data.len() {
-            sheet.data[row].clone()
-        } else {
-            Vec::new()
-        };
+        let row_data = sheet
+            .data
+            .row(row)
+            .map_or_else(Vec::new, |cells| cells.to_cells(sheet.data.width()));
 
         // Create and add undo action
         let row_action = RowAction {
@@ -326,6 +597,8 @@ impl AppState<'_> {
             return self.delete_row(start_row);
         }
 
+        self.workbook.ensure_current_sheet_editable()?;
+
         let sheet = self.workbook.get_current_sheet();
 
         // If the entire range is outside the valid range, return success
@@ -345,11 +618,12 @@ impl AppState<'_> {
         let mut rows_data = Vec::with_capacity(rows_to_save);
 
         for row in start_row..=effective_end_row {
-            if row < sheet.data.len() {
-                rows_data.push(sheet.data[row].clone());
-            } else {
-                rows_data.push(Vec::new());
-            }
+            rows_data.push(
+                sheet
+                    .data
+                    .row(row)
+                    .map_or_else(Vec::new, |cells| cells.to_cells(sheet.data.width())),
+            );
         }
 
         // Create and add batch undo action
@@ -382,6 +656,8 @@ impl AppState<'_> {
     }
 
     pub fn delete_current_column(&mut self) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+
         let col = self.selected_cell.1;
         let sheet = self.workbook.get_current_sheet();
 
@@ -395,14 +671,7 @@ impl AppState<'_> {
         let sheet_name = self.workbook.get_current_sheet_name();
 
         // Extract the column data from each row
-        let mut column_data = Vec::with_capacity(sheet.data.len());
-        for row in &sheet.data {
-            if col < row.len() {
-                column_data.push(row[col].clone());
-            } else {
-                column_data.push(crate::excel::Cell::empty());
-            }
-        }
+        let column_data: Vec<_> = sheet.data.rows().map(|row| row[col].clone()).collect();
 
         // Save the column width
         let column_width = if col < self.column_widths.len() {
@@ -450,6 +719,8 @@ impl AppState<'_> {
     }
 
     pub fn delete_column(&mut self, col: usize) -> Result<()> {
+        self.workbook.ensure_current_sheet_editable()?;
+
         let sheet = self.workbook.get_current_sheet();
 
         // If column is outside the valid range, return success
@@ -462,14 +733,7 @@ impl AppState<'_> {
         let sheet_name = self.workbook.get_current_sheet_name();
 
         // Extract the column data from each row
-        let mut column_data = Vec::with_capacity(sheet.data.len());
-        for row in &sheet.data {
-            if col < row.len() {
-                column_data.push(row[col].clone());
-            } else {
-                column_data.push(crate::excel::Cell::empty());
-            }
-        }
+        let column_data: Vec<_> = sheet.data.rows().map(|row| row[col].clone()).collect();
 
         // Save the column width
         let column_width = if col < self.column_widths.len() {
@@ -521,6 +785,8 @@ impl AppState<'_> {
             return self.delete_column(start_col);
         }
 
+        self.workbook.ensure_current_sheet_editable()?;
+
         let sheet = self.workbook.get_current_sheet();
 
         // If the entire range is outside the valid range, return success
@@ -542,15 +808,7 @@ impl AppState<'_> {
 
         for col in start_col..=effective_end_col {
             // Extract the column data from each row
-            let mut column_data = Vec::with_capacity(sheet.data.len());
-            for row in &sheet.data {
-                if col < row.len() {
-                    column_data.push(row[col].clone());
-                } else {
-                    column_data.push(crate::excel::Cell::empty());
-                }
-            }
-            columns_data.push(column_data);
+            columns_data.push(sheet.data.rows().map(|row| row[col].clone()).collect());
 
             // Save the column width
             let column_width = if col < self.column_widths.len() {
@@ -661,6 +919,36 @@ impl AppState<'_> {
         }
     }
 
+    pub fn adjust_column_width(&mut self, delta: isize) {
+        let col = self.selected_cell.1;
+        let old_width = self.get_column_width(col);
+        let width = self.set_column_width(col, old_width.saturating_add_signed(delta));
+        if width != old_width {
+            self.ensure_column_visible(col);
+        }
+
+        self.add_notification(format!(
+            "Column {} width: {}",
+            index_to_col_name(col),
+            width
+        ));
+    }
+
+    /// Sets the display width of a column, kept between 5 and 50. Returns the width set
+    pub fn set_column_width(&mut self, col: usize, width: usize) -> usize {
+        self.ensure_column_widths();
+        if col >= self.column_widths.len() {
+            self.column_widths.resize(col + 1, 15);
+        }
+
+        let width = width.clamp(5, 50);
+        if width != self.column_widths[col] {
+            self.column_widths[col] = width;
+            self.store_current_column_widths();
+        }
+        width
+    }
+
     fn calculate_column_width(&self, col: usize) -> usize {
         let sheet = self.workbook.get_current_sheet();
 
@@ -670,28 +958,17 @@ impl AppState<'_> {
 
         // Calculate max width from all cells in the column
         for row in 1..=sheet.max_rows {
-            if row >= sheet.data.len() || col >= sheet.data[row].len() {
-                continue;
-            }
-
-            let content = &sheet.data[row][col].value;
+            let content = &sheet.cell(row, col).value;
             if content.is_empty() {
                 continue;
             }
 
-            let mut display_width = 0;
-
-            for c in content.chars() {
-                if c.is_ascii() {
-                    display_width += 1;
-                } else {
-                    display_width += 2;
-                }
-            }
+            // Multi-line content is measured by its widest line
+            let display_width = content.lines().map(|line| line.width()).max().unwrap_or(0);
 
             max_width = max_width.max(display_width);
         }
-        max_width
+        max_width.min(MAX_AUTO_FIT_WIDTH)
     }
 
     pub fn get_column_width(&self, col: usize) -> usize {
@@ -702,6 +979,25 @@ impl AppState<'_> {
         }
     }
 
+    /// Hidden columns have a width of 0, as in Excel
+    #[must_use]
+    pub fn is_column_hidden(&self, col: usize) -> bool {
+        self.get_column_width(col) == 0
+    }
+
+    /// Moves the widths along with columns reordered by `Workbook::reorder_columns`
+    pub fn reorder_column_widths(&mut self, order: &[usize]) {
+        let widths: Vec<usize> = order
+            .iter()
+            .map(|&col| self.get_column_width(col))
+            .collect();
+        if self.column_widths.len() <= order.len() {
+            self.column_widths.resize(order.len() + 1, 15);
+        }
+        self.column_widths.splice(1..=order.len(), widths);
+        self.store_current_column_widths();
+    }
+
     pub fn ensure_column_widths(&mut self) {
         let sheet = self.workbook.get_current_sheet();
         self.adjust_column_widths(sheet.max_cols);
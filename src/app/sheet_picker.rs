@@ -0,0 +1,113 @@
+use crate::app::{AppState, InputMode};
+
+/// State of the sheet picker opened with gs
+#[derive(Default)]
+pub struct SheetPicker {
+    pub query: String,
+    pub selected: usize,
+}
+
+// Scores `name` when the query's characters appear in it in order, ignoring case.
+// Matches at the start, after a separator and right after the previous match score higher
+fn fuzzy_score(query: &str, name: &str) -> Option<usize> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + name[pos..].iter().position(|&c| c == q)?;
+
+        score += 1;
+        if found == 0 || !name[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        if previous.is_some_and(|prev| prev + 1 == found) {
+            score += 3;
+        }
+
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+impl SheetPicker {
+    /// Indices and names of the sheets matching the query, best match first
+    #[must_use]
+    pub fn filtered(&self, sheet_names: &[String]) -> Vec<(usize, String)> {
+        let mut matches: Vec<(usize, usize, String)> = sheet_names
+            .iter()
+            .enumerate()
+            .filter_map(|(index, name)| {
+                fuzzy_score(&self.query, name).map(|score| (score, index, name.clone()))
+            })
+            .collect();
+
+        // Workbook order among equally good matches
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        matches
+            .into_iter()
+            .map(|(_, index, name)| (index, name))
+            .collect()
+    }
+}
+
+impl AppState<'_> {
+    /// gs: lists every sheet with a fuzzy filter, starting on the current one
+    pub fn open_sheet_picker(&mut self) {
+        self.sheet_picker = Some(SheetPicker {
+            query: String::new(),
+            selected: self.workbook.get_current_sheet_index(),
+        });
+        self.input_mode = InputMode::SheetPicker;
+    }
+
+    pub fn move_sheet_picker_selection(&mut self, delta: isize) {
+        let sheet_names = self.workbook.get_sheet_names();
+        if let Some(picker) = &mut self.sheet_picker {
+            let count = picker.filtered(&sheet_names).len();
+            picker.selected = picker
+                .selected
+                .saturating_add_signed(delta)
+                .min(count.saturating_sub(1));
+        }
+    }
+
+    pub fn update_sheet_picker_query(&mut self, c: Option<char>) {
+        if let Some(picker) = &mut self.sheet_picker {
+            match c {
+                Some(c) => picker.query.push(c),
+                None => {
+                    picker.query.pop();
+                }
+            }
+            picker.selected = 0;
+        }
+    }
+
+    pub fn close_sheet_picker(&mut self) {
+        self.sheet_picker = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn confirm_sheet_picker(&mut self) {
+        let Some(picker) = self.sheet_picker.take() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+
+        let sheet_names = self.workbook.get_sheet_names();
+        let Some(&(index, _)) = picker.filtered(&sheet_names).get(picker.selected) else {
+            self.add_notification(format!("No sheet matches \"{}\"", picker.query));
+            return;
+        };
+
+        if index != self.workbook.get_current_sheet_index() {
+            if let Err(e) = self.switch_sheet_by_index(index) {
+                self.add_notification(format!("Failed to switch sheet: {e}"));
+            }
+        }
+    }
+}
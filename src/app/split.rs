@@ -0,0 +1,135 @@
+use std::mem;
+
+use crate::app::{AppState, InputMode};
+
+/// The pane of :split or :vsplit without the focus. The focused pane is the one in the
+/// fields of `AppState`, moving the focus trades its sheet and position with this one
+pub struct SplitPane {
+    pub vertical: bool, // Side by side rather than one above the other
+    pub first: bool,    // This pane is the top or left one
+    sheet_name: String,
+    selected_cell: (usize, usize),
+    start_row: usize,
+    start_col: usize,
+    visible_rows: usize,
+    visible_cols: usize,
+}
+
+impl SplitPane {
+    pub fn rename_sheet(&mut self, old_name: &str, new_name: &str) {
+        if self.sheet_name == old_name {
+            self.sheet_name = new_name.to_string();
+        }
+    }
+
+    #[must_use]
+    pub fn sheet_name(&self) -> &str {
+        &self.sheet_name
+    }
+}
+
+impl AppState<'_> {
+    /// :split and :vsplit: shows the current view twice, one above the other or side by
+    /// side, and puts the focus on the new pane. With a sheet name or number the new pane
+    /// shows that sheet. A second split replaces the pane without the focus
+    pub fn split_view(&mut self, vertical: bool, sheet: &str) {
+        self.split = Some(SplitPane {
+            vertical,
+            first: false,
+            sheet_name: self.workbook.get_current_sheet_name(),
+            selected_cell: self.selected_cell,
+            start_row: self.start_row,
+            start_col: self.start_col,
+            visible_rows: self.visible_rows,
+            visible_cols: self.visible_cols,
+        });
+
+        let sheet = sheet.trim();
+        if sheet.is_empty() {
+            self.add_notification("Ctrl+w w moves the focus to the other pane".to_string());
+        } else {
+            self.switch_to_sheet(sheet);
+        }
+    }
+
+    /// Ctrl+w w: moves the focus to the other pane
+    pub fn switch_pane(&mut self) {
+        let Some(mut pane) = self.split.take() else {
+            self.add_notification("No split to move in, :split or :vsplit adds one".to_string());
+            return;
+        };
+        if self.exchange_pane(&mut pane) {
+            self.search_results.clear();
+            self.current_search_idx = None;
+        }
+        pane.first = !pane.first;
+        self.split = Some(pane);
+        self.clear_selection();
+        self.handle_scrolling();
+        self.update_row_number_width();
+
+        let index = self.workbook.get_current_sheet_index();
+        if self.workbook.is_lazy_loading() && !self.workbook.is_sheet_loaded(index) {
+            self.input_mode = InputMode::LazyLoading;
+        }
+    }
+
+    /// :only: closes the pane without the focus
+    pub fn close_other_pane(&mut self) {
+        if self.split.take().is_none() {
+            self.add_notification("Already only one pane".to_string());
+        }
+    }
+
+    /// :close: closes the focused pane, the other one takes the whole grid
+    pub fn close_pane(&mut self) {
+        if self.split.is_none() {
+            self.add_notification("Can't close the only pane, use :q to quit".to_string());
+            return;
+        }
+        self.switch_pane();
+        self.split = None;
+    }
+
+    /// Trades the focused view for the one of `pane`, returning whether the sheet changed.
+    /// Also used to draw the pane without the focus, so it says nothing
+    pub fn exchange_pane(&mut self, pane: &mut SplitPane) -> bool {
+        self.store_current_column_widths();
+        let sheet_name = self.workbook.get_current_sheet_name();
+
+        // A sheet deleted meanwhile leaves the pane on the focused sheet
+        let index = self
+            .workbook
+            .get_sheet_names()
+            .iter()
+            .position(|name| *name == pane.sheet_name)
+            .unwrap_or_else(|| self.workbook.get_current_sheet_index());
+        // Switching can only fail for an index out of range, and this one comes from the list
+        let _ = self.workbook.switch_sheet(index);
+        let new_name = self.workbook.get_current_sheet_name();
+        let changed = new_name != sheet_name;
+        pane.sheet_name = sheet_name;
+
+        mem::swap(&mut self.selected_cell, &mut pane.selected_cell);
+        mem::swap(&mut self.start_row, &mut pane.start_row);
+        mem::swap(&mut self.start_col, &mut pane.start_col);
+        mem::swap(&mut self.visible_rows, &mut pane.visible_rows);
+        mem::swap(&mut self.visible_cols, &mut pane.visible_cols);
+
+        if changed {
+            self.column_widths = self
+                .sheet_column_widths
+                .get(&new_name)
+                .cloned()
+                .unwrap_or_else(|| vec![15; 1]);
+            self.ensure_column_widths();
+        }
+        // Rows or columns removed in the other pane may have left the cursor outside the sheet
+        let sheet = self.workbook.get_current_sheet();
+        self.selected_cell = (
+            self.selected_cell.0.min(sheet.max_rows.max(1)),
+            self.selected_cell.1.min(sheet.max_cols.max(1)),
+        );
+        changed
+    }
+}
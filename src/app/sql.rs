@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, bail, Context, Result};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+
+use crate::app::script::number_text;
+use crate::app::AppState;
+use crate::excel::{Cell, CellGrid, CellType, Sheet, StyleTable};
+use crate::utils::index_to_col_name;
+
+// Quotes a sheet or column name so any name works as an SQL identifier
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Column names from the header row. A column without a header is named by its letter,
+// a repeated header gets a number like "amount_2"
+fn column_names(sheet: &Sheet) -> Vec<String> {
+    let mut taken = HashSet::new();
+    (1..=sheet.max_cols.max(1))
+        .map(|col| {
+            let header = sheet.cell(1, col).value.trim();
+            let base = if header.is_empty() {
+                index_to_col_name(col)
+            } else {
+                header.to_string()
+            };
+            let name = (1..)
+                .map(|n| {
+                    if n == 1 {
+                        base.clone()
+                    } else {
+                        format!("{base}_{n}")
+                    }
+                })
+                .find(|name| !taken.contains(&name.to_lowercase()))
+                .unwrap_or_default();
+            taken.insert(name.to_lowercase());
+            name
+        })
+        .collect()
+}
+
+// Numbers go in as numbers so SUM and comparisons work on them, empty cells as NULL
+fn sql_value(cell: &Cell) -> Value {
+    let value = cell.value.as_str();
+    match cell.cell_type {
+        CellType::Empty => Value::Null,
+        _ if value.is_empty() => Value::Null,
+        CellType::Number => match (value.parse::<i64>(), value.parse::<f64>()) {
+            (Ok(n), _) => Value::Integer(n),
+            (_, Ok(n)) if n.is_finite() => Value::Real(n),
+            _ => Value::Text(value.to_string()),
+        },
+        _ => Value::Text(value.to_string()),
+    }
+}
+
+fn value_text(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(n) => n.to_string(),
+        Value::Real(n) => number_text(n),
+        Value::Text(text) => text,
+        Value::Blob(bytes) => format!("<{} bytes>", bytes.len()),
+    }
+}
+
+// A table named after the sheet with a column per header, the rows below the header
+// are its records
+fn create_table(connection: &mut Connection, sheet: &Sheet) -> Result<()> {
+    let table = quote_identifier(&sheet.name);
+    let columns = column_names(sheet);
+    let definitions: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+    connection
+        .execute(
+            &format!("CREATE TABLE {table} ({})", definitions.join(", ")),
+            [],
+        )
+        .with_context(|| format!("Cannot read sheet {} as a table", sheet.name))?;
+
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let transaction = connection.transaction()?;
+    {
+        let mut insert =
+            transaction.prepare(&format!("INSERT INTO {table} VALUES ({placeholders})"))?;
+        for row in 2..=sheet.max_rows {
+            let values = (1..=columns.len()).map(|col| sql_value(sheet.cell(row, col)));
+            insert.execute(params_from_iter(values))?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+// Runs the query, returning its column names and rows
+fn run_query(connection: &Connection, query: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    // The error for a mistyped query repeats the whole query, its message alone is enough
+    let mut statement = connection.prepare(query).map_err(|e| match e {
+        rusqlite::Error::SqlInputError { msg, .. } => anyhow!(msg),
+        e => e.into(),
+    })?;
+    if statement.column_count() == 0 {
+        bail!("only queries returning rows are supported, the sheets themselves stay as they are");
+    }
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut results = statement.query([])?;
+    while let Some(row) = results.next()? {
+        let values = (0..columns.len())
+            .map(|i| row.get::<_, Value>(i).map(value_text))
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        rows.push(values);
+    }
+    Ok((columns, rows))
+}
+
+// The result with the column names as its header row
+fn result_sheet(name: &str, columns: &[String], rows: Vec<Vec<String>>) -> Sheet {
+    let max_rows = rows.len() + 1;
+    let max_cols = columns.len();
+    let mut data = CellGrid::with_size(max_rows + 1, max_cols + 1);
+    for (col, column) in columns.iter().enumerate() {
+        data.set(1, col + 1, Cell::new(column.clone(), false));
+    }
+    for (row, values) in rows.into_iter().enumerate() {
+        for (col, value) in values.into_iter().enumerate() {
+            if !value.is_empty() {
+                data.set(row + 2, col + 1, Cell::new(value, false));
+            }
+        }
+    }
+
+    Sheet {
+        name: name.to_string(),
+        data,
+        max_rows,
+        max_cols,
+        is_loaded: true,
+        styles: StyleTable::default(),
+    }
+}
+
+impl AppState<'_> {
+    /// :sql query: runs SQL over the sheets it names, each read as a table with the
+    /// first row as column names, and opens the result in a new sheet
+    pub fn run_sql(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            self.add_notification(
+                "Usage: :sql query, e.g. :sql SELECT * FROM Sheet1 LIMIT 10".to_string(),
+            );
+            return;
+        }
+
+        match self.query_sheets(query) {
+            Ok((columns, rows)) => {
+                let name = self.free_sheet_name("SQL");
+                let count = rows.len();
+                let sheet = result_sheet(&name, &columns, rows);
+                let sheet_index = self.workbook.get_current_sheet_index() + 1;
+                match self.insert_new_sheet(sheet, sheet_index, vec![15; columns.len() + 1]) {
+                    Ok(()) => self.add_notification(format!("{count} row(s) in sheet {name}")),
+                    Err(e) => self.add_notification(format!("SQL: {e}")),
+                }
+            }
+            Err(e) => self.add_notification(format!("SQL error: {e:#}")),
+        }
+    }
+
+    // Copies the sheets the query mentions into an in-memory database and runs it there
+    fn query_sheets(&mut self, query: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+        let lowercase = query.to_lowercase();
+        let names: Vec<(usize, String)> = self
+            .workbook
+            .get_sheet_names()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, name)| lowercase.contains(&name.to_lowercase()))
+            .collect();
+
+        let mut connection = Connection::open_in_memory()?;
+        let mut tables = HashSet::new();
+        for (index, name) in names {
+            // Table names are case-insensitive, "Data" and "data" can't both be one
+            if !tables.insert(name.to_lowercase()) {
+                continue;
+            }
+            self.workbook.ensure_sheet_loaded(index, &name)?;
+            if let Some(sheet) = self.workbook.get_sheet_by_index(index) {
+                create_table(&mut connection, sheet)?;
+            }
+        }
+        run_query(&connection, query)
+    }
+}
@@ -1,11 +1,22 @@
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tui_textarea::TextArea;
 
 use crate::actions::UndoHistory;
-use crate::app::VimState;
-use crate::excel::Workbook;
+use crate::app::flash::DEFAULT_FLASH_TIME;
+use crate::app::{
+    command_history_path, Autosave, Buffer, CellRenderers, Clipboard, ColumnFormat, ColumnManager,
+    ColumnMapping, CommandHistory, DiffList, DistinctPicker, ExternalEdit, FileWatch, Flash,
+    JumpList, MemoryUsage, Minimap, MouseAreas, MouseDrag, NotificationBatch, PendingSave,
+    ReplaceJob, SearchJob, SearchOrigin, SelectionStats, SheetPicker, SplitPane, UndoList,
+    ValidationRule, VimState, WorkbookDiff,
+};
+use crate::commands::CommandRegistry;
+use crate::excel::{ImportProfile, Workbook};
+use crate::json_export::HeaderDirection;
+use crate::utils::NumberFormat;
 
 /// Represents a cell position in a sheet, including both the selected cell and view position
 #[derive(Clone, Copy)]
@@ -25,6 +36,19 @@ pub enum InputMode {
     Help,
     LazyLoading,
     CommandInLazyLoading,
+    DistinctPicker,
+    CellPopup,
+    ColumnMapping,
+    Replacing,
+    Searching,
+    Minimap,
+    Messages,
+    ColumnManager,
+    SheetPicker,
+    ConfirmDeleteSheet,
+    ConfirmReload,
+    UndoList,
+    DiffList,
 }
 
 pub struct AppState<'a> {
@@ -35,6 +59,25 @@ pub struct AppState<'a> {
     pub start_col: usize,
     pub visible_rows: usize,
     pub visible_cols: usize,
+    pub frozen_rows: usize, // Number of leading rows kept visible while scrolling
+    pub frozen_cols: usize, // Number of leading columns kept visible while scrolling
+    pub wrap_enabled: bool, // Wrap the content of the selected row over multiple lines
+    pub recovery_enabled: bool, // :q! writes unsaved changes to a recovery file
+    pub autosave: Autosave, // :set autosave, copies the modified workbook every few minutes
+    pub pending_save: Option<PendingSave>, // :w writing the file in the background
+    pub recovery_path: Option<PathBuf>, // Recovery file written on quit, reported after exit
+    pub profile: Option<ImportProfile>, // Profile the file was opened with
+    pub json_headers: Option<(HeaderDirection, usize)>, // Headers of the last JSON export
+    pub ignore_case: bool,  // Search ignores case
+    pub search_column: Option<usize>, // Restricts search to one column, set by :csearch
+    pub smart_case: bool,   // Search becomes case-sensitive when the query has an uppercase letter
+    pub inc_search: bool,   // Preview matches while typing a search
+    pub flash_time: Duration, // How long changed cells are highlighted, zero to disable
+    pub smart_numbers: bool, // Typed or pasted $1,234.56, 42% or 3.5k become numbers
+    pub undo_file: bool,    // Undo history is kept on save and restored when the file is reopened
+    pub type_row: bool,     // Dominant type of each column pinned under the header
+    pub cell_renderers: CellRenderers, // Presentation of URLs, timestamps, booleans and JSON in the grid
+    pub search_origin: Option<SearchOrigin>, // Where the open search prompt started
     pub input_mode: InputMode,
     pub input_buffer: String,
     pub text_area: TextArea<'a>,
@@ -42,22 +85,65 @@ pub struct AppState<'a> {
     pub column_widths: Vec<usize>, // Store width for current sheet's columns
     pub sheet_column_widths: HashMap<String, Vec<usize>>, // Store column widths for each sheet
     pub sheet_cell_positions: HashMap<String, CellPosition>, // Store cell positions for each sheet
-    pub clipboard: Option<String>, // Store copied/cut cell content
+    pub clipboard: Option<Clipboard>, // Copied/cut cell content or range of cells
     pub g_pressed: bool,           // Track if 'g' was pressed for 'gg' command
-    pub row_number_width: usize,   // Width for displaying row numbers
-    pub search_query: String,      // Current search query
+    pub z_pressed: bool,           // 'z' waiting for z, t or b
+    pub window_pressed: bool,      // Ctrl+w waiting for w, s, v, o or q
+    pub y_pressed: bool,           // 'y' just copied a cell, y or c widens it to the row or column
+    pub selection_anchor: Option<(usize, usize)>, // Corner of the visual selection opposite the cursor
+    pub pending_mark: Option<char>,               // 'm' or '\'' waiting for the mark name
+    pub pending_bracket: Option<(char, Instant)>, // '[' or ']' waiting for f, t, c or the timeout
+    pub flash: Option<Flash>,
+    pub marks: HashMap<String, HashMap<char, (usize, usize)>>, // Marks of each sheet by sheet name
+    pub validation_rules: HashMap<String, BTreeMap<usize, ValidationRule>>, // :validate rules of each sheet by column
+    pub strict_validation: bool, // :set strictvalidate, edits breaking a rule are held back
+    pub jump_list: JumpList,
+    pub command_history: CommandHistory,
+    pub commands: CommandRegistry, // Built-in :commands and those of extensions and scripts
+    pub minimap: Option<Minimap>,  // Structure map beside the grid, None when hidden
+    pub split: Option<SplitPane>,  // :split and :vsplit, the pane without the focus
+    pub diff: Option<WorkbookDiff>, // :diff, the workbook the open one is compared with
+    pub diff_list: Option<DiffList>, // :sheetdiff, the differing cells for its panel
+    pub file_watch: Option<FileWatch>, // Notices other programs changing the file, None if it can't be watched
+    pub file_changed: bool,            // The file changed on disk since it was read
+    pub reload_prompt: bool,           // The reload question waits for normal mode
+    pub buffers: Vec<Option<Buffer>>, // Open workbooks in :ls order, None in place of the shown one
+    pub memory_status: Option<MemoryUsage>, // :set memstatus, size of the loaded data in the status bar
+    pub number_format: Option<NumberFormat>, // :set numfmt, how number cells are shown in the grid
+    pub column_formats: HashMap<String, BTreeMap<usize, ColumnFormat>>, // :format of each sheet by column
+    pub stats_enabled: bool, // :set stats, figures of the selection or column in the status bar
+    pub selection_stats: Option<SelectionStats>, // Those figures, kept until the selection or data changes
+    pub row_number_width: usize,                 // Width for displaying row numbers
+    pub mouse_areas: MouseAreas, // Where the last frame drew tabs and cells, for clicks
+    pub mouse_drag: Option<MouseDrag>, // What the held left button does as the mouse moves
+    pub search_query: String,    // Current search query
     pub search_results: Vec<(usize, usize)>, // List of cells matching the search query
     pub current_search_idx: Option<usize>, // Index of current search result
-    pub search_direction: bool,    // true for forward, false for backward
-    pub highlight_enabled: bool,   // Control whether search results are highlighted
+    pub search_direction: bool,  // true for forward, false for backward
+    pub highlight_enabled: bool, // Control whether search results are highlighted
     pub info_panel_height: usize,
+    pub info_scroll: usize, // Lines the cell content panel is scrolled by the mouse wheel
+    pub info_scroll_cell: (usize, usize), // The cell info_scroll is for, others start at the top
     pub notification_messages: Vec<String>,
     pub max_notifications: usize,
+    pub message_history: Vec<String>, // Every recent notification, for :messages
+    pub notification_batch: Option<NotificationBatch>,
     pub help_text: String,
     pub help_scroll: usize,
     pub help_visible_lines: usize,
+    pub cell_popup_scroll: usize,
+    pub cell_popup_lines: usize, // Total wrapped lines of the cell popup content
+    pub cell_popup_visible_lines: usize,
     pub undo_history: UndoHistory,
     pub vim_state: Option<VimState>,
+    pub external_edit: Option<ExternalEdit>, // Text to open in $EDITOR before the next frame
+    pub distinct_picker: Option<DistinctPicker>,
+    pub sheet_picker: Option<SheetPicker>,
+    pub undo_list: Option<UndoList>,
+    pub column_mapping: Option<ColumnMapping>,
+    pub column_manager: Option<ColumnManager>,
+    pub replace_job: Option<ReplaceJob>,
+    pub search_job: Option<SearchJob>, // Search of a large sheet running between key presses
 }
 
 impl AppState<'_> {
@@ -117,6 +203,9 @@ impl AppState<'_> {
         // Check if the workbook is using lazy loading and the first sheet is not loaded
         let is_lazy_loading = workbook.is_lazy_loading() && !workbook.is_sheet_loaded(0);
 
+        // Changes other programs make to the file are noticed while it is open
+        let file_watch = FileWatch::new(&file_path).ok();
+
         // Set initial input mode based on lazy loading status
         let initial_input_mode = if is_lazy_loading {
             InputMode::LazyLoading
@@ -132,6 +221,25 @@ impl AppState<'_> {
             start_col: 1,
             visible_rows: 30, // Default values, will be adjusted based on window size
             visible_cols: 15, // Default values, will be adjusted based on window size
+            frozen_rows: 0,
+            frozen_cols: 0,
+            wrap_enabled: false,
+            recovery_enabled: true,
+            autosave: Autosave::default(),
+            pending_save: None,
+            recovery_path: None,
+            profile: None,
+            json_headers: None,
+            ignore_case: true,
+            search_column: None,
+            smart_case: false,
+            inc_search: true,
+            flash_time: DEFAULT_FLASH_TIME,
+            smart_numbers: false,
+            undo_file: false,
+            type_row: false,
+            cell_renderers: CellRenderers::default(),
+            search_origin: None,
             input_mode: initial_input_mode,
             input_buffer: String::new(),
             text_area,
@@ -141,7 +249,37 @@ impl AppState<'_> {
             sheet_cell_positions,
             clipboard: None,
             g_pressed: false,
+            z_pressed: false,
+            window_pressed: false,
+            y_pressed: false,
+            selection_anchor: None,
+            pending_mark: None,
+            pending_bracket: None,
+            flash: None,
+            marks: HashMap::new(),
+            validation_rules: HashMap::new(),
+            strict_validation: false,
+            jump_list: JumpList::default(),
+            command_history: command_history_path()
+                .map(|path| CommandHistory::load(&path))
+                .unwrap_or_default(),
+            commands: CommandRegistry::with_builtins(),
+            minimap: None,
+            split: None,
+            diff: None,
+            diff_list: None,
+            file_watch,
+            file_changed: false,
+            reload_prompt: false,
+            buffers: vec![None],
+            memory_status: None,
+            number_format: None,
+            column_formats: HashMap::new(),
+            stats_enabled: true,
+            selection_stats: None,
             row_number_width,
+            mouse_areas: MouseAreas::default(),
+            mouse_drag: None,
             search_query: String::new(),
             search_results: Vec::new(),
             current_search_idx: None,
@@ -150,22 +288,29 @@ impl AppState<'_> {
             info_panel_height: 10,
             notification_messages: Vec::new(),
             max_notifications: 5,
+            info_scroll: 0,
+            info_scroll_cell: (1, 1),
+            message_history: Vec::new(),
+            notification_batch: None,
             help_text: String::new(),
             help_scroll: 0,
             help_visible_lines: 20,
+            cell_popup_scroll: 0,
+            cell_popup_lines: 0,
+            cell_popup_visible_lines: 20,
             undo_history: UndoHistory::new(),
             vim_state: None,
+            external_edit: None,
+            distinct_picker: None,
+            sheet_picker: None,
+            undo_list: None,
+            column_mapping: None,
+            column_manager: None,
+            replace_job: None,
+            search_job: None,
         })
     }
 
-    pub fn add_notification(&mut self, message: String) {
-        self.notification_messages.push(message);
-
-        if self.notification_messages.len() > self.max_notifications {
-            self.notification_messages.remove(0);
-        }
-    }
-
     /// Updates the row number width based on the maximum row number in the current sheet
     pub fn update_row_number_width(&mut self) {
         let max_rows = self.workbook.get_current_sheet().max_rows;
@@ -185,8 +330,7 @@ impl AppState<'_> {
     pub fn get_cell_content(&self, row: usize, col: usize) -> String {
         let sheet = self.workbook.get_current_sheet();
 
-        if row < sheet.data.len() && col < sheet.data[0].len() {
-            let cell = &sheet.data[row][col];
+        if let Some(cell) = sheet.data.get(row, col) {
             if cell.is_formula {
                 let mut result = String::with_capacity(9 + cell.value.len());
                 result.push_str("Formula: ");
@@ -219,8 +363,8 @@ impl AppState<'_> {
     }
 
     pub fn cancel_input(&mut self) {
-        // If in help mode, just close the help window
-        if let InputMode::Help = self.input_mode {
+        // If in help mode or viewing cell content, just close the popup
+        if let InputMode::Help | InputMode::CellPopup = self.input_mode {
             self.input_mode = InputMode::Normal;
             return;
         }
@@ -250,10 +394,12 @@ impl AppState<'_> {
     pub fn start_command_mode(&mut self) {
         self.input_mode = InputMode::Command;
         self.input_buffer = String::new();
+        self.command_history.reset();
     }
 
     pub fn start_command_in_lazy_loading_mode(&mut self) {
         self.input_mode = InputMode::CommandInLazyLoading;
         self.input_buffer = String::new();
+        self.command_history.reset();
     }
 }
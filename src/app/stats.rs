@@ -0,0 +1,117 @@
+use crate::app::script::number_text;
+use crate::app::AppState;
+use crate::utils::index_to_col_name;
+
+/// Count, sum, average, min and max of the visual selection, or of the current column
+/// while nothing is selected, for the status bar
+pub struct SelectionStats {
+    sheet_index: usize,
+    revision: u64,
+    range: ((usize, usize), (usize, usize)),
+    column: Option<usize>, // Set when the stats are those of the current column
+    count: usize,          // Non-empty cells
+    numbers: usize,        // Cells holding a number, the others only count
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+// Averages are cut to a few decimals, the status bar has no room for the rest
+fn short_number(n: f64) -> String {
+    if n.fract() == 0.0 {
+        return number_text(n);
+    }
+    let text = format!("{n:.4}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+impl SelectionStats {
+    /// The text of the status bar, e.g. "count 5 sum 27 avg 5.4 min 2 max 10"
+    #[must_use]
+    pub fn label(&self) -> Option<String> {
+        if self.count == 0 {
+            return None;
+        }
+        let column = self
+            .column
+            .map_or_else(String::new, |col| format!("{}: ", index_to_col_name(col)));
+        if self.numbers == 0 {
+            return Some(format!("{column}count {}", self.count));
+        }
+        Some(format!(
+            "{column}count {} sum {} avg {} min {} max {}",
+            self.count,
+            short_number(self.sum),
+            short_number(self.sum / self.numbers as f64),
+            number_text(self.min),
+            number_text(self.max)
+        ))
+    }
+}
+
+impl AppState<'_> {
+    /// :set stats shows the figures of the selection in the status bar, :set nostats hides them
+    pub fn set_selection_stats(&mut self, enabled: bool) {
+        self.stats_enabled = enabled;
+        self.selection_stats = None;
+    }
+
+    /// Adds up the selection again when it, the sheet or its data changed since the last time
+    pub fn refresh_selection_stats(&mut self) {
+        if !self.stats_enabled {
+            return;
+        }
+        let sheet_index = self.workbook.get_current_sheet_index();
+        let revision = self.undo_history.revision();
+        let sheet = self.workbook.get_current_sheet();
+        let (range, column) = match self.selection_range() {
+            Some(range) => (range, None),
+            // The header row is left out of a whole column
+            None => {
+                let col = self.selected_cell.1;
+                (((2, col), (sheet.max_rows, col)), Some(col))
+            }
+        };
+
+        if let Some(stats) = &self.selection_stats {
+            if stats.sheet_index == sheet_index
+                && stats.revision == revision
+                && stats.range == range
+                && stats.column == column
+            {
+                return;
+            }
+        }
+
+        let mut stats = SelectionStats {
+            sheet_index,
+            revision,
+            range,
+            column,
+            count: 0,
+            numbers: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        };
+        let ((top, left), (bottom, right)) = range;
+        for row in top..=bottom.min(sheet.max_rows) {
+            for col in left..=right.min(sheet.max_cols) {
+                let value = sheet.cell(row, col).value.trim();
+                if value.is_empty() {
+                    continue;
+                }
+                stats.count += 1;
+                if let Ok(number) = value.parse::<f64>() {
+                    if number.is_finite() {
+                        stats.numbers += 1;
+                        stats.sum += number;
+                        stats.min = stats.min.min(number);
+                        stats.max = stats.max.max(number);
+                    }
+                }
+            }
+        }
+        self.selection_stats = Some(stats);
+    }
+}
@@ -0,0 +1,417 @@
+use std::time::{Duration, Instant};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::excel::{RowScan, RowStream};
+use crate::utils::{cell_reference, parse_cell_reference};
+
+// Widest a column gets from fitting its content
+const MAX_STREAM_COLUMN_WIDTH: usize = 30;
+
+// Rows searched between two looks at the clock
+const SEARCH_BATCH: usize = 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StreamMode {
+    Normal,
+    Command,
+    Search,
+}
+
+/// A search scanning the file from disk, forward from the cursor and around to where it started
+pub struct StreamSearch {
+    query: String,
+    needle: String, // The query lowercased when the search ignores case
+    ignore_case: bool,
+    scanner: Box<dyn RowScan>,
+    origin: (usize, usize),
+    wrapped: bool,
+    rows_scanned: usize,
+}
+
+impl StreamSearch {
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn rows_scanned(&self) -> usize {
+        self.rows_scanned
+    }
+}
+
+// Only cells after the origin count before wrapping, and only up to it after
+fn is_searched(origin: (usize, usize), wrapped: bool, row: usize, col: usize) -> bool {
+    if wrapped {
+        row < origin.0 || (row == origin.0 && col <= origin.1)
+    } else {
+        row > origin.0 || col > origin.1
+    }
+}
+
+/// State of the read-only viewer started with --stream, rows come from a `RowStream`
+pub struct StreamViewer {
+    pub stream: Box<dyn RowStream>,
+    pub selected_cell: (usize, usize), // (row, col)
+    pub start_row: usize,
+    pub start_col: usize,
+    pub visible_rows: usize,
+    pub grid_width: usize, // Columns of the screen available to cells, set by the renderer
+    pub column_widths: Vec<usize>, // Indexed by 1-based column, grows as wider content shows up
+    pub mode: StreamMode,
+    pub input_buffer: String,
+    pub search_query: String,
+    pub search: Option<StreamSearch>,
+    pub message: Option<String>,
+    pub g_pressed: bool,
+    pub should_quit: bool,
+}
+
+impl StreamViewer {
+    #[must_use]
+    pub fn new(stream: Box<dyn RowStream>) -> Self {
+        Self {
+            stream,
+            selected_cell: (1, 1),
+            start_row: 1,
+            start_col: 1,
+            visible_rows: 20,
+            grid_width: 80,
+            column_widths: vec![0],
+            mode: StreamMode::Normal,
+            input_buffer: String::new(),
+            search_query: String::new(),
+            search: None,
+            message: None,
+            g_pressed: false,
+            should_quit: false,
+        }
+    }
+
+    /// Indexes more of the file, reads the rows to show and moves a running search on,
+    /// called between key presses
+    pub fn tick(&mut self, budget: Duration) {
+        if let Err(e) = self.stream.index_for(budget) {
+            self.message = Some(format!("Failed to index file: {e}"));
+        }
+        if let Err(e) = self.stream.read_for(budget) {
+            self.message = Some(format!("Failed to read rows: {e}"));
+        }
+        self.continue_search(budget);
+    }
+
+    /// Whether there is work to do without waiting for a key
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        !self.stream.is_indexed() || self.stream.is_reading() || self.search.is_some()
+    }
+
+    #[must_use]
+    pub fn column_width(&self, col: usize) -> usize {
+        self.column_widths.get(col).copied().unwrap_or(5)
+    }
+
+    /// Widens columns to fit the given rows, columns never shrink so the layout doesn't jump
+    pub fn fit_columns(&mut self, rows: &[Vec<String>]) {
+        for row in rows {
+            if self.column_widths.len() <= row.len() {
+                self.column_widths.resize(row.len() + 1, 5);
+            }
+            for (idx, value) in row.iter().enumerate() {
+                let width = (value.width() + 2).min(MAX_STREAM_COLUMN_WIDTH);
+                let current = &mut self.column_widths[idx + 1];
+                *current = (*current).max(width);
+            }
+        }
+    }
+
+    fn last_row(&self) -> usize {
+        self.stream.rows().max(1)
+    }
+
+    fn last_col(&self) -> usize {
+        self.stream.max_cols().max(1)
+    }
+
+    pub fn move_cursor(&mut self, delta_row: isize, delta_col: isize) {
+        let (row, col) = self.selected_cell;
+        self.selected_cell = (
+            row.saturating_add_signed(delta_row)
+                .clamp(1, self.last_row()),
+            col.saturating_add_signed(delta_col)
+                .clamp(1, self.last_col()),
+        );
+        self.handle_scrolling();
+    }
+
+    pub fn scroll_page(&mut self, forward: bool) {
+        let page = self.visible_rows.max(1) as isize;
+        self.move_cursor(if forward { page } else { -page }, 0);
+    }
+
+    pub fn scroll_half_page(&mut self, forward: bool) {
+        let half = (self.visible_rows as isize / 2).max(1);
+        self.move_cursor(if forward { half } else { -half }, 0);
+    }
+
+    pub fn jump_to_first_row(&mut self) {
+        self.selected_cell.0 = 1;
+        self.handle_scrolling();
+    }
+
+    /// G: the last row indexed so far, the end of the file once indexing is done
+    pub fn jump_to_last_row(&mut self) {
+        self.selected_cell.0 = self.last_row();
+        if !self.stream.is_indexed() {
+            self.message = Some(format!(
+                "Still indexing, jumped to the last row found so far ({})",
+                self.stream.rows()
+            ));
+        }
+        self.handle_scrolling();
+    }
+
+    pub fn jump_to_first_col(&mut self) {
+        self.selected_cell.1 = 1;
+        self.handle_scrolling();
+    }
+
+    pub fn jump_to_last_col(&mut self) {
+        self.selected_cell.1 = self.last_col();
+        self.handle_scrolling();
+    }
+
+    pub fn handle_scrolling(&mut self) {
+        let (row, col) = self.selected_cell;
+        let visible_rows = self.visible_rows.max(1);
+
+        if row < self.start_row {
+            self.start_row = row;
+        } else if row >= self.start_row + visible_rows {
+            self.start_row = row - visible_rows + 1;
+        }
+
+        if col < self.start_col {
+            self.start_col = col;
+        } else {
+            // Scroll right until the selected column fits on the screen
+            while self.start_col < col
+                && (self.start_col..=col)
+                    .map(|c| self.column_width(c))
+                    .sum::<usize>()
+                    > self.grid_width
+            {
+                self.start_col += 1;
+            }
+        }
+    }
+
+    /// [ and ]: shows the previous or next sheet of a workbook from its first row
+    pub fn switch_sheet(&mut self, forward: bool) {
+        self.search = None;
+        match self.stream.switch_sheet(forward) {
+            Ok(true) => {
+                self.selected_cell = (1, 1);
+                self.start_row = 1;
+                self.start_col = 1;
+                self.column_widths = vec![0];
+                self.message = self
+                    .stream
+                    .sheet_name()
+                    .map(|name| format!("Switched to sheet: {name}"));
+            }
+            Ok(false) => {
+                self.message = Some(
+                    if forward {
+                        "Already at the last sheet"
+                    } else {
+                        "Already at the first sheet"
+                    }
+                    .to_string(),
+                );
+            }
+            Err(e) => self.message = Some(format!("Failed to switch sheet: {e}")),
+        }
+    }
+
+    pub fn start_input(&mut self, mode: StreamMode) {
+        self.mode = mode;
+        self.input_buffer.clear();
+        self.message = None;
+    }
+
+    pub fn cancel_input(&mut self) {
+        self.mode = StreamMode::Normal;
+        self.input_buffer.clear();
+    }
+
+    /// Enter in the command or search prompt
+    pub fn submit_input(&mut self) {
+        let input = std::mem::take(&mut self.input_buffer);
+        let mode = self.mode;
+        self.mode = StreamMode::Normal;
+
+        match mode {
+            StreamMode::Command => self.execute_command(input.trim()),
+            StreamMode::Search => {
+                if !input.is_empty() {
+                    self.search_query = input;
+                }
+                self.search_next();
+            }
+            StreamMode::Normal => {}
+        }
+    }
+
+    fn execute_command(&mut self, command: &str) {
+        match command {
+            "" => {}
+            "q" | "q!" | "quit" => self.should_quit = true,
+            _ => {
+                if let Ok(row) = command.parse::<usize>() {
+                    self.goto((row.max(1), self.selected_cell.1));
+                } else if let Some(cell) = parse_cell_reference(command) {
+                    self.goto(cell);
+                } else {
+                    self.message = Some(format!(
+                        "Unknown command in streaming mode: {command} (read-only, :q quits)"
+                    ));
+                }
+            }
+        }
+    }
+
+    fn goto(&mut self, (row, col): (usize, usize)) {
+        if row > self.stream.rows() {
+            self.message = Some(if self.stream.is_indexed() {
+                format!(
+                    "Row {row} is past the end of the file ({} rows)",
+                    self.stream.rows()
+                )
+            } else {
+                format!(
+                    "Row {row} isn't indexed yet ({} rows so far)",
+                    self.stream.rows()
+                )
+            });
+            return;
+        }
+
+        self.selected_cell = (row, col.clamp(1, self.last_col()));
+        self.handle_scrolling();
+    }
+
+    /// / and n: scans forward from the cursor for the last search, wrapping around once
+    pub fn search_next(&mut self) {
+        if self.search_query.is_empty() {
+            self.message = Some("No previous search".to_string());
+            return;
+        }
+
+        let (row, col) = self.selected_cell;
+        match self.stream.scan_from(row) {
+            Ok(scanner) => {
+                // Smart case: an uppercase letter makes the search case-sensitive
+                let ignore_case = !self.search_query.chars().any(char::is_uppercase);
+                self.search = Some(StreamSearch {
+                    query: self.search_query.clone(),
+                    needle: if ignore_case {
+                        self.search_query.to_lowercase()
+                    } else {
+                        self.search_query.clone()
+                    },
+                    ignore_case,
+                    scanner,
+                    origin: (row, col),
+                    wrapped: false,
+                    rows_scanned: 0,
+                });
+            }
+            Err(e) => self.message = Some(format!("Search failed: {e}")),
+        }
+    }
+
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.message = Some(format!("Search for {} cancelled", search.query));
+        }
+    }
+
+    fn continue_search(&mut self, budget: Duration) {
+        let Some(mut search) = self.search.take() else {
+            return;
+        };
+
+        let started = Instant::now();
+        while started.elapsed() < budget {
+            for _ in 0..SEARCH_BATCH {
+                // Rows past the index can't be shown yet, wait for indexing to catch up
+                let next_row = search.scanner.next_row_number();
+                let at_end = next_row > self.stream.rows();
+                if at_end && !self.stream.is_indexed() {
+                    self.search = Some(search);
+                    return;
+                }
+                if at_end || (search.wrapped && next_row > search.origin.0) {
+                    if search.wrapped {
+                        self.message = Some(format!("Pattern not found: {}", search.query));
+                        return;
+                    }
+                    match self.stream.scan_from(1) {
+                        Ok(scanner) => {
+                            search.scanner = scanner;
+                            search.wrapped = true;
+                            continue;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Search failed: {e}"));
+                            return;
+                        }
+                    }
+                }
+
+                let cells = match search.scanner.next_row() {
+                    Ok(Some((_, cells))) => cells,
+                    Ok(None) => {
+                        self.message = Some(format!("Pattern not found: {}", search.query));
+                        return;
+                    }
+                    Err(e) => {
+                        self.message = Some(format!("Search failed: {e}"));
+                        return;
+                    }
+                };
+
+                let (origin, wrapped) = (search.origin, search.wrapped);
+                let found = cells.iter().enumerate().find_map(|(idx, value)| {
+                    let col = idx + 1;
+                    if !is_searched(origin, wrapped, next_row, col) {
+                        return None;
+                    }
+                    let matched = if search.ignore_case {
+                        value.to_lowercase().contains(&search.needle)
+                    } else {
+                        value.contains(&search.needle)
+                    };
+                    matched.then_some(col)
+                });
+                search.rows_scanned += 1;
+
+                if let Some(col) = found {
+                    self.selected_cell = (next_row, col);
+                    self.handle_scrolling();
+                    self.message = Some(if search.wrapped {
+                        format!(
+                            "Search wrapped to top, found at {}",
+                            cell_reference((next_row, col))
+                        )
+                    } else {
+                        format!("Found at {}", cell_reference((next_row, col)))
+                    });
+                    return;
+                }
+            }
+        }
+
+        self.search = Some(search);
+    }
+}
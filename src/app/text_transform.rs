@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use crate::app::AppState;
+
+// Characters that show as nothing but still make values differ, like a byte order mark
+fn is_invisible(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t')
+        || matches!(
+            c,
+            '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{AD}'
+        )
+}
+
+/// Letter case set by :upper, :lower and :title
+#[derive(Clone, Copy)]
+pub enum TextCase {
+    Upper,
+    Lower,
+    Title,
+}
+
+impl TextCase {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Upper => "Upper",
+            Self::Lower => "Lower",
+            Self::Title => "Title",
+        }
+    }
+
+    fn apply(self, value: &str) -> String {
+        match self {
+            Self::Upper => value.to_uppercase(),
+            Self::Lower => value.to_lowercase(),
+            // A word starts after anything but a letter, a digit or an apostrophe, so
+            // "o'neil-smith" becomes "O'neil-Smith"
+            Self::Title => {
+                let mut title = String::with_capacity(value.len());
+                let mut in_word = false;
+                for c in value.chars() {
+                    if in_word {
+                        title.extend(c.to_lowercase());
+                    } else {
+                        title.extend(c.to_uppercase());
+                    }
+                    in_word = c.is_alphanumeric() || c == '\'';
+                }
+                title
+            }
+        }
+    }
+}
+
+/// What :clean does to each value, all three unless some are named
+struct Clean {
+    trim: bool,
+    squeeze: bool,
+    printable: bool,
+}
+
+impl Clean {
+    fn parse(input: &str) -> Result<Self, String> {
+        let options: Vec<&str> = input.split_whitespace().collect();
+        if options.is_empty() || options == ["all"] {
+            return Ok(Self {
+                trim: true,
+                squeeze: true,
+                printable: true,
+            });
+        }
+        let mut clean = Self {
+            trim: false,
+            squeeze: false,
+            printable: false,
+        };
+        for option in options {
+            match option {
+                "trim" => clean.trim = true,
+                "squeeze" => clean.squeeze = true,
+                "printable" | "print" => clean.printable = true,
+                _ => {
+                    return Err(format!(
+                        "Unknown option {option}, use trim, squeeze, printable or all"
+                    ))
+                }
+            }
+        }
+        Ok(clean)
+    }
+
+    fn apply(&self, value: &str) -> String {
+        let mut value = value.to_string();
+        if self.printable {
+            value.retain(|c| !is_invisible(c));
+        }
+        // Runs of spaces and tabs become one space, line breaks stay
+        if self.squeeze {
+            let mut squeezed = String::with_capacity(value.len());
+            let mut in_space = false;
+            for c in value.chars() {
+                if c.is_whitespace() && c != '\n' {
+                    if !in_space {
+                        squeezed.push(' ');
+                    }
+                    in_space = true;
+                } else {
+                    squeezed.push(c);
+                    in_space = false;
+                }
+            }
+            value = squeezed;
+        }
+        if self.trim {
+            value = value
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string();
+        }
+        value
+    }
+}
+
+impl AppState<'_> {
+    // Cells of the selection, or of the whole sheet without one
+    fn selection_or_sheet(&self) -> ((usize, usize), (usize, usize)) {
+        self.selection_range().unwrap_or_else(|| {
+            let sheet = self.workbook.get_current_sheet();
+            ((1, 1), (sheet.max_rows, sheet.max_cols))
+        })
+    }
+
+    // Cells of the selection, or of the data rows of the current column without one
+    fn selection_or_column(&self) -> ((usize, usize), (usize, usize)) {
+        self.selection_range().unwrap_or_else(|| {
+            let col = self.selected_cell.1;
+            let max_rows = self.workbook.get_current_sheet().max_rows;
+            ((self.first_data_row(), col), (max_rows, col))
+        })
+    }
+
+    // Rewrites the values in the range with `transform` as one undo step, formulas and
+    // empty cells are left alone. Returns how many cells changed
+    fn transform_cells(
+        &mut self,
+        name: &str,
+        ((top, left), (bottom, right)): ((usize, usize), (usize, usize)),
+        transform: impl Fn(&str) -> String,
+    ) -> usize {
+        if let Err(e) = self.workbook.ensure_current_sheet_editable() {
+            self.add_notification(format!("{name}: {e}"));
+            return 0;
+        }
+
+        let sheet = self.workbook.get_current_sheet();
+        let mut changes = BTreeMap::new();
+        for row in top..=bottom.min(sheet.max_rows) {
+            for col in left..=right.min(sheet.max_cols) {
+                let cell = sheet.cell(row, col);
+                if cell.is_formula || cell.value.is_empty() {
+                    continue;
+                }
+                let value = transform(&cell.value);
+                if value != cell.value {
+                    changes.insert((row, col), value);
+                }
+            }
+        }
+        if changes.is_empty() {
+            self.add_notification(format!("{name}: nothing to change"));
+            return 0;
+        }
+        self.apply_script_changes(name, changes)
+    }
+
+    /// :clean [trim] [squeeze] [printable]: strips the spaces around values, collapses
+    /// runs of spaces inside them and drops invisible characters, in the selection or the
+    /// whole sheet. Without options it does all three
+    pub fn clean_cells(&mut self, input: &str) {
+        let clean = match Clean::parse(input.trim()) {
+            Ok(clean) => clean,
+            Err(e) => {
+                self.add_notification(format!("Clean: {e}"));
+                return;
+            }
+        };
+        let range = self.selection_or_sheet();
+        self.transform_cells("Clean", range, |value| clean.apply(value));
+    }
+
+    /// :upper, :lower and :title: changes the letter case of the selection, or of the
+    /// current column below its header without one, as one undo step
+    pub fn change_case(&mut self, case: TextCase) {
+        let range = self.selection_or_column();
+        self.transform_cells(case.name(), range, |value| case.apply(value));
+    }
+}
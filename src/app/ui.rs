@@ -1,105 +1,272 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
 use crate::app::AppState;
 use crate::app::InputMode;
+use crate::excel::SheetError;
+
+/// A :w running in the background, finished by `continue_save`
+pub struct PendingSave {
+    revision: u64, // Undo history revision the copy was taken at
+    quit: bool,    // :wq quits once it's written
+}
+
+// Keys and options of each :help section, the section's commands follow them
+const HELP_SECTIONS: [(&str, &str); 10] = [
+    (
+        "FILE OPERATIONS",
+        "Esc         - Cancel a running save when nothing is selected\n\
+         :set norecovery - Discard unsaved changes on :q! instead\n\
+         :set autosave 5m - Copy the modified workbook to <name>_autosave.xlsx every 5 minutes\n\
+         :set noautosave - Stop autosaving\n\
+         Up/Down after : - Recall earlier commands starting with the typed text",
+    ),
+    (
+        "NAVIGATION",
+        ":[cell]     - Jump to cell (e.g., :B10), :Sheet2!A1 also switches sheet\n\
+         hjkl        - Move cursor (left, down, up, right)\n\
+         0           - Jump to first column\n\
+         ^           - Jump to first non-empty column\n\
+         $           - Jump to last column\n\
+         gg          - Jump to first row\n\
+         G           - Jump to last row\n\
+         m{a-z}      - Set a mark at the current cell (per sheet)\n\
+         '{a-z}      - Jump to a mark, '' jumps back, :marks lists them\n\
+         Ctrl+o/Ctrl+i - Go back / forward in the jump list (gg, G, search, goto, marks)\n\
+         Ctrl+w w    - Move the focus to the other pane of :split / :vsplit\n\
+         Ctrl+arrows - Jump to the edge of the data block, then to the next block\n\
+         Shift+Ctrl+arrows - Extend the visual selection the same way\n\
+         v           - Start / end a visual selection, Esc ends it\n\
+         y, d, p     - With a selection: copy / cut the range, p pastes it at the\n              \
+                       cursor on any sheet as one undo step\n\
+         yy, yc      - Copy the current row / column, p pastes it over the row /\n              \
+                       column under the cursor, also on another sheet\n\
+         p, paste    - A block copied elsewhere (tabs/CSV lines) fills a range at the\n              \
+                       cursor; p reads the system clipboard until y or d is used\n\
+         gm          - Pick a spot on the structure map (:set minimap) and jump there\n\
+         Ctrl+f/Ctrl+b - Scroll a page down / up (also PageDown/PageUp)\n\
+         Ctrl+d/Ctrl+u - Scroll half a page down / up\n\
+         zz, zt, zb  - Scroll the cursor row to the middle / top / bottom\n\
+         Mouse click - Move to the cell clicked, or switch to the sheet of a tab\n\
+         Mouse drag  - Select the range dragged over as v does, past the edge scrolls\n              \
+                       From the space after a column letter: resize the column\n\
+         Mouse wheel - Scroll the grid 3 rows, with Shift 3 columns; also scrolls this\n              \
+                       help, the cell popup and the cell content panel\n\
+         [           - Switch to previous sheet\n\
+         ]           - Switch to next sheet\n\
+         gs          - Pick a sheet from a list filtered as you type\n\
+         ge          - Edit the cell in $VISUAL / $EDITOR, one undo step\n\
+         ]f / [f     - Jump to next / previous formula cell, :formulas counts them\n\
+         ]t / [t     - Jump to next / previous cell not matching the column type\n\
+         ]c / [c     - Jump to next / previous cell differing from the :diff file\n\
+         :set wrap   - Wrap long or multi-line content of the current row\n\
+         :set nowrap - Truncate cell content to a single line\n\
+         :set minimap - Show a structure map of the sheet beside the grid\n\
+         :set typerow - Pin each column's dominant type and its share under the header\n\
+         :set numfmt=#,##0.00 - Show numbers with separators and decimals (:set nonumfmt)\n\
+         :set strictvalidate - Hold back edits breaking a rule instead of warning\n\
+         :set relativetime, :set rt - Show timestamps as 2h ago (:set nort)\n\
+         :set norenderers - Show URLs, booleans and JSON as stored (:set renderers)\n\
+         :set flashtime=400 - Highlight cells changed by paste, undo and redo\n              \
+                       for this many ms (:set noflash turns it off)",
+    ),
+    (
+        "EDITING",
+        "Enter       - Edit current cell\n\
+         K, Space    - Show full content of current cell\n\
+         :set smartnumbers, :set sn - Store entered or pasted $1,234.56, 42%, 3.5k\n              \
+                       as numbers with a matching number format (:set nosn)\n\
+         u           - Undo last operation\n\
+         Ctrl+r      - Redo last undone operation\n\
+         .           - Repeat the last edit, paste, cut or row/column deletion here\n\
+         :set undofile, :set udf - Keep the undo history on save and restore it when the\n              \
+                       saved file is opened again (on by default, :set noudf)\n\
+         :set undolevels=N, :set ul=N - Keep at most N changes to undo (1000 by default),\n              \
+                       :set ul shows how many are held and their memory",
+    ),
+    (
+        "SEARCH",
+        "/           - Search forward\n\
+         ?           - Search backward\n\
+         n           - Jump to next search result\n\
+         N           - Jump to previous search result\n\
+         Esc         - Cancel a search still running on a large sheet\n\
+         :set ignorecase, :set noic - Ignore case in search (default) / match case\n\
+         :set smartcase - Match case when the query has an uppercase letter\n\
+         :set noincsearch - Don't preview matches while typing a search\n\
+         Esc while a replace is running cancels it without changing any cell\n\
+         :set memstatus - Show the total of :mem in the status bar (:set nomemstatus)\n\
+         :set nostats - Hide count / sum / avg / min / max of the selection or column",
+    ),
+    (
+        "COLUMN OPERATIONS",
+        "< / >       - Shrink / grow current column width (also Alt+←/→)",
+    ),
+    ("ROW OPERATIONS", ""),
+    ("EXPORT", ""),
+    ("SHEET OPERATIONS", ""),
+    (
+        "UI ADJUSTMENTS",
+        "+/=         - Increase info panel height\n\
+         -           - Decrease info panel height",
+    ),
+    (
+        "EDITING MODE",
+        "Esc         - Exit Vim mode and save changes\n\
+         i           - Enter Insert mode\n\
+         v           - Enter Visual mode\n\
+         Ctrl+e      - Continue editing the text in $VISUAL / $EDITOR\n\
+         y           - Yank (copy) text in Visual mode or with operator\n\
+         d           - Delete text in Visual mode or with operator\n\
+         c           - Change text in Visual mode or with operator\n\
+         p           - Paste yanked or deleted text\n\
+         u           - Undo last change\n\
+         Ctrl+r      - Redo last undone change\n\
+         h,j,k,l     - Move cursor left, down, up, right\n\
+         w           - Move to next word\n\
+         b           - Move to beginning of word\n\
+         e           - Move to end of word\n\
+         $           - Move to end of line\n\
+         ^           - Move to first non-blank character of line\n\
+         gg          - Move to first line\n\
+         G           - Move to last line\n\
+         x           - Delete character under cursor\n\
+         D           - Delete to end of line\n\
+         C           - Change to end of line\n\
+         o           - Open new line below and enter Insert mode\n\
+         O           - Open new line above and enter Insert mode\n\
+         A           - Append at end of line\n\
+         I           - Insert at beginning of line",
+    ),
+];
 
 impl AppState<'_> {
     pub fn show_help(&mut self) {
-        self.help_scroll = 0;
+        self.open_help(true);
+    }
 
-        self.help_text = "FILE OPERATIONS:\n\
-             :w          - Save file\n\
-             :wq, :x     - Save and quit\n\
-             :q          - Quit (will warn if unsaved changes)\n\
-             :q!         - Force quit without saving\n\n\
-             NAVIGATION:\n\
-             :[cell]     - Jump to cell (e.g., :B10)\n\
-             hjkl        - Move cursor (left, down, up, right)\n\
-             0           - Jump to first column\n\
-             ^           - Jump to first non-empty column\n\
-             $           - Jump to last column\n\
-             gg          - Jump to first row\n\
-             G           - Jump to last row\n\
-             Ctrl+arrows - Jump to next non-empty cell\n\
-             [           - Switch to previous sheet\n\
-             ]           - Switch to next sheet\n\
-             :sheet [name/number] - Switch to sheet by name or index\n\n\
-             EDITING:\n\
-             Enter       - Edit current cell\n\
-             :y          - Copy current cell\n\
-             :d          - Cut current cell\n\
-             :put, :pu   - Paste to current cell\n\
-             u           - Undo last operation\n\
-             Ctrl+r      - Redo last undone operation\n\n\
-             SEARCH:\n\
-             /           - Search forward\n\
-             ?           - Search backward\n\
-             n           - Jump to next search result\n\
-             N           - Jump to previous search result\n\
-             :nohlsearch, :noh - Disable search highlighting\n\n\
-             COLUMN OPERATIONS:\n\
-             :cw fit     - Adjust width of current column to fit its content\n\
-             :cw fit all - Adjust width of all columns to fit their content\n\
-             :cw min     - Set current column width to minimum (5 characters)\n\
-             :cw min all - Set all columns width to minimum\n\
-             :cw [number] - Set current column width to specific number of characters\n\
-             :dc         - Delete current column\n\
-             :dc [col]   - Delete specific column (e.g., :dc A or :dc 1)\n\
-             :dc [start] [end] - Delete columns from start to end (e.g., :dc A C)\n\n\
-             ROW OPERATIONS:\n\
-             :dr         - Delete current row\n\
-             :dr [row]   - Delete specific row\n\
-             :dr [start] [end] - Delete rows from start to end\n\n\
-             EXPORT:\n\
-             :ej [h|v] [rows]  - Export current sheet to JSON\n\
-             :eja [h|v] [rows] - Export all sheets to a single JSON file\n\
-                                h=horizontal (default), v=vertical\n\
-                                [rows]=number of header rows (default: 1)\n\n\
-             SHEET OPERATIONS:\n\
-             :delsheet   - Delete the current sheet\n\n\
-             UI ADJUSTMENTS:\n\
-             +/=         - Increase info panel height\n\
-             -           - Decrease info panel height\n\n\
-             EDITING MODE:\n\
-             Esc         - Exit Vim mode and save changes\n\
-             i           - Enter Insert mode\n\
-             v           - Enter Visual mode\n\
-             y           - Yank (copy) text in Visual mode or with operator\n\
-             d           - Delete text in Visual mode or with operator\n\
-             c           - Change text in Visual mode or with operator\n\
-             p           - Paste yanked or deleted text\n\
-             u           - Undo last change\n\
-             Ctrl+r      - Redo last undone change\n\
-             h,j,k,l     - Move cursor left, down, up, right\n\
-             w           - Move to next word\n\
-             b           - Move to beginning of word\n\
-             e           - Move to end of word\n\
-             $           - Move to end of line\n\
-             ^           - Move to first non-blank character of line\n\
-             gg          - Move to first line\n\
-             G           - Move to last line\n\
-             x           - Delete character under cursor\n\
-             D           - Delete to end of line\n\
-             C           - Change to end of line\n\
-             o           - Open new line below and enter Insert mode\n\
-             O           - Open new line above and enter Insert mode\n\
-             A           - Append at end of line\n\
-             I           - Insert at beginning of line"
-            .to_string();
+    /// Opens the help popup on the commands of each section, listed from the command
+    /// registry, with the keys and options of the section first when `with_keys`
+    pub(crate) fn open_help(&mut self, with_keys: bool) {
+        // Extensions list their commands in sections of their own after the built-in ones
+        let mut sections: Vec<&str> = HELP_SECTIONS.iter().map(|&(section, _)| section).collect();
+        for command in self.commands.iter() {
+            if !sections.contains(&command.section) {
+                sections.push(command.section);
+            }
+        }
+
+        let mut blocks = Vec::new();
+        for section in sections {
+            let mut lines: Vec<String> = Vec::new();
+            if with_keys {
+                let keys = HELP_SECTIONS
+                    .iter()
+                    .find(|&&(name, _)| name == section)
+                    .map_or("", |&(_, keys)| keys);
+                lines.extend(keys.lines().map(ToString::to_string));
+            }
+            lines.extend(
+                self.commands
+                    .iter()
+                    .filter(|command| command.section == section)
+                    .map(|command| command.help_lines()),
+            );
+            if !lines.is_empty() {
+                blocks.push(format!("{section}:\n{}", lines.join("\n")));
+            }
+        }
 
+        self.help_text = blocks.join("\n\n");
+        self.help_scroll = 0;
         self.input_mode = InputMode::Help;
     }
 
-    pub fn save_and_exit(&mut self) {
+    pub fn show_cell_popup(&mut self) {
+        let (row, col) = self.selected_cell;
+        if self.get_cell_content(row, col).is_empty() {
+            self.add_notification("Cell is empty".to_string());
+            return;
+        }
+
+        self.cell_popup_scroll = 0;
+        self.input_mode = InputMode::CellPopup;
+    }
+
+    pub fn scroll_cell_popup(&mut self, delta: isize) {
+        let max_scroll = self
+            .cell_popup_lines
+            .saturating_sub(self.cell_popup_visible_lines);
+        self.cell_popup_scroll =
+            (self.cell_popup_scroll as isize + delta).clamp(0, max_scroll as isize) as usize;
+    }
+
+    pub fn scroll_help(&mut self, delta: isize) {
+        let max_scroll = self
+            .help_text
+            .lines()
+            .count()
+            .saturating_sub(self.help_visible_lines);
+        self.help_scroll = self
+            .help_scroll
+            .saturating_add_signed(delta)
+            .min(max_scroll);
+    }
+
+    /// Scrolls the long content of the current cell in the panel below the grid, the next
+    /// frame keeps it from going past the last line
+    pub fn scroll_info_panel(&mut self, delta: isize) {
+        if self.info_scroll_cell != self.selected_cell {
+            self.info_scroll_cell = self.selected_cell;
+            self.info_scroll = 0;
+        }
+        self.info_scroll = self.info_scroll.saturating_add_signed(delta);
+    }
+
+    pub fn save_and_exit(&mut self, force: bool) {
+        if self.report_running_save() {
+            return;
+        }
         if !self.workbook.is_modified() {
             self.add_notification("No changes to save".to_string());
             self.should_quit = true;
             return;
         }
 
-        match self.workbook.save() {
+        if !force && self.warn_lossy_save("wq!") {
+            return;
+        }
+
+        self.start_save(true);
+    }
+
+    pub fn save(&mut self, force: bool) -> Result<(), anyhow::Error> {
+        if self.report_running_save() {
+            return Ok(());
+        }
+        if !self.workbook.is_modified() {
+            self.add_notification("No changes to save".to_string());
+            return Ok(());
+        }
+
+        if !force && self.warn_lossy_save("w!") {
+            return Ok(());
+        }
+
+        self.start_save(false);
+        Ok(())
+    }
+
+    // The file is written on a background thread, so input isn't held up by big workbooks
+    fn start_save(&mut self, quit: bool) {
+        self.store_current_column_widths();
+
+        match self.workbook.start_save(&self.sheet_column_widths) {
             Ok(_) => {
-                self.undo_history.clear();
-                self.add_notification("File saved".to_string());
-                self.should_quit = true;
+                self.pending_save = Some(PendingSave {
+                    revision: self.undo_history.revision(),
+                    quit,
+                });
             }
             Err(e) => {
                 self.add_notification(format!("Save failed: {e}"));
@@ -108,25 +275,139 @@ impl AppState<'_> {
         }
     }
 
-    pub fn save(&mut self) -> Result<(), anyhow::Error> {
-        if !self.workbook.is_modified() {
-            self.add_notification("No changes to save".to_string());
-            return Ok(());
-        }
+    /// Reports a background save that finished, called between key presses
+    pub fn continue_save(&mut self) {
+        let Some((saved_path, result)) = self.workbook.poll_save() else {
+            return;
+        };
+        let Some(save) = self.pending_save.take() else {
+            return;
+        };
 
-        match self.workbook.save() {
-            Ok(_) => {
-                self.undo_history.clear();
-                self.add_notification("File saved".to_string());
+        let elapsed = match result {
+            Ok(elapsed) => elapsed,
+            Err(SheetError::Cancelled) => {
+                self.add_notification("Save cancelled, nothing was written".to_string());
+                return;
             }
             Err(e) => {
                 self.add_notification(format!("Save failed: {e}"));
+                return;
             }
+        };
+        self.remove_autosave_copy();
+
+        // Edits made while it was writing aren't in the file, so the workbook stays modified
+        if self.undo_history.revision() != save.revision {
+            self.add_notification(format!(
+                "File saved in {:.1}s without the changes made while saving, :w again to keep them",
+                elapsed.as_secs_f64()
+            ));
+            return;
+        }
+
+        self.workbook.set_modified(false);
+        self.keep_undo_history(Some(&saved_path));
+        self.add_notification(format!("File saved in {:.1}s", elapsed.as_secs_f64()));
+        if save.quit {
+            self.should_quit = true;
         }
-        Ok(())
     }
 
+    /// Esc while saving: stops the running save, the workbook stays modified.
+    /// Returns false when there is no save to stop
+    pub fn cancel_save(&mut self) -> bool {
+        if !self.workbook.cancel_save() {
+            return false;
+        }
+        self.add_notification("Cancelling the save...".to_string());
+        true
+    }
+
+    // Another save or quitting would cut the running one short, so they wait for it
+    pub(super) fn report_running_save(&mut self) -> bool {
+        let Some((path, _)) = self.workbook.saving() else {
+            return false;
+        };
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+        self.add_notification(format!("Still saving {name}, try again once it's written"));
+        self.input_mode = InputMode::Normal;
+        true
+    }
+
+    // With :set undofile the history stays and is stored for the saved copy, otherwise it's cleared
+    fn keep_undo_history(&mut self, saved_path: Option<&Path>) {
+        let Some(saved_path) = saved_path.filter(|_| self.undo_file) else {
+            self.undo_history.clear();
+            return;
+        };
+        self.undo_history.mark_saved();
+        if let Err(e) = self.write_undo_file(saved_path) {
+            self.add_notification(format!("Undo history not saved: {e:#}"));
+        }
+    }
+
+    // Returns true when the save was held back because the target format would lose data
+    fn warn_lossy_save(&mut self, force_command: &str) -> bool {
+        let warnings = match self.workbook.save_warnings() {
+            Ok(warnings) => warnings,
+            Err(e) => {
+                self.add_notification(format!("Save failed: {e}"));
+                return true;
+            }
+        };
+
+        if warnings.is_empty() {
+            return false;
+        }
+
+        self.with_notification_batch("Save", |app| {
+            for warning in warnings {
+                app.add_notification(warning);
+            }
+            app.add_notification(format!("Not saved. Use :{force_command} to save anyway."));
+        });
+        true
+    }
+
+    /// Quits without saving. Unsaved changes are written to a recovery file
+    /// first unless `:set norecovery` is in effect.
     pub fn exit_without_saving(&mut self) {
+        if self.report_running_save() {
+            return;
+        }
+        if self.recovery_enabled && self.workbook.is_modified() {
+            self.store_current_column_widths();
+
+            match self
+                .workbook
+                .save_recovery_copy(&recovery_dir(), &self.sheet_column_widths)
+            {
+                Ok(path) => self.recovery_path = Some(path),
+                Err(e) => {
+                    self.add_notification(format!("Failed to write recovery file: {e}"));
+                    self.add_notification(
+                        "Use :set norecovery and :q! to quit without it".to_string(),
+                    );
+                    return;
+                }
+            }
+        }
+
         self.should_quit = true;
     }
 }
+
+// Recovery files go to the user's cache directory, e.g. ~/.cache/excel-cli/recovery
+fn recovery_dir() -> PathBuf {
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(env::temp_dir);
+
+    cache_dir.join("excel-cli").join("recovery")
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActionCommand;
+use crate::app::{state_dir, AppState};
+use crate::excel::{CellStyle, StyleId};
+
+// Undo files written in another format are ignored
+const UNDO_FILE_VERSION: u32 = 2;
+
+/// The undo history as stored by :set undofile when the workbook is saved
+#[derive(Serialize, Deserialize)]
+struct UndoFile {
+    version: u32,
+    file_len: u64, // Size and modification time of the saved workbook, a file
+    file_modified: SystemTime, // changed since doesn't match the history anymore
+    styles: HashMap<String, Vec<CellStyle>>, // Style table of each sheet, by sheet name
+    undo: Vec<ActionCommand>,
+    redo: Vec<ActionCommand>,
+}
+
+/// Location of the undo history of a workbook, named after its full path like Vim's
+/// undodir, e.g. ~/.local/state/excel-cli/undo/%home%me%data.xlsx. None without a state
+/// directory
+#[must_use]
+pub fn undo_file_path(workbook_path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(workbook_path).unwrap_or_else(|_| workbook_path.to_path_buf());
+    let name: String = path
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':') {
+                '%'
+            } else {
+                c
+            }
+        })
+        .collect();
+    Some(state_dir()?.join("undo").join(name))
+}
+
+fn fingerprint(path: &Path) -> io::Result<(u64, SystemTime)> {
+    let metadata = fs::metadata(path)?;
+    Ok((metadata.len(), metadata.modified()?))
+}
+
+impl AppState<'_> {
+    /// Stores the undo history for the workbook just saved to `saved_path`, readable by
+    /// the user only as it holds the cells as they were
+    pub fn write_undo_file(&self, saved_path: &Path) -> Result<()> {
+        let Some(path) = undo_file_path(saved_path) else {
+            return Ok(());
+        };
+        let (file_len, file_modified) = fingerprint(saved_path)?;
+        let (undo, redo) = self.undo_history.snapshot();
+        let styles = self
+            .workbook
+            .sheets()
+            .map(|sheet| (sheet.name.clone(), sheet.styles.clone().into()))
+            .collect();
+
+        let undo_file = UndoFile {
+            version: UNDO_FILE_VERSION,
+            file_len,
+            file_modified,
+            styles,
+            undo,
+            redo,
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Unable to create {}", dir.display()))?;
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(&serde_json::to_vec(&undo_file)?))
+            .with_context(|| format!("Unable to write {}", path.display()))
+    }
+
+    /// Restores the undo history stored when the workbook was saved, as long as the file
+    /// hasn't changed since. Returns how many changes can be undone and redone
+    pub fn read_undo_file(&mut self) -> Result<usize> {
+        let Some(path) = undo_file_path(&self.file_path) else {
+            return Ok(0);
+        };
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).with_context(|| format!("Unable to read {}", path.display())),
+        };
+
+        let undo_file: UndoFile = serde_json::from_slice(&content)
+            .with_context(|| format!("Invalid undo file {}", path.display()))?;
+        if undo_file.version != UNDO_FILE_VERSION
+            || fingerprint(&self.file_path)? != (undo_file.file_len, undo_file.file_modified)
+        {
+            return Ok(0);
+        }
+
+        // Style ids of the stored cells point into the style tables as they were saved
+        let UndoFile {
+            styles,
+            mut undo,
+            mut redo,
+            ..
+        } = undo_file;
+        for sheet in self.workbook.sheets_mut() {
+            let Some(saved_styles) = styles.get(&sheet.name) else {
+                continue;
+            };
+            let ids: Vec<StyleId> = saved_styles
+                .iter()
+                .map(|style| sheet.styles.intern(style.clone()))
+                .collect();
+            for action in undo.iter_mut().chain(redo.iter_mut()) {
+                action.remap_styles(&sheet.name, &ids);
+            }
+        }
+
+        let count = undo.len() + redo.len();
+        self.undo_history.restore(undo, redo);
+        Ok(count)
+    }
+
+    /// Restores the stored undo history of the file just opened, telling how much came back
+    pub fn restore_undo_file(&mut self) {
+        match self.read_undo_file() {
+            Ok(0) => {}
+            Ok(count) => {
+                self.add_notification(format!("Restored {count} changes of undo history"));
+            }
+            Err(e) => self.add_notification(format!("Undo history not restored: {e:#}")),
+        }
+    }
+}
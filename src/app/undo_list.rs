@@ -0,0 +1,104 @@
+use anyhow::Result;
+
+use crate::app::{AppState, InputMode};
+
+/// One line of the :undolist panel
+pub struct UndoListEntry {
+    pub summary: String,
+    pub applied: bool, // Still in effect, false for undone changes Ctrl+r can bring back
+}
+
+/// State of the panel opened with :undolist
+#[derive(Default)]
+pub struct UndoList {
+    pub selected: usize,
+}
+
+impl AppState<'_> {
+    /// Undone changes first, then the changes in effect from newest to oldest and the file
+    /// as it was opened last. The newest change in effect is the current state
+    #[must_use]
+    pub fn undo_list_entries(&self) -> Vec<UndoListEntry> {
+        let undone = self
+            .undo_history
+            .redo_actions()
+            .iter()
+            .map(|action| UndoListEntry {
+                summary: action.summary(),
+                applied: false,
+            });
+        let applied = self
+            .undo_history
+            .undo_actions()
+            .iter()
+            .rev()
+            .map(|action| UndoListEntry {
+                summary: action.summary(),
+                applied: true,
+            });
+        let original = UndoListEntry {
+            summary: "Before any change".to_string(),
+            applied: true,
+        };
+
+        undone.chain(applied).chain([original]).collect()
+    }
+
+    /// Position of the current state in the undo list
+    #[must_use]
+    pub fn undo_list_current(&self) -> usize {
+        self.undo_history.redo_actions().len()
+    }
+
+    /// :undolist: shows the undo history with the old and new values of each change
+    pub fn open_undo_list(&mut self) {
+        self.undo_list = Some(UndoList {
+            selected: self.undo_list_current(),
+        });
+        self.input_mode = InputMode::UndoList;
+    }
+
+    pub fn move_undo_list_selection(&mut self, delta: isize) {
+        let count = self.undo_list_entries().len();
+        if let Some(list) = &mut self.undo_list {
+            list.selected = list
+                .selected
+                .saturating_add_signed(delta)
+                .min(count.saturating_sub(1));
+        }
+    }
+
+    pub fn close_undo_list(&mut self) {
+        self.undo_list = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Undoes or redoes as many changes as it takes to get to the selected entry
+    pub fn confirm_undo_list(&mut self) -> Result<()> {
+        let Some(list) = self.undo_list.take() else {
+            self.input_mode = InputMode::Normal;
+            return Ok(());
+        };
+        self.input_mode = InputMode::Normal;
+
+        let current = self.undo_list_current();
+        let steps = list.selected.abs_diff(current);
+        for _ in 0..steps {
+            if list.selected > current {
+                self.undo()?;
+            } else {
+                self.redo()?;
+            }
+        }
+
+        match steps {
+            0 => self.add_notification("Already at this change".to_string()),
+            1 => {}
+            steps if list.selected > current => {
+                self.add_notification(format!("Undid {steps} changes"));
+            }
+            steps => self.add_notification(format!("Redid {steps} changes")),
+        }
+        Ok(())
+    }
+}
@@ -1,16 +1,43 @@
 use crate::actions::{
-    ActionCommand, ActionExecutor, ActionType, CellAction, ColumnAction, MultiColumnAction,
-    MultiRowAction, RowAction, SheetAction,
+    ActionCommand, ActionExecutor, ActionType, AddSheetAction, CellAction, ColumnAction,
+    GroupAction, InsertRowsAction, MoveSheetAction, MultiCellAction, MultiColumnAction,
+    MultiRowAction, ReorderColumnsAction, RowAction, SheetAction,
 };
 use crate::app::AppState;
-use crate::utils::index_to_col_name;
+use crate::excel::{SheetResult, SparseRow};
+use crate::utils::{format_bytes, index_to_col_name};
 use anyhow::Result;
-use std::rc::Rc;
 
 impl AppState<'_> {
+    /// Runs `f` with every change it makes joined into one undo step, also when it fails
+    /// halfway so that what it did change can still be undone
+    pub fn grouped<T>(
+        &mut self,
+        label: impl Into<String>,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.undo_history.begin_group(label);
+        let result = f(self);
+        self.undo_history.end_group();
+        result
+    }
+
+    /// The :set undolevels limit and what the history holds, e.g.
+    /// `Undo keeps up to 1000 changes, 12 held (3.4 KB)`
+    #[must_use]
+    pub fn undo_levels_summary(&self) -> String {
+        format!(
+            "Undo keeps up to {} changes, {} held ({})",
+            self.undo_history.levels(),
+            self.undo_history.len(),
+            format_bytes(self.undo_history.memory_used())
+        )
+    }
+
     pub fn undo(&mut self) -> Result<()> {
         if let Some(action) = self.undo_history.undo() {
             self.apply_action(&action, true)?;
+            self.flash_action(&action);
 
             self.workbook.recalculate_max_rows();
             self.workbook.recalculate_max_cols();
@@ -25,11 +52,7 @@ impl AppState<'_> {
                 self.selected_cell.1 = sheet.max_cols.max(1);
             }
 
-            if self.undo_history.all_undone() {
-                self.workbook.set_modified(false);
-            } else {
-                self.workbook.set_modified(true);
-            }
+            self.workbook.set_modified(!self.undo_history.is_at_saved());
         } else {
             self.add_notification("No operations to undo".to_string());
         }
@@ -39,6 +62,7 @@ impl AppState<'_> {
     pub fn redo(&mut self) -> Result<()> {
         if let Some(action) = self.undo_history.redo() {
             self.apply_action(&action, false)?;
+            self.flash_action(&action);
 
             self.workbook.recalculate_max_rows();
             self.workbook.recalculate_max_cols();
@@ -53,15 +77,62 @@ impl AppState<'_> {
                 self.selected_cell.1 = sheet.max_cols.max(1);
             }
 
-            self.workbook.set_modified(true);
+            self.workbook.set_modified(!self.undo_history.is_at_saved());
         } else {
             self.add_notification("No operations to redo".to_string());
         }
         Ok(())
     }
 
-    fn apply_action(&mut self, action: &Rc<ActionCommand>, is_undo: bool) -> Result<()> {
-        match action.as_ref() {
+    /// :undo col / :undo row: undoes the newest change in the current column or row
+    /// only, later edits elsewhere are kept. Ctrl+r brings it back like any undo
+    pub fn undo_in_scope(&mut self, scope: &str) -> Result<()> {
+        let (row, col) = self.selected_cell;
+        let sheet_name = self.workbook.get_current_sheet_name();
+
+        let (label, changes) = match scope {
+            "col" | "column" => (
+                format!("column {}", index_to_col_name(col)),
+                self.undo_history
+                    .take_scoped_changes(&sheet_name, |_, c| c == col),
+            ),
+            "row" => (
+                format!("row {row}"),
+                self.undo_history
+                    .take_scoped_changes(&sheet_name, |r, _| r == row),
+            ),
+            _ => {
+                self.add_notification("Usage: :undo [col|row]".to_string());
+                return Ok(());
+            }
+        };
+
+        let Some(changes) = changes else {
+            self.add_notification(format!(
+                "No change in {label} to undo, row and column inserts or deletes stop the search"
+            ));
+            return Ok(());
+        };
+
+        for change in &changes.cells {
+            self.workbook.ensure_cell_exists(change.row, change.col);
+            self.workbook.get_current_sheet_mut().data.set(
+                change.row,
+                change.col,
+                change.old_value.clone(),
+            );
+        }
+        self.workbook.set_modified(!self.undo_history.is_at_saved());
+
+        self.add_notification(format!("Undid {} cell(s) in {label}", changes.cells.len()));
+        let action = ActionCommand::MultiCell(changes);
+        self.flash_action(&action);
+        self.undo_history.push_redo(action);
+        Ok(())
+    }
+
+    fn apply_action(&mut self, action: &ActionCommand, is_undo: bool) -> Result<()> {
+        match action {
             ActionCommand::Cell(cell_action) => {
                 let value = if is_undo {
                     &cell_action.old_value
@@ -79,12 +150,30 @@ impl AppState<'_> {
             ActionCommand::Sheet(sheet_action) => {
                 self.apply_sheet_action(sheet_action, is_undo)?;
             }
+            ActionCommand::AddSheet(add_sheet_action) => {
+                self.apply_add_sheet_action(add_sheet_action, is_undo);
+            }
+            ActionCommand::MoveSheet(move_sheet_action) => {
+                self.apply_move_sheet_action(move_sheet_action, is_undo);
+            }
             ActionCommand::MultiRow(multi_row_action) => {
                 self.apply_multi_row_action(multi_row_action, is_undo)?;
             }
             ActionCommand::MultiColumn(multi_column_action) => {
                 self.apply_multi_column_action(multi_column_action, is_undo)?;
             }
+            ActionCommand::ReorderColumns(reorder_action) => {
+                self.apply_reorder_columns_action(reorder_action, is_undo);
+            }
+            ActionCommand::InsertRows(insert_rows_action) => {
+                self.apply_insert_rows_action(insert_rows_action, is_undo)?;
+            }
+            ActionCommand::MultiCell(multi_cell_action) => {
+                self.apply_multi_cell_action(multi_cell_action, is_undo)?;
+            }
+            ActionCommand::Group(group_action) => {
+                self.apply_group_action(group_action, is_undo)?;
+            }
         }
         Ok(())
     }
@@ -108,8 +197,11 @@ impl AppState<'_> {
             }
         }
 
-        self.workbook.get_current_sheet_mut().data[cell_action.row][cell_action.col] =
-            value.clone();
+        self.workbook.get_current_sheet_mut().data.set(
+            cell_action.row,
+            cell_action.col,
+            value.clone(),
+        );
 
         self.selected_cell = (cell_action.row, cell_action.col);
         self.handle_scrolling();
@@ -160,9 +252,10 @@ impl AppState<'_> {
         let sheet = self.workbook.get_current_sheet_mut();
 
         if is_undo {
-            sheet
-                .data
-                .insert(row_action.row, row_action.row_data.clone());
+            sheet.data.insert_row(
+                row_action.row,
+                SparseRow::from_cells(row_action.row_data.clone()),
+            );
 
             sheet.max_rows = sheet.max_rows.saturating_add(1);
 
@@ -172,7 +265,7 @@ impl AppState<'_> {
 
             self.add_notification(format!("Undid row {} deletion", row_action.row));
         } else if row_action.row < sheet.data.len() {
-            sheet.data.remove(row_action.row);
+            sheet.data.remove_row(row_action.row);
             sheet.max_rows = sheet.max_rows.saturating_sub(1);
 
             if self.selected_cell.0 > sheet.max_rows {
@@ -206,20 +299,7 @@ impl AppState<'_> {
         let col = column_action.col;
 
         if is_undo {
-            let column_data = &column_action.column_data;
-
-            for (i, row) in sheet.data.iter_mut().enumerate() {
-                if i < column_data.len() {
-                    if col <= row.len() {
-                        row.insert(col, column_data[i].clone());
-                    } else {
-                        while row.len() < col {
-                            row.push(crate::excel::Cell::empty());
-                        }
-                        row.push(column_data[i].clone());
-                    }
-                }
-            }
+            sheet.data.insert_column(col, &column_action.column_data);
 
             // Update both max_cols and max_rows when restoring a column
             sheet.max_cols = sheet.max_cols.saturating_add(1);
@@ -243,11 +323,7 @@ impl AppState<'_> {
             self.ensure_column_visible(col);
             self.add_notification(format!("Undid column {} deletion", index_to_col_name(col)));
         } else {
-            for row in sheet.data.iter_mut() {
-                if col < row.len() {
-                    row.remove(col);
-                }
-            }
+            sheet.data.remove_column(col);
 
             sheet.max_cols = sheet.max_cols.saturating_sub(1);
 
@@ -331,6 +407,87 @@ impl AppState<'_> {
         Ok(())
     }
 
+    fn apply_reorder_columns_action(&mut self, action: &ReorderColumnsAction, is_undo: bool) {
+        if self.workbook.get_current_sheet_index() != action.sheet_index {
+            if let Err(e) = self.switch_sheet_by_index(action.sheet_index) {
+                self.add_notification(format!(
+                    "Cannot switch to sheet {}: {}",
+                    action.sheet_name, e
+                ));
+                return;
+            }
+        }
+
+        let order = if is_undo {
+            action.inverse_order()
+        } else {
+            action.order.clone()
+        };
+        if let Err(e) = self.workbook.reorder_columns(&order) {
+            self.add_notification(format!("Failed to reorder columns: {e}"));
+            return;
+        }
+        self.reorder_column_widths(&order);
+
+        let action_word = if is_undo { "Undid" } else { "Redid" };
+        self.add_notification(format!("{action_word} column reorder"));
+    }
+
+    fn apply_move_sheet_action(&mut self, action: &MoveSheetAction, is_undo: bool) {
+        let (from, to) = if is_undo {
+            (action.to_index, action.from_index)
+        } else {
+            (action.from_index, action.to_index)
+        };
+
+        if let Err(e) = self.workbook.move_sheet(from, to) {
+            self.add_notification(format!("Failed to move sheet {}: {}", action.sheet_name, e));
+            return;
+        }
+
+        let action_word = if is_undo { "Undid" } else { "Redid" };
+        self.add_notification(format!("{action_word} move of sheet {}", action.sheet_name));
+    }
+
+    fn apply_add_sheet_action(&mut self, action: &AddSheetAction, is_undo: bool) {
+        if is_undo {
+            if let Err(e) = self.switch_sheet_by_index(action.sheet_index) {
+                self.add_notification(format!(
+                    "Cannot switch to sheet {} to remove it: {}",
+                    action.sheet_name, e
+                ));
+                return;
+            }
+
+            if let Err(e) = self.workbook.delete_current_sheet() {
+                self.add_notification(format!("Failed to remove sheet: {e}"));
+                return;
+            }
+
+            self.cleanup_after_sheet_deletion(&action.sheet_name);
+            self.add_notification(format!("Undid creation of sheet {}", action.sheet_name));
+        } else {
+            if let Err(e) = self.execute_add_sheet_action(action) {
+                self.add_notification(format!(
+                    "Failed to recreate sheet {}: {}",
+                    action.sheet_name, e
+                ));
+                return;
+            }
+            self.sheet_column_widths
+                .insert(action.sheet_name.clone(), action.column_widths.clone());
+
+            if let Err(e) = self.switch_sheet_by_index(action.sheet_index) {
+                self.add_notification(format!(
+                    "Recreated sheet {} but couldn't switch to it: {}",
+                    action.sheet_name, e
+                ));
+            } else {
+                self.add_notification(format!("Redid creation of sheet {}", action.sheet_name));
+            }
+        }
+    }
+
     fn cleanup_after_sheet_deletion(&mut self, sheet_name: &str) {
         self.sheet_column_widths.remove(sheet_name);
         self.sheet_cell_positions.remove(sheet_name);
@@ -503,14 +660,133 @@ impl AppState<'_> {
         Ok(())
     }
 
+    fn apply_insert_rows_action(
+        &mut self,
+        insert_rows_action: &InsertRowsAction,
+        is_undo: bool,
+    ) -> Result<()> {
+        let current_sheet_index = self.workbook.get_current_sheet_index();
+
+        if current_sheet_index != insert_rows_action.sheet_index {
+            if let Err(e) = self.switch_sheet_by_index(insert_rows_action.sheet_index) {
+                self.add_notification(format!(
+                    "Cannot switch to sheet {}: {}",
+                    insert_rows_action.sheet_name, e
+                ));
+                return Ok(());
+            }
+        }
+
+        let start_row = insert_rows_action.start_row;
+        let end_row = start_row + insert_rows_action.rows_data.len().saturating_sub(1);
+
+        if is_undo {
+            self.workbook.delete_rows(start_row, end_row)?;
+
+            let sheet = self.workbook.get_current_sheet();
+            if self.selected_cell.0 > sheet.max_rows {
+                self.selected_cell.0 = sheet.max_rows.max(1);
+            }
+
+            self.add_notification(format!("Undid insertion of rows {start_row} to {end_row}"));
+        } else {
+            self.workbook
+                .insert_rows(start_row, &insert_rows_action.rows_data)?;
+            self.selected_cell.0 = start_row;
+
+            self.add_notification(format!("Redid insertion of rows {start_row} to {end_row}"));
+        }
+
+        self.handle_scrolling();
+        self.search_results.clear();
+        self.current_search_idx = None;
+
+        Ok(())
+    }
+
+    fn apply_multi_cell_action(
+        &mut self,
+        multi_cell_action: &MultiCellAction,
+        is_undo: bool,
+    ) -> Result<()> {
+        let current_sheet_index = self.workbook.get_current_sheet_index();
+
+        if current_sheet_index != multi_cell_action.sheet_index {
+            if let Err(e) = self.switch_sheet_by_index(multi_cell_action.sheet_index) {
+                self.add_notification(format!(
+                    "Cannot switch to sheet {}: {}",
+                    multi_cell_action.sheet_name, e
+                ));
+                return Ok(());
+            }
+        }
+
+        for change in &multi_cell_action.cells {
+            self.workbook.ensure_cell_exists(change.row, change.col);
+            let value = if is_undo {
+                &change.old_value
+            } else {
+                &change.new_value
+            };
+            self.workbook
+                .get_current_sheet_mut()
+                .data
+                .set(change.row, change.col, value.clone());
+        }
+
+        if let Some(first) = multi_cell_action.cells.first() {
+            self.selected_cell = (first.row, first.col);
+            self.handle_scrolling();
+        }
+
+        let operation_text = match multi_cell_action.action_type {
+            ActionType::Replace => "replace",
+            _ => "cell operation",
+        };
+        let action_word = if is_undo { "Undid" } else { "Redid" };
+        self.add_notification(format!(
+            "{} {} operation on {} cells",
+            action_word,
+            operation_text,
+            multi_cell_action.cells.len()
+        ));
+
+        self.search_results.clear();
+        self.current_search_idx = None;
+
+        Ok(())
+    }
+
+    fn apply_group_action(&mut self, group_action: &GroupAction, is_undo: bool) -> Result<()> {
+        if is_undo {
+            for action in group_action.actions.iter().rev() {
+                self.apply_action(action, true)?;
+            }
+        } else {
+            for action in &group_action.actions {
+                self.apply_action(action, false)?;
+            }
+        }
+
+        let action_word = if is_undo { "Undid" } else { "Redid" };
+        self.add_notification(format!(
+            "{} {} ({} changes)",
+            action_word,
+            group_action.label,
+            group_action.actions.len()
+        ));
+        Ok(())
+    }
+
     fn restore_rows(
         sheet: &mut crate::excel::Sheet,
         position: usize,
         rows_data: &[Vec<crate::excel::Cell>],
     ) {
-        // Pre-allocate space by extending the vector
         for row_data in rows_data.iter().rev() {
-            sheet.data.insert(position, row_data.clone());
+            sheet
+                .data
+                .insert_row(position, SparseRow::from_cells(row_data.clone()));
         }
     }
 
@@ -519,20 +795,7 @@ impl AppState<'_> {
         position: usize,
         column_data: &[crate::excel::Cell],
     ) {
-        for (i, row) in sheet.data.iter_mut().enumerate() {
-            if i < column_data.len() {
-                if position <= row.len() {
-                    row.insert(position, column_data[i].clone());
-                } else {
-                    let additional = position - row.len();
-                    row.reserve(additional + 1);
-                    while row.len() < position {
-                        row.push(crate::excel::Cell::empty());
-                    }
-                    row.push(column_data[i].clone());
-                }
-            }
-        }
+        sheet.data.insert_column(position, column_data);
     }
 
     fn restore_column_width(
@@ -577,41 +840,88 @@ impl AppState<'_> {
 }
 
 impl ActionExecutor for AppState<'_> {
-    fn execute_action(&mut self, action: &ActionCommand) -> Result<()> {
+    fn execute_action(&mut self, action: &ActionCommand) -> SheetResult<()> {
         match action {
             ActionCommand::Cell(action) => self.execute_cell_action(action),
             ActionCommand::Row(action) => self.execute_row_action(action),
             ActionCommand::Column(action) => self.execute_column_action(action),
             ActionCommand::Sheet(action) => self.execute_sheet_action(action),
+            ActionCommand::AddSheet(action) => self.execute_add_sheet_action(action),
+            ActionCommand::MoveSheet(action) => self.execute_move_sheet_action(action),
             ActionCommand::MultiRow(action) => self.execute_multi_row_action(action),
             ActionCommand::MultiColumn(action) => self.execute_multi_column_action(action),
+            ActionCommand::ReorderColumns(action) => self.execute_reorder_columns_action(action),
+            ActionCommand::InsertRows(action) => self.execute_insert_rows_action(action),
+            ActionCommand::MultiCell(action) => self.execute_multi_cell_action(action),
+            ActionCommand::Group(action) => self.execute_group_action(action),
         }
     }
 
-    fn execute_cell_action(&mut self, action: &CellAction) -> Result<()> {
+    fn execute_cell_action(&mut self, action: &CellAction) -> SheetResult<()> {
         self.workbook
-            .set_cell_value(action.row, action.col, action.new_value.value.clone())
+            .set_cell_value(action.row, action.col, action.new_value.value.clone())?;
+        // Smart numbers change the number format along with the value
+        self.workbook
+            .set_cell_style(action.row, action.col, action.new_value.style)
     }
 
-    fn execute_row_action(&mut self, action: &RowAction) -> Result<()> {
+    fn execute_row_action(&mut self, action: &RowAction) -> SheetResult<()> {
         self.workbook.delete_row(action.row)
     }
 
-    fn execute_column_action(&mut self, action: &ColumnAction) -> Result<()> {
+    fn execute_column_action(&mut self, action: &ColumnAction) -> SheetResult<()> {
         self.workbook.delete_column(action.col)
     }
 
-    fn execute_sheet_action(&mut self, action: &SheetAction) -> Result<()> {
+    fn execute_sheet_action(&mut self, action: &SheetAction) -> SheetResult<()> {
         self.switch_sheet_by_index(action.sheet_index)?;
         self.workbook.delete_current_sheet()
     }
 
-    fn execute_multi_row_action(&mut self, action: &MultiRowAction) -> Result<()> {
+    fn execute_add_sheet_action(&mut self, action: &AddSheetAction) -> SheetResult<()> {
+        // The sheet may have been renamed after it was added
+        let mut sheet = action.sheet_data.clone();
+        sheet.name.clone_from(&action.sheet_name);
+        self.workbook.add_sheet(sheet, action.sheet_index)
+    }
+
+    fn execute_move_sheet_action(&mut self, action: &MoveSheetAction) -> SheetResult<()> {
+        self.workbook.move_sheet(action.from_index, action.to_index)
+    }
+
+    fn execute_multi_row_action(&mut self, action: &MultiRowAction) -> SheetResult<()> {
         self.workbook.delete_rows(action.start_row, action.end_row)
     }
 
-    fn execute_multi_column_action(&mut self, action: &MultiColumnAction) -> Result<()> {
+    fn execute_multi_column_action(&mut self, action: &MultiColumnAction) -> SheetResult<()> {
         self.workbook
             .delete_columns(action.start_col, action.end_col)
     }
+
+    fn execute_reorder_columns_action(&mut self, action: &ReorderColumnsAction) -> SheetResult<()> {
+        self.workbook.reorder_columns(&action.order)
+    }
+
+    fn execute_insert_rows_action(&mut self, action: &InsertRowsAction) -> SheetResult<()> {
+        self.workbook
+            .insert_rows(action.start_row, &action.rows_data)
+    }
+
+    fn execute_multi_cell_action(&mut self, action: &MultiCellAction) -> SheetResult<()> {
+        for change in &action.cells {
+            self.workbook
+                .set_cell_value(change.row, change.col, change.new_value.value.clone())?;
+            // :format! changes only the number format of the cells
+            self.workbook
+                .set_cell_style(change.row, change.col, change.new_value.style)?;
+        }
+        Ok(())
+    }
+
+    fn execute_group_action(&mut self, action: &GroupAction) -> SheetResult<()> {
+        for action in &action.actions {
+            self.execute_action(action)?;
+        }
+        Ok(())
+    }
 }
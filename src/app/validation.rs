@@ -0,0 +1,248 @@
+use anyhow::{bail, Result};
+
+use crate::app::query::find_column;
+use crate::app::script::number_text;
+use crate::app::AppState;
+use crate::utils::{cell_reference, index_to_col_name, Pattern};
+
+/// What the values of a column have to be, set with :validate. Empty cells and formulas
+/// always pass
+pub enum ValidationRule {
+    Range { min: Option<f64>, max: Option<f64> },
+    Regex(Pattern),
+    List(Vec<String>),
+}
+
+// A bound of `range`, * leaves that side open
+fn bound(text: &str) -> Result<Option<f64>> {
+    if text == "*" {
+        return Ok(None);
+    }
+    match text.parse::<f64>() {
+        Ok(number) => Ok(Some(number)),
+        Err(_) => bail!("{text} is not a number, use * for no bound"),
+    }
+}
+
+impl ValidationRule {
+    /// The rule after the column in :validate, e.g. `range 1 100`, `regex ^[A-Z]{2}\d+$`
+    /// or `list open,closed,pending`
+    fn parse(kind: &str, rest: &str) -> Result<Self> {
+        match kind.to_lowercase().as_str() {
+            "range" => {
+                let bounds: Vec<&str> = rest.split_whitespace().collect();
+                let [min, max] = bounds.as_slice() else {
+                    bail!("Usage: :validate col range min max, * for no bound")
+                };
+                let (min, max) = (bound(min)?, bound(max)?);
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        bail!("The range {min} to {max} is empty");
+                    }
+                }
+                Ok(Self::Range { min, max })
+            }
+            "regex" | "re" => {
+                if rest.is_empty() {
+                    bail!("Usage: :validate col regex pattern");
+                }
+                Ok(Self::Regex(Pattern::new(rest).map_err(anyhow::Error::msg)?))
+            }
+            "list" | "in" => {
+                let values: Vec<String> = rest
+                    .split(',')
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect();
+                if values.is_empty() {
+                    bail!("Usage: :validate col list a,b,c");
+                }
+                Ok(Self::List(values))
+            }
+            _ => bail!("Unknown rule {kind}, use range, regex or list"),
+        }
+    }
+
+    #[must_use]
+    pub fn allows(&self, value: &str) -> bool {
+        let value = value.trim();
+        if value.is_empty() || value.starts_with('=') {
+            return true;
+        }
+        match self {
+            Self::Range { min, max } => value.parse::<f64>().is_ok_and(|number| {
+                min.map_or(true, |min| number >= min) && max.map_or(true, |max| number <= max)
+            }),
+            Self::Regex(pattern) => pattern.is_match(value),
+            Self::List(values) => values.iter().any(|allowed| allowed == value),
+        }
+    }
+
+    /// e.g. `a number from 1 to 100`, for the notifications
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Range { min, max } => match (min, max) {
+                (Some(min), Some(max)) => {
+                    format!(
+                        "a number from {} to {}",
+                        number_text(*min),
+                        number_text(*max)
+                    )
+                }
+                (Some(min), None) => format!("a number of at least {}", number_text(*min)),
+                (None, Some(max)) => format!("a number of at most {}", number_text(*max)),
+                (None, None) => "a number".to_string(),
+            },
+            Self::Regex(pattern) => format!("text matching {}", pattern.as_str()),
+            Self::List(values) => format!("one of {}", values.join(", ")),
+        }
+    }
+}
+
+impl AppState<'_> {
+    /// The rule of column `col` of the current sheet, if any
+    #[must_use]
+    pub fn validation_rule(&self, col: usize) -> Option<&ValidationRule> {
+        self.validation_rules
+            .get(&self.workbook.get_current_sheet_name())?
+            .get(&col)
+    }
+
+    /// Whether a data cell of the current sheet breaks the rule of its column, for the grid
+    #[must_use]
+    pub fn violates_rule(&self, row: usize, col: usize) -> bool {
+        row >= self.first_data_row()
+            && self.validation_rule(col).is_some_and(|rule| {
+                !rule.allows(&self.workbook.get_current_sheet().cell(row, col).value)
+            })
+    }
+
+    /// :validate col rule: checks the data cells of a column against a rule, marking those
+    /// that break it. `:validate col off` drops the rule, `:validate off` all of the sheet's
+    /// and a bare :validate lists them
+    pub fn set_validation(&mut self, input: &str) {
+        let input = input.trim();
+        let sheet_name = self.workbook.get_current_sheet_name();
+        if input.is_empty() {
+            self.list_validation_rules();
+            return;
+        }
+        if input.eq_ignore_ascii_case("off") {
+            self.validation_rules.remove(&sheet_name);
+            self.add_notification("Validation rules of this sheet removed".to_string());
+            return;
+        }
+
+        let mut words = input.splitn(3, char::is_whitespace);
+        let column = words.next().unwrap_or_default();
+        let kind = words.next().unwrap_or_default();
+        let rest = words.next().unwrap_or_default().trim();
+
+        let sheet = self.workbook.get_current_sheet();
+        let headers: Vec<String> = (1..=sheet.max_cols)
+            .map(|col| sheet.cell(1, col).value.trim().to_string())
+            .collect();
+        let col = match find_column(&headers, column) {
+            Ok(col) => col,
+            Err(e) => {
+                self.add_notification(format!("Validate: {e}"));
+                return;
+            }
+        };
+        let name = index_to_col_name(col);
+
+        if kind.eq_ignore_ascii_case("off") {
+            let removed = self
+                .validation_rules
+                .get_mut(&sheet_name)
+                .and_then(|rules| rules.remove(&col));
+            self.add_notification(match removed {
+                Some(_) => format!("Validation rule of column {name} removed"),
+                None => format!("Column {name} has no validation rule"),
+            });
+            return;
+        }
+        let rule = match ValidationRule::parse(kind, rest) {
+            Ok(rule) => rule,
+            Err(e) => {
+                self.add_notification(format!("Validate: {e}"));
+                return;
+            }
+        };
+
+        let description = rule.describe();
+        self.validation_rules
+            .entry(sheet_name)
+            .or_default()
+            .insert(col, rule);
+        let invalid = self.invalid_cells(col);
+        let found = match invalid.first() {
+            Some(&row) => format!(
+                ", {} cell(s) don't, the first is {}",
+                invalid.len(),
+                cell_reference((row, col))
+            ),
+            None => String::new(),
+        };
+        self.add_notification(format!("Column {name} must hold {description}{found}"));
+    }
+
+    // Rows of the data cells of column `col` that break its rule
+    fn invalid_cells(&self, col: usize) -> Vec<usize> {
+        let sheet = self.workbook.get_current_sheet();
+        (self.first_data_row()..=sheet.max_rows)
+            .filter(|&row| self.violates_rule(row, col))
+            .collect()
+    }
+
+    fn list_validation_rules(&mut self) {
+        let Some(rules) = self
+            .validation_rules
+            .get(&self.workbook.get_current_sheet_name())
+            .filter(|rules| !rules.is_empty())
+        else {
+            self.add_notification(
+                "No validation rules, e.g. :validate B range 1 100 adds one".to_string(),
+            );
+            return;
+        };
+        let list: Vec<String> = rules
+            .iter()
+            .map(|(&col, rule)| {
+                format!(
+                    "{} {} ({} invalid)",
+                    index_to_col_name(col),
+                    rule.describe(),
+                    self.invalid_cells(col).len()
+                )
+            })
+            .collect();
+        self.add_notification(format!("Validation: {}", list.join("; ")));
+    }
+
+    /// Checks an edited value against the rule of its column before it's written. Returns
+    /// false when strict validation keeps the edit from going in, warning either way
+    pub fn check_edit(&mut self, row: usize, col: usize, value: &str) -> bool {
+        if row < self.first_data_row() {
+            return true;
+        }
+        let Some(rule) = self.validation_rule(col).filter(|rule| !rule.allows(value)) else {
+            return true;
+        };
+        let message = format!(
+            "{} must hold {}",
+            cell_reference((row, col)),
+            rule.describe()
+        );
+        if self.strict_validation {
+            self.add_notification(format!(
+                "{message}, fix the value (u undoes the typing) before leaving the cell"
+            ));
+            false
+        } else {
+            self.add_notification(format!("Warning: {message}"));
+            true
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::{write_system_clipboard, AppState};
+use crate::utils::cell_reference;
+
+/// Text formats :yank can put a selection on the system clipboard as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    Markdown,
+    Csv,
+    Tsv,
+}
+
+impl TextFormat {
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "md" | "markdown" => Some(TextFormat::Markdown),
+            "csv" => Some(TextFormat::Csv),
+            "tsv" => Some(TextFormat::Tsv),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            TextFormat::Markdown => "Markdown table",
+            TextFormat::Csv => "CSV",
+            TextFormat::Tsv => "TSV",
+        }
+    }
+
+    /// Writes rows of values in this format, the first row is the header of a Markdown table
+    #[must_use]
+    pub fn format(self, rows: &[Vec<String>]) -> String {
+        let mut bytes = Vec::new();
+        // Writing to a Vec can't fail
+        let _ = self.write_rows(
+            &mut bytes,
+            rows.iter()
+                .map(|row| row.iter().map(String::as_str).collect()),
+        );
+        String::from_utf8_lossy(&bytes)
+            .trim_end_matches(['\r', '\n'])
+            .to_string()
+    }
+
+    /// Writes rows of values in this format as they are taken from `rows`, without
+    /// holding them all. A Markdown table goes through the rows once more first to line
+    /// up its columns
+    pub fn write_rows<'a, W, I>(self, out: &mut W, rows: I) -> io::Result<()>
+    where
+        W: Write,
+        I: Iterator<Item = Vec<&'a str>> + Clone,
+    {
+        match self {
+            TextFormat::Markdown => write_markdown_table(out, rows),
+            TextFormat::Csv => write_delimited(out, rows, b','),
+            TextFormat::Tsv => write_delimited(out, rows, b'\t'),
+        }
+    }
+}
+
+// Pipes would end the cell and line breaks the row
+fn escape_markdown_cell(value: &str) -> String {
+    value
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+// Columns are padded to line up when the table is read as plain text
+fn write_markdown_table<'a, W, I>(out: &mut W, rows: I) -> io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = Vec<&'a str>> + Clone,
+{
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows.clone() {
+        widths.resize(widths.len().max(row.len()), 3);
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(escape_markdown_cell(value).width());
+        }
+    }
+
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    for (idx, row) in rows.enumerate() {
+        let cells = row
+            .into_iter()
+            .zip(&widths)
+            .map(|(value, &width)| {
+                let value = escape_markdown_cell(value);
+                let padding = " ".repeat(width - value.width());
+                value + &padding
+            })
+            .collect();
+        writeln!(out, "{}", line(cells))?;
+        if idx == 0 {
+            let rule = widths.iter().map(|&width| "-".repeat(width)).collect();
+            writeln!(out, "{}", line(rule))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_delimited<'a, W, I>(out: &mut W, rows: I, delimiter: u8) -> io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = Vec<&'a str>>,
+{
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(out);
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.flush()
+}
+
+/// The header rows written before a range starting at row `top` with --with-header: the
+/// frozen rows, or row 1 when none are frozen, down to the row above the range
+#[must_use]
+pub fn header_rows(frozen_rows: usize, top: usize) -> Range<usize> {
+    1..(frozen_rows.max(1) + 1).min(top)
+}
+
+impl AppState<'_> {
+    /// :yank md|csv|tsv: puts the visual selection, or the current cell, on the system
+    /// clipboard as text. Formulas are written as formulas. With `with_header` the header
+    /// rows above the selection come first, as :ec --with-header does for a column
+    pub fn yank_as_text(&mut self, format: TextFormat, with_header: bool) {
+        let ((top, left), (bottom, right)) = self
+            .selection_range()
+            .unwrap_or((self.selected_cell, self.selected_cell));
+        let header_rows = if with_header {
+            header_rows(self.frozen_rows, top)
+        } else {
+            1..1
+        };
+        let sheet = self.workbook.get_current_sheet();
+        let rows: Vec<Vec<String>> = header_rows
+            .chain(top..=bottom)
+            .map(|row| {
+                (left..=right)
+                    .map(|col| sheet.cell(row, col).value.clone())
+                    .collect()
+            })
+            .collect();
+
+        let range = format!(
+            "{}:{}",
+            cell_reference((top, left)),
+            cell_reference((bottom, right))
+        );
+        match write_system_clipboard(&format.format(&rows)) {
+            Ok(via) => {
+                self.clear_selection();
+                self.add_notification(format!(
+                    "Copied {range} as {} to the clipboard ({via})",
+                    format.name()
+                ));
+            }
+            Err(e) => self.add_notification(format!("Yank failed: {e}")),
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use std::path::Path;
+use std::time::Duration;
 
-use crate::app::AppState;
+use crate::app::{AppState, TextCase, TextFormat};
+use crate::commands::CommandRegistry;
+use crate::excel::formats::DelimitedFormat;
 use crate::json_export::{export_all_sheets_json, export_json, HeaderDirection};
-use crate::utils::col_name_to_index;
+use crate::utils::{col_name_to_index, index_to_col_name, parse_cell_reference};
 
 impl AppState<'_> {
     pub fn execute_command(&mut self) {
@@ -13,76 +16,36 @@ impl AppState<'_> {
         if command.is_empty() {
             return;
         }
+        self.command_history.push(&command);
 
-        // Handle cell navigation (e.g., :A1, :B10)
-        if let Some(cell_ref) = parse_cell_reference(&command) {
-            self.jump_to_cell(cell_ref);
+        // Handle cell navigation (e.g., :A1, :B10, :Sheet2!C5). A sheet part has to name an
+        // existing sheet, so commands with a `!` in them like `:!echo x!A1` still run
+        let sheet_names = self.workbook.get_sheet_names();
+        if let Some((sheet, cell_ref)) = parse_cell_address(&command).filter(|(sheet, _)| {
+            sheet.map_or(true, |sheet| {
+                sheet_names.iter().any(|n| n.eq_ignore_ascii_case(sheet))
+            })
+        }) {
+            self.goto_address(sheet, cell_ref);
             return;
         }
 
-        // Handle commands
-        match command.as_str() {
-            "w" => {
-                if let Err(e) = self.save() {
-                    self.add_notification(format!("Save failed: {e}"));
-                }
-            }
-            "wq" | "x" => self.save_and_exit(),
-            "q" => {
-                if self.workbook.is_modified() {
-                    self.add_notification(
-                        "File has unsaved changes. Use :q! to force quit or :wq to save and quit."
-                            .to_string(),
-                    );
-                } else {
-                    self.should_quit = true;
-                }
-            }
-            "q!" => self.exit_without_saving(),
-            "y" => self.copy_cell(),
-            "d" => {
-                if let Err(e) = self.cut_cell() {
-                    self.add_notification(format!("Cut failed: {e}"));
-                }
-            }
-            "put" | "pu" => {
-                if let Err(e) = self.paste_cell() {
-                    self.add_notification(format!("Paste failed: {e}"));
-                }
-            }
-            "nohlsearch" | "noh" => self.disable_search_highlight(),
-            "help" => self.show_help(),
-            "delsheet" => self.delete_current_sheet(),
-            _ => {
-                // Handle commands with parameters
-                if command.starts_with("cw ") {
-                    self.handle_column_width_command(&command);
-                } else if command.starts_with("ej") {
-                    self.handle_json_export_command(&command);
-                } else if command.starts_with("sheet ") {
-                    let sheet_name = command.strip_prefix("sheet ").unwrap().trim();
-                    self.switch_to_sheet(sheet_name);
-                } else if command.starts_with("dr") {
-                    self.handle_delete_row_command(&command);
-                } else if command.starts_with("dc") {
-                    self.handle_delete_column_command(&command);
-                } else {
-                    self.add_notification(format!("Unknown command: {}", command));
-                }
-            }
+        match self.commands.find(&command) {
+            Some((handler, args)) => handler(self, args),
+            None => self.add_notification(format!("Unknown command: {}", command)),
         }
     }
 
-    fn handle_column_width_command(&mut self, cmd: &str) {
-        let parts: Vec<&str> = cmd.split_whitespace().collect();
+    fn handle_column_width_command(&mut self, args: &str) {
+        let parts: Vec<&str> = args.split_whitespace().collect();
 
-        if parts.len() < 2 {
+        if parts.is_empty() {
             self.add_notification("Usage: :cw [fit|min|number] [all]".to_string());
             return;
         }
 
-        let action = parts[1];
-        let apply_to_all = parts.len() > 2 && parts[2] == "all";
+        let action = parts[0];
+        let apply_to_all = parts.len() > 1 && parts[1] == "all";
 
         match action {
             "fit" => {
@@ -120,10 +83,241 @@ impl AppState<'_> {
         }
     }
 
-    fn handle_delete_row_command(&mut self, cmd: &str) {
-        let parts: Vec<&str> = cmd.split_whitespace().collect();
+    fn handle_set_command(&mut self, option: &str) {
+        match option {
+            "wrap" => {
+                self.wrap_enabled = true;
+                self.add_notification("Wrapping enabled for the current row".to_string());
+            }
+            "nowrap" => {
+                self.wrap_enabled = false;
+                self.add_notification("Wrapping disabled".to_string());
+            }
+            "ignorecase" | "ic" => {
+                self.ignore_case = true;
+                self.add_notification("Search ignores case".to_string());
+            }
+            "noignorecase" | "noic" => {
+                self.ignore_case = false;
+                self.add_notification("Search is case-sensitive".to_string());
+            }
+            "smartcase" | "scs" => {
+                self.smart_case = true;
+                self.add_notification(
+                    "Smart case enabled: uppercase letters make the search case-sensitive"
+                        .to_string(),
+                );
+            }
+            "nosmartcase" | "noscs" => {
+                self.smart_case = false;
+                self.add_notification("Smart case disabled".to_string());
+            }
+            "recovery" => {
+                self.recovery_enabled = true;
+                self.add_notification(
+                    "Unsaved changes are kept in a recovery file on :q!".to_string(),
+                );
+            }
+            "norecovery" => {
+                self.recovery_enabled = false;
+                self.add_notification("Unsaved changes are discarded on :q!".to_string());
+            }
+            "incsearch" | "is" => {
+                self.inc_search = true;
+                self.add_notification("Matches are previewed while typing a search".to_string());
+            }
+            "noincsearch" | "nois" => {
+                self.inc_search = false;
+                self.add_notification("Incremental search disabled".to_string());
+            }
+            "minimap" => {
+                self.set_minimap(true);
+                self.add_notification(
+                    "Structure map shown, gm picks a spot to jump to".to_string(),
+                );
+            }
+            "nominimap" => {
+                self.set_minimap(false);
+                self.add_notification("Structure map hidden".to_string());
+            }
+            "memstatus" => {
+                self.set_memory_status(true);
+                self.add_notification(
+                    "Memory taken by the loaded sheets shown in the status bar, :mem lists each"
+                        .to_string(),
+                );
+            }
+            "nomemstatus" => {
+                self.set_memory_status(false);
+                self.add_notification("Memory hidden from the status bar".to_string());
+            }
+            "strictvalidate" => {
+                self.strict_validation = true;
+                self.add_notification(
+                    "Edits breaking a :validate rule are held back until fixed".to_string(),
+                );
+            }
+            "nostrictvalidate" => {
+                self.strict_validation = false;
+                self.add_notification(
+                    "Edits breaking a :validate rule go in with a warning".to_string(),
+                );
+            }
+            "stats" => {
+                self.set_selection_stats(true);
+                self.add_notification(
+                    "Count, sum, average, min and max of the selection or column shown in the status bar"
+                        .to_string(),
+                );
+            }
+            "nostats" => {
+                self.set_selection_stats(false);
+                self.add_notification("Selection figures hidden from the status bar".to_string());
+            }
+            "smartnumbers" | "sn" => {
+                self.smart_numbers = true;
+                self.add_notification(
+                    "Entered or pasted values like $1,234.56, 42% or 3.5k become numbers"
+                        .to_string(),
+                );
+            }
+            "nosmartnumbers" | "nosn" => {
+                self.smart_numbers = false;
+                self.add_notification("Entered and pasted values are kept as typed".to_string());
+            }
+            "undofile" | "udf" => {
+                self.undo_file = true;
+                self.add_notification(
+                    "Undo history is kept on save and restored when the file is reopened"
+                        .to_string(),
+                );
+            }
+            "noundofile" | "noudf" => {
+                self.undo_file = false;
+                self.add_notification("Undo history is cleared on save".to_string());
+            }
+            "renderers" => {
+                self.cell_renderers.enabled = true;
+                self.add_notification(
+                    "URLs, booleans and long JSON are presented in the grid".to_string(),
+                );
+            }
+            "norenderers" => {
+                self.cell_renderers.enabled = false;
+                self.add_notification("The grid shows values as stored".to_string());
+            }
+            "relativetime" | "rt" => {
+                self.cell_renderers.relative_time = true;
+                self.add_notification(
+                    "Timestamps are shown relative to now, e.g. 2h ago".to_string(),
+                );
+            }
+            "norelativetime" | "nort" => {
+                self.cell_renderers.relative_time = false;
+                self.add_notification("Timestamps are shown as written".to_string());
+            }
+            "typerow" => {
+                self.type_row = true;
+                self.add_notification(
+                    "Column types shown under the header, ]t/[t jump to cells that don't match"
+                        .to_string(),
+                );
+            }
+            "notyperow" => {
+                self.type_row = false;
+                self.add_notification("Column types hidden".to_string());
+            }
+            "noautosave" => self.set_autosave(None),
+            "noflash" => {
+                self.flash_time = Duration::ZERO;
+                self.add_notification("Changed cells are no longer highlighted".to_string());
+            }
+            _ if option.starts_with("autosave") => {
+                let value = option.strip_prefix("autosave").unwrap();
+                self.set_autosave(Some(value.trim_start_matches([' ', '='])));
+            }
+            "undolevels" | "ul" => self.add_notification(self.undo_levels_summary()),
+            _ if option.starts_with("undolevels=") || option.starts_with("ul=") => {
+                let value = option.split_once('=').map(|(_, value)| value).unwrap();
+                match value.parse::<usize>() {
+                    Ok(levels) => {
+                        let dropped = self.undo_history.set_levels(levels);
+                        let mut message = self.undo_levels_summary();
+                        if dropped > 0 {
+                            message.push_str(&format!(", {dropped} older changes dropped"));
+                        }
+                        self.add_notification(message);
+                    }
+                    Err(_) => self.add_notification(
+                        "Usage: :set undolevels=<changes>, 0 keeps no undo history".to_string(),
+                    ),
+                }
+            }
+            "nonumfmt" => self.set_number_format(""),
+            _ if option.starts_with("numfmt=") => {
+                self.set_number_format(option.strip_prefix("numfmt=").unwrap_or_default());
+            }
+            _ if option.starts_with("flashtime=") => {
+                match option.strip_prefix("flashtime=").unwrap().parse::<u64>() {
+                    Ok(ms) => {
+                        self.flash_time = Duration::from_millis(ms);
+                        self.add_notification(format!(
+                            "Changed cells are highlighted for {ms} ms after paste, undo and redo"
+                        ));
+                    }
+                    Err(_) => self.add_notification(
+                        "Usage: :set flashtime=<milliseconds>, 0 turns it off".to_string(),
+                    ),
+                }
+            }
+            _ => self.add_notification(format!("Unknown option: {option}")),
+        }
+    }
 
-        if parts.len() == 1 {
+    // :sheetins <position> <name>, the new sheet ends up at the 1-based position
+    fn handle_sheet_insert_command(&mut self, args: &str) {
+        let Some((position, name)) = args.split_once(char::is_whitespace) else {
+            self.add_notification("Usage: :sheetins <position> <name>".to_string());
+            return;
+        };
+
+        let sheet_count = self.workbook.get_sheet_names().len();
+        match position.parse::<usize>() {
+            Ok(position) if (1..=sheet_count + 1).contains(&position) => {
+                self.add_sheet(name.trim(), Some(position - 1));
+            }
+            _ => self.add_notification(format!(
+                "Invalid sheet position {position}, expected 1 to {}",
+                sheet_count + 1
+            )),
+        }
+    }
+
+    fn handle_freeze_command(&mut self, args: &str) {
+        let parts: Vec<&str> = args.split_whitespace().collect();
+
+        if parts.is_empty() || parts.len() > 2 {
+            self.add_notification("Usage: :freeze [rows] [cols]".to_string());
+            return;
+        }
+
+        let rows = parts[0].parse::<usize>();
+        let cols = if parts.len() == 2 {
+            parts[1].parse::<usize>()
+        } else {
+            Ok(0)
+        };
+
+        match (rows, cols) {
+            (Ok(rows), Ok(cols)) => self.freeze_panes(rows, cols),
+            _ => self.add_notification("Invalid freeze arguments".to_string()),
+        }
+    }
+
+    fn handle_delete_row_command(&mut self, args: &str) {
+        let parts: Vec<&str> = args.split_whitespace().collect();
+
+        if parts.is_empty() {
             // Delete current row
             if let Err(e) = self.delete_current_row() {
                 self.add_notification(format!("Failed to delete row: {e}"));
@@ -131,22 +325,22 @@ impl AppState<'_> {
             return;
         }
 
-        if parts.len() == 2 {
+        if parts.len() == 1 {
             // Delete specific row
-            if let Ok(row) = parts[1].parse::<usize>() {
+            if let Ok(row) = parts[0].parse::<usize>() {
                 if let Err(e) = self.delete_row(row) {
                     self.add_notification(format!("Failed to delete row {}: {}", row, e));
                 }
             } else {
-                self.add_notification(format!("Invalid row number: {}", parts[1]));
+                self.add_notification(format!("Invalid row number: {}", parts[0]));
             }
             return;
         }
 
-        if parts.len() == 3 {
+        if parts.len() == 2 {
             // Delete range of rows
             if let (Ok(start_row), Ok(end_row)) =
-                (parts[1].parse::<usize>(), parts[2].parse::<usize>())
+                (parts[0].parse::<usize>(), parts[1].parse::<usize>())
             {
                 if let Err(e) = self.delete_rows(start_row, end_row) {
                     self.add_notification(format!(
@@ -163,10 +357,10 @@ impl AppState<'_> {
         self.add_notification("Usage: :dr [row] [end_row]".to_string());
     }
 
-    fn handle_delete_column_command(&mut self, cmd: &str) {
-        let parts: Vec<&str> = cmd.split_whitespace().collect();
+    fn handle_delete_column_command(&mut self, args: &str) {
+        let parts: Vec<&str> = args.split_whitespace().collect();
 
-        if parts.len() == 1 {
+        if parts.is_empty() {
             // Delete current column
             if let Err(e) = self.delete_current_column() {
                 self.add_notification(format!("Failed to delete column: {e}"));
@@ -174,9 +368,9 @@ impl AppState<'_> {
             return;
         }
 
-        if parts.len() == 2 {
+        if parts.len() == 1 {
             // Delete specific column
-            let col_str = parts[1].to_uppercase();
+            let col_str = parts[0].to_uppercase();
 
             // Try to parse as a column letter (A, B, C, etc.)
             if let Some(col) = col_name_to_index(&col_str) {
@@ -198,10 +392,10 @@ impl AppState<'_> {
             return;
         }
 
-        if parts.len() == 3 {
+        if parts.len() == 2 {
             // Delete range of columns
-            let start_col_str = parts[1].to_uppercase();
-            let end_col_str = parts[2].to_uppercase();
+            let start_col_str = parts[0].to_uppercase();
+            let end_col_str = parts[1].to_uppercase();
 
             let start_col =
                 col_name_to_index(&start_col_str).or_else(|| start_col_str.parse::<usize>().ok());
@@ -224,30 +418,74 @@ impl AppState<'_> {
         self.add_notification("Usage: :dc [col] [end_col]".to_string());
     }
 
-    fn handle_json_export_command(&mut self, cmd: &str) {
-        // Check if this is an export all command
-        let export_all = cmd.starts_with("eja ") || cmd == "eja";
-
-        // Parse command
-        let parts: Vec<&str> = if cmd.starts_with("ej ") {
-            cmd.strip_prefix("ej ")
-                .unwrap()
-                .split_whitespace()
-                .collect()
-        } else if cmd == "ej" {
-            // No arguments provided, use default values
-            vec!["h", "1"] // Default to horizontal headers with 1 header row
-        } else if cmd.starts_with("eja ") {
-            cmd.strip_prefix("eja ")
-                .unwrap()
-                .split_whitespace()
-                .collect()
-        } else if cmd == "eja" {
-            // No arguments provided, use default values
-            vec!["h", "1"] // Default to horizontal headers with 1 header row
+    // Export a single column to CSV. The header rows (the frozen rows, or row 1)
+    // are only written with --with-header, otherwise just the data rows are.
+    fn handle_column_export_command(&mut self, args: &str) {
+        let mut with_header = false;
+        let mut col = self.selected_cell.1;
+
+        for arg in args.split_whitespace() {
+            if arg == "--with-header" {
+                with_header = true;
+            } else if let Some(parsed) = parse_column(arg) {
+                col = parsed;
+            } else {
+                self.add_notification("Usage: :ec [col] [--with-header]".to_string());
+                return;
+            }
+        }
+
+        let sheet = self.workbook.get_current_sheet();
+        let first_row = if with_header {
+            1
         } else {
-            self.add_notification("Invalid JSON export command".to_string());
-            return;
+            self.first_data_row()
+        };
+        let values: Vec<&str> = sheet
+            .column(col)
+            .filter(|cell| cell.row >= first_row)
+            .map(|cell| cell.value())
+            .collect();
+
+        let file_path = self.workbook.get_file_path().to_string();
+        let original_file = Path::new(&file_path);
+        let file_stem = original_file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("export");
+        let parent_dir = original_file.parent().unwrap_or_else(|| Path::new(""));
+
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+        let filename = format!(
+            "{}_sheet_{}_column_{}_{}.csv",
+            file_stem,
+            sheet.name,
+            index_to_col_name(col),
+            timestamp
+        );
+        let new_filepath = parent_dir.join(filename);
+
+        let row_count = values.len();
+        let result = DelimitedFormat::csv()
+            .write_records(&new_filepath, values.into_iter().map(|value| vec![value]));
+
+        match result {
+            Ok(()) => self.add_notification(format!(
+                "Exported {} rows of column {} to {}",
+                row_count,
+                index_to_col_name(col),
+                new_filepath.display()
+            )),
+            Err(e) => self.add_notification(format!("Export failed: {e}")),
+        }
+    }
+
+    // :ej and :eja, without arguments horizontal headers in one row
+    fn handle_json_export_command(&mut self, export_all: bool, args: &str) {
+        let parts: Vec<&str> = if args.is_empty() {
+            vec!["h", "1"]
+        } else {
+            args.split_whitespace().collect()
         };
 
         // Check if we have enough arguments for direction and header count
@@ -319,6 +557,7 @@ impl AppState<'_> {
 
         match result {
             Ok(_) => {
+                self.json_headers = Some((direction, header_count));
                 self.add_notification(format!("Exported to {}", new_filepath.display()));
             }
             Err(e) => {
@@ -327,6 +566,32 @@ impl AppState<'_> {
         }
     }
 
+    // Jumps to a cell, switching to the named sheet first when the address has one
+    fn goto_address(&mut self, sheet: Option<&str>, cell_ref: (usize, usize)) {
+        self.record_jump();
+
+        if let Some(sheet) = sheet {
+            let Some(index) = self
+                .workbook
+                .get_sheet_names()
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(sheet))
+            else {
+                self.add_notification(format!("Sheet '{sheet}' not found"));
+                return;
+            };
+
+            if index != self.workbook.get_current_sheet_index() {
+                if let Err(e) = self.switch_sheet_by_index(index) {
+                    self.add_notification(format!("Failed to switch to sheet '{sheet}': {e}"));
+                    return;
+                }
+            }
+        }
+
+        self.jump_to_cell(cell_ref);
+    }
+
     fn jump_to_cell(&mut self, cell_ref: (usize, usize)) {
         let (row, col) = cell_ref; // Fixed: cell_ref is already (row, col)
 
@@ -343,14 +608,7 @@ impl AppState<'_> {
         }
 
         self.selected_cell = (row, col);
-        // Handle scrolling
-        if self.selected_cell.0 < self.start_row {
-            self.start_row = self.selected_cell.0;
-        } else if self.selected_cell.0 >= self.start_row + self.visible_rows {
-            self.start_row = self.selected_cell.0 - self.visible_rows + 1;
-        }
-
-        self.ensure_column_visible(self.selected_cell.1);
+        self.handle_scrolling();
 
         self.add_notification(format!(
             "Jumped to cell {}{}",
@@ -360,34 +618,758 @@ impl AppState<'_> {
     }
 }
 
-// Parse a cell reference like "A1", "B10", etc.
-fn parse_cell_reference(input: &str) -> Option<(usize, usize)> {
-    // Cell references should have at least 2 characters (e.g., A1)
-    if input.len() < 2 {
-        return None;
+impl CommandRegistry {
+    /// The built-in commands, listed by :help in this order within their sections
+    #[must_use]
+    pub fn with_builtins() -> Self {
+        let mut commands = Self::default();
+        add_file_commands(&mut commands);
+        add_navigation_commands(&mut commands);
+        add_editing_commands(&mut commands);
+        add_search_commands(&mut commands);
+        add_column_commands(&mut commands);
+        add_row_commands(&mut commands);
+        add_export_commands(&mut commands);
+        add_sheet_commands(&mut commands);
+        commands
     }
+}
 
-    // Find the first digit to separate column and row parts
-    let mut col_end = 0;
-    for (i, c) in input.chars().enumerate() {
-        if c.is_ascii_digit() {
-            col_end = i;
-            break;
+fn add_file_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "FILE OPERATIONS";
+    commands.builtin(
+        SECTION,
+        &["w"],
+        "",
+        "Save file in the background, editing can go on meanwhile",
+        |app, _| {
+            if let Err(e) = app.save(false) {
+                app.add_notification(format!("Save failed: {e}"));
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["w!"],
+        "",
+        "Save even if the file format cannot keep everything",
+        |app, _| {
+            if let Err(e) = app.save(true) {
+                app.add_notification(format!("Save failed: {e}"));
+            }
+        },
+    );
+    commands.builtin(SECTION, &["wq", "x"], "", "Save and quit", |app, _| {
+        if !app.warn_modified_buffer("wq!") {
+            app.save_and_exit(false);
         }
-    }
+    });
+    commands.builtin(
+        SECTION,
+        &["wq!", "x!"],
+        "",
+        "Save and quit even if the file format cannot keep everything",
+        |app, _| app.save_and_exit(true),
+    );
+    commands.builtin(
+        SECTION,
+        &["q"],
+        "",
+        "Quit (will warn if unsaved changes), :q expr runs :query",
+        |app, args| {
+            if !args.is_empty() {
+                app.run_row_query(args);
+            } else if app.workbook.is_modified() {
+                app.add_notification(
+                    "File has unsaved changes. Use :q! to force quit or :wq to save and quit."
+                        .to_string(),
+                );
+            } else if !app.warn_modified_buffer("q!") {
+                app.should_quit = true;
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["q!"],
+        "",
+        "Force quit without saving, keeping changes in a recovery file",
+        |app, _| app.exit_without_saving(),
+    );
+    commands.builtin(
+        SECTION,
+        &["reload", "e!"],
+        "",
+        "Discard unsaved changes and read the file again",
+        |app, _| app.reload_workbook(),
+    );
+    commands.builtin(
+        SECTION,
+        &["e"],
+        " [file]",
+        "Read the file again if unmodified, with a file the same as :open",
+        |app, args| {
+            if !args.is_empty() {
+                app.open_buffer(args);
+            } else if app.workbook.is_modified() {
+                app.add_notification(
+                    "File has unsaved changes. Use :e! to reload anyway or :w to save them."
+                        .to_string(),
+                );
+            } else {
+                app.reload_workbook();
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["open"],
+        " file",
+        "Open another workbook beside this one",
+        |app, args| app.open_buffer(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["ls", "buffers"],
+        "",
+        "List the open workbooks",
+        |app, _| app.list_buffers(),
+    );
+    commands.builtin(
+        SECTION,
+        &["bn", "bnext"],
+        "",
+        "Switch to the next workbook",
+        |app, _| app.cycle_buffer(true),
+    );
+    commands.builtin(
+        SECTION,
+        &["bp", "bprevious", "bN"],
+        "",
+        "Switch to the previous workbook",
+        |app, _| app.cycle_buffer(false),
+    );
+    commands.builtin(
+        SECTION,
+        &["b"],
+        " N",
+        "Switch to the workbook numbered N by :ls",
+        |app, args| app.goto_buffer(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["bd", "bdelete"],
+        "",
+        "Close the workbook shown, warning about unsaved changes",
+        |app, _| app.close_buffer(false),
+    );
+    commands.builtin(
+        SECTION,
+        &["bd!", "bdelete!"],
+        "",
+        "Close the workbook shown, discarding its changes",
+        |app, _| app.close_buffer(true),
+    );
+    commands.builtin(
+        SECTION,
+        &["profile"],
+        " [save name]",
+        "Show the profile in use, with save name save the delimiter,\n\
+         headers and transforms as a profile",
+        |app, args| {
+            if args.is_empty() {
+                let message = app.show_profile();
+                app.add_notification(message);
+            } else if let Some(name) = args
+                .strip_prefix("save")
+                .filter(|name| name.is_empty() || name.starts_with(' '))
+            {
+                app.save_profile(name.trim());
+            } else {
+                app.add_notification("Usage: :profile [save name]".to_string());
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["set"],
+        " option",
+        "Change an option, the options are listed in the sections they belong to",
+        |app, args| app.handle_set_command(args),
+    );
+}
 
-    if col_end == 0 {
-        return None; // No digits found
-    }
+fn add_navigation_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "NAVIGATION";
+    commands.builtin(
+        SECTION,
+        &["goto"],
+        " [cell]",
+        "Jump to cell, sheet!A1 also switches sheet",
+        |app, address| match parse_cell_address(address) {
+            Some((sheet, cell_ref)) => app.goto_address(sheet, cell_ref),
+            None => app.add_notification(
+                "Usage: :goto [cell], e.g. :goto D45 or :goto Sheet2!A1".to_string(),
+            ),
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["marks"],
+        "",
+        "List the marks of the current sheet",
+        |app, _| app.show_marks(),
+    );
+    commands.builtin(
+        SECTION,
+        &["formulas"],
+        "",
+        "Count the formula cells of the current sheet",
+        |app, _| app.show_formula_counts(),
+    );
+    commands.builtin(
+        SECTION,
+        &["sheet"],
+        " [name/number]",
+        "Switch to sheet by name or index",
+        |app, name| {
+            if name.is_empty() {
+                app.add_notification("Usage: :sheet <name or number>".to_string());
+            } else {
+                app.switch_to_sheet(name);
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["freeze"],
+        " [rows] [cols]",
+        "Keep leading rows and columns visible, without them toggle\n\
+         freezing of the header row",
+        |app, args| {
+            if !args.is_empty() {
+                app.handle_freeze_command(args);
+            } else if app.frozen_rows == 0 && app.frozen_cols == 0 {
+                app.freeze_panes(1, 0);
+            } else {
+                app.freeze_panes(0, 0);
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["unfreeze"],
+        "",
+        "Remove frozen rows and columns",
+        |app, _| app.freeze_panes(0, 0),
+    );
+    commands.builtin(
+        SECTION,
+        &["format"],
+        " col number:2dp|percent:1dp|date:%d/%m/%Y",
+        "Show a column that way, off removes",
+        |app, args| app.set_column_format(args, false),
+    );
+    commands.builtin(
+        SECTION,
+        &["format!"],
+        " col spec",
+        "Also give its cells that number format for saving",
+        |app, args| app.set_column_format(args, true),
+    );
+    commands.builtin(
+        SECTION,
+        &["typecheck", "tc"],
+        "",
+        "List the cells not matching their column's type",
+        |app, _| app.check_column_types(),
+    );
+    commands.builtin(
+        SECTION,
+        &["validate"],
+        " col range 1 100|regex ^\\d+$|list a,b",
+        "Check a column, off removes",
+        |app, args| app.set_validation(args),
+    );
+}
+
+fn add_editing_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "EDITING";
+    commands.builtin(SECTION, &["y"], "", "Copy current cell", |app, _| {
+        app.copy_cell();
+    });
+    commands.builtin(SECTION, &["d"], "", "Cut current cell", |app, _| {
+        if let Err(e) = app.cut_cell() {
+            app.add_notification(format!("Cut failed: {e}"));
+        }
+    });
+    commands.builtin(
+        SECTION,
+        &["put", "pu"],
+        "",
+        "Paste to current cell",
+        |app, _| {
+            if let Err(e) = app.paste_cell() {
+                app.add_notification(format!("Paste failed: {e}"));
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["yank"],
+        " md|csv|tsv",
+        "Copy the selection (or cell) to the system clipboard as a\n\
+         Markdown table, CSV or TSV text; --with-header adds the header\n\
+         rows above the selection",
+        |app, args| {
+            let args: Vec<&str> = args.split_whitespace().collect();
+            let with_header = args.contains(&"--with-header");
+            let format = args.iter().find(|&&arg| arg != "--with-header");
+            match format.and_then(|format| TextFormat::parse(format)) {
+                Some(format) => app.yank_as_text(format, with_header),
+                None => {
+                    app.add_notification("Usage: :yank md|csv|tsv [--with-header]".to_string());
+                }
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["undo"],
+        " [col|row]",
+        "Undo the last change, with col / row the last one in the\n\
+         current column / row only",
+        |app, scope| {
+            let result = if scope.is_empty() {
+                app.undo()
+            } else {
+                app.undo_in_scope(scope)
+            };
+            if let Err(e) = result {
+                app.add_notification(format!("Undo failed: {e}"));
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["undolist", "undol"],
+        "",
+        "List the changes with old -> new values, Enter undoes or\n\
+         redoes up to the selected one",
+        |app, _| app.open_undo_list(),
+    );
+}
+
+fn add_search_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "SEARCH";
+    commands.builtin(
+        SECTION,
+        &["nohlsearch", "nohl", "noh"],
+        "",
+        "Disable search highlighting",
+        |app, _| app.disable_search_highlight(),
+    );
+    commands.builtin(
+        SECTION,
+        &["csearch"],
+        " [text]",
+        "Search only in the current column (n/N stay in it)",
+        |app, query| app.search_in_column(query),
+    );
+    commands.builtin(
+        SECTION,
+        &["s"],
+        "/pat/rep/[g][i]",
+        "Replace in the selection or sheet (g: all in cell, i: ignore case)",
+        |app, args| substitute_or_usage(app, &format!("s{args}")),
+    );
+    commands.builtin(
+        SECTION,
+        &["%"],
+        "s/pat/rep/",
+        "Replace in the whole sheet even with a selection",
+        |app, args| substitute_or_usage(app, &format!("%{args}")),
+    );
+    commands.builtin(
+        SECTION,
+        &["distinct"],
+        " [col]",
+        "Browse distinct values of a column with counts",
+        |app, col_str| {
+            if col_str.is_empty() {
+                app.open_distinct_picker(app.selected_cell.1);
+                return;
+            }
+            match parse_column(col_str) {
+                Some(col) => app.open_distinct_picker(col),
+                None => app.add_notification(format!("Invalid column: {col_str}")),
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["sql"],
+        " query",
+        "Run SQL over the sheets it names, the result opens in a new sheet",
+        |app, query| app.run_sql(query),
+    );
+    commands.builtin(
+        SECTION,
+        &["diff"],
+        " file",
+        "Highlight the cells that differ from another workbook",
+        |app, path| app.start_diff(path),
+    );
+    commands.builtin(
+        SECTION,
+        &["diffoff"],
+        "",
+        "Stop highlighting the differences of :diff or :sheetdiff",
+        |app, _| app.stop_diff(),
+    );
+    commands.builtin(
+        SECTION,
+        &["sheetdiff"],
+        " s1 [s2]",
+        "Highlight and list the cells differing between two sheets",
+        |app, sheets| app.start_sheet_diff(sheets),
+    );
+    commands.builtin(
+        SECTION,
+        &["split", "sp"],
+        " [sheet]",
+        "Show a second view above the first",
+        |app, sheet| app.split_view(false, sheet),
+    );
+    commands.builtin(
+        SECTION,
+        &["vsplit", "vs"],
+        " [sheet]",
+        "Show a second view beside the first",
+        |app, sheet| app.split_view(true, sheet),
+    );
+    commands.builtin(
+        SECTION,
+        &["only", "on"],
+        "",
+        "Close the other pane of a split",
+        |app, _| app.close_other_pane(),
+    );
+    commands.builtin(
+        SECTION,
+        &["close", "clo"],
+        "",
+        "Close the focused pane of a split",
+        |app, _| app.close_pane(),
+    );
+    commands.builtin(
+        SECTION,
+        &["query"],
+        " expr [| cols]",
+        "Copy the rows matching an expression to a new sheet",
+        |app, query| app.run_row_query(query),
+    );
+    commands.builtin(
+        SECTION,
+        &["pivot"],
+        " rows [cols] value [agg]",
+        "Group and sum/count/avg/min/max into a new sheet",
+        |app, args| app.create_pivot(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["!"],
+        "cmd",
+        "Filter the selection (or cell) through a shell command as TSV",
+        |app, shell_command| app.filter_selection(shell_command),
+    );
+    commands.builtin(
+        SECTION,
+        &["messages", "mes"],
+        "",
+        "Show recent notifications in a scrollable list",
+        |app, _| app.show_messages(),
+    );
+    commands.builtin(
+        SECTION,
+        &["mem"],
+        "",
+        "Show the memory taken by each loaded sheet and the undo history",
+        |app, _| app.show_memory_usage(),
+    );
+    commands.builtin(
+        SECTION,
+        &["lua"],
+        " [code]",
+        "Run Lua on the current sheet, e.g. :lua max_row()",
+        |app, code| app.run_lua(code),
+    );
+    commands.builtin(
+        SECTION,
+        &["luafile"],
+        " [path]",
+        "Run a Lua script file on the current sheet",
+        |app, path| app.run_lua_file(path),
+    );
+    commands.builtin(
+        SECTION,
+        &["py"],
+        " [code]",
+        "Run Python on the current sheet as `sheet` (--features python)",
+        |app, code| app.run_python(code),
+    );
+    commands.builtin(SECTION, &["help"], "", "Show this help", |app, _| {
+        app.show_help()
+    });
+    commands.builtin(
+        SECTION,
+        &["commands"],
+        "",
+        "List every command with its help, those of extensions last",
+        |app, _| app.show_registered_commands(),
+    );
+}
+
+fn add_column_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "COLUMN OPERATIONS";
+    commands.builtin(
+        SECTION,
+        &["cw"],
+        " fit|min|[number] [all]",
+        "Fit the current column to its content, set it to the\n\
+         minimum (5 characters) or to a number of characters; all\n\
+         applies fit and min to every column",
+        |app, args| app.handle_column_width_command(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["autofit"],
+        " [col]",
+        "Fit all columns (capped at 50), or a specific one, to their content",
+        |app, col_str| {
+            if col_str.is_empty() {
+                app.auto_adjust_column_width(None);
+                return;
+            }
+            match parse_column(col_str) {
+                Some(col) => app.auto_adjust_column_width(Some(col)),
+                None => app.add_notification(format!("Invalid column: {col_str}")),
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["columns"],
+        "",
+        "Reorder, hide, pin and resize columns on one screen\n\
+         (j/k select, J/K move, Space show/hide, p pin,\n\
+         h/l width, Enter apply, Esc cancel)",
+        |app, _| app.open_column_manager(),
+    );
+    commands.builtin(
+        SECTION,
+        &["dc"],
+        " [col] [end]",
+        "Delete the current column, a specific one (e.g., :dc A or\n\
+         :dc 1) or the columns from col to end (e.g., :dc A C)",
+        |app, args| app.handle_delete_column_command(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["combine"],
+        " A B [sep]",
+        "Join columns into a new last column, e.g. :combine A B \" - \"",
+        |app, args| app.combine_columns(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["clean"],
+        " [trim] [squeeze] [printable]",
+        "Tidy the selection (or sheet), all three by default",
+        |app, args| app.clean_cells(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["upper"],
+        "",
+        "Upper-case the selection (or current column)",
+        |app, _| app.change_case(TextCase::Upper),
+    );
+    commands.builtin(
+        SECTION,
+        &["lower"],
+        "",
+        "Lower-case the selection (or current column)",
+        |app, _| app.change_case(TextCase::Lower),
+    );
+    commands.builtin(
+        SECTION,
+        &["title"],
+        "",
+        "Title-case the selection (or current column)",
+        |app, _| app.change_case(TextCase::Title),
+    );
+}
 
-    let col_part = &input[0..col_end];
-    let row_part = &input[col_end..];
+fn add_row_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "ROW OPERATIONS";
+    commands.builtin(
+        SECTION,
+        &["dr"],
+        " [row] [end]",
+        "Delete the current row, a specific one or the rows from row to end",
+        |app, args| app.handle_delete_row_command(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["append"],
+        " [file]",
+        "Append data rows of a CSV/Excel file, matching headers.\n\
+         Unmatched headers open a column mapping screen: ←→ change\n\
+         target, s skip, n new column",
+        |app, path| {
+            if let Err(e) = app.append_rows_from_file(path) {
+                app.add_notification(format!("Append failed: {e}"));
+            }
+        },
+    );
+}
 
-    // Convert column letters to index
-    let col = col_name_to_index(&col_part.to_uppercase())?;
+fn add_export_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "EXPORT";
+    commands.builtin(
+        SECTION,
+        &["ej"],
+        " [h|v] [rows]",
+        "Export current sheet to JSON, h=horizontal (default),\n\
+         v=vertical, [rows]=number of header rows (default: 1)",
+        |app, args| app.handle_json_export_command(false, args),
+    );
+    commands.builtin(
+        SECTION,
+        &["eja"],
+        " [h|v] [rows]",
+        "Export all sheets to a single JSON file",
+        |app, args| app.handle_json_export_command(true, args),
+    );
+    commands.builtin(
+        SECTION,
+        &["ec"],
+        " [col] [--with-header]",
+        "Export a column to CSV, optionally with its header",
+        |app, args| app.handle_column_export_command(args),
+    );
+}
 
-    // Parse row number
-    let row = row_part.parse::<usize>().ok()?;
+fn add_sheet_commands(commands: &mut CommandRegistry) {
+    const SECTION: &str = "SHEET OPERATIONS";
+    commands.builtin(
+        SECTION,
+        &["sheetnew"],
+        " [name]",
+        "Add an empty sheet after the last one",
+        |app, name| {
+            if name.is_empty() {
+                app.add_notification("Usage: :sheetnew <name>".to_string());
+            } else {
+                app.add_sheet(name, None);
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["sheetins"],
+        " [pos] [name]",
+        "Add an empty sheet at a position (1-based)",
+        |app, args| app.handle_sheet_insert_command(args),
+    );
+    commands.builtin(
+        SECTION,
+        &["sheetrename"],
+        " [name]",
+        "Rename the current sheet",
+        |app, name| {
+            if name.is_empty() {
+                app.add_notification("Usage: :sheetrename <name>".to_string());
+            } else {
+                app.rename_current_sheet(name);
+            }
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["sheetmove"],
+        " [pos]",
+        "Move the current sheet to a position (1-based)",
+        |app, position| match position.parse::<usize>() {
+            Ok(position) => app.move_current_sheet(position),
+            Err(_) => app.add_notification("Usage: :sheetmove <position>".to_string()),
+        },
+    );
+    commands.builtin(
+        SECTION,
+        &["sheetcopy"],
+        " [name]",
+        "Copy the current sheet into a new sheet after it",
+        |app, name| app.copy_current_sheet(Some(name).filter(|name| !name.is_empty())),
+    );
+    commands.builtin(
+        SECTION,
+        &["sheetdelete", "delsheet"],
+        "",
+        "Delete the current sheet after a y/n confirmation",
+        |app, _| app.request_sheet_delete(),
+    );
+    commands.builtin(
+        SECTION,
+        &["load"],
+        "",
+        "Load the current sheet when lazy loading is enabled, in the\n\
+         background with a progress bar while [ and ] switch to\n\
+         loaded sheets, Esc cancels it",
+        |app, _| app.load_current_sheet(),
+    );
+}
+
+// :s and :% take the rest of the line as delimited pattern and replacement
+fn substitute_or_usage(app: &mut AppState<'_>, command: &str) {
+    if is_substitute_command(command) {
+        app.substitute(command);
+    } else {
+        app.add_notification("Usage: :s/pattern/replacement/[g][i]".to_string());
+    }
+}
+
+// Parse a column given either as letters (A, b) or as a 1-based number
+fn parse_column(input: &str) -> Option<usize> {
+    let col_str = input.to_uppercase();
+    col_name_to_index(&col_str)
+        .or_else(|| col_str.parse::<usize>().ok())
+        .filter(|&col| col > 0)
+}
+
+// Parse a cell address with an optional sheet, like "A1", "Sheet2!B10" or "'My Sheet'!C3"
+fn parse_cell_address(input: &str) -> Option<(Option<&str>, (usize, usize))> {
+    match input.rsplit_once('!') {
+        Some((sheet, cell)) => {
+            let sheet = sheet
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .unwrap_or(sheet);
+            if sheet.is_empty() {
+                return None;
+            }
+            Some((Some(sheet), parse_cell_reference(cell)?))
+        }
+        None => Some((None, parse_cell_reference(input)?)),
+    }
+}
 
-    Some((row, col))
+// `:s` and `:%s` take any non-alphanumeric delimiter, e.g. `:s/a/b/` or `:s#a/b#c#`
+fn is_substitute_command(command: &str) -> bool {
+    command
+        .strip_prefix("%s")
+        .or_else(|| command.strip_prefix('s'))
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| !c.is_alphanumeric() && !c.is_whitespace() && c != '\\')
 }
@@ -1 +1,4 @@
 mod executor;
+mod registry;
+
+pub use registry::{CommandHandler, CommandRegistry, RegisteredCommand};
@@ -0,0 +1,150 @@
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+
+use crate::app::AppState;
+
+/// Runs a registered command with what was typed after its name, trimmed
+pub type CommandHandler = Rc<dyn Fn(&mut AppState<'_>, &str)>;
+
+/// :help section of the commands added by extensions and scripts
+pub const EXTENSION_SECTION: &str = "EXTENSION COMMANDS";
+
+/// A `:command`, built in or added by an extension
+#[derive(Clone)]
+pub struct RegisteredCommand {
+    pub name: String,
+    pub aliases: Vec<String>, // Other names it runs under, e.g. :nohl for :nohlsearch
+    pub usage: String,        // Shown after the name in :help, e.g. " [col]" or "/pat/rep/"
+    pub help: String,         // Shown in :help and :commands, may run over several lines
+    pub section: &'static str, // :help section the command is listed in
+    pub handler: CommandHandler,
+}
+
+impl RegisteredCommand {
+    /// The names, usage and help of the command as listed in :help
+    #[must_use]
+    pub fn help_lines(&self) -> String {
+        let names: Vec<String> = std::iter::once(format!(":{}{}", self.name, self.usage))
+            .chain(self.aliases.iter().map(|alias| format!(":{alias}")))
+            .collect();
+        let mut lines = self.help.lines();
+        let mut text = format!(
+            "{:<11} - {}",
+            names.join(", "),
+            lines.next().unwrap_or_default()
+        );
+        for line in lines {
+            text.push_str(&format!("\n{:14}{line}", ""));
+        }
+        text
+    }
+}
+
+/// Every `:command`: the built-in ones and those added by crates embedding the app or by
+/// scripts at runtime, which may also replace a built-in one
+#[derive(Clone, Default)]
+pub struct CommandRegistry {
+    commands: Vec<RegisteredCommand>, // In the order they were first registered
+}
+
+impl CommandRegistry {
+    /// Adds `:name`, replacing a command registered under the same name before
+    pub fn register<F>(&mut self, name: &str, help: &str, handler: F) -> Result<()>
+    where
+        F: Fn(&mut AppState<'_>, &str) + 'static,
+    {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            bail!("Invalid command name \"{name}\", use letters, digits, _ and -");
+        }
+
+        self.insert(RegisteredCommand {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            usage: String::new(),
+            help: help.to_string(),
+            section: EXTENSION_SECTION,
+            handler: Rc::new(handler),
+        });
+        Ok(())
+    }
+
+    /// Adds a built-in command under its names, the first one is listed first in :help.
+    /// Built-in names may end in `!` or be a single symbol like `:!`
+    pub(crate) fn builtin<F>(
+        &mut self,
+        section: &'static str,
+        names: &[&str],
+        usage: &str,
+        help: &str,
+        handler: F,
+    ) where
+        F: Fn(&mut AppState<'_>, &str) + 'static,
+    {
+        self.insert(RegisteredCommand {
+            name: names[0].to_string(),
+            aliases: names[1..].iter().map(ToString::to_string).collect(),
+            usage: usage.to_string(),
+            help: help.to_string(),
+            section,
+            handler: Rc::new(handler),
+        });
+    }
+
+    fn insert(&mut self, command: RegisteredCommand) {
+        // A name taken over from another command's aliases now runs the new one
+        for existing in &mut self.commands {
+            existing
+                .aliases
+                .retain(|alias| *alias != command.name && !command.aliases.contains(alias));
+        }
+        match self.commands.iter_mut().find(|c| c.name == command.name) {
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    /// Removes `:name`, returning whether it was registered
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let count = self.commands.len();
+        self.commands.retain(|c| c.name != name);
+        self.commands.len() != count
+    }
+
+    /// The command a typed line starts with, and its arguments. The name is the letters,
+    /// digits, _ and - up to an optional `!`, or the first character when it is none of
+    /// those, so `:s/a/b/` runs `:s` and `:!ls` runs `:!`
+    #[must_use]
+    pub fn find<'a>(&self, line: &'a str) -> Option<(CommandHandler, &'a str)> {
+        let word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+        let mut end = line.find(|c| !word(c)).unwrap_or(line.len());
+        if line[end..].starts_with('!') || end == 0 {
+            end += line[end..].chars().next().map_or(0, char::len_utf8);
+        }
+        let (name, args) = line.split_at(end);
+        self.commands
+            .iter()
+            .find(|c| c.name == name || c.aliases.iter().any(|alias| alias == name))
+            .map(|c| (Rc::clone(&c.handler), args.trim()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredCommand> {
+        self.commands.iter()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl AppState<'_> {
+    /// :commands: every command with its help, as in :help without the keys
+    pub fn show_registered_commands(&mut self) {
+        self.open_help(false);
+    }
+}
@@ -1,12 +1,20 @@
-#[derive(Clone)]
+use std::mem::size_of;
+
+use serde::{Deserialize, Serialize};
+
+use crate::excel::{StyleId, DEFAULT_STYLE};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Cell {
     pub value: String,
     pub is_formula: bool,
     pub cell_type: CellType,
     pub original_type: Option<DataTypeInfo>,
+    /// Id in the owning sheet's style table
+    pub style: StyleId,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum CellType {
     Text,
     Number,
@@ -15,7 +23,7 @@ pub enum CellType {
     Empty,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum DataTypeInfo {
     Empty,
     String,
@@ -61,6 +69,7 @@ impl Cell {
             is_formula,
             cell_type,
             original_type,
+            style: DEFAULT_STYLE,
         }
     }
 
@@ -70,6 +79,19 @@ impl Cell {
             is_formula: false,
             cell_type: CellType::Empty,
             original_type: Some(DataTypeInfo::Empty),
+            style: DEFAULT_STYLE,
         }
     }
+
+    /// Bytes the cell takes in memory, its text included
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        let original = match &self.original_type {
+            Some(DataTypeInfo::DateTimeIso(text) | DataTypeInfo::DurationIso(text)) => {
+                text.capacity()
+            }
+            _ => 0,
+        };
+        size_of::<Cell>() + self.value.capacity() + original
+    }
 }
@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+pub type SheetResult<T> = std::result::Result<T, SheetError>;
+
+/// Errors returned by the workbook, format and action layers, so callers can
+/// react to the kind of failure instead of only showing its message.
+#[derive(Debug)]
+pub enum SheetError {
+    /// Reading or writing a file failed
+    Io { path: PathBuf, source: io::Error },
+    /// A file or worksheet could not be parsed, with the position when known
+    Parse {
+        path: PathBuf,
+        sheet: Option<String>,
+        row: Option<usize>,
+        col: Option<usize>,
+        message: String,
+    },
+    /// The file format cannot be opened or saved, `operation` is "opened" or "saved"
+    UnsupportedFormat {
+        format: String,
+        operation: &'static str,
+    },
+    /// Writing the data in the target format failed
+    Write { path: PathBuf, message: String },
+    /// An index passed to the workbook is past the end, `what` names the kind of index
+    OutOfRange {
+        what: &'static str,
+        index: usize,
+        max: usize,
+    },
+    /// The sheet is lazily loaded and has not been loaded yet
+    SheetNotLoaded { sheet: String },
+    /// The file does not contain any worksheet
+    NoSheets,
+    /// Deleting the only remaining sheet
+    LastSheet,
+    /// A new sheet name that Excel would reject, `reason` says which rule it breaks
+    InvalidSheetName { name: String, reason: &'static str },
+    /// A background load or save was stopped with Esc before it finished
+    Cancelled,
+}
+
+impl SheetError {
+    pub fn parse(
+        path: impl Into<PathBuf>,
+        sheet: Option<&str>,
+        message: impl fmt::Display,
+    ) -> Self {
+        SheetError::Parse {
+            path: path.into(),
+            sheet: sheet.map(str::to_string),
+            row: None,
+            col: None,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn write(path: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+        SheetError::Write {
+            path: path.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Fills in the file path for errors converted without one
+    #[must_use]
+    pub fn with_path(mut self, file: impl Into<PathBuf>) -> Self {
+        match &mut self {
+            SheetError::Io { path, .. }
+            | SheetError::Parse { path, .. }
+            | SheetError::Write { path, .. }
+                if path.as_os_str().is_empty() =>
+            {
+                *path = file.into();
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+impl fmt::Display for SheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetError::Io { path, source } => {
+                write!(f, "Unable to access file {}: {}", path.display(), source)
+            }
+            SheetError::Parse {
+                path,
+                sheet,
+                row,
+                col,
+                message,
+            } => {
+                write!(f, "Unable to parse {}", path.display())?;
+                if let Some(sheet) = sheet {
+                    write!(f, ", worksheet '{sheet}'")?;
+                }
+                match (row, col) {
+                    (Some(row), Some(col)) => write!(f, " at row {row}, column {col}")?,
+                    (Some(row), None) => write!(f, " at row {row}")?,
+                    _ => {}
+                }
+                write!(f, ": {message}")
+            }
+            SheetError::UnsupportedFormat { format, operation } => {
+                write!(f, "{format} files cannot be {operation}")
+            }
+            SheetError::Write { path, message } => {
+                write!(f, "Failed to write to file {}: {}", path.display(), message)
+            }
+            SheetError::OutOfRange { what, index, max } => {
+                write!(f, "{what} index {index} out of range (max index: {max})")
+            }
+            SheetError::SheetNotLoaded { sheet } => write!(
+                f,
+                "Sheet '{sheet}' is not loaded yet, use :load to load it before editing"
+            ),
+            SheetError::NoSheets => write!(f, "No worksheets found in file"),
+            SheetError::LastSheet => write!(f, "Cannot delete the last sheet"),
+            SheetError::InvalidSheetName { name, reason } => {
+                write!(f, "Invalid sheet name '{name}': {reason}")
+            }
+            SheetError::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl Error for SheetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SheetError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<csv::Error> for SheetError {
+    fn from(error: csv::Error) -> Self {
+        let row = error.position().map(|position| position.line() as usize);
+        let message = error.to_string();
+
+        match error.into_kind() {
+            csv::ErrorKind::Io(source) => SheetError::Io {
+                path: PathBuf::new(),
+                source,
+            },
+            _ => SheetError::Parse {
+                path: PathBuf::new(),
+                sheet: None,
+                row,
+                col: None,
+                message,
+            },
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::excel::formats::{FormatCapabilities, SheetFormat};
+use crate::excel::{
+    Cell, CellGrid, Progress, Sheet, SheetError, SheetResult, StyleTable, Workbook,
+};
+
+/// Plain text files with one record per line. Only the current sheet is
+/// written and formulas are stored as their text.
+pub struct DelimitedFormat {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    delimiter: u8,
+}
+
+impl DelimitedFormat {
+    pub fn csv() -> Self {
+        Self {
+            name: "CSV",
+            extensions: &["csv"],
+            delimiter: b',',
+        }
+    }
+
+    pub fn tsv() -> Self {
+        Self {
+            name: "TSV",
+            extensions: &["tsv", "tab"],
+            delimiter: b'\t',
+        }
+    }
+
+    /// The same format with another field separator, e.g. `;` for CSV files from some locales
+    #[must_use]
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// Writes the given records, used for saving and for partial exports
+    pub fn write_records<'a>(
+        &self,
+        path: &Path,
+        records: impl IntoIterator<Item = Vec<&'a str>>,
+    ) -> SheetResult<()> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| SheetError::from(e).with_path(path))?;
+
+        for record in records {
+            writer
+                .write_record(&record)
+                .map_err(|e| SheetError::from(e).with_path(path))?;
+        }
+
+        writer.flush().map_err(|source| SheetError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Ok(())
+    }
+}
+
+impl SheetFormat for DelimitedFormat {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        self.extensions
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: true,
+            lazy_loading: false,
+            multiple_sheets: false,
+            formulas: false,
+            column_widths: false,
+            styles: false,
+        }
+    }
+
+    fn open(&self, path: &Path, _enable_lazy_loading: bool) -> SheetResult<Workbook> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| SheetError::from(e).with_path(path))?;
+
+        // Only non-empty fields become cells, using 1-based indexing like the other formats
+        let mut data = CellGrid::with_size(1, 1);
+        let mut max_rows = 0;
+        let mut max_cols = 0;
+        for record in reader.records() {
+            let record = record.map_err(|e| SheetError::from(e).with_path(path))?;
+            max_rows += 1;
+            max_cols = max_cols.max(record.len());
+            data.expand(max_rows + 1, max_cols + 1);
+
+            for (col_idx, value) in record.iter().enumerate() {
+                if !value.is_empty() {
+                    let is_formula = value.starts_with('=');
+                    data.set(
+                        max_rows,
+                        col_idx + 1,
+                        Cell::new(value.to_string(), is_formula),
+                    );
+                }
+            }
+        }
+
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Sheet1")
+            .to_string();
+
+        let sheet = Sheet {
+            name,
+            data,
+            max_rows,
+            max_cols,
+            is_loaded: true,
+            styles: StyleTable::default(),
+        };
+
+        Ok(Workbook::from_sheets(
+            vec![sheet],
+            path.to_string_lossy().to_string(),
+        ))
+    }
+
+    fn save(
+        &self,
+        workbook: &Workbook,
+        path: &Path,
+        _column_widths: &HashMap<String, Vec<usize>>,
+        progress: &Progress,
+    ) -> SheetResult<()> {
+        let sheet = workbook.get_current_sheet();
+        progress.start(sheet.max_rows);
+
+        self.write_records(
+            path,
+            sheet
+                .rows()
+                .take_while(|_| !progress.is_cancelled())
+                .map(|row| {
+                    progress.advance(1);
+                    (1..=sheet.max_cols)
+                        .map(|col| row.get(col).value.as_str())
+                        .collect()
+                }),
+        )
+    }
+}
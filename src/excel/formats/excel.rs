@@ -0,0 +1,251 @@
+use rust_xlsxwriter::{Format, FormatAlign, Formula, Workbook as XlsxWorkbook, XlsxError};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::excel::formats::{FormatCapabilities, SheetFormat};
+use crate::excel::{
+    open_calamine_workbook, Cell, CellStyle, CellType, DataTypeInfo, HorizontalAlignment, Progress,
+    SheetError, SheetResult, Workbook,
+};
+
+/// Spreadsheet files read through calamine. Only the xlsx family can be
+/// written back, the other formats are saved as xlsx instead.
+pub struct ExcelFormat {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    writable: bool,
+}
+
+impl ExcelFormat {
+    pub fn xlsx() -> Self {
+        Self {
+            name: "Excel",
+            extensions: &["xlsx", "xlsm"],
+            writable: true,
+        }
+    }
+
+    pub fn legacy() -> Self {
+        Self {
+            name: "Spreadsheet",
+            extensions: &["xls", "xlsb", "xla", "xlam", "ods"],
+            writable: false,
+        }
+    }
+}
+
+impl SheetFormat for ExcelFormat {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        self.extensions
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: true,
+            write: self.writable,
+            lazy_loading: self.writable,
+            multiple_sheets: true,
+            formulas: true,
+            column_widths: self.writable,
+            styles: self.writable,
+        }
+    }
+
+    fn open(&self, path: &Path, enable_lazy_loading: bool) -> SheetResult<Workbook> {
+        // Only enable lazy loading if both the flag is set AND the format supports it
+        open_calamine_workbook(
+            path,
+            enable_lazy_loading && self.capabilities().lazy_loading,
+        )
+    }
+
+    fn save(
+        &self,
+        workbook: &Workbook,
+        path: &Path,
+        column_widths: &HashMap<String, Vec<usize>>,
+        progress: &Progress,
+    ) -> SheetResult<()> {
+        if !self.writable {
+            return Err(SheetError::UnsupportedFormat {
+                format: self.name.to_string(),
+                operation: "saved",
+            });
+        }
+
+        write_xlsx(workbook, path, column_widths, progress).map_err(|e| SheetError::write(path, e))
+    }
+}
+
+// The number format a cell is written with, dates keep their time of day and
+// durations count hours past a day
+fn number_format_code(cell: &Cell) -> Option<&'static str> {
+    match (&cell.cell_type, &cell.original_type) {
+        (CellType::Number, _) => Some("General"),
+        (CellType::Date, Some(DataTypeInfo::Duration(_))) => Some("[h]:mm:ss"),
+        (CellType::Date, Some(DataTypeInfo::DateTime(serial))) if serial.fract() != 0.0 => {
+            Some("yyyy-mm-dd hh:mm:ss")
+        }
+        (CellType::Date, _) => Some("yyyy-mm-dd"),
+        _ => None,
+    }
+}
+
+// Writes every sheet with rust_xlsxwriter, cells are typed from their CellType
+fn write_xlsx(
+    workbook: &Workbook,
+    path: &Path,
+    column_widths: &HashMap<String, Vec<usize>>,
+    progress: &Progress,
+) -> Result<(), XlsxError> {
+    // Create a new workbook with rust_xlsxwriter
+    let mut xlsx = XlsxWorkbook::new();
+
+    // Rows of every sheet count towards the progress, one after the other
+    progress.start(workbook.sheets().map(|sheet| sheet.max_rows).sum());
+    let mut rows_before = 0;
+
+    // Process each sheet
+    for sheet in workbook.sheets() {
+        let worksheet = xlsx.add_worksheet().set_name(&sheet.name)?;
+
+        // Set column widths
+        let widths = column_widths.get(&sheet.name);
+        for col in 0..sheet.max_cols {
+            let width = widths
+                .and_then(|widths| widths.get(col + 1))
+                .copied()
+                .unwrap_or(15);
+            if width == 0 {
+                worksheet.set_column_hidden(col as u16)?;
+            } else {
+                worksheet.set_column_width(col as u16, width as f64)?;
+            }
+        }
+
+        let mut formats = HashMap::new();
+
+        // Write cell data, only the cells stored in the sheet
+        let cells = sheet.data.cells().filter(|&(row, col, _)| {
+            (1..=sheet.max_rows).contains(&row) && (1..=sheet.max_cols).contains(&col)
+        });
+        for (row, col, cell) in cells {
+            // Skip empty cells
+            if cell.value.is_empty() {
+                continue;
+            }
+
+            let row_idx = (row - 1) as u32;
+            let col_idx = (col - 1) as u16;
+            progress.set_done(rows_before + row);
+            // Whoever cancelled takes care of the missing file
+            if progress.is_cancelled() {
+                return Ok(());
+            }
+
+            // Formats are shared by all cells with the same style and number format
+            let num_format = number_format_code(cell);
+            let format = formats.entry((cell.style, num_format)).or_insert_with(|| {
+                let base = match num_format {
+                    Some(code) => Format::new().set_num_format(code),
+                    None => Format::new(),
+                };
+                apply_cell_style(base, sheet.styles.get(cell.style))
+            });
+
+            // Write cell based on its type
+            match cell.cell_type {
+                CellType::Number => {
+                    if let Ok(num) = cell.value.parse::<f64>() {
+                        worksheet.write_number_with_format(row_idx, col_idx, num, format)?;
+                    } else {
+                        worksheet.write_string_with_format(
+                            row_idx,
+                            col_idx,
+                            &cell.value,
+                            format,
+                        )?;
+                    }
+                }
+                // Serial dates read from Excel go back as numbers, in the 1900 date system
+                CellType::Date => match cell.original_type {
+                    Some(DataTypeInfo::DateTime(serial) | DataTypeInfo::Duration(serial)) => {
+                        worksheet.write_number_with_format(row_idx, col_idx, serial, format)?;
+                    }
+                    _ => {
+                        worksheet.write_string_with_format(
+                            row_idx,
+                            col_idx,
+                            &cell.value,
+                            format,
+                        )?;
+                    }
+                },
+                CellType::Boolean => {
+                    if let Ok(b) = cell.value.parse::<bool>() {
+                        worksheet.write_boolean_with_format(row_idx, col_idx, b, format)?;
+                    } else {
+                        worksheet.write_string_with_format(
+                            row_idx,
+                            col_idx,
+                            &cell.value,
+                            format,
+                        )?;
+                    }
+                }
+                CellType::Text => {
+                    if cell.is_formula {
+                        let formula = Formula::new(&cell.value);
+                        worksheet.write_formula_with_format(row_idx, col_idx, formula, format)?;
+                    } else {
+                        worksheet.write_string_with_format(
+                            row_idx,
+                            col_idx,
+                            &cell.value,
+                            format,
+                        )?;
+                    }
+                }
+                CellType::Empty => {}
+            }
+        }
+        rows_before += sheet.max_rows;
+    }
+
+    // How long packing the file takes isn't known
+    progress.start(0);
+    xlsx.save(path)?;
+
+    Ok(())
+}
+
+fn apply_cell_style(mut format: Format, style: &CellStyle) -> Format {
+    if let Some(fg) = style.fg {
+        format = format.set_font_color(fg.to_hex());
+    }
+    if let Some(bg) = style.bg {
+        format = format.set_background_color(bg.to_hex());
+    }
+    if style.bold {
+        format = format.set_bold();
+    }
+    if style.italic {
+        format = format.set_italic();
+    }
+    if let Some(code) = &style.number_format_code {
+        format = format.set_num_format(code);
+    } else if let Some(number_format) = style.number_format {
+        format = format.set_num_format_index(number_format);
+    }
+
+    match style.alignment {
+        HorizontalAlignment::General => format,
+        HorizontalAlignment::Left => format.set_align(FormatAlign::Left),
+        HorizontalAlignment::Center => format.set_align(FormatAlign::Center),
+        HorizontalAlignment::Right => format.set_align(FormatAlign::Right),
+    }
+}
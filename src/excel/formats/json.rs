@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::excel::formats::{FormatCapabilities, SheetFormat};
+use crate::excel::{Progress, SheetError, SheetResult, Workbook};
+use crate::json_export::{generate_all_sheets_json, serialize_to_json, HeaderDirection};
+
+/// Write-only format producing the same output as `:eja h 1`.
+pub struct JsonFormat;
+
+impl SheetFormat for JsonFormat {
+    fn name(&self) -> &'static str {
+        "JSON"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json"]
+    }
+
+    fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities {
+            read: false,
+            write: true,
+            lazy_loading: false,
+            multiple_sheets: true,
+            formulas: false,
+            column_widths: false,
+            styles: false,
+        }
+    }
+
+    fn open(&self, _path: &Path, _enable_lazy_loading: bool) -> SheetResult<Workbook> {
+        Err(SheetError::UnsupportedFormat {
+            format: self.name().to_string(),
+            operation: "opened",
+        })
+    }
+
+    fn save(
+        &self,
+        workbook: &Workbook,
+        path: &Path,
+        _column_widths: &HashMap<String, Vec<usize>>,
+        _progress: &Progress,
+    ) -> SheetResult<()> {
+        let all_sheets = generate_all_sheets_json(workbook, HeaderDirection::Horizontal, 1)
+            .map_err(|e| SheetError::write(path, e))?;
+        let json_string = serialize_to_json(&all_sheets).map_err(|e| SheetError::write(path, e))?;
+
+        fs::write(path, json_string).map_err(|source| SheetError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Ok(())
+    }
+}
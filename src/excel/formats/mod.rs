@@ -0,0 +1,145 @@
+mod delimited;
+mod excel;
+mod json;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::excel::{Progress, SheetError, SheetResult, Workbook};
+
+pub use delimited::DelimitedFormat;
+pub use excel::ExcelFormat;
+pub use json::JsonFormat;
+
+/// Describes what a format can round-trip, so callers can decide how to
+/// open a file and what will be lost when saving to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatCapabilities {
+    pub read: bool,
+    pub write: bool,
+    pub lazy_loading: bool,
+    pub multiple_sheets: bool,
+    pub formulas: bool,
+    pub column_widths: bool,
+    pub styles: bool,
+}
+
+/// A file format that can open files into a `Workbook` and/or save one back.
+pub trait SheetFormat {
+    fn name(&self) -> &'static str;
+
+    /// Lowercase file extensions handled by this format, the first one is used when saving
+    fn extensions(&self) -> &'static [&'static str];
+
+    fn capabilities(&self) -> FormatCapabilities;
+
+    fn open(&self, path: &Path, enable_lazy_loading: bool) -> SheetResult<Workbook>;
+
+    // Column widths are keyed by sheet name and indexed by 1-based column,
+    // `progress` counts the rows written for the status bar
+    fn save(
+        &self,
+        workbook: &Workbook,
+        path: &Path,
+        column_widths: &HashMap<String, Vec<usize>>,
+        progress: &Progress,
+    ) -> SheetResult<()>;
+}
+
+pub struct FormatRegistry {
+    formats: Vec<Box<dyn SheetFormat>>,
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(ExcelFormat::xlsx());
+        registry.register(ExcelFormat::legacy());
+        registry.register(DelimitedFormat::csv());
+        registry.register(DelimitedFormat::tsv());
+        registry.register(JsonFormat);
+        registry
+    }
+}
+
+impl FormatRegistry {
+    pub fn new() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+
+    /// The default formats with CSV and TSV files separated by `delimiter`
+    pub fn with_delimiter(delimiter: u8) -> Self {
+        let mut registry = Self::default();
+        registry.register(DelimitedFormat::csv().with_delimiter(delimiter));
+        registry.register(DelimitedFormat::tsv().with_delimiter(delimiter));
+        registry
+    }
+
+    /// Formats registered later take precedence for the same extension
+    pub fn register<F: SheetFormat + 'static>(&mut self, format: F) {
+        self.formats.insert(0, Box::new(format));
+    }
+
+    pub fn formats(&self) -> impl Iterator<Item = &dyn SheetFormat> {
+        self.formats.iter().map(|format| format.as_ref())
+    }
+
+    /// Extensions of every format that can be opened
+    pub fn readable_extensions(&self) -> Vec<&'static str> {
+        self.formats()
+            .filter(|format| format.capabilities().read)
+            .flat_map(|format| format.extensions().iter().copied())
+            .collect()
+    }
+
+    pub fn for_extension(&self, extension: &str) -> Option<&dyn SheetFormat> {
+        let extension = extension.to_lowercase();
+        self.formats()
+            .find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    pub fn for_path(&self, path: &Path) -> Option<&dyn SheetFormat> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.for_extension(ext))
+    }
+
+    /// Picks the format used to open a file, unknown extensions are left to
+    /// calamine's content sniffing
+    pub fn reader_for_path(&self, path: &Path) -> SheetResult<&dyn SheetFormat> {
+        match self.for_path(path) {
+            Some(format) if format.capabilities().read => Ok(format),
+            Some(format) => Err(SheetError::UnsupportedFormat {
+                format: format.name().to_string(),
+                operation: "opened",
+            }),
+            None => self
+                .for_extension("xlsx")
+                .ok_or_else(|| unknown_format(path, "opened")),
+        }
+    }
+
+    /// Picks the format used to save a file, falling back to xlsx for formats
+    /// that can only be read
+    pub fn writer_for_path(&self, path: &Path) -> SheetResult<&dyn SheetFormat> {
+        match self.for_path(path) {
+            Some(format) if format.capabilities().write => Ok(format),
+            _ => self
+                .for_extension("xlsx")
+                .filter(|format| format.capabilities().write)
+                .ok_or_else(|| unknown_format(path, "saved")),
+        }
+    }
+}
+
+fn unknown_format(path: &Path, operation: &'static str) -> SheetError {
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    SheetError::UnsupportedFormat { format, operation }
+}
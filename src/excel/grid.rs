@@ -0,0 +1,306 @@
+use std::mem::size_of;
+use std::ops::{Index, IndexMut};
+use std::slice;
+
+use serde::{Deserialize, Serialize};
+
+use crate::excel::{Cell, CellType, DEFAULT_STYLE};
+
+// Shared empty cell returned for positions without a stored cell
+pub(crate) static EMPTY_CELL: Cell = Cell {
+    value: String::new(),
+    is_formula: false,
+    cell_type: CellType::Empty,
+    original_type: None,
+    style: DEFAULT_STYLE,
+};
+
+static EMPTY_ROW: SparseRow = SparseRow::new();
+
+// A cell that looks the same as no cell at all, so it needn't be stored
+fn is_blank(cell: &Cell) -> bool {
+    cell.value.is_empty() && !cell.is_formula && cell.style == DEFAULT_STYLE
+}
+
+/// Cells of one row ordered by column. Only cells with a value or a style are stored,
+/// every other column reads as an empty cell
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SparseRow {
+    cells: Vec<(usize, Cell)>,
+}
+
+impl SparseRow {
+    #[must_use]
+    pub const fn new() -> Self {
+        SparseRow { cells: Vec::new() }
+    }
+
+    /// Row from cells indexed by column, blank ones are left out
+    #[must_use]
+    pub fn from_cells(cells: Vec<Cell>) -> Self {
+        SparseRow {
+            cells: cells
+                .into_iter()
+                .enumerate()
+                .filter(|(_, cell)| !is_blank(cell))
+                .collect(),
+        }
+    }
+
+    /// Cells indexed by column, at least `width` of them with empty cells filled in
+    #[must_use]
+    pub fn to_cells(&self, width: usize) -> Vec<Cell> {
+        let width = width.max(self.cells.last().map_or(0, |(col, _)| col + 1));
+        let mut cells = vec![Cell::empty(); width];
+        for (col, cell) in &self.cells {
+            cells[*col] = cell.clone();
+        }
+        cells
+    }
+
+    fn position(&self, col: usize) -> Result<usize, usize> {
+        self.cells.binary_search_by_key(&col, |(stored, _)| *stored)
+    }
+
+    /// The stored cell in a column, `None` when the column is empty
+    #[must_use]
+    pub fn get(&self, col: usize) -> Option<&Cell> {
+        self.position(col).ok().map(|index| &self.cells[index].1)
+    }
+
+    pub fn get_mut(&mut self, col: usize) -> Option<&mut Cell> {
+        self.position(col)
+            .ok()
+            .map(|index| &mut self.cells[index].1)
+    }
+
+    /// The cell in a column to change in place, stored as an empty cell if there was none
+    pub fn entry(&mut self, col: usize) -> &mut Cell {
+        let index = match self.position(col) {
+            Ok(index) => index,
+            Err(index) => {
+                self.cells.insert(index, (col, Cell::empty()));
+                index
+            }
+        };
+        &mut self.cells[index].1
+    }
+
+    /// Puts a cell in a column, returning the one it replaces
+    pub fn set(&mut self, col: usize, cell: Cell) -> Cell {
+        match (self.position(col), is_blank(&cell)) {
+            (Ok(index), true) => self.cells.remove(index).1,
+            (Ok(index), false) => std::mem::replace(&mut self.cells[index].1, cell),
+            (Err(_), true) => Cell::empty(),
+            (Err(index), false) => {
+                self.cells.insert(index, (col, cell));
+                Cell::empty()
+            }
+        }
+    }
+
+    /// Moves the cells from `col` on one column to the right
+    pub fn insert_column(&mut self, col: usize) {
+        for (stored, _) in self.cells.iter_mut().filter(|(stored, _)| *stored >= col) {
+            *stored += 1;
+        }
+    }
+
+    /// Takes the cell out of `col` and moves the cells after it one column to the left
+    pub fn remove_column(&mut self, col: usize) -> Cell {
+        let removed = self
+            .position(col)
+            .map_or_else(|_| Cell::empty(), |index| self.cells.remove(index).1);
+        for (stored, _) in self.cells.iter_mut().filter(|(stored, _)| *stored > col) {
+            *stored -= 1;
+        }
+        removed
+    }
+
+    /// Stored cells with their column, in column order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Cell)> {
+        self.cells.iter().map(|(col, cell)| (*col, cell))
+    }
+
+    /// The last column with a value, `None` for a row without any
+    #[must_use]
+    pub fn last_filled_col(&self) -> Option<usize> {
+        self.cells
+            .iter()
+            .rev()
+            .find(|(_, cell)| !cell.value.is_empty())
+            .map(|(col, _)| *col)
+    }
+
+    #[must_use]
+    pub fn has_values(&self) -> bool {
+        self.cells.iter().any(|(_, cell)| !cell.value.is_empty())
+    }
+
+    /// Bytes the row takes in memory besides its own struct
+    #[must_use]
+    pub fn heap_size(&self) -> usize {
+        let spare = self.cells.capacity() - self.cells.len();
+        spare * size_of::<(usize, Cell)>()
+            + self
+                .cells
+                .iter()
+                .map(|(_, cell)| size_of::<usize>() + cell.memory_size())
+                .sum::<usize>()
+    }
+}
+
+impl Index<usize> for SparseRow {
+    type Output = Cell;
+
+    fn index(&self, col: usize) -> &Cell {
+        self.get(col).unwrap_or(&EMPTY_CELL)
+    }
+}
+
+impl IndexMut<usize> for SparseRow {
+    fn index_mut(&mut self, col: usize) -> &mut Cell {
+        self.entry(col)
+    }
+}
+
+/// The cells of a sheet as `len` rows by `width` columns, indexed from 0 like the dense
+/// grid it replaces so row and column 0 stay unused. Rows only hold the cells that have
+/// something in them, a wide sheet that is mostly empty costs little more than its values
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CellGrid {
+    rows: Vec<SparseRow>,
+    width: usize,
+}
+
+impl CellGrid {
+    /// A grid of `len` rows and `width` columns without any cells
+    #[must_use]
+    pub fn with_size(len: usize, width: usize) -> Self {
+        CellGrid {
+            rows: vec![SparseRow::new(); len],
+            width,
+        }
+    }
+
+    /// Number of rows, including the unused row 0
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Number of columns, including the unused column 0
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Grows the grid to at least `len` rows and `width` columns
+    pub fn expand(&mut self, len: usize, width: usize) {
+        if self.rows.len() < len {
+            self.rows.resize_with(len, SparseRow::new);
+        }
+        self.width = self.width.max(width);
+    }
+
+    /// The cell at a position inside the grid, empty if nothing is stored there
+    #[must_use]
+    pub fn get(&self, row: usize, col: usize) -> Option<&Cell> {
+        if col >= self.width {
+            return None;
+        }
+        self.rows.get(row).map(|cells| &cells[col])
+    }
+
+    /// The stored cell at a position to change in place, `None` when nothing is stored
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut Cell> {
+        self.rows.get_mut(row)?.get_mut(col)
+    }
+
+    /// Puts a cell inside the grid, returning the one it replaces
+    pub fn set(&mut self, row: usize, col: usize, cell: Cell) -> Cell {
+        self.rows[row].set(col, cell)
+    }
+
+    #[must_use]
+    pub fn row(&self, row: usize) -> Option<&SparseRow> {
+        self.rows.get(row)
+    }
+
+    /// Inserts a row before `row`, widening the grid to fit its cells
+    pub fn insert_row(&mut self, row: usize, cells: SparseRow) {
+        if let Some((col, _)) = cells.cells.last() {
+            self.width = self.width.max(col + 1);
+        }
+        self.rows.insert(row, cells);
+    }
+
+    pub fn remove_row(&mut self, row: usize) -> SparseRow {
+        self.rows.remove(row)
+    }
+
+    /// Inserts a column before `col` holding `cells`, indexed by row
+    pub fn insert_column(&mut self, col: usize, cells: &[Cell]) {
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            row.insert_column(col);
+            if let Some(cell) = cells.get(index) {
+                row.set(col, cell.clone());
+            }
+        }
+        self.width = self.width.max(col) + 1;
+    }
+
+    /// Removes column `col` if it is inside the grid
+    pub fn remove_column(&mut self, col: usize) {
+        if col >= self.width {
+            return;
+        }
+        for row in &mut self.rows {
+            row.remove_column(col);
+        }
+        self.width -= 1;
+    }
+
+    pub fn rows(&self) -> slice::Iter<'_, SparseRow> {
+        self.rows.iter()
+    }
+
+    pub fn rows_mut(&mut self) -> slice::IterMut<'_, SparseRow> {
+        self.rows.iter_mut()
+    }
+
+    /// Bytes the grid takes in memory, cells and their text included
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.rows.capacity() * size_of::<SparseRow>()
+            + self.rows.iter().map(SparseRow::heap_size).sum::<usize>()
+    }
+
+    /// Stored cells with their row and column, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &Cell)> {
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cells)| cells.iter().map(move |(col, cell)| (row, col, cell)))
+    }
+}
+
+impl Index<usize> for CellGrid {
+    type Output = SparseRow;
+
+    fn index(&self, row: usize) -> &SparseRow {
+        self.rows.get(row).unwrap_or(&EMPTY_ROW)
+    }
+}
+
+impl IndexMut<usize> for CellGrid {
+    fn index_mut(&mut self, row: usize) -> &mut SparseRow {
+        &mut self.rows[row]
+    }
+}
@@ -0,0 +1,10 @@
+use std::path::Path;
+
+use crate::excel::{open_workbook, Sheet, SheetResult};
+
+/// Loads the first sheet of a file so its rows can be merged into another workbook.
+pub fn load_sheet_from_file<P: AsRef<Path>>(path: P) -> SheetResult<Sheet> {
+    let mut workbook = open_workbook(path, false)?;
+    workbook.switch_sheet(0)?;
+    Ok(workbook.get_current_sheet().clone())
+}
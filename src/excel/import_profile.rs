@@ -0,0 +1,331 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::excel::{Cell, Sheet, SheetError, SheetResult};
+use crate::utils::col_name_to_index;
+
+/// A cleanup step applied to every value of a column when a file is opened
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ColumnTransform {
+    Trim,
+    Uppercase,
+    Lowercase,
+    StripCurrency,
+    /// Parses dates written in the given chrono format and rewrites them as YYYY-MM-DD
+    ParseDate(String),
+}
+
+impl FromStr for ColumnTransform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(format) = s.strip_prefix("parse_date:") {
+            return Ok(ColumnTransform::ParseDate(format.to_string()));
+        }
+
+        match s {
+            "trim" => Ok(ColumnTransform::Trim),
+            "uppercase" => Ok(ColumnTransform::Uppercase),
+            "lowercase" => Ok(ColumnTransform::Lowercase),
+            "strip_currency" => Ok(ColumnTransform::StripCurrency),
+            _ => Err(format!(
+                "Unknown transform '{s}', expected trim, uppercase, lowercase, strip_currency or parse_date:<format>"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColumnTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnTransform::Trim => write!(f, "trim"),
+            ColumnTransform::Uppercase => write!(f, "uppercase"),
+            ColumnTransform::Lowercase => write!(f, "lowercase"),
+            ColumnTransform::StripCurrency => write!(f, "strip_currency"),
+            ColumnTransform::ParseDate(format) => write!(f, "parse_date:{format}"),
+        }
+    }
+}
+
+impl From<ColumnTransform> for String {
+    fn from(transform: ColumnTransform) -> Self {
+        transform.to_string()
+    }
+}
+
+impl TryFrom<String> for ColumnTransform {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl ColumnTransform {
+    /// The transformed value, values that don't apply (e.g. unparsable dates) are kept as is
+    #[must_use]
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            ColumnTransform::Trim => value.trim().to_string(),
+            ColumnTransform::Uppercase => value.to_uppercase(),
+            ColumnTransform::Lowercase => value.to_lowercase(),
+            ColumnTransform::StripCurrency => value
+                .chars()
+                .filter(|c| !is_currency_symbol(*c))
+                .collect::<String>()
+                .trim()
+                .to_string(),
+            ColumnTransform::ParseDate(format) => {
+                let trimmed = value.trim();
+                if let Ok(date) = NaiveDate::parse_from_str(trimmed, format) {
+                    date.format("%Y-%m-%d").to_string()
+                } else if let Ok(datetime) = NaiveDateTime::parse_from_str(trimmed, format) {
+                    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+                } else {
+                    value.to_string()
+                }
+            }
+        }
+    }
+}
+
+/// Text encoding of CSV/TSV files. Files are read as UTF-8 only, so a profile naming any
+/// other encoding fails to load instead of having it ignored
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FileEncoding {
+    Utf8,
+}
+
+impl FromStr for FileEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(FileEncoding::Utf8),
+            _ => Err(format!(
+                "Unsupported encoding '{s}', files can only be read as utf-8"
+            )),
+        }
+    }
+}
+
+impl From<FileEncoding> for String {
+    fn from(encoding: FileEncoding) -> Self {
+        match encoding {
+            FileEncoding::Utf8 => "utf-8".to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for FileEncoding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+fn is_currency_symbol(c: char) -> bool {
+    matches!(
+        c,
+        '$' | '€' | '£' | '¥' | '₹' | '₩' | '₽' | '¢' | '₺' | '₫' | '₪' | '฿' | '₱' | '₴'
+    )
+}
+
+/// Import and export settings applied to files whose name matches `pattern`,
+/// or picked by `name` with `--profile`. Columns are named by their header in
+/// the first row or by their letter, e.g.
+/// `{"pattern": "sales_*.csv", "columns": {"Amount": ["trim", "strip_currency"]}}`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ImportProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Field separator of CSV/TSV files, used for opening and saving them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delimiter: Option<char>,
+    /// Number of header rows, used by the JSON export
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header_rows: Option<usize>,
+    /// "h" or "v", see `HeaderDirection`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header_direction: Option<String>,
+    /// Encoding of CSV/TSV files, only "utf-8" is accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<FileEncoding>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub columns: IndexMap<String, Vec<ColumnTransform>>,
+}
+
+impl ImportProfile {
+    /// Whether the file name of `path` matches the profile's `*`/`?` pattern, ignoring case
+    #[must_use]
+    pub fn matches(&self, path: &Path) -> bool {
+        let Some(pattern) = &self.pattern else {
+            return false;
+        };
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let pattern = pattern.to_lowercase();
+
+        wildcard_match(
+            &pattern.chars().collect::<Vec<_>>(),
+            &file_name.chars().collect::<Vec<_>>(),
+        )
+    }
+
+    /// The name shown to the user, falling back to the pattern
+    #[must_use]
+    pub fn display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .or(self.pattern.as_deref())
+            .unwrap_or("unnamed")
+    }
+
+    /// Whether the profile is called `name`, ignoring case
+    #[must_use]
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name
+            .as_deref()
+            .is_some_and(|own| own.eq_ignore_ascii_case(name))
+    }
+
+    /// The delimiter as the byte the CSV reader expects, only ASCII separators are supported
+    pub fn delimiter_byte(&self) -> Result<Option<u8>, String> {
+        self.delimiter
+            .map(|c| {
+                u8::try_from(c)
+                    .ok()
+                    .filter(u8::is_ascii)
+                    .ok_or_else(|| format!("Profile delimiter '{c}' is not an ASCII character"))
+            })
+            .transpose()
+    }
+
+    /// Applies the transforms to a sheet, skipping the header row. Returns the number of
+    /// cells that changed
+    pub(crate) fn apply_to_sheet(&self, sheet: &mut Sheet) -> usize {
+        let mut changed = 0;
+
+        for (column, transforms) in &self.columns {
+            let Some(col) = find_column(sheet, column) else {
+                continue;
+            };
+
+            for row in 2..=sheet.max_rows {
+                let Some(cell) = sheet.data.get_mut(row, col) else {
+                    continue;
+                };
+                if cell.value.is_empty() || cell.is_formula {
+                    continue;
+                }
+
+                let value = transforms
+                    .iter()
+                    .fold(cell.value.clone(), |value, transform| {
+                        transform.apply(&value)
+                    });
+
+                if value != cell.value {
+                    let style = cell.style;
+                    *cell = Cell::new(value, false);
+                    cell.style = style;
+                    changed += 1;
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+// A header in the first row wins over a column letter of the same spelling
+fn find_column(sheet: &Sheet, column: &str) -> Option<usize> {
+    (1..=sheet.max_cols)
+        .find(|&col| sheet.cell(1, col).value.trim().eq_ignore_ascii_case(column))
+        .or_else(|| col_name_to_index(column))
+        .filter(|&col| col >= 1 && col <= sheet.max_cols)
+}
+
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| wildcard_match(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && wildcard_match(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && wildcard_match(rest, &text[1..]),
+    }
+}
+
+/// Location of the import profiles, e.g. ~/.config/excel-cli/import_profiles.json
+#[must_use]
+pub fn import_profiles_path() -> PathBuf {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default();
+
+    config_dir.join("excel-cli").join("import_profiles.json")
+}
+
+/// Reads the import profiles, a missing file means there are none
+pub fn load_import_profiles(path: &Path) -> SheetResult<Vec<ImportProfile>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(SheetError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    serde_json::from_str(&content).map_err(|e| SheetError::Parse {
+        path: path.to_path_buf(),
+        sheet: None,
+        row: Some(e.line()),
+        col: Some(e.column()),
+        message: e.to_string(),
+    })
+}
+
+/// Adds `profile` to the profiles file, replacing a profile with the same name
+pub fn save_import_profile(path: &Path, profile: ImportProfile) -> SheetResult<()> {
+    let mut profiles = load_import_profiles(path)?;
+
+    let name = profile.name.clone().unwrap_or_default();
+    match profiles
+        .iter_mut()
+        .find(|existing| existing.is_named(&name))
+    {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+
+    let io_error = |source| SheetError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+
+    let json = serde_json::to_string_pretty(&profiles).map_err(|e| SheetError::write(path, e))?;
+    fs::write(path, json + "\n").map_err(io_error)
+}
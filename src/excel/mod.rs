@@ -1,7 +1,27 @@
 mod cell;
+mod error;
+pub mod formats;
+mod grid;
+mod import;
+mod import_profile;
+mod progress;
 mod sheet;
+mod stream;
+mod style;
 mod workbook;
+mod xlsx_stream;
+mod xlsx_styles;
 
 pub use cell::*;
+pub use error::*;
+pub use formats::{FormatCapabilities, FormatRegistry, SheetFormat};
+pub use grid::{CellGrid, SparseRow};
+pub use import::*;
+pub use import_profile::*;
+pub use progress::Progress;
 pub use sheet::*;
+pub use stream::*;
+pub use style::*;
 pub use workbook::*;
+pub use xlsx_stream::*;
+pub(crate) use xlsx_styles::apply_number_formats;
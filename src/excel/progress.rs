@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// How much of a job running on a background thread is done, shared between the thread
+/// doing the work and the UI drawing it. Clones count into the same totals. The UI can
+/// also ask the job to stop, the thread checks `is_cancelled` as it goes
+#[derive(Clone, Default)]
+pub struct Progress {
+    done: Arc<AtomicUsize>,
+    total: Arc<AtomicUsize>, // 0 while the size of the job isn't known
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Progress {
+    /// Starts counting again towards `total` units of work
+    pub fn start(&self, total: usize) {
+        self.done.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn set_done(&self, done: usize) {
+        self.done.store(done, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, units: usize) {
+        self.done.fetch_add(units, Ordering::Relaxed);
+    }
+
+    /// Fraction of the work done between 0 and 1, `None` when the total isn't known
+    #[must_use]
+    pub fn ratio(&self) -> Option<f64> {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return None;
+        }
+        let done = self.done.load(Ordering::Relaxed).min(total);
+        Some(done as f64 / total as f64)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
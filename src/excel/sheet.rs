@@ -1,10 +1,129 @@
-use crate::excel::Cell;
+use std::mem::size_of;
 
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+
+use crate::excel::grid::EMPTY_CELL;
+use crate::excel::{Cell, CellGrid, CellStyle, SparseRow, StyleTable};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Sheet {
     pub name: String,
-    pub data: Vec<Vec<Cell>>,
+    pub data: CellGrid,
     pub max_rows: usize,
     pub max_cols: usize,
     pub is_loaded: bool,
+    pub styles: StyleTable,
+}
+
+/// Borrowed view of a single cell with its 1-based position
+#[derive(Clone, Copy)]
+pub struct CellView<'a> {
+    pub row: usize,
+    pub col: usize,
+    pub cell: &'a Cell,
+}
+
+impl<'a> CellView<'a> {
+    #[must_use]
+    pub fn value(&self) -> &'a str {
+        &self.cell.value
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cell.value.is_empty()
+    }
+}
+
+/// Borrowed view of a sheet row, indexed by 1-based column numbers
+#[derive(Clone, Copy)]
+pub struct RowView<'a> {
+    row: usize,
+    max_cols: usize,
+    cells: &'a SparseRow,
+}
+
+impl<'a> RowView<'a> {
+    /// The 1-based row number
+    #[must_use]
+    pub fn index(&self) -> usize {
+        self.row
+    }
+
+    /// The cell in the given 1-based column, empty if outside the stored grid
+    #[must_use]
+    pub fn get(&self, col: usize) -> &'a Cell {
+        if col == 0 {
+            return &EMPTY_CELL;
+        }
+        &self.cells[col]
+    }
+
+    /// Cells of columns 1 to `max_cols` of the sheet
+    pub fn cells(&self) -> impl Iterator<Item = CellView<'a>> + 'a {
+        let row = self.row;
+        let view = *self;
+        (1..=self.max_cols).map(move |col| CellView {
+            row,
+            col,
+            cell: view.get(col),
+        })
+    }
+}
+
+impl Sheet {
+    /// A loaded sheet without any cells
+    #[must_use]
+    pub fn empty(name: &str) -> Self {
+        Sheet {
+            name: name.to_string(),
+            data: CellGrid::with_size(1, 1),
+            max_rows: 0,
+            max_cols: 0,
+            is_loaded: true,
+            styles: StyleTable::default(),
+        }
+    }
+
+    /// The cell at the given 1-based position, empty if outside the stored grid
+    #[must_use]
+    pub fn cell(&self, row: usize, col: usize) -> &Cell {
+        if row == 0 || col == 0 {
+            return &EMPTY_CELL;
+        }
+        &self.data[row][col]
+    }
+
+    /// Bytes the sheet's data takes in memory
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.name.capacity()
+            + self.data.memory_size()
+            + self.styles.memory_size()
+    }
+
+    /// The resolved style of the cell at the given 1-based position
+    #[must_use]
+    pub fn cell_style(&self, row: usize, col: usize) -> &CellStyle {
+        self.styles.get(self.cell(row, col).style)
+    }
+
+    /// Rows 1 to `max_rows`
+    pub fn rows(&self) -> impl Iterator<Item = RowView<'_>> {
+        (1..=self.max_rows).map(move |row| RowView {
+            row,
+            max_cols: self.max_cols,
+            cells: &self.data[row],
+        })
+    }
+
+    /// Cells of the given 1-based column for rows 1 to `max_rows`
+    pub fn column(&self, col: usize) -> impl Iterator<Item = CellView<'_>> {
+        (1..=self.max_rows).map(move |row| CellView {
+            row,
+            col,
+            cell: self.cell(row, col),
+        })
+    }
 }
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use csv::{ByteRecord, Position, Reader, ReaderBuilder};
+
+use crate::excel::{SheetError, SheetResult};
+
+// Rows between two indexed offsets, a row is read by seeking to the offset before it
+const CHECKPOINT_INTERVAL: usize = 1024;
+
+// Blocks of CHECKPOINT_INTERVAL rows kept in memory, the least recently used goes first
+const CACHED_BLOCKS: usize = 8;
+
+// Rows indexed between two looks at the clock
+const INDEX_BATCH: usize = 4096;
+
+/// Rows of a sheet read from disk on demand by the --stream viewer instead of loaded
+/// into a `Sheet`
+pub trait RowStream {
+    fn path(&self) -> &Path;
+
+    /// Name of the sheet shown, for files that hold several
+    fn sheet_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Moves to the next or previous sheet, false when there is none
+    fn switch_sheet(&mut self, _forward: bool) -> SheetResult<bool> {
+        Ok(false)
+    }
+
+    /// Rows indexed so far, all of them once `is_indexed` is true
+    fn rows(&self) -> usize;
+
+    /// Widest row indexed so far
+    fn max_cols(&self) -> usize;
+
+    fn is_indexed(&self) -> bool;
+
+    /// Fraction of the file indexed, between 0 and 1, if it can tell
+    fn index_progress(&self) -> Option<f64>;
+
+    /// Indexes rows for about `budget`, so the UI keeps responding while a large file is scanned
+    fn index_for(&mut self, budget: Duration) -> SheetResult<()>;
+
+    /// Whether rows asked for by `row` are still being read in the background
+    fn is_reading(&self) -> bool {
+        false
+    }
+
+    /// Reads the rows asked for by `row` for about `budget`
+    fn read_for(&mut self, _budget: Duration) -> SheetResult<()> {
+        Ok(())
+    }
+
+    /// Reads the rows from the 1-based `row` on with their own reader, the row must be indexed
+    fn scan_from(&self, row: usize) -> SheetResult<Box<dyn RowScan>>;
+
+    /// The cells of the 1-based `row`, `None` if it isn't indexed yet, is still being read
+    /// (see `is_reading`) or is beyond the end
+    fn row(&mut self, row: usize) -> SheetResult<Option<&[String]>>;
+}
+
+/// Reads the rows of a `RowStream` one after another from a given row, for scans
+/// such as search that would otherwise push the viewed rows out of the cache
+pub trait RowScan {
+    /// The 1-based number of the row `next_row` reads
+    fn next_row_number(&self) -> usize;
+
+    /// The next row and its 1-based number, `None` at the end of the file
+    fn next_row(&mut self) -> SheetResult<Option<(usize, Vec<String>)>>;
+}
+
+/// A CSV or TSV file read from disk on demand instead of loaded into a `Sheet`.
+/// Only every `CHECKPOINT_INTERVAL`th row offset and a few blocks of rows are kept
+/// in memory, so files larger than the available RAM can be viewed.
+pub struct CsvStream {
+    path: PathBuf,
+    delimiter: u8,
+    file_size: u64,
+    checkpoints: Vec<u64>, // Byte offsets of rows 1, 1 + CHECKPOINT_INTERVAL, ...
+    indexer: Option<Reader<File>>, // Dropped once the whole file is indexed
+    bytes_indexed: u64,
+    rows: usize, // Rows indexed so far
+    max_cols: usize,
+    reader: Reader<File>,
+    cache: HashMap<usize, Vec<Vec<String>>>,
+    cache_order: VecDeque<usize>,
+}
+
+/// Scans the rows of a `CsvStream` with a reader of its own
+pub struct RowScanner {
+    reader: Reader<File>,
+    next_row: usize,
+    record: ByteRecord,
+}
+
+impl RowScan for RowScanner {
+    fn next_row_number(&self) -> usize {
+        self.next_row
+    }
+
+    fn next_row(&mut self) -> SheetResult<Option<(usize, Vec<String>)>> {
+        if !self.reader.read_byte_record(&mut self.record)? {
+            return Ok(None);
+        }
+        let row = self.next_row;
+        self.next_row += 1;
+        Ok(Some((row, record_fields(&self.record))))
+    }
+}
+
+fn record_fields(record: &ByteRecord) -> Vec<String> {
+    record
+        .iter()
+        .map(|field| String::from_utf8_lossy(field).into_owned())
+        .collect()
+}
+
+fn csv_reader(path: &Path, delimiter: u8) -> SheetResult<Reader<File>> {
+    ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| SheetError::from(e).with_path(path))
+}
+
+// Moves `reader` to the 0-based `row`, which must already be indexed
+fn seek_to_row(
+    reader: &mut Reader<File>,
+    checkpoints: &[u64],
+    row: usize,
+    path: &Path,
+) -> SheetResult<()> {
+    let block = row / CHECKPOINT_INTERVAL;
+    let mut position = Position::new();
+    position
+        .set_byte(checkpoints[block])
+        .set_record((block * CHECKPOINT_INTERVAL) as u64);
+    reader
+        .seek(position)
+        .map_err(|e| SheetError::from(e).with_path(path))?;
+
+    let mut record = ByteRecord::new();
+    for _ in block * CHECKPOINT_INTERVAL..row {
+        reader
+            .read_byte_record(&mut record)
+            .map_err(|e| SheetError::from(e).with_path(path))?;
+    }
+    Ok(())
+}
+
+impl CsvStream {
+    /// Opens the file without reading it, rows become available as `index_for` gets through it
+    pub fn open(path: &Path, delimiter: u8) -> SheetResult<Self> {
+        let file_size = std::fs::metadata(path)
+            .map_err(|source| SheetError::Io {
+                path: path.to_path_buf(),
+                source,
+            })?
+            .len();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            delimiter,
+            file_size,
+            checkpoints: Vec::new(),
+            indexer: Some(csv_reader(path, delimiter)?),
+            bytes_indexed: 0,
+            rows: 0,
+            max_cols: 0,
+            reader: csv_reader(path, delimiter)?,
+            cache: HashMap::new(),
+            cache_order: VecDeque::new(),
+        })
+    }
+
+    fn load_block(&mut self, block: usize) -> SheetResult<()> {
+        let reader = &mut self.reader;
+        seek_to_row(
+            reader,
+            &self.checkpoints,
+            block * CHECKPOINT_INTERVAL,
+            &self.path,
+        )?;
+
+        let mut rows = Vec::with_capacity(CHECKPOINT_INTERVAL);
+        let mut record = ByteRecord::new();
+        while rows.len() < CHECKPOINT_INTERVAL
+            && reader
+                .read_byte_record(&mut record)
+                .map_err(|e| SheetError::from(e).with_path(&self.path))?
+        {
+            rows.push(record_fields(&record));
+        }
+
+        if self.cache.len() >= CACHED_BLOCKS {
+            if let Some(oldest) = self.cache_order.pop_front() {
+                self.cache.remove(&oldest);
+            }
+        }
+        self.cache.insert(block, rows);
+        Ok(())
+    }
+}
+
+impl RowStream for CsvStream {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rows(&self) -> usize {
+        self.rows
+    }
+
+    fn max_cols(&self) -> usize {
+        self.max_cols
+    }
+
+    fn is_indexed(&self) -> bool {
+        self.indexer.is_none()
+    }
+
+    fn index_progress(&self) -> Option<f64> {
+        if self.is_indexed() || self.file_size == 0 {
+            Some(1.0)
+        } else {
+            Some(self.bytes_indexed as f64 / self.file_size as f64)
+        }
+    }
+
+    fn index_for(&mut self, budget: Duration) -> SheetResult<()> {
+        let Some(mut reader) = self.indexer.take() else {
+            return Ok(());
+        };
+
+        let started = Instant::now();
+        let mut record = ByteRecord::new();
+
+        while started.elapsed() < budget {
+            for _ in 0..INDEX_BATCH {
+                let offset = reader.position().byte();
+                let has_record = reader
+                    .read_byte_record(&mut record)
+                    .map_err(|e| SheetError::from(e).with_path(&self.path))?;
+                if !has_record {
+                    self.bytes_indexed = self.file_size;
+                    return Ok(());
+                }
+
+                if self.rows % CHECKPOINT_INTERVAL == 0 {
+                    self.checkpoints.push(offset);
+                }
+                self.rows += 1;
+                self.max_cols = self.max_cols.max(record.len());
+            }
+            self.bytes_indexed = reader.position().byte();
+        }
+
+        self.indexer = Some(reader);
+        Ok(())
+    }
+
+    fn scan_from(&self, row: usize) -> SheetResult<Box<dyn RowScan>> {
+        let mut reader = csv_reader(&self.path, self.delimiter)?;
+        seek_to_row(
+            &mut reader,
+            &self.checkpoints,
+            row.saturating_sub(1),
+            &self.path,
+        )?;
+
+        Ok(Box::new(RowScanner {
+            reader,
+            next_row: row.max(1),
+            record: ByteRecord::new(),
+        }))
+    }
+
+    fn row(&mut self, row: usize) -> SheetResult<Option<&[String]>> {
+        if row == 0 || row > self.rows {
+            return Ok(None);
+        }
+
+        let block = (row - 1) / CHECKPOINT_INTERVAL;
+        if !self.cache.contains_key(&block) {
+            self.load_block(block)?;
+        }
+
+        // Most recently used block goes to the back of the eviction queue
+        if let Some(index) = self.cache_order.iter().position(|&cached| cached == block) {
+            self.cache_order.remove(index);
+        }
+        self.cache_order.push_back(block);
+
+        Ok(self
+            .cache
+            .get(&block)
+            .and_then(|rows| rows.get((row - 1) % CHECKPOINT_INTERVAL))
+            .map(Vec::as_slice))
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+
+use serde::{Deserialize, Serialize};
+
+/// Index into a sheet's `StyleTable`, `DEFAULT_STYLE` means no formatting
+pub type StyleId = u32;
+
+pub const DEFAULT_STYLE: StyleId = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// The color as 0xRRGGBB
+    #[must_use]
+    pub fn to_hex(self) -> u32 {
+        (u32::from(self.0) << 16) | (u32::from(self.1) << 8) | u32::from(self.2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HorizontalAlignment {
+    #[default]
+    General,
+    Left,
+    Center,
+    Right,
+}
+
+/// Formatting of a single cell. Readers fill in what their format stores,
+/// the renderer and writers apply whatever is set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CellStyle {
+    pub fg: Option<Rgb>,
+    pub bg: Option<Rgb>,
+    pub bold: bool,
+    pub italic: bool,
+    /// Built-in Excel number format id, e.g. 14 for dates
+    pub number_format: Option<u8>,
+    /// A number format of the workbook's own such as `0.0%`, used instead of the id
+    #[serde(default)]
+    pub number_format_code: Option<String>,
+    pub alignment: HorizontalAlignment,
+}
+
+impl CellStyle {
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        *self == CellStyle::default()
+    }
+}
+
+/// Interned styles of a sheet, so cells only store a small id and
+/// identical styles are kept once. Stored as the list of styles in id order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "Vec<CellStyle>", into = "Vec<CellStyle>")]
+pub struct StyleTable {
+    styles: Vec<CellStyle>,
+    ids: HashMap<CellStyle, StyleId>,
+}
+
+impl Default for StyleTable {
+    fn default() -> Self {
+        let default_style = CellStyle::default();
+        Self {
+            styles: vec![default_style.clone()],
+            ids: HashMap::from([(default_style, DEFAULT_STYLE)]),
+        }
+    }
+}
+
+impl From<Vec<CellStyle>> for StyleTable {
+    fn from(styles: Vec<CellStyle>) -> Self {
+        if styles.is_empty() {
+            return Self::default();
+        }
+
+        let mut ids = HashMap::with_capacity(styles.len());
+        for (id, style) in styles.iter().enumerate() {
+            // Ids stay as they were, a style listed twice resolves to its first id
+            ids.entry(style.clone()).or_insert(id as StyleId);
+        }
+        Self { styles, ids }
+    }
+}
+
+impl From<StyleTable> for Vec<CellStyle> {
+    fn from(table: StyleTable) -> Self {
+        table.styles
+    }
+}
+
+impl StyleTable {
+    /// Returns the id of `style`, adding it to the table if it is new
+    pub fn intern(&mut self, style: CellStyle) -> StyleId {
+        if let Some(&id) = self.ids.get(&style) {
+            return id;
+        }
+
+        let id = self.styles.len() as StyleId;
+        self.styles.push(style.clone());
+        self.ids.insert(style, id);
+        id
+    }
+
+    /// The style with the given id, unknown ids fall back to the default style
+    #[must_use]
+    pub fn get(&self, id: StyleId) -> &CellStyle {
+        self.styles
+            .get(id as usize)
+            .unwrap_or(&self.styles[DEFAULT_STYLE as usize])
+    }
+
+    /// Bytes the table takes in memory
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.styles.capacity() * size_of::<CellStyle>()
+            + self.ids.capacity() * (size_of::<CellStyle>() + size_of::<StyleId>())
+    }
+
+    /// Number of distinct styles, including the default one
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.styles.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.styles.len() <= 1
+    }
+}
@@ -1,13 +1,21 @@
-use anyhow::{Context, Result};
-use calamine::{open_workbook_auto, Data, Reader, Xls, Xlsx};
+use calamine::{
+    open_workbook_auto, Data, DataRef, Dimensions, Range, Reader, Sheets, Xls, Xlsx, XlsxError,
+};
 use chrono::Local;
-use rust_xlsxwriter::{Format, Workbook as XlsxWorkbook};
-use std::collections::HashSet;
-use std::fs::File;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
 use std::io::BufReader;
-use std::path::Path;
-
-use crate::excel::{Cell, CellType, DataTypeInfo, Sheet};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::excel::formats::{ExcelFormat, FormatRegistry, SheetFormat};
+use crate::excel::{apply_number_formats, ImportProfile, Progress, SheetError, SheetResult};
+use crate::excel::{Cell, CellGrid, CellType, DataTypeInfo, Sheet, SparseRow, StyleId, StyleTable};
+use crate::utils::datetime_to_serial;
 
 pub enum CalamineWorkbook {
     Xlsx(Box<Xlsx<BufReader<File>>>),
@@ -21,6 +29,24 @@ impl Clone for CalamineWorkbook {
     }
 }
 
+/// A sheet read from the file on a background thread, which holds the calamine reader
+/// until it is done
+struct SheetLoad {
+    sheet_name: String,
+    started: Instant,
+    progress: Progress,
+    receiver: Receiver<(CalamineWorkbook, SheetResult<Sheet>)>,
+}
+
+/// A copy of the workbook written to `path` on a background thread, edits made
+/// meanwhile don't reach the file
+struct BackgroundSave {
+    path: PathBuf,
+    started: Instant,
+    progress: Progress,
+    receiver: Receiver<SheetResult<()>>,
+}
+
 pub struct Workbook {
     sheets: Vec<Sheet>,
     current_sheet_index: usize,
@@ -29,6 +55,10 @@ pub struct Workbook {
     calamine_workbook: CalamineWorkbook,
     lazy_loading: bool,
     loaded_sheets: HashSet<usize>, // Track which sheets have been loaded
+    delimiter: Option<u8>,         // Field separator of CSV/TSV files when not the default
+    sheet_load: Option<SheetLoad>, // Sheet being loaded in the background
+    save: Option<BackgroundSave>,  // Copy being written by :w
+    import_profile: Option<ImportProfile>, // Column transforms, also for sheets loaded later
 }
 
 impl Clone for Workbook {
@@ -41,27 +71,45 @@ impl Clone for Workbook {
             calamine_workbook: CalamineWorkbook::None,
             lazy_loading: false,
             loaded_sheets: self.loaded_sheets.clone(),
+            delimiter: self.delimiter,
+            sheet_load: None,
+            save: None,
+            import_profile: self.import_profile.clone(),
         }
     }
 }
 
-pub fn open_workbook<P: AsRef<Path>>(path: P, enable_lazy_loading: bool) -> Result<Workbook> {
-    let path_str = path.as_ref().to_string_lossy().to_string();
-    let path_ref = path.as_ref();
+/// Opens a file through the format registered for its extension
+pub fn open_workbook<P: AsRef<Path>>(path: P, enable_lazy_loading: bool) -> SheetResult<Workbook> {
+    open_workbook_with_delimiter(path, enable_lazy_loading, None)
+}
 
-    // Determine if the file format supports lazy loading
-    let extension = path_ref
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext.to_lowercase());
+/// Like `open_workbook`, reading CSV/TSV files with the given field separator.
+/// The separator is kept so the file is saved back with it.
+pub fn open_workbook_with_delimiter<P: AsRef<Path>>(
+    path: P,
+    enable_lazy_loading: bool,
+    delimiter: Option<u8>,
+) -> SheetResult<Workbook> {
+    let registry = format_registry(delimiter);
+    let format = registry.reader_for_path(path.as_ref())?;
+    let mut workbook = format.open(path.as_ref(), enable_lazy_loading)?;
+    workbook.delimiter = delimiter;
+    Ok(workbook)
+}
 
-    // Only enable lazy loading if both the flag is set AND the format supports it
-    let supports_lazy_loading =
-        enable_lazy_loading && matches!(extension.as_deref(), Some("xlsx" | "xlsm"));
+fn format_registry(delimiter: Option<u8>) -> FormatRegistry {
+    delimiter.map_or_else(FormatRegistry::default, FormatRegistry::with_delimiter)
+}
+
+pub(crate) fn open_calamine_workbook(
+    path: &Path,
+    supports_lazy_loading: bool,
+) -> SheetResult<Workbook> {
+    let path_str = path.to_string_lossy().to_string();
 
     // Open workbook directly from path
-    let mut workbook = open_workbook_auto(&path)
-        .with_context(|| format!("Unable to parse Excel file: {}", path_str))?;
+    let workbook = open_workbook_auto(path).map_err(|e| SheetError::parse(path, None, e))?;
 
     let sheet_names = workbook.sheet_names().to_vec();
 
@@ -78,17 +126,18 @@ pub fn open_workbook<P: AsRef<Path>>(path: P, enable_lazy_loading: bool) -> Resu
             // Create a minimal sheet with just the name
             let sheet = Sheet {
                 name: name.to_string(),
-                data: vec![vec![Cell::empty(); 1]; 1],
+                data: CellGrid::with_size(1, 1),
                 max_rows: 0,
                 max_cols: 0,
                 is_loaded: false,
+                styles: StyleTable::default(),
             };
 
             sheets.push(sheet);
         }
 
         // Try to reopen the file to get a fresh reader for lazy loading
-        if let Ok(file) = File::open(&path) {
+        if let Ok(file) = File::open(path) {
             let reader = BufReader::new(file);
 
             // Try to open as XLSX first
@@ -96,7 +145,7 @@ pub fn open_workbook<P: AsRef<Path>>(path: P, enable_lazy_loading: bool) -> Resu
                 calamine_workbook = CalamineWorkbook::Xlsx(Box::new(xlsx_workbook));
             } else {
                 // If not XLSX, try to open as XLS
-                if let Ok(file) = File::open(&path) {
+                if let Ok(file) = File::open(path) {
                     let reader = BufReader::new(file);
                     if let Ok(xls_workbook) = Xls::new(reader) {
                         calamine_workbook = CalamineWorkbook::Xls(xls_workbook);
@@ -106,19 +155,11 @@ pub fn open_workbook<P: AsRef<Path>>(path: P, enable_lazy_loading: bool) -> Resu
         }
     } else {
         // For formats that don't support lazy loading or if lazy loading is disabled,
-        for name in &sheet_names {
-            let range = workbook
-                .worksheet_range(name)
-                .with_context(|| format!("Unable to read worksheet: {}", name))?;
-
-            let mut sheet = create_sheet_from_range(name, range);
-            sheet.is_loaded = true;
-            sheets.push(sheet);
-        }
+        sheets = read_sheets_in_parallel(path, workbook, &sheet_names)?;
     }
 
     if sheets.is_empty() {
-        anyhow::bail!("No worksheets found in file");
+        return Err(SheetError::NoSheets);
     }
 
     let mut loaded_sheets = HashSet::new();
@@ -137,84 +178,228 @@ pub fn open_workbook<P: AsRef<Path>>(path: P, enable_lazy_loading: bool) -> Resu
         calamine_workbook,
         lazy_loading: supports_lazy_loading,
         loaded_sheets,
+        delimiter: None,
+        sheet_load: None,
+        save: None,
+        import_profile: None,
     })
 }
 
-fn create_sheet_from_range(name: &str, range: calamine::Range<Data>) -> Sheet {
-    let (height, width) = range.get_size();
+// Reads every sheet up front on a few threads. A calamine reader reads one sheet at a
+// time, so each thread opens the file itself and takes the next sheet nobody has taken,
+// the first one reusing the reader that listed the sheets
+fn read_sheets_in_parallel(
+    path: &Path,
+    workbook: Sheets<BufReader<File>>,
+    sheet_names: &[String],
+) -> SheetResult<Vec<Sheet>> {
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(sheet_names.len());
+    let next_sheet = AtomicUsize::new(0);
+
+    let read = |mut workbook: Sheets<BufReader<File>>| {
+        let mut read = Vec::new();
+        loop {
+            let index = next_sheet.fetch_add(1, Ordering::Relaxed);
+            let Some(name) = sheet_names.get(index) else {
+                return read;
+            };
+            let sheet = workbook
+                .worksheet_range(name)
+                .map_err(|e| SheetError::parse(path, Some(name), e))
+                .map(|range| {
+                    let start = range.start().unwrap_or_default();
+                    let mut sheet = create_sheet_from_range(name, range);
+                    apply_number_formats(path, &mut sheet, start);
+                    sheet
+                });
+            read.push((index, sheet));
+        }
+    };
+
+    let mut results = thread::scope(|scope| {
+        let mut workbook = Some(workbook);
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                let workbook = workbook.take();
+                scope.spawn(|| match workbook {
+                    Some(workbook) => Ok(read(workbook)),
+                    None => open_workbook_auto(path)
+                        .map(read)
+                        .map_err(|e| SheetError::parse(path, None, e)),
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(sheet_names.len());
+        for handle in handles {
+            let sheets_read = handle.join().unwrap_or_else(|_| {
+                Err(SheetError::parse(
+                    path,
+                    None,
+                    "a sheet reader stopped unexpectedly",
+                ))
+            })?;
+            results.extend(sheets_read);
+        }
+        Ok::<_, SheetError>(results)
+    })?;
 
-    // Create a data grid with empty cells, adding 1 to dimensions for 1-based indexing
-    let mut data = vec![vec![Cell::empty(); width + 1]; height + 1];
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, sheet)| sheet).collect()
+}
 
-    // Process only non-empty cells
-    for (row_idx, col_idx, cell) in range.used_cells() {
-        // Extract value, cell_type, and original_type from the Data
-        let (value, cell_type, original_type) = match cell {
-            Data::Empty => (String::new(), CellType::Empty, Some(DataTypeInfo::Empty)),
+// Reads one sheet of a lazily loaded workbook, counting the rows read of an xlsx sheet
+fn read_sheet(
+    calamine_workbook: &mut CalamineWorkbook,
+    file_path: &str,
+    sheet_name: &str,
+    progress: &Progress,
+) -> SheetResult<Sheet> {
+    let range = match calamine_workbook {
+        CalamineWorkbook::Xlsx(xlsx) => read_xlsx_range(xlsx, sheet_name, progress)
+            .map_err(|e| SheetError::parse(file_path, Some(sheet_name), e))?,
+        CalamineWorkbook::Xls(xls) => xls
+            .worksheet_range(sheet_name)
+            .map_err(|e| SheetError::parse(file_path, Some(sheet_name), e))?,
+        CalamineWorkbook::None => {
+            return Err(SheetError::parse(
+                file_path,
+                Some(sheet_name),
+                "no workbook available to load the sheet from",
+            ));
+        }
+    };
+    if progress.is_cancelled() {
+        return Err(SheetError::Cancelled);
+    }
 
-            Data::String(s) => {
-                let value = s.clone();
-                (value, CellType::Text, Some(DataTypeInfo::String))
-            }
+    let start = range.start().unwrap_or_default();
+    let mut sheet = create_sheet_from_range(sheet_name, range);
+    apply_number_formats(Path::new(file_path), &mut sheet, start);
+    Ok(sheet)
+}
 
-            Data::Float(f) => {
-                let value = if *f == (*f as i64) as f64 && f.abs() < 1e10 {
-                    (*f as i64).to_string()
-                } else {
-                    f.to_string()
-                };
-                (value, CellType::Number, Some(DataTypeInfo::Float(*f)))
-            }
+// Same as `worksheet_range`, but reading cell by cell to count the rows done against
+// the size the sheet records
+fn read_xlsx_range(
+    xlsx: &mut Xlsx<BufReader<File>>,
+    sheet_name: &str,
+    progress: &Progress,
+) -> Result<Range<Data>, XlsxError> {
+    let mut reader = match xlsx.worksheet_cells_reader(sheet_name) {
+        Ok(reader) => reader,
+        // Chart sheets read as empty, like `worksheet_range` does
+        Err(XlsxError::NotAWorksheet(_)) => return Ok(Range::default()),
+        Err(e) => return Err(e),
+    };
+    let dimensions = reader.dimensions();
+    if dimensions != Dimensions::default() {
+        progress.start(dimensions.end.0 as usize + 1);
+    }
 
-            Data::Int(i) => (i.to_string(), CellType::Number, Some(DataTypeInfo::Int(*i))),
-
-            Data::Bool(b) => (
-                if *b {
-                    "TRUE".to_string()
-                } else {
-                    "FALSE".to_string()
-                },
-                CellType::Boolean,
-                Some(DataTypeInfo::Bool(*b)),
-            ),
-
-            Data::Error(e) => {
-                let mut value = String::with_capacity(15);
-                value.push_str("Error: ");
-                value.push_str(&format!("{:?}", e));
-                (value, CellType::Text, Some(DataTypeInfo::Error))
-            }
+    let mut cells = Vec::new();
+    while let Some(cell) = reader.next_cell()? {
+        // The caller throws away what was read
+        if progress.is_cancelled() {
+            break;
+        }
+        if matches!(cell.get_value(), DataRef::Empty) {
+            continue;
+        }
+        let (row, col) = cell.get_position();
+        progress.set_done(row as usize + 1);
+        cells.push(calamine::Cell::new(
+            (row, col),
+            Data::from(cell.get_value().clone()),
+        ));
+    }
+    Ok(Range::from_sparse(cells))
+}
+
+// Converts a value read by calamine into a cell
+pub(crate) fn cell_from_data(data: &Data) -> Cell {
+    let (value, cell_type, original_type) = match data {
+        Data::Empty => (String::new(), CellType::Empty, Some(DataTypeInfo::Empty)),
+
+        Data::String(s) => {
+            let value = s.clone();
+            (value, CellType::Text, Some(DataTypeInfo::String))
+        }
+
+        Data::Float(f) => {
+            let value = if *f == (*f as i64) as f64 && f.abs() < 1e10 {
+                (*f as i64).to_string()
+            } else {
+                f.to_string()
+            };
+            (value, CellType::Number, Some(DataTypeInfo::Float(*f)))
+        }
+
+        Data::Int(i) => (i.to_string(), CellType::Number, Some(DataTypeInfo::Int(*i))),
+
+        Data::Bool(b) => (
+            if *b {
+                "TRUE".to_string()
+            } else {
+                "FALSE".to_string()
+            },
+            CellType::Boolean,
+            Some(DataTypeInfo::Bool(*b)),
+        ),
+
+        Data::Error(e) => {
+            let mut value = String::with_capacity(15);
+            value.push_str("Error: ");
+            value.push_str(&format!("{:?}", e));
+            (value, CellType::Text, Some(DataTypeInfo::Error))
+        }
 
-            Data::DateTime(dt) => (
-                dt.to_string(),
+        // The value stays the serial number of the file, the grid shows it as a date.
+        // Workbooks in the 1904 date system count from another day, the type info keeps
+        // the serial of the 1900 system that the display and exports expect
+        Data::DateTime(dt) => {
+            let info = if dt.is_duration() {
+                DataTypeInfo::Duration(dt.as_f64())
+            } else {
+                DataTypeInfo::DateTime(dt.as_datetime().map_or(dt.as_f64(), datetime_to_serial))
+            };
+            (dt.to_string(), CellType::Date, Some(info))
+        }
+
+        Data::DateTimeIso(s) => {
+            let value = s.clone();
+            (
+                value.clone(),
                 CellType::Date,
-                Some(DataTypeInfo::DateTime(dt.as_f64())),
-            ),
-
-            Data::DateTimeIso(s) => {
-                let value = s.clone();
-                (
-                    value.clone(),
-                    CellType::Date,
-                    Some(DataTypeInfo::DateTimeIso(value)),
-                )
-            }
+                Some(DataTypeInfo::DateTimeIso(value)),
+            )
+        }
 
-            Data::DurationIso(s) => {
-                let value = s.clone();
-                (
-                    value.clone(),
-                    CellType::Text,
-                    Some(DataTypeInfo::DurationIso(value)),
-                )
-            }
-        };
+        Data::DurationIso(s) => {
+            let value = s.clone();
+            (
+                value.clone(),
+                CellType::Text,
+                Some(DataTypeInfo::DurationIso(value)),
+            )
+        }
+    };
 
-        let is_formula = !value.is_empty() && value.starts_with('=');
+    let is_formula = !value.is_empty() && value.starts_with('=');
+    Cell::new_with_type(value, is_formula, cell_type, original_type)
+}
 
-        // Store the cell in data grid (using 1-based indexing)
-        data[row_idx + 1][col_idx + 1] =
-            Cell::new_with_type(value, is_formula, cell_type, original_type);
+fn create_sheet_from_range(name: &str, range: calamine::Range<Data>) -> Sheet {
+    let (height, width) = range.get_size();
+
+    // Create an empty data grid, adding 1 to dimensions for 1-based indexing
+    let mut data = CellGrid::with_size(height + 1, width + 1);
+
+    // Process only non-empty cells, stored in the data grid using 1-based indexing
+    for (row_idx, col_idx, cell) in range.used_cells() {
+        data.set(row_idx + 1, col_idx + 1, cell_from_data(cell));
     }
 
     Sheet {
@@ -223,10 +408,51 @@ fn create_sheet_from_range(name: &str, range: calamine::Range<Data>) -> Sheet {
         max_rows: height,
         max_cols: width,
         is_loaded: true,
+        styles: StyleTable::default(),
     }
 }
 
 impl Workbook {
+    /// Builds a fully loaded workbook, used by formats that read everything up front
+    pub(crate) fn from_sheets(sheets: Vec<Sheet>, file_path: String) -> Self {
+        let loaded_sheets = (0..sheets.len()).collect();
+
+        Workbook {
+            sheets,
+            current_sheet_index: 0,
+            file_path,
+            is_modified: false,
+            calamine_workbook: CalamineWorkbook::None,
+            lazy_loading: false,
+            loaded_sheets,
+            delimiter: None,
+            sheet_load: None,
+            save: None,
+            import_profile: None,
+        }
+    }
+
+    /// Applies the column transforms of an import profile to every loaded sheet, and to
+    /// each sheet loaded later. Returns the number of cells that changed now
+    pub fn set_import_profile(&mut self, profile: ImportProfile) -> usize {
+        let changed: usize = self
+            .sheets
+            .iter_mut()
+            .filter(|sheet| sheet.is_loaded)
+            .map(|sheet| profile.apply_to_sheet(sheet))
+            .sum();
+        if changed > 0 {
+            self.is_modified = true;
+        }
+        self.import_profile = Some(profile);
+        changed
+    }
+
+    #[must_use]
+    pub fn import_profile(&self) -> Option<&ImportProfile> {
+        self.import_profile.as_ref()
+    }
+
     pub fn get_current_sheet(&self) -> &Sheet {
         &self.sheets[self.current_sheet_index]
     }
@@ -235,49 +461,166 @@ impl Workbook {
         &mut self.sheets[self.current_sheet_index]
     }
 
-    pub fn ensure_sheet_loaded(&mut self, sheet_index: usize, sheet_name: &str) -> Result<()> {
+    pub fn ensure_sheet_loaded(&mut self, sheet_index: usize, sheet_name: &str) -> SheetResult<()> {
         if !self.lazy_loading || self.sheets[sheet_index].is_loaded {
             return Ok(());
         }
 
+        // The reader is busy with a background load, which may be this very sheet
+        if let Some(load) = self.sheet_load.take() {
+            let received = load.receiver.recv().ok();
+            let _ = self.finish_sheet_load(load, received);
+            if self.sheets[sheet_index].is_loaded {
+                return Ok(());
+            }
+        }
+
         // Load the sheet data from the calamine workbook
-        match &mut self.calamine_workbook {
-            CalamineWorkbook::Xlsx(xlsx) => {
-                if let Ok(range) = xlsx.worksheet_range(sheet_name) {
-                    // Replace the placeholder sheet with a fully loaded one
-                    let mut sheet = create_sheet_from_range(sheet_name, range);
+        let sheet = read_sheet(
+            &mut self.calamine_workbook,
+            &self.file_path,
+            sheet_name,
+            &Progress::default(),
+        )?;
+        self.install_sheet(sheet_index, sheet);
+        Ok(())
+    }
 
-                    // Preserve the original name in case it was customized
-                    let original_name = self.sheets[sheet_index].name.clone();
-                    sheet.name = original_name;
+    /// Starts reading a sheet that isn't loaded yet on a background thread, collected by
+    /// `poll_sheet_load`. Returns false while another sheet is still loading
+    pub fn start_sheet_load(&mut self, sheet_index: usize) -> bool {
+        if self.is_sheet_loaded(sheet_index) {
+            return true;
+        }
+        let sheet_name = self.sheets[sheet_index].name.clone();
+        if let Some(load) = &self.sheet_load {
+            return load.sheet_name == sheet_name;
+        }
 
-                    self.sheets[sheet_index] = sheet;
+        let mut calamine_workbook =
+            std::mem::replace(&mut self.calamine_workbook, CalamineWorkbook::None);
+        let file_path = self.file_path.clone();
+        let name = sheet_name.clone();
+        let progress = Progress::default();
+        let counter = progress.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = read_sheet(&mut calamine_workbook, &file_path, &name, &counter);
+            let _ = sender.send((calamine_workbook, result));
+        });
+
+        self.sheet_load = Some(SheetLoad {
+            sheet_name,
+            started: Instant::now(),
+            progress,
+            receiver,
+        });
+        true
+    }
 
-                    // Mark the sheet as loaded
-                    self.loaded_sheets.insert(sheet_index);
-                }
-            }
-            CalamineWorkbook::Xls(xls) => {
-                if let Ok(range) = xls.worksheet_range(sheet_name) {
-                    // Replace the placeholder sheet with a fully loaded one
-                    let mut sheet = create_sheet_from_range(sheet_name, range);
+    /// Name of the sheet loading in the background and how long it has been loading
+    pub fn loading_sheet(&self) -> Option<(&str, Duration)> {
+        self.sheet_load
+            .as_ref()
+            .map(|load| (load.sheet_name.as_str(), load.started.elapsed()))
+    }
 
-                    // Preserve the original name in case it was customized
-                    let original_name = self.sheets[sheet_index].name.clone();
-                    sheet.name = original_name;
+    /// Fraction of the rows of the loading sheet read so far, `None` when it can't be told
+    #[must_use]
+    pub fn sheet_load_progress(&self) -> Option<f64> {
+        self.sheet_load.as_ref()?.progress.ratio()
+    }
 
-                    self.sheets[sheet_index] = sheet;
+    /// Asks the background load to stop, `poll_sheet_load` then reports it as cancelled
+    /// and the sheet stays unloaded. Returns false when no sheet is loading
+    pub fn cancel_sheet_load(&self) -> bool {
+        self.sheet_load
+            .as_ref()
+            .map(|load| load.progress.cancel())
+            .is_some()
+    }
 
-                    // Mark the sheet as loaded
-                    self.loaded_sheets.insert(sheet_index);
-                }
-            }
-            CalamineWorkbook::None => {
-                return Err(anyhow::anyhow!("Cannot load sheet: no workbook available"));
-            }
+    /// Puts a sheet finished loading in the background in place. Returns its name, how
+    /// long it took and how many cells the import profile changed, or `None` while it is
+    /// still loading
+    pub fn poll_sheet_load(&mut self) -> Option<(String, SheetResult<(Duration, usize)>)> {
+        let received = match self.sheet_load.as_ref()?.receiver.try_recv() {
+            Ok(received) => Some(received),
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => None,
+        };
+
+        let load = self.sheet_load.take()?;
+        let sheet_name = load.sheet_name.clone();
+        let elapsed = load.started.elapsed();
+        let result = self
+            .finish_sheet_load(load, received)
+            .map(|changed| (elapsed, changed));
+        Some((sheet_name, result))
+    }
+
+    fn finish_sheet_load(
+        &mut self,
+        load: SheetLoad,
+        received: Option<(CalamineWorkbook, SheetResult<Sheet>)>,
+    ) -> SheetResult<usize> {
+        let Some((calamine_workbook, result)) = received else {
+            return Err(SheetError::parse(
+                &self.file_path,
+                Some(&load.sheet_name),
+                "the loader stopped unexpectedly",
+            ));
+        };
+        self.calamine_workbook = calamine_workbook;
+        let sheet = result?;
+
+        // Sheets may have been added, moved or deleted while it was loading
+        let Some(sheet_index) = self
+            .sheets
+            .iter()
+            .position(|sheet| !sheet.is_loaded && sheet.name == load.sheet_name)
+        else {
+            return Err(SheetError::parse(
+                &self.file_path,
+                Some(&load.sheet_name),
+                "the sheet was deleted while it was loading",
+            ));
+        };
+        Ok(self.install_sheet(sheet_index, sheet))
+    }
+
+    // Replaces the placeholder of a sheet with its loaded data, cleaned up by the import
+    // profile. Returns the number of cells the profile changed
+    fn install_sheet(&mut self, sheet_index: usize, mut sheet: Sheet) -> usize {
+        // Preserve the original name in case it was customized
+        sheet.name.clone_from(&self.sheets[sheet_index].name);
+        let changed = self
+            .import_profile
+            .as_ref()
+            .map_or(0, |profile| profile.apply_to_sheet(&mut sheet));
+        if changed > 0 {
+            self.is_modified = true;
         }
+        self.sheets[sheet_index] = sheet;
 
-        Ok(())
+        // Mark the sheet as loaded
+        self.loaded_sheets.insert(sheet_index);
+        changed
+    }
+
+    /// All sheets in workbook order
+    pub fn sheets(&self) -> impl Iterator<Item = &Sheet> {
+        self.sheets.iter()
+    }
+
+    pub fn sheets_mut(&mut self) -> impl Iterator<Item = &mut Sheet> {
+        self.sheets.iter_mut()
+    }
+
+    /// The sheets alone, for a workbook only read to look at its cells
+    #[must_use]
+    pub fn into_sheets(self) -> Vec<Sheet> {
+        self.sheets
     }
 
     pub fn get_sheet_by_index(&self, index: usize) -> Option<&Sheet> {
@@ -289,30 +632,29 @@ impl Workbook {
 
         // Expand rows if needed
         if row >= sheet.data.len() {
-            let default_row_len = if sheet.data.is_empty() {
-                col + 1
-            } else {
-                sheet.data[0].len()
-            };
-            let rows_to_add = row + 1 - sheet.data.len();
-
-            sheet
-                .data
-                .extend(vec![vec![Cell::empty(); default_row_len]; rows_to_add]);
+            sheet.data.expand(row + 1, col + 1);
             sheet.max_rows = sheet.max_rows.max(row);
         }
 
         // Expand columns if needed
-        if col >= sheet.data[0].len() {
-            for row_data in &mut sheet.data {
-                row_data.resize_with(col + 1, Cell::empty);
-            }
-
+        if col >= sheet.data.width() {
+            sheet.data.expand(row + 1, col + 1);
             sheet.max_cols = sheet.max_cols.max(col);
         }
     }
 
-    pub fn set_cell_value(&mut self, row: usize, col: usize, value: String) -> Result<()> {
+    // Fail instead of writing into the placeholder of a sheet that is not loaded yet
+    pub fn ensure_current_sheet_editable(&self) -> SheetResult<()> {
+        if !self.is_sheet_loaded(self.current_sheet_index) {
+            return Err(SheetError::SheetNotLoaded {
+                sheet: self.sheets[self.current_sheet_index].name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn set_cell_value(&mut self, row: usize, col: usize, value: String) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
         self.ensure_cell_exists(row, col);
 
         let sheet = &mut self.sheets[self.current_sheet_index];
@@ -321,12 +663,14 @@ impl Workbook {
         // Only set modified flag if value actually changes
         if current_value != &value {
             let is_formula = value.starts_with('=');
-            sheet.data[row][col] = Cell::new(value, is_formula);
+            let mut cell = Cell::new(value, is_formula);
+            cell.style = sheet.data[row][col].style;
 
             // Update max_cols if needed
-            if col > sheet.max_cols && !sheet.data[row][col].value.is_empty() {
+            if col > sheet.max_cols && !cell.value.is_empty() {
                 sheet.max_cols = col;
             }
+            sheet.data.set(row, col, cell);
 
             self.is_modified = true;
         }
@@ -334,6 +678,20 @@ impl Workbook {
         Ok(())
     }
 
+    pub fn set_cell_style(&mut self, row: usize, col: usize, style: StyleId) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+        self.ensure_cell_exists(row, col);
+
+        let data = &mut self.sheets[self.current_sheet_index].data;
+        if data[row][col].style != style {
+            let mut cell = data[row][col].clone();
+            cell.style = style;
+            data.set(row, col, cell);
+            self.is_modified = true;
+        }
+        Ok(())
+    }
+
     pub fn get_sheet_names(&self) -> Vec<String> {
         let mut names = Vec::with_capacity(self.sheets.len());
         for sheet in &self.sheets {
@@ -350,19 +708,23 @@ impl Workbook {
         self.current_sheet_index
     }
 
-    pub fn switch_sheet(&mut self, index: usize) -> Result<()> {
+    pub fn switch_sheet(&mut self, index: usize) -> SheetResult<()> {
         if index >= self.sheets.len() {
-            anyhow::bail!("Sheet index out of range");
+            return Err(SheetError::OutOfRange {
+                what: "Sheet",
+                index,
+                max: self.sheets.len().saturating_sub(1),
+            });
         }
 
         self.current_sheet_index = index;
         Ok(())
     }
 
-    pub fn delete_current_sheet(&mut self) -> Result<()> {
+    pub fn delete_current_sheet(&mut self) -> SheetResult<()> {
         // Prevent deleting the last sheet
         if self.sheets.len() <= 1 {
-            anyhow::bail!("Cannot delete the last sheet");
+            return Err(SheetError::LastSheet);
         }
 
         self.sheets.remove(self.current_sheet_index);
@@ -376,7 +738,9 @@ impl Workbook {
         Ok(())
     }
 
-    pub fn delete_row(&mut self, row: usize) -> Result<()> {
+    pub fn delete_row(&mut self, row: usize) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+
         let sheet = &mut self.sheets[self.current_sheet_index];
 
         // If row is less than 1, return early with success
@@ -391,7 +755,7 @@ impl Workbook {
 
         // Only remove the row if it exists in the data
         if row < sheet.data.len() {
-            sheet.data.remove(row);
+            sheet.data.remove_row(row);
             self.recalculate_max_cols();
             self.is_modified = true;
         }
@@ -400,7 +764,9 @@ impl Workbook {
     }
 
     // Delete a range of rows from the current sheet
-    pub fn delete_rows(&mut self, start_row: usize, end_row: usize) -> Result<()> {
+    pub fn delete_rows(&mut self, start_row: usize, end_row: usize) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+
         let sheet = &mut self.sheets[self.current_sheet_index];
 
         // If start_row is less than 1 or start_row > end_row, return early with success
@@ -428,7 +794,7 @@ impl Workbook {
             // Remove rows in reverse order to avoid index shifting issues
             for row in (start_row..=effective_end_row).rev() {
                 if row < sheet.data.len() {
-                    sheet.data.remove(row);
+                    sheet.data.remove_row(row);
                 }
             }
 
@@ -439,7 +805,9 @@ impl Workbook {
         Ok(())
     }
 
-    pub fn delete_column(&mut self, col: usize) -> Result<()> {
+    pub fn delete_column(&mut self, col: usize) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+
         let sheet = &mut self.sheets[self.current_sheet_index];
 
         // If column is less than 1, return early with success
@@ -452,19 +820,9 @@ impl Workbook {
             return Ok(());
         }
 
-        let mut has_data = false;
-        for row in &sheet.data {
-            if col < row.len() && !row[col].value.is_empty() {
-                has_data = true;
-                break;
-            }
-        }
+        let has_data = sheet.data.rows().any(|row| !row[col].value.is_empty());
 
-        for row in sheet.data.iter_mut() {
-            if col < row.len() {
-                row.remove(col);
-            }
-        }
+        sheet.data.remove_column(col);
 
         self.recalculate_max_cols();
         self.recalculate_max_rows();
@@ -477,7 +835,9 @@ impl Workbook {
     }
 
     // Delete a range of columns from the current sheet
-    pub fn delete_columns(&mut self, start_col: usize, end_col: usize) -> Result<()> {
+    pub fn delete_columns(&mut self, start_col: usize, end_col: usize) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+
         let sheet = &mut self.sheets[self.current_sheet_index];
 
         // If start_col is less than 1 or start_col > end_col, return early with success
@@ -493,25 +853,13 @@ impl Workbook {
         // If start_col is valid but end_col exceeds max_cols, adjust end_col to max_cols
         let effective_end_col = end_col.min(sheet.max_cols);
 
-        let mut has_data = false;
-        for row in &sheet.data {
-            for col in start_col..=effective_end_col {
-                if col < row.len() && !row[col].value.is_empty() {
-                    has_data = true;
-                    break;
-                }
-            }
-            if has_data {
-                break;
-            }
-        }
+        let has_data = sheet
+            .data
+            .rows()
+            .any(|row| (start_col..=effective_end_col).any(|col| !row[col].value.is_empty()));
 
-        for row in sheet.data.iter_mut() {
-            for col in (start_col..=effective_end_col).rev() {
-                if col < row.len() {
-                    row.remove(col);
-                }
-            }
+        for col in (start_col..=effective_end_col).rev() {
+            sheet.data.remove_column(col);
         }
 
         self.recalculate_max_cols();
@@ -524,6 +872,30 @@ impl Workbook {
         Ok(())
     }
 
+    // Insert rows into the current sheet starting at start_row, padding the sheet as needed
+    pub fn insert_rows(&mut self, start_row: usize, rows: &[Vec<Cell>]) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+
+        if start_row < 1 || rows.is_empty() {
+            return Ok(());
+        }
+
+        let sheet = &mut self.sheets[self.current_sheet_index];
+
+        sheet.data.expand(start_row, 1);
+        for (offset, row_data) in rows.iter().enumerate() {
+            sheet
+                .data
+                .insert_row(start_row + offset, SparseRow::from_cells(row_data.clone()));
+        }
+
+        self.recalculate_max_rows();
+        self.recalculate_max_cols();
+        self.is_modified = true;
+
+        Ok(())
+    }
+
     pub fn is_modified(&self) -> bool {
         self.is_modified
     }
@@ -536,6 +908,11 @@ impl Workbook {
         &self.file_path
     }
 
+    /// Field separator the file was opened with, `None` for the format's default
+    pub fn delimiter(&self) -> Option<u8> {
+        self.delimiter
+    }
+
     pub fn is_lazy_loading(&self) -> bool {
         self.lazy_loading
     }
@@ -548,112 +925,329 @@ impl Workbook {
         self.sheets[sheet_index].is_loaded
     }
 
-    pub fn save(&mut self) -> Result<()> {
-        if !self.is_modified {
-            println!("No changes to save.");
-            return Ok(());
+    /// Lists what would be lost by saving to the format this workbook is written as
+    pub fn save_warnings(&self) -> SheetResult<Vec<String>> {
+        let registry = format_registry(self.delimiter);
+        let format = registry.writer_for_path(Path::new(&self.file_path))?;
+        let capabilities = format.capabilities();
+        let mut warnings = Vec::new();
+
+        if !capabilities.multiple_sheets && self.sheets.len() > 1 {
+            warnings.push(format!(
+                "{} keeps only the current sheet '{}', {} other sheet(s) will be dropped",
+                format.name(),
+                self.get_current_sheet_name(),
+                self.sheets.len() - 1
+            ));
+        }
+
+        if !capabilities.formulas {
+            let formula_count: usize = self
+                .sheets
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| {
+                    capabilities.multiple_sheets || *index == self.current_sheet_index
+                })
+                .map(|(_, sheet)| {
+                    sheet
+                        .rows()
+                        .flat_map(|row| row.cells())
+                        .filter(|cell| cell.cell.is_formula)
+                        .count()
+                })
+                .sum();
+
+            if formula_count > 0 {
+                warnings.push(format!(
+                    "{} cannot store formulas, {} formula cell(s) will be saved as plain text",
+                    format.name(),
+                    formula_count
+                ));
+            }
         }
 
-        // Create a new workbook with rust_xlsxwriter
-        let mut workbook = XlsxWorkbook::new();
+        if !capabilities.styles && self.sheets.iter().any(|sheet| !sheet.styles.is_empty()) {
+            warnings.push(format!("{} cannot store cell formatting", format.name()));
+        }
+
+        let unloaded: Vec<&str> = self
+            .sheets
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.is_sheet_loaded(*index))
+            .map(|(_, sheet)| sheet.name.as_str())
+            .collect();
+
+        if !unloaded.is_empty() {
+            warnings.push(format!(
+                "Sheet(s) {} are not loaded yet and will be saved empty, use :load first",
+                unloaded.join(", ")
+            ));
+        }
+
+        Ok(warnings)
+    }
+
+    /// Starts writing a timestamped copy of the workbook on a background thread and
+    /// returns its path, `poll_save` tells when it is done. The copy is written from a
+    /// snapshot, so the workbook stays modified and can be edited meanwhile.
+    /// Column widths are keyed by sheet name and indexed by 1-based column
+    pub fn start_save(
+        &mut self,
+        column_widths: &HashMap<String, Vec<usize>>,
+    ) -> SheetResult<PathBuf> {
+        let path = self.save_path()?;
+        let snapshot = self.clone();
+        let column_widths = column_widths.clone();
+        let target = path.clone();
+        let progress = Progress::default();
+        let counter = progress.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut result = snapshot.write_copy(&target, &column_widths, &counter);
+            // A cancelled save leaves nothing behind, not even a file it finished
+            if counter.is_cancelled() {
+                let _ = fs::remove_file(&target);
+                result = Err(SheetError::Cancelled);
+            }
+            let _ = sender.send(result);
+        });
+
+        self.save = Some(BackgroundSave {
+            path: path.clone(),
+            started: Instant::now(),
+            progress,
+            receiver,
+        });
+        Ok(path)
+    }
+
+    /// The copy being written and how long it has been, if a save is running
+    #[must_use]
+    pub fn saving(&self) -> Option<(&Path, Duration)> {
+        let save = self.save.as_ref()?;
+        Some((&save.path, save.started.elapsed()))
+    }
+
+    /// Fraction of the running save written so far, `None` when it can't be told
+    #[must_use]
+    pub fn save_progress(&self) -> Option<f64> {
+        self.save.as_ref()?.progress.ratio()
+    }
+
+    /// Asks the running save to stop, `poll_save` then reports it as cancelled.
+    /// Returns false when no save is running
+    pub fn cancel_save(&self) -> bool {
+        self.save
+            .as_ref()
+            .map(|save| save.progress.cancel())
+            .is_some()
+    }
+
+    /// The path of a finished save and how long it took, or `None` while it is running
+    pub fn poll_save(&mut self) -> Option<(PathBuf, SheetResult<Duration>)> {
+        let result = match self.save.as_ref()?.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(SheetError::Io {
+                path: self.save.as_ref()?.path.clone(),
+                source: std::io::Error::other("the writer stopped unexpectedly"),
+            }),
+        };
+
+        let save = self.save.take()?;
+        let elapsed = save.started.elapsed();
+        Some((save.path, result.map(|()| elapsed)))
+    }
+
+    // Where a save made now goes, next to the file with a timestamp in its name
+    fn save_path(&self) -> SheetResult<PathBuf> {
+        let registry = format_registry(self.delimiter);
+        let path = Path::new(&self.file_path);
+        let format = registry.writer_for_path(path)?;
+
+        // Keep the original extension when the format can write it
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| ext.to_lowercase())
+            .filter(|ext| format.extensions().contains(&ext.as_str()))
+            .unwrap_or_else(|| format.extensions()[0].to_string());
 
         let now = Local::now();
         let timestamp = now.format("%Y%m%d_%H%M%S").to_string();
-        let path = Path::new(&self.file_path);
         let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("sheet");
-        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("xlsx");
         let parent_dir = path.parent().unwrap_or_else(|| Path::new(""));
         let new_filename = format!("{}_{}.{}", file_stem, timestamp, extension);
-        let new_filepath = parent_dir.join(new_filename);
+        Ok(parent_dir.join(new_filename))
+    }
 
-        // Create formats
-        let number_format = Format::new().set_num_format("General");
-        let date_format = Format::new().set_num_format("yyyy-mm-dd");
+    // Writes the workbook to `path` in the format of the file it was opened from
+    fn write_copy(
+        &self,
+        path: &Path,
+        column_widths: &HashMap<String, Vec<usize>>,
+        progress: &Progress,
+    ) -> SheetResult<()> {
+        let registry = format_registry(self.delimiter);
+        let format = registry.writer_for_path(Path::new(&self.file_path))?;
+        format.save(self, path, column_widths, progress)
+    }
 
-        // Process each sheet
-        for sheet in &self.sheets {
-            let worksheet = workbook.add_worksheet().set_name(&sheet.name)?;
+    /// Writes the whole workbook as xlsx into `dir` without marking it saved,
+    /// so unsaved work survives a forced quit. Returns the path written.
+    pub fn save_recovery_copy(
+        &self,
+        dir: &Path,
+        column_widths: &HashMap<String, Vec<usize>>,
+    ) -> SheetResult<PathBuf> {
+        fs::create_dir_all(dir).map_err(|source| SheetError::Io {
+            path: dir.to_path_buf(),
+            source,
+        })?;
+
+        let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+        let file_stem = Path::new(&self.file_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("sheet");
+        let path = dir.join(format!("{file_stem}_recovery_{timestamp}.xlsx"));
+
+        ExcelFormat::xlsx().save(self, &path, column_widths, &Progress::default())?;
+        Ok(path)
+    }
 
-            // Set column widths
-            for col in 0..sheet.max_cols {
-                worksheet.set_column_width(col as u16, 15)?;
-            }
+    /// Where autosave keeps its copy, next to the file as `<name>_autosave.xlsx`
+    #[must_use]
+    pub fn autosave_path(&self) -> PathBuf {
+        let path = Path::new(&self.file_path);
+        let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("sheet");
+        let parent_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        parent_dir.join(format!("{file_stem}_autosave.xlsx"))
+    }
 
-            // Write cell data
-            for row in 1..sheet.data.len() {
-                if row <= sheet.max_rows {
-                    for col in 1..sheet.data[0].len() {
-                        if col <= sheet.max_cols {
-                            let cell = &sheet.data[row][col];
-
-                            // Skip empty cells
-                            if cell.value.is_empty() {
-                                continue;
-                            }
-
-                            let row_idx = (row - 1) as u32;
-                            let col_idx = (col - 1) as u16;
-
-                            // Write cell based on its type
-                            match cell.cell_type {
-                                CellType::Number => {
-                                    if let Ok(num) = cell.value.parse::<f64>() {
-                                        worksheet.write_number_with_format(
-                                            row_idx,
-                                            col_idx,
-                                            num,
-                                            &number_format,
-                                        )?;
-                                    } else {
-                                        worksheet.write_string(row_idx, col_idx, &cell.value)?;
-                                    }
-                                }
-                                CellType::Date => {
-                                    worksheet.write_string_with_format(
-                                        row_idx,
-                                        col_idx,
-                                        &cell.value,
-                                        &date_format,
-                                    )?;
-                                }
-                                CellType::Boolean => {
-                                    if let Ok(b) = cell.value.parse::<bool>() {
-                                        worksheet.write_boolean(row_idx, col_idx, b)?;
-                                    } else {
-                                        worksheet.write_string(row_idx, col_idx, &cell.value)?;
-                                    }
-                                }
-                                CellType::Text => {
-                                    if cell.is_formula {
-                                        let formula = rust_xlsxwriter::Formula::new(&cell.value);
-                                        worksheet.write_formula(row_idx, col_idx, formula)?;
-                                    } else {
-                                        worksheet.write_string(row_idx, col_idx, &cell.value)?;
-                                    }
-                                }
-                                CellType::Empty => {}
-                            }
-                        }
-                    }
-                }
+    /// Writes the whole workbook as xlsx to `autosave_path` without marking it saved.
+    /// The copy is written to a temporary file first and renamed over the previous one,
+    /// so an interrupted write never leaves a broken autosave behind.
+    pub fn save_autosave_copy(
+        &self,
+        column_widths: &HashMap<String, Vec<usize>>,
+    ) -> SheetResult<PathBuf> {
+        let path = self.autosave_path();
+        let mut partial = path.clone().into_os_string();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+
+        ExcelFormat::xlsx().save(self, &partial, column_widths, &Progress::default())?;
+        fs::rename(&partial, &path).map_err(|source| SheetError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(path)
+    }
+
+    pub fn insert_sheet_at_index(&mut self, sheet: Sheet, index: usize) -> SheetResult<()> {
+        if index > self.sheets.len() {
+            return Err(SheetError::OutOfRange {
+                what: "Sheet",
+                index,
+                max: self.sheets.len(),
+            });
+        }
+        self.sheets.insert(index, sheet);
+        self.is_modified = true;
+
+        // Keep pointing at the same sheet when one is inserted before it
+        if index <= self.current_sheet_index && self.sheets.len() > 1 {
+            self.current_sheet_index += 1;
+        }
+        Ok(())
+    }
+
+    /// Moves the sheet at `from` to `to`, the current sheet stays the current one
+    pub fn move_sheet(&mut self, from: usize, to: usize) -> SheetResult<()> {
+        let max = self.sheets.len().saturating_sub(1);
+        for index in [from, to] {
+            if index > max {
+                return Err(SheetError::OutOfRange {
+                    what: "Sheet",
+                    index,
+                    max,
+                });
             }
         }
 
-        workbook.save(&new_filepath)?;
-        self.is_modified = false;
+        let current_name = self.get_current_sheet_name();
+        let sheet = self.sheets.remove(from);
+        self.sheets.insert(to, sheet);
+        self.current_sheet_index = self
+            .sheets
+            .iter()
+            .position(|sheet| sheet.name == current_name)
+            .unwrap_or(to);
+        self.is_modified = true;
+        Ok(())
+    }
+
+    /// Inserts a new sheet at `index`, checking its name against Excel's rules
+    pub fn add_sheet(&mut self, sheet: Sheet, index: usize) -> SheetResult<()> {
+        self.check_sheet_name(&sheet.name, None)?;
+        self.insert_sheet_at_index(sheet, index)
+    }
 
+    /// Renames the current sheet, checking the new name against Excel's rules. A lazily
+    /// loaded sheet is read by its name, so it has to be loaded first
+    pub fn rename_current_sheet(&mut self, name: &str) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+        self.check_sheet_name(name, Some(self.current_sheet_index))?;
+        self.sheets[self.current_sheet_index].name = name.to_string();
+        self.is_modified = true;
         Ok(())
     }
 
-    pub fn insert_sheet_at_index(&mut self, sheet: Sheet, index: usize) -> Result<()> {
-        if index > self.sheets.len() {
-            anyhow::bail!(
-                "Cannot insert sheet at index {}: index out of bounds (max index: {})",
-                index,
-                self.sheets.len()
-            );
+    // Excel refuses empty, long and duplicate names (ignoring case) and a few characters,
+    // the sheet at `renamed` may keep its own name
+    fn check_sheet_name(&self, name: &str, renamed: Option<usize>) -> SheetResult<()> {
+        let invalid = |reason| SheetError::InvalidSheetName {
+            name: name.to_string(),
+            reason,
+        };
+
+        if name.trim().is_empty() {
+            return Err(invalid("the name is empty"));
         }
-        self.sheets.insert(index, sheet);
+        if name.chars().count() > 31 {
+            return Err(invalid("names are limited to 31 characters"));
+        }
+        if name.contains(['[', ']', ':', '*', '?', '/', '\\']) {
+            return Err(invalid("names cannot contain [ ] : * ? / \\"));
+        }
+        if self
+            .sheets
+            .iter()
+            .enumerate()
+            .any(|(index, sheet)| Some(index) != renamed && sheet.name.eq_ignore_ascii_case(name))
+        {
+            return Err(invalid("a sheet with this name already exists"));
+        }
+        Ok(())
+    }
+
+    /// Moves column `order[i]` to position i + 1, `order` lists each of the first
+    /// `order.len()` columns once
+    pub fn reorder_columns(&mut self, order: &[usize]) -> SheetResult<()> {
+        self.ensure_current_sheet_editable()?;
+
+        let sheet = &mut self.sheets[self.current_sheet_index];
+        sheet.data.expand(0, order.len() + 1);
+        for row in sheet.data.rows_mut() {
+            let moved: Vec<Cell> = order.iter().map(|&col| row[col].clone()).collect();
+            for (index, cell) in moved.into_iter().enumerate() {
+                row.set(index + 1, cell);
+            }
+        }
+
         self.is_modified = true;
         Ok(())
     }
@@ -664,16 +1258,8 @@ impl Workbook {
         // Find maximum non-empty column across all rows
         let actual_max_col = sheet
             .data
-            .iter()
-            .map(|row| {
-                // Find last non-empty cell in this row
-                row.iter()
-                    .enumerate()
-                    .rev()
-                    .find(|(_, cell)| !cell.value.is_empty())
-                    .map(|(idx, _)| idx)
-                    .unwrap_or(0)
-            })
+            .rows()
+            .map(|row| row.last_filled_col().unwrap_or(0))
             .max()
             .unwrap_or(0);
 
@@ -686,10 +1272,10 @@ impl Workbook {
         // Find last row with any non-empty cells
         let actual_max_row = sheet
             .data
-            .iter()
+            .rows()
             .enumerate()
             .rev()
-            .find(|(_, row)| row.iter().any(|cell| !cell.value.is_empty()))
+            .find(|(_, row)| row.has_values())
             .map(|(idx, _)| idx)
             .unwrap_or(0);
 
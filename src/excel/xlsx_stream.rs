@@ -0,0 +1,362 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use calamine::{Data, Dimensions, Reader, Xlsx};
+
+use crate::excel::workbook::cell_from_data;
+use crate::excel::{RowScan, RowStream, SheetError, SheetResult};
+
+// Rows read together into the cache
+const BLOCK_ROWS: usize = 1024;
+
+// Blocks of BLOCK_ROWS rows kept in memory, the least recently used goes first
+const CACHED_BLOCKS: usize = 8;
+
+// Rows a reader parses ahead of the one asked for, so scrolling on finds them ready
+const READ_AHEAD: usize = BLOCK_ROWS;
+
+type RowMessage = SheetResult<(usize, Vec<String>)>;
+
+fn open_xlsx(path: &Path) -> SheetResult<Xlsx<BufReader<File>>> {
+    let file = File::open(path).map_err(|source| SheetError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Xlsx::new(BufReader::new(file)).map_err(|e| SheetError::parse(path, None, e))
+}
+
+// Size of the sheet as recorded in the file, without reading its cells
+fn sheet_dimensions(path: &Path, sheet_name: &str) -> SheetResult<Dimensions> {
+    let mut xlsx = open_xlsx(path)?;
+    let reader = xlsx
+        .worksheet_cells_reader(sheet_name)
+        .map_err(|e| SheetError::parse(path, Some(sheet_name), e))?;
+    Ok(reader.dimensions())
+}
+
+// Reads the rows of a sheet on a background thread and sends them from the 1-based
+// `first_row` on, rows without cells as empty ones. The thread stops once the
+// receiver is dropped and runs at most READ_AHEAD rows ahead of it
+fn spawn_row_reader(path: PathBuf, sheet_name: String, first_row: usize) -> Receiver<RowMessage> {
+    let (sender, receiver) = mpsc::sync_channel(READ_AHEAD);
+    thread::spawn(move || {
+        if let Err(e) = read_rows(&path, &sheet_name, first_row, &sender) {
+            let _ = sender.send(Err(e));
+        }
+    });
+    receiver
+}
+
+fn read_rows(
+    path: &Path,
+    sheet_name: &str,
+    first_row: usize,
+    sender: &SyncSender<RowMessage>,
+) -> SheetResult<()> {
+    let mut xlsx = open_xlsx(path)?;
+    let mut reader = xlsx
+        .worksheet_cells_reader(sheet_name)
+        .map_err(|e| SheetError::parse(path, Some(sheet_name), e))?;
+
+    // False once the receiver is gone
+    let send =
+        |row: usize, cells: Vec<String>| row < first_row || sender.send(Ok((row, cells))).is_ok();
+
+    // Cells come row by row, collected until the next row starts
+    let mut row_number = 1;
+    let mut cells: Vec<String> = Vec::new();
+    while let Some(cell) = reader
+        .next_cell()
+        .map_err(|e| SheetError::parse(path, Some(sheet_name), e))?
+    {
+        let (row, col) = cell.get_position();
+        let (row, col) = (row as usize + 1, col as usize + 1);
+        while row_number < row {
+            if !send(row_number, std::mem::take(&mut cells)) {
+                return Ok(());
+            }
+            row_number += 1;
+        }
+
+        let value = cell_from_data(&Data::from(cell.get_value().clone())).value;
+        if cells.len() < col {
+            cells.resize(col, String::new());
+        }
+        cells[col - 1] = value;
+    }
+    send(row_number, cells);
+    Ok(())
+}
+
+/// A sheet of an XLSX file read from disk on demand instead of loaded into a `Sheet`.
+/// Rows are parsed on a background thread and only a few blocks of them are kept in
+/// memory, so sheets with hundreds of thousands of rows open at once and scroll without
+/// being loaded in full. Moving back before the cached blocks reads the sheet again.
+/// A block not cached yet is filled by `read_for` between key presses, `row` doesn't wait
+pub struct XlsxStream {
+    path: PathBuf,
+    sheet_names: Vec<String>,
+    sheet_index: usize,
+    rows: usize,
+    max_cols: usize,
+    indexer: Option<Receiver<RowMessage>>, // Counts the rows of sheets that don't record their size
+    reader: Option<(usize, Receiver<RowMessage>)>, // Next row the reader sends
+    filling: Option<(usize, Vec<Vec<String>>)>, // Block being read and its rows so far
+    cache: HashMap<usize, Vec<Vec<String>>>,
+    cache_order: VecDeque<usize>,
+}
+
+/// Scans the rows of an `XlsxStream` with a reader of its own
+pub struct XlsxRowScanner {
+    receiver: Receiver<RowMessage>,
+    next_row: usize,
+}
+
+impl RowScan for XlsxRowScanner {
+    fn next_row_number(&self) -> usize {
+        self.next_row
+    }
+
+    fn next_row(&mut self) -> SheetResult<Option<(usize, Vec<String>)>> {
+        match self.receiver.recv() {
+            Ok(Ok((row, cells))) => {
+                self.next_row = row + 1;
+                Ok(Some((row, cells)))
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+impl XlsxStream {
+    /// Opens the first sheet, reading only the size it records
+    pub fn open(path: &Path) -> SheetResult<Self> {
+        let sheet_names = open_xlsx(path)?.sheet_names();
+        if sheet_names.is_empty() {
+            return Err(SheetError::parse(path, None, "the workbook has no sheets"));
+        }
+
+        let mut stream = Self {
+            path: path.to_path_buf(),
+            sheet_names,
+            sheet_index: 0,
+            rows: 0,
+            max_cols: 0,
+            indexer: None,
+            reader: None,
+            filling: None,
+            cache: HashMap::new(),
+            cache_order: VecDeque::new(),
+        };
+        stream.open_sheet(0)?;
+        Ok(stream)
+    }
+
+    fn open_sheet(&mut self, sheet_index: usize) -> SheetResult<()> {
+        let sheet_name = self.sheet_names[sheet_index].clone();
+        let dimensions = sheet_dimensions(&self.path, &sheet_name)?;
+
+        self.sheet_index = sheet_index;
+        self.reader = None;
+        self.filling = None;
+        self.cache.clear();
+        self.cache_order.clear();
+
+        // Without a recorded size the rows are counted by reading the sheet once
+        if dimensions == Dimensions::default() {
+            self.rows = 0;
+            self.max_cols = 0;
+            self.indexer = Some(spawn_row_reader(self.path.clone(), sheet_name, 1));
+        } else {
+            self.rows = dimensions.end.0 as usize + 1;
+            self.max_cols = dimensions.end.1 as usize + 1;
+            self.indexer = None;
+        }
+        Ok(())
+    }
+
+    fn sheet(&self) -> &str {
+        &self.sheet_names[self.sheet_index]
+    }
+
+    // Starts reading a block, continuing where the reader is when it hasn't passed the
+    // block yet
+    fn start_block(&mut self, block: usize) {
+        let first_row = block * BLOCK_ROWS + 1;
+        let reuse = matches!(&self.reader, Some((next_row, _)) if *next_row <= first_row);
+        if !reuse {
+            let receiver = spawn_row_reader(self.path.clone(), self.sheet().to_string(), first_row);
+            self.reader = Some((first_row, receiver));
+        }
+        self.filling = Some((block, Vec::new()));
+    }
+
+    // Moves the block being filled to the cache
+    fn finish_block(&mut self) {
+        let Some((block, mut rows)) = self.filling.take() else {
+            return;
+        };
+        let first_row = block * BLOCK_ROWS + 1;
+        let last_row = (first_row + BLOCK_ROWS - 1).min(self.rows);
+        rows.resize(last_row + 1 - first_row, Vec::new());
+
+        if self.cache.len() >= CACHED_BLOCKS {
+            if let Some(oldest) = self.cache_order.pop_front() {
+                self.cache.remove(&oldest);
+            }
+        }
+        self.cache.insert(block, rows);
+    }
+}
+
+impl RowStream for XlsxStream {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn sheet_name(&self) -> Option<&str> {
+        Some(self.sheet())
+    }
+
+    fn switch_sheet(&mut self, forward: bool) -> SheetResult<bool> {
+        let sheet_index = if forward {
+            self.sheet_index + 1
+        } else {
+            match self.sheet_index.checked_sub(1) {
+                Some(index) => index,
+                None => return Ok(false),
+            }
+        };
+        if sheet_index >= self.sheet_names.len() {
+            return Ok(false);
+        }
+
+        self.open_sheet(sheet_index)?;
+        Ok(true)
+    }
+
+    fn rows(&self) -> usize {
+        self.rows
+    }
+
+    fn max_cols(&self) -> usize {
+        self.max_cols
+    }
+
+    fn is_indexed(&self) -> bool {
+        self.indexer.is_none()
+    }
+
+    // Counted rows don't tell how much of the sheet is left
+    fn index_progress(&self) -> Option<f64> {
+        self.is_indexed().then_some(1.0)
+    }
+
+    fn index_for(&mut self, budget: Duration) -> SheetResult<()> {
+        let Some(indexer) = &self.indexer else {
+            return Ok(());
+        };
+
+        let started = Instant::now();
+        while started.elapsed() < budget {
+            match indexer.try_recv() {
+                Ok(Ok((row, cells))) => {
+                    self.rows = row;
+                    self.max_cols = self.max_cols.max(cells.len());
+                }
+                Ok(Err(e)) => {
+                    self.indexer = None;
+                    return Err(e);
+                }
+                Err(TryRecvError::Empty) => thread::sleep(Duration::from_millis(1)),
+                Err(TryRecvError::Disconnected) => {
+                    self.indexer = None;
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_reading(&self) -> bool {
+        self.filling.is_some()
+    }
+
+    fn read_for(&mut self, budget: Duration) -> SheetResult<()> {
+        let Some((block, rows)) = &mut self.filling else {
+            return Ok(());
+        };
+        let first_row = *block * BLOCK_ROWS + 1;
+        let last_row = (first_row + BLOCK_ROWS - 1).min(self.rows);
+
+        let started = Instant::now();
+        while let Some((next_row, receiver)) = &mut self.reader {
+            if *next_row > last_row {
+                break;
+            }
+            if started.elapsed() >= budget {
+                return Ok(());
+            }
+            match receiver.try_recv() {
+                Ok(Ok((row, cells))) => {
+                    *next_row = row + 1;
+                    if row >= first_row {
+                        self.max_cols = self.max_cols.max(cells.len());
+                        rows.push(cells);
+                    }
+                }
+                Ok(Err(e)) => {
+                    self.reader = None;
+                    self.filling = None;
+                    return Err(e);
+                }
+                Err(TryRecvError::Empty) => thread::sleep(Duration::from_millis(1)),
+                // No cells below this point, the rest of the sheet is empty
+                Err(TryRecvError::Disconnected) => self.reader = None,
+            }
+        }
+        self.finish_block();
+        Ok(())
+    }
+
+    fn scan_from(&self, row: usize) -> SheetResult<Box<dyn RowScan>> {
+        let row = row.max(1);
+        Ok(Box::new(XlsxRowScanner {
+            receiver: spawn_row_reader(self.path.clone(), self.sheet().to_string(), row),
+            next_row: row,
+        }))
+    }
+
+    fn row(&mut self, row: usize) -> SheetResult<Option<&[String]>> {
+        if row == 0 || row > self.rows {
+            return Ok(None);
+        }
+
+        // A block not read yet is asked for and shown once read_for has filled it
+        let block = (row - 1) / BLOCK_ROWS;
+        if !self.cache.contains_key(&block) {
+            if !matches!(self.filling, Some((filling, _)) if filling == block) {
+                self.start_block(block);
+            }
+            return Ok(None);
+        }
+
+        // Most recently used block goes to the back of the eviction queue
+        if let Some(index) = self.cache_order.iter().position(|&cached| cached == block) {
+            self.cache_order.remove(index);
+        }
+        self.cache_order.push_back(block);
+
+        Ok(self
+            .cache
+            .get(&block)
+            .and_then(|rows| rows.get((row - 1) % BLOCK_ROWS))
+            .map(Vec::as_slice))
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+use crate::excel::{CellStyle, Sheet};
+
+// Number formats from 164 on are the workbook's own, listed in styles.xml
+const FIRST_CUSTOM_FORMAT: u32 = 164;
+
+type Archive = ZipArchive<BufReader<File>>;
+
+// The value of an attribute by its name without namespace prefix, xlsx parts are UTF-8
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    let attribute = element
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.local_name().as_ref() == name)?;
+    let value = String::from_utf8_lossy(&attribute.value);
+    unescape(&value).ok().map(|value| value.into_owned())
+}
+
+// Calls `visit` with every start or empty element of a file in the archive, and
+// whether it is still open, until it returns false
+fn visit_elements(
+    archive: &mut Archive,
+    name: &str,
+    mut visit: impl FnMut(&BytesStart, bool) -> bool,
+) -> Option<()> {
+    let file = archive.by_name(name).ok()?;
+    let mut reader = Reader::from_reader(BufReader::new(file));
+    let mut buf = Vec::new();
+    loop {
+        let keep_going = match reader.read_event_into(&mut buf).ok()? {
+            Event::Start(element) => visit(&element, true),
+            Event::Empty(element) => visit(&element, false),
+            Event::Eof => return Some(()),
+            _ => true,
+        };
+        if !keep_going {
+            return Some(());
+        }
+        buf.clear();
+    }
+}
+
+// The path in the archive of the worksheet with the given name
+fn worksheet_path(archive: &mut Archive, sheet_name: &str) -> Option<String> {
+    let mut id = None;
+    visit_elements(archive, "xl/workbook.xml", |element, _| {
+        if element.local_name().as_ref() == b"sheet"
+            && attribute(element, b"name").as_deref() == Some(sheet_name)
+        {
+            id = attribute(element, b"id");
+        }
+        id.is_none()
+    })?;
+    let id = id?;
+
+    let mut target = None;
+    visit_elements(archive, "xl/_rels/workbook.xml.rels", |element, _| {
+        if element.local_name().as_ref() == b"Relationship"
+            && attribute(element, b"Id").as_deref() == Some(id.as_str())
+        {
+            target = attribute(element, b"Target");
+        }
+        target.is_none()
+    })?;
+    // Targets are relative to xl/, or absolute from the root of the archive
+    target.map(|target| match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("xl/{target}"),
+    })
+}
+
+// The number format of each cell style of styles.xml by index, `None` for General
+fn cell_formats(archive: &mut Archive) -> Option<Vec<Option<CellStyle>>> {
+    let mut codes = HashMap::new();
+    let mut formats = Vec::new();
+    // <xf> also lists the named styles in <cellStyleXfs>, only those of <cellXfs> count
+    let mut in_cell_xfs = false;
+    visit_elements(archive, "xl/styles.xml", |element, open| {
+        match element.local_name().as_ref() {
+            b"numFmt" => {
+                let id = attribute(element, b"numFmtId").and_then(|id| id.parse::<u32>().ok());
+                if let (Some(id), Some(code)) = (id, attribute(element, b"formatCode")) {
+                    codes.insert(id, code);
+                }
+            }
+            b"cellXfs" => in_cell_xfs = open,
+            b"cellStyleXfs" | b"cellStyles" => in_cell_xfs = false,
+            b"xf" if in_cell_xfs => {
+                let id = attribute(element, b"numFmtId")
+                    .and_then(|id| id.parse::<u32>().ok())
+                    .unwrap_or(0);
+                formats.push((id != 0).then_some(id));
+            }
+            _ => {}
+        }
+        true
+    })?;
+
+    let styles = formats
+        .into_iter()
+        .map(|id| {
+            let id = id?;
+            Some(match u8::try_from(id) {
+                Ok(builtin) if id < FIRST_CUSTOM_FORMAT => CellStyle {
+                    number_format: Some(builtin),
+                    ..CellStyle::default()
+                },
+                _ => CellStyle {
+                    number_format_code: Some(codes.get(&id)?.clone()),
+                    ..CellStyle::default()
+                },
+            })
+        })
+        .collect();
+    Some(styles)
+}
+
+// A cell reference such as AB12 as 0-based row and column
+fn parse_reference(reference: &str) -> Option<(u32, u32)> {
+    let digits = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, number) = reference.split_at(digits);
+    let col = letters.bytes().try_fold(0u32, |col, letter| {
+        letter
+            .is_ascii_uppercase()
+            .then(|| col * 26 + u32::from(letter - b'A') + 1)
+    })?;
+    let row = number.parse::<u32>().ok()?;
+    Some((row.checked_sub(1)?, col.checked_sub(1)?))
+}
+
+/// Gives the cells of a sheet read from an xlsx file the number formats they have
+/// there, such as `0.0%` or `dd/mm/yyyy`, since calamine only reads the values.
+/// `start` is the 0-based position in the file of the sheet's first cell. Files that
+/// aren't xlsx or can't be read are left as they are, the formats only change how
+/// values look
+pub(crate) fn apply_number_formats(path: &Path, sheet: &mut Sheet, start: (u32, u32)) {
+    let Ok(file) = File::open(path) else {
+        return;
+    };
+    let Ok(mut archive) = ZipArchive::new(BufReader::new(file)) else {
+        return;
+    };
+    let Some(formats) = cell_formats(&mut archive) else {
+        return;
+    };
+    if formats.iter().all(Option::is_none) {
+        return;
+    }
+    let Some(worksheet) = worksheet_path(&mut archive, &sheet.name) else {
+        return;
+    };
+
+    let mut ids = HashMap::new();
+    let (mut row, mut col) = (0, 0);
+    visit_elements(&mut archive, &worksheet, |element, _| {
+        match element.local_name().as_ref() {
+            // Rows and cells may leave out where they are, they follow the one before
+            b"row" => {
+                row = attribute(element, b"r")
+                    .and_then(|r| r.parse::<u32>().ok())
+                    .unwrap_or(row + 1);
+                col = 0;
+            }
+            b"c" => {
+                let position = attribute(element, b"r")
+                    .and_then(|reference| parse_reference(&reference))
+                    .unwrap_or((row.saturating_sub(1), col));
+                col = position.1 + 1;
+                let style = attribute(element, b"s").and_then(|s| s.parse::<usize>().ok());
+                let Some(style) = style.and_then(|s| formats.get(s)?.as_ref()) else {
+                    return true;
+                };
+                let (Some(row), Some(col)) = (
+                    position.0.checked_sub(start.0),
+                    position.1.checked_sub(start.1),
+                ) else {
+                    return true;
+                };
+                let (row, col) = (row as usize + 1, col as usize + 1);
+                // Empty cells keep no style, they are made when something is typed in
+                if sheet
+                    .data
+                    .get(row, col)
+                    .is_some_and(|cell| !cell.value.is_empty())
+                {
+                    let id = *ids
+                        .entry(style)
+                        .or_insert_with(|| sheet.styles.intern(style.clone()));
+                    if let Some(cell) = sheet.data.get_mut(row, col) {
+                        cell.style = id;
+                    }
+                }
+            }
+            _ => {}
+        }
+        true
+    });
+}
@@ -0,0 +1,111 @@
+use serde::Serialize;
+use std::fmt::Write;
+
+use crate::excel::{CellType, Sheet, Workbook};
+
+/// Statistics of a workbook printed by `excel-cli inspect`
+#[derive(Serialize)]
+pub struct WorkbookReport {
+    pub file: String,
+    pub sheets: Vec<SheetReport>,
+}
+
+#[derive(Serialize)]
+pub struct SheetReport {
+    pub name: String,
+    pub rows: usize,
+    pub cols: usize,
+    pub cells: usize,
+    pub cell_types: CellTypeCounts,
+    pub formulas: usize,
+    /// Share of empty cells in the used range, 0 for an empty sheet
+    pub empty_ratio: f64,
+}
+
+/// How many cells of the used range hold each type of value
+#[derive(Serialize, Default)]
+pub struct CellTypeCounts {
+    pub text: usize,
+    pub number: usize,
+    pub date: usize,
+    pub boolean: usize,
+    pub empty: usize,
+}
+
+fn inspect_sheet(sheet: &Sheet) -> SheetReport {
+    let mut cell_types = CellTypeCounts::default();
+    let mut formulas = 0;
+
+    for cell in sheet.rows().flat_map(|row| row.cells()) {
+        if cell.cell.is_formula {
+            formulas += 1;
+        }
+
+        let count = if cell.is_empty() {
+            &mut cell_types.empty
+        } else {
+            match cell.cell.cell_type {
+                CellType::Number => &mut cell_types.number,
+                CellType::Date => &mut cell_types.date,
+                CellType::Boolean => &mut cell_types.boolean,
+                CellType::Text | CellType::Empty => &mut cell_types.text,
+            }
+        };
+        *count += 1;
+    }
+
+    let cells = sheet.max_rows * sheet.max_cols;
+    let empty_ratio = if cells == 0 {
+        0.0
+    } else {
+        cell_types.empty as f64 / cells as f64
+    };
+
+    SheetReport {
+        name: sheet.name.clone(),
+        rows: sheet.max_rows,
+        cols: sheet.max_cols,
+        cells,
+        cell_types,
+        formulas,
+        empty_ratio,
+    }
+}
+
+/// Collects the statistics of every sheet, the workbook must be fully loaded
+#[must_use]
+pub fn inspect_workbook(workbook: &Workbook, file: &str) -> WorkbookReport {
+    WorkbookReport {
+        file: file.to_string(),
+        sheets: workbook.sheets().map(inspect_sheet).collect(),
+    }
+}
+
+/// The report as plain text, one block per sheet
+#[must_use]
+pub fn format_report(report: &WorkbookReport) -> String {
+    let mut text = format!("{}: {} sheet(s)\n", report.file, report.sheets.len());
+
+    for sheet in &report.sheets {
+        let types = &sheet.cell_types;
+        let _ = write!(
+            text,
+            "\n{}\n  size: {} rows x {} columns ({} cells)\n  \
+             text: {}, number: {}, date: {}, boolean: {}, empty: {}\n  \
+             formulas: {}\n  empty ratio: {:.1}%\n",
+            sheet.name,
+            sheet.rows,
+            sheet.cols,
+            sheet.cells,
+            types.text,
+            types.number,
+            types.date,
+            types.boolean,
+            types.empty,
+            sheet.formulas,
+            sheet.empty_ratio * 100.0
+        );
+    }
+
+    text
+}
@@ -55,9 +55,7 @@ pub fn process_sheet_for_json(
                 let mut row_data = IndexMap::with_capacity(ordered_headers.len());
 
                 for (col_idx, header) in &ordered_headers {
-                    if row_idx < sheet.data.len() && *col_idx < sheet.data[row_idx].len() {
-                        let cell = &sheet.data[row_idx][*col_idx];
-
+                    if let Some(cell) = sheet.data.get(row_idx, *col_idx) {
                         if !header.is_empty() {
                             let json_value = process_cell_value(cell);
                             row_data.insert((*header).clone(), json_value);
@@ -73,13 +71,13 @@ pub fn process_sheet_for_json(
             Ok(sheet_data)
         }
         HeaderDirection::Vertical => {
-            if header_count == 0 || header_count >= sheet.data[0].len() {
+            if header_count == 0 || header_count >= sheet.data.width() {
                 anyhow::bail!("Invalid header columns: {}", header_count);
             }
 
             let headers = extract_vertical_headers(sheet, header_count)?;
 
-            let col_count = sheet.data[0].len().saturating_sub(header_count + 1);
+            let col_count = sheet.data.width().saturating_sub(header_count + 1);
             let mut sheet_data = Vec::with_capacity(col_count);
 
             let mut ordered_headers: Vec<(usize, &String)> = headers
@@ -89,13 +87,11 @@ pub fn process_sheet_for_json(
             ordered_headers.sort_by_key(|(row_idx, _)| *row_idx);
 
             // Process each data column
-            for col_idx in (header_count + 1)..sheet.data[0].len() {
+            for col_idx in (header_count + 1)..sheet.data.width() {
                 let mut obj = IndexMap::with_capacity(ordered_headers.len());
 
                 for (row_idx, header) in &ordered_headers {
-                    if *row_idx < sheet.data.len() && col_idx < sheet.data[*row_idx].len() {
-                        let cell = &sheet.data[*row_idx][col_idx];
-
+                    if let Some(cell) = sheet.data.get(*row_idx, col_idx) {
                         if !header.is_empty() {
                             let json_value = process_cell_value(cell);
                             obj.insert((*header).clone(), json_value);
@@ -129,24 +125,12 @@ pub fn generate_all_sheets_json(
     direction: HeaderDirection,
     header_count: usize,
 ) -> Result<IndexMap<String, OrderedSheetData>> {
-    let sheet_names = workbook.get_sheet_names();
-
-    let mut all_sheets = IndexMap::with_capacity(sheet_names.len());
-
-    let current_sheet_index = workbook.get_current_sheet_index();
+    let mut all_sheets = IndexMap::with_capacity(workbook.get_sheet_names().len());
 
     // Process each sheet
-    for (index, sheet_name) in sheet_names.iter().enumerate() {
-        let sheet_data = if index == current_sheet_index {
-            process_sheet_for_json(workbook.get_current_sheet(), direction, header_count)?
-        } else {
-            // Need to switch sheets - create a clone and process
-            let mut wb_clone = workbook.clone();
-            wb_clone.switch_sheet(index)?;
-            process_sheet_for_json(wb_clone.get_current_sheet(), direction, header_count)?
-        };
-
-        all_sheets.insert(sheet_name.clone(), sheet_data);
+    for sheet in workbook.sheets() {
+        let sheet_data = process_sheet_for_json(sheet, direction, header_count)?;
+        all_sheets.insert(sheet.name.clone(), sheet_data);
     }
 
     Ok(all_sheets)
@@ -20,4 +20,15 @@ impl FromStr for HeaderDirection {
     }
 }
 
+impl HeaderDirection {
+    /// The short name taken by `:ej` and stored in import profiles
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HeaderDirection::Horizontal => "h",
+            HeaderDirection::Vertical => "v",
+        }
+    }
+}
+
 pub type OrderedSheetData = Vec<IndexMap<String, Value>>;
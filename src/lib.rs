@@ -2,6 +2,7 @@ pub mod actions;
 pub mod app;
 pub mod commands;
 pub mod excel;
+pub mod inspect;
 pub mod json_export;
 pub mod ui;
 pub mod utils;
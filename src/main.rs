@@ -1,59 +1,324 @@
-use anyhow::Result;
-use clap::Parser;
-use std::io::IsTerminal;
-use std::path::PathBuf;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use excel_cli::app;
 use excel_cli::excel;
+use excel_cli::inspect;
 use excel_cli::json_export;
 use excel_cli::ui;
+use excel_cli::utils;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Excel file path
     #[arg(required = true)]
-    file_path: PathBuf,
+    file_path: Option<PathBuf>,
+
+    /// More files to open as buffers, :bn and :bp switch between them
+    #[arg(conflicts_with_all = ["json_export", "stream"])]
+    more_files: Vec<PathBuf>,
 
     /// Export all sheets to JSON and output to stdout (for piping)
     #[arg(long, short = 'j')]
     json_export: bool,
 
-    /// Header direction for JSON export: 'h' for horizontal (top rows), 'v' for vertical (left columns)
-    #[arg(long, short = 'd', default_value = "h")]
-    direction: String,
+    /// Header direction for JSON export: 'h' for horizontal (top rows), 'v' for vertical (left columns) [default: h]
+    #[arg(long, short = 'd')]
+    direction: Option<String>,
 
-    /// Number of header rows (for horizontal) or columns (for vertical) in JSON export
-    #[arg(long, short = 'r', default_value = "1")]
-    header_count: usize,
+    /// Number of header rows (for horizontal) or columns (for vertical) in JSON export [default: 1]
+    #[arg(long, short = 'r')]
+    header_count: Option<usize>,
 
     /// Enable lazy loading for large Excel files
     #[arg(long, short = 'l')]
     lazy_loading: bool,
+
+    /// View a large CSV, TSV or XLSX file read-only, reading rows from disk instead of loading the whole file
+    #[arg(long, short = 's', conflicts_with = "json_export")]
+    stream: bool,
+
+    /// Use the named profile (delimiter, header rows, column transforms) instead of the one matching the file name
+    #[arg(long, short = 'p')]
+    profile: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the sheets, their size, cell types and share of empty cells without starting the UI
+    Inspect {
+        /// Spreadsheet file path
+        file_path: PathBuf,
+
+        /// Print the statistics as JSON, for scripts and CI checks
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the cells of a sheet or a range to stdout without starting the UI
+    Cat {
+        /// Spreadsheet file path
+        file_path: PathBuf,
+
+        /// Sheet name or 1-based number [default: the first sheet]
+        #[arg(long, short = 's')]
+        sheet: Option<String>,
+
+        /// Cells to print, e.g. A1:D100 [default: all cells in use]
+        #[arg(long, short = 'r')]
+        range: Option<String>,
+
+        /// Output format: csv, tsv or md (a Markdown table with the first row as header)
+        #[arg(long, short = 'f', default_value = "csv")]
+        format: String,
+
+        /// Print the header row first when the range starts below it, as :yank --with-header
+        /// does with no frozen rows
+        #[arg(long)]
+        with_header: bool,
+    },
+    /// Open NEW_FILE with the cells that differ from OLD_FILE highlighted, ]c and [c jump between them
+    Diff {
+        /// The earlier version of the workbook
+        old_file: PathBuf,
+
+        /// The workbook to open and compare with OLD_FILE
+        new_file: PathBuf,
+    },
+}
+
+fn inspect_file(file_path: &Path, json: bool) -> Result<()> {
+    let workbook = excel::open_workbook(file_path, false)?;
+    let report = inspect::inspect_workbook(&workbook, &file_path.to_string_lossy());
+
+    if json {
+        println!("{}", json_export::serialize_to_json(&report)?);
+    } else {
+        print!("{}", inspect::format_report(&report));
+    }
+    Ok(())
+}
+
+fn cat_range(
+    file_path: &Path,
+    sheet: Option<&str>,
+    range: Option<&str>,
+    format: &str,
+    with_header: bool,
+) -> Result<()> {
+    let Some(format) = app::TextFormat::parse(format) else {
+        anyhow::bail!("Unknown format '{format}', use csv, tsv or md")
+    };
+
+    // Only the sheet asked for is read
+    let mut workbook = excel::open_workbook(file_path, true)?;
+    let names = workbook.get_sheet_names();
+    let index = match sheet {
+        None => 0,
+        Some(sheet) => names
+            .iter()
+            .position(|name| name == sheet)
+            .or_else(|| {
+                let number = sheet.parse::<usize>().ok()?;
+                (1..=names.len()).contains(&number).then(|| number - 1)
+            })
+            .with_context(|| format!("Sheet '{sheet}' not found. Sheets: {}", names.join(", ")))?,
+    };
+    workbook.ensure_sheet_loaded(index, &names[index])?;
+    let sheet = workbook
+        .get_sheet_by_index(index)
+        .context("The workbook has no sheets")?;
+
+    let ((top, left), (bottom, right)) = match range {
+        Some(range) => {
+            let cells = utils::parse_cell_range(range)
+                .with_context(|| format!("Invalid range '{range}', expected e.g. A1:D100"))?;
+            let (bottom, right) = cells.1;
+            if bottom > utils::MAX_ROWS || right > utils::MAX_COLS {
+                anyhow::bail!(
+                    "Range '{range}' goes beyond XFD1048576, sheets have {} rows and {} columns",
+                    utils::MAX_ROWS,
+                    utils::MAX_COLS
+                );
+            }
+            cells
+        }
+        None => ((1, 1), (utils::MAX_ROWS, utils::MAX_COLS)),
+    };
+    // Only the cells the sheet has are written
+    let bottom = bottom.min(sheet.max_rows.max(1));
+    let right = right.min(sheet.max_cols.max(1));
+    // No panes are frozen outside the app, so the header is row 1
+    let header_rows = if with_header {
+        app::header_rows(0, top)
+    } else {
+        1..1
+    };
+    let rows = header_rows.chain(top..=bottom).map(|row| {
+        (left..=right)
+            .map(|col| sheet.cell(row, col).value.as_str())
+            .collect()
+    });
+
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    format.write_rows(&mut out, rows)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn stream_file(
+    file_path: &Path,
+    delimiter: Option<u8>,
+    profile: Option<&excel::ImportProfile>,
+) -> Result<()> {
+    let csv_stream = |format: excel::formats::DelimitedFormat| {
+        let delimiter = delimiter.unwrap_or_else(|| format.delimiter());
+        excel::CsvStream::open(file_path, delimiter)
+    };
+    let stream: Box<dyn excel::RowStream> = match file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("csv") => Box::new(csv_stream(excel::formats::DelimitedFormat::csv())?),
+        Some("tsv" | "tab") => Box::new(csv_stream(excel::formats::DelimitedFormat::tsv())?),
+        Some("xlsx" | "xlsm") => Box::new(excel::XlsxStream::open(file_path)?),
+        _ => anyhow::bail!("--stream only works with CSV, TSV and XLSX files"),
+    };
+
+    let mut viewer = app::StreamViewer::new(stream);
+    if profile.is_some_and(|p| !p.columns.is_empty()) {
+        viewer.message =
+            Some("Import profile column transforms are not applied in streaming mode".to_string());
+    }
+    ui::run_stream_viewer(viewer)
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    match &cli.command {
+        Some(Command::Inspect { file_path, json }) => return inspect_file(file_path, *json),
+        Some(Command::Cat {
+            file_path,
+            sheet,
+            range,
+            format,
+            with_header,
+        }) => {
+            return cat_range(
+                file_path,
+                sheet.as_deref(),
+                range.as_deref(),
+                format,
+                *with_header,
+            )
+        }
+        Some(Command::Diff { .. }) | None => {}
+    }
+    let (file_path, diff_with) = match cli.command {
+        Some(Command::Diff { old_file, new_file }) => (new_file, Some(old_file)),
+        _ => match cli.file_path {
+            Some(file_path) => (file_path, None),
+            None => anyhow::bail!("No file given"),
+        },
+    };
+
     if !std::io::stdout().is_terminal() && !cli.json_export {
         eprintln!("Excel-cli error: Pipe detected but -j or --json-export flag not provided.");
         std::process::exit(1);
     }
 
+    // A profile named with --profile must exist, otherwise the first one matching the file name is used
+    let profiles = excel::load_import_profiles(&excel::import_profiles_path());
+    let (profile, mut profile_message) = match (&cli.profile, profiles) {
+        (Some(name), Ok(profiles)) => match profiles.iter().find(|p| p.is_named(name)) {
+            Some(profile) => (Some(profile.clone()), None),
+            None => {
+                let names: Vec<&str> = profiles.iter().filter_map(|p| p.name.as_deref()).collect();
+                if names.is_empty() {
+                    anyhow::bail!(
+                        "Profile '{name}' not found, no named profiles in {}",
+                        excel::import_profiles_path().display()
+                    )
+                }
+                anyhow::bail!(
+                    "Profile '{name}' not found. Available profiles: {}",
+                    names.join(", ")
+                )
+            }
+        },
+        (Some(_), Err(e)) => anyhow::bail!("Unable to read profiles: {e}"),
+        (None, Ok(profiles)) => (profiles.into_iter().find(|p| p.matches(&file_path)), None),
+        (None, Err(e)) => (None, Some(format!("Import profiles not applied: {e}"))),
+    };
+
+    let delimiter = match profile.as_ref().map(excel::ImportProfile::delimiter_byte) {
+        Some(Ok(delimiter)) => delimiter,
+        Some(Err(e)) => anyhow::bail!(e),
+        None => None,
+    };
+
+    if cli.stream {
+        return stream_file(&file_path, delimiter, profile.as_ref());
+    }
+
     // Open Excel file
-    let workbook = excel::open_workbook(&cli.file_path, cli.lazy_loading)?;
+    let mut workbook =
+        match excel::open_workbook_with_delimiter(&file_path, cli.lazy_loading, delimiter) {
+            Ok(workbook) => workbook,
+            Err(e @ excel::SheetError::UnsupportedFormat { .. }) => {
+                let mut extensions = excel::FormatRegistry::default().readable_extensions();
+                extensions.sort_unstable();
+                anyhow::bail!("{e}. Supported formats: {}", extensions.join(", "))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+    // Clean up the data with the profile's column transforms
+    if let Some(profile) = profile.as_ref().filter(|p| !p.columns.is_empty()) {
+        let changed = workbook.set_import_profile(profile.clone());
+        let later = if workbook.is_lazy_loading() {
+            ", other sheets as they load"
+        } else {
+            ""
+        };
+        profile_message = Some(format!(
+            "Import profile '{}' changed {changed} cell(s){later}",
+            profile.display_name()
+        ));
+    }
 
     // If JSON export flag is set, export to stdout and exit
     if cli.json_export {
+        if let Some(message) = &profile_message {
+            eprintln!("{message}");
+        }
+
+        // Command-line flags win over the profile
+        let direction = cli
+            .direction
+            .or_else(|| profile.as_ref().and_then(|p| p.header_direction.clone()))
+            .unwrap_or_else(|| "h".to_string());
+        let header_count = cli
+            .header_count
+            .or_else(|| profile.as_ref().and_then(|p| p.header_rows))
+            .unwrap_or(1);
+
         // Parse header direction
-        let Ok(direction) = json_export::HeaderDirection::from_str(&cli.direction) else {
-            anyhow::bail!("Invalid header direction: {}", cli.direction)
+        let Ok(direction) = json_export::HeaderDirection::from_str(&direction) else {
+            anyhow::bail!("Invalid header direction: {direction}")
         };
 
         // Generate JSON for all sheets
-        let all_sheets =
-            json_export::generate_all_sheets_json(&workbook, direction, cli.header_count)?;
+        let all_sheets = json_export::generate_all_sheets_json(&workbook, direction, header_count)?;
 
         // Serialize to JSON and print to stdout
         let json_string = json_export::serialize_to_json(&all_sheets)?;
@@ -63,7 +328,30 @@ fn main() -> Result<()> {
     }
 
     // Otherwise, run the interactive UI
-    let app_state = app::AppState::new(workbook, cli.file_path)?;
+    let mut app_state = app::AppState::new(workbook, file_path)?;
+    if let Some(message) = profile_message {
+        app_state.add_notification(message);
+    }
+    app_state.profile = profile;
+    match app::CellRenderers::load(&app::cell_renderers_path()) {
+        Ok(renderers) => app_state.cell_renderers = renderers,
+        Err(e) => app_state.add_notification(format!("Cell renderers not applied: {e:#}")),
+    }
+    app_state.run_lua_init(&app::lua_init_path());
+    if let Some(old_file) = diff_with {
+        app_state.start_diff(&old_file.to_string_lossy());
+    }
+    // Sheets of a lazily loaded workbook aren't read yet, so their styles can't be matched
+    if !cli.lazy_loading {
+        app_state.restore_undo_file();
+    }
+    // The other files are opened as buffers behind the first one
+    if !cli.more_files.is_empty() {
+        for path in &cli.more_files {
+            app_state.open_buffer(&path.to_string_lossy());
+        }
+        app_state.switch_buffer(0);
+    }
     ui::run_app(app_state)?;
 
     Ok(())
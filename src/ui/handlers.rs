@@ -1,15 +1,36 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use tui_textarea::{Input, Key, TextArea};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use tui_textarea::{Input, Key};
 
-use crate::app::{AppState, InputMode};
+use crate::app::{AppState, InputMode, MappingTarget};
 
 pub fn handle_key_event(app_state: &mut AppState, key: KeyEvent) {
+    // [ or ] followed by anything but f, t or c switches sheets before the key is handled
+    if app_state.pending_bracket.is_some()
+        && !(matches!(key.code, KeyCode::Char('f' | 't' | 'c')) && key.modifiers.is_empty())
+    {
+        app_state.flush_pending_bracket(true);
+    }
+
     match app_state.input_mode {
         InputMode::Normal => {
-            if key.modifiers.contains(KeyModifiers::CONTROL)
+            if app_state.window_pressed {
+                app_state.window_pressed = false;
+                handle_window_key(app_state, key.code);
+            } else if key.modifiers.contains(KeyModifiers::CONTROL)
                 || key.modifiers.contains(KeyModifiers::SUPER)
             {
+                // Shift+Ctrl+arrow extends the selection as far as Ctrl+arrow moves
+                if key.modifiers.contains(KeyModifiers::SHIFT)
+                    && matches!(
+                        key.code,
+                        KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
+                    )
+                {
+                    app_state.start_selection();
+                }
                 handle_ctrl_key(app_state, key.code);
+            } else if key.modifiers.contains(KeyModifiers::ALT) {
+                handle_alt_key(app_state, key.code);
             } else {
                 handle_normal_mode(app_state, key.code);
             }
@@ -21,6 +42,26 @@ pub fn handle_key_event(app_state: &mut AppState, key: KeyEvent) {
         InputMode::SearchBackward => handle_search_mode(app_state, key.code),
         InputMode::Help => handle_help_mode(app_state, key.code),
         InputMode::LazyLoading => handle_lazy_loading_mode(app_state, key.code),
+        InputMode::DistinctPicker => handle_distinct_picker_mode(app_state, key.code),
+        InputMode::CellPopup | InputMode::Messages => handle_cell_popup_mode(app_state, key.code),
+        InputMode::ColumnMapping => handle_column_mapping_mode(app_state, key.code),
+        InputMode::ColumnManager => handle_column_manager_mode(app_state, key),
+        InputMode::SheetPicker => handle_sheet_picker_mode(app_state, key.code),
+        InputMode::UndoList => handle_undo_list_mode(app_state, key.code),
+        InputMode::DiffList => handle_diff_list_mode(app_state, key.code),
+        InputMode::ConfirmDeleteSheet => handle_confirm_delete_sheet_mode(app_state, key.code),
+        InputMode::ConfirmReload => handle_confirm_reload_mode(app_state, key.code),
+        InputMode::Minimap => handle_minimap_mode(app_state, key.code),
+        InputMode::Replacing => {
+            if key.code == KeyCode::Esc {
+                app_state.cancel_replace();
+            }
+        }
+        InputMode::Searching => {
+            if key.code == KeyCode::Esc {
+                app_state.cancel_running_search();
+            }
+        }
     }
 }
 
@@ -44,6 +85,34 @@ fn handle_ctrl_key(app_state: &mut AppState, key_code: KeyCode) {
                 app_state.add_notification(format!("Redo failed: {e}"));
             }
         }
+        KeyCode::Char('f') => app_state.scroll_page(true),
+        KeyCode::Char('b') => app_state.scroll_page(false),
+        KeyCode::Char('d') => app_state.scroll_half_page(true),
+        KeyCode::Char('u') => app_state.scroll_half_page(false),
+        KeyCode::Char('o') => app_state.jump_back(),
+        KeyCode::Char('i') | KeyCode::Tab => app_state.jump_forward(),
+        KeyCode::Char('w') => app_state.window_pressed = true,
+        _ => {}
+    }
+}
+
+// The key after Ctrl+w, with or without Ctrl held. With two panes at most, every
+// direction leads to the other one
+fn handle_window_key(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Char('w' | 'p' | 'h' | 'j' | 'k' | 'l') => app_state.switch_pane(),
+        KeyCode::Char('s') => app_state.split_view(false, ""),
+        KeyCode::Char('v') => app_state.split_view(true, ""),
+        KeyCode::Char('o') => app_state.close_other_pane(),
+        KeyCode::Char('q' | 'c') => app_state.close_pane(),
+        _ => {}
+    }
+}
+
+fn handle_alt_key(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Left => app_state.adjust_column_width(-1),
+        KeyCode::Right => app_state.adjust_column_width(1),
         _ => {}
     }
 }
@@ -53,6 +122,8 @@ fn handle_command_mode(app_state: &mut AppState, key_code: KeyCode) {
         KeyCode::Enter => app_state.execute_command(),
         KeyCode::Esc => app_state.cancel_input(),
         KeyCode::Backspace => app_state.delete_char_from_input(),
+        KeyCode::Up => app_state.recall_previous_command(),
+        KeyCode::Down => app_state.recall_next_command(),
         KeyCode::Char(c) => app_state.add_char_to_input(c),
         _ => {}
     }
@@ -84,27 +155,73 @@ fn handle_command_in_lazy_loading_mode(app_state: &mut AppState, key_code: KeyCo
             app_state.input_buffer = String::new();
         }
         KeyCode::Backspace => app_state.delete_char_from_input(),
+        KeyCode::Up => app_state.recall_previous_command(),
+        KeyCode::Down => app_state.recall_next_command(),
         KeyCode::Char(c) => app_state.add_char_to_input(c),
         _ => {}
     }
 }
 
 fn handle_normal_mode(app_state: &mut AppState, key_code: KeyCode) {
+    // The key after m or ' names the mark
+    if let Some(command) = app_state.pending_mark.take() {
+        if let KeyCode::Char(name) = key_code {
+            if command == 'm' {
+                app_state.set_mark(name);
+            } else {
+                app_state.jump_to_mark(name);
+            }
+        }
+        return;
+    }
+
+    // zz, zt and zb scroll the cursor row to the middle, top or bottom
+    if app_state.z_pressed {
+        app_state.z_pressed = false;
+        if let KeyCode::Char(position @ ('z' | 't' | 'b')) = key_code {
+            app_state.scroll_cursor_row_to(position);
+        }
+        return;
+    }
+
+    // yy and yc widen the cell y just copied to its whole row or column
+    if app_state.y_pressed {
+        app_state.y_pressed = false;
+        match key_code {
+            KeyCode::Char('y') => {
+                app_state.yank_row();
+                return;
+            }
+            KeyCode::Char('c') => {
+                app_state.yank_column();
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    // ]f and [f jump between formula cells, ]t and [t between cells not matching the column
+    // type, ]c and [c between cells differing from the :diff file
+    if let Some((bracket, _)) = app_state.pending_bracket.take() {
+        match key_code {
+            KeyCode::Char('t') => app_state.jump_to_nonconforming(bracket == ']'),
+            KeyCode::Char('c') => app_state.jump_to_difference(bracket == ']'),
+            _ => app_state.jump_to_formula(bracket == ']'),
+        }
+        return;
+    }
+
     match key_code {
         KeyCode::Enter => {
             app_state.g_pressed = false;
 
             // Check if the current sheet is loaded
             let index = app_state.workbook.get_current_sheet_index();
-            let sheet_name = app_state.workbook.get_current_sheet_name();
 
             if app_state.workbook.is_lazy_loading() && !app_state.workbook.is_sheet_loaded(index) {
                 // If the sheet is not loaded, load it first
-                if let Err(e) = app_state.workbook.ensure_sheet_loaded(index, &sheet_name) {
-                    app_state.add_notification(format!("Failed to load sheet: {e}"));
-                } else {
-                    app_state.start_editing();
-                }
+                app_state.load_current_sheet();
+                app_state.input_mode = InputMode::LazyLoading;
             } else {
                 app_state.start_editing();
             }
@@ -139,17 +256,21 @@ fn handle_normal_mode(app_state: &mut AppState, key_code: KeyCode) {
             app_state.g_pressed = false;
             app_state.adjust_info_panel_height(-1);
         }
-        KeyCode::Char('[') => {
+        KeyCode::Char('K' | ' ') => {
             app_state.g_pressed = false;
-            if let Err(e) = app_state.prev_sheet() {
-                app_state.add_notification(format!("Failed to switch to previous sheet: {e}"));
-            }
+            app_state.show_cell_popup();
         }
-        KeyCode::Char(']') => {
+        KeyCode::Char('<') => {
             app_state.g_pressed = false;
-            if let Err(e) = app_state.next_sheet() {
-                app_state.add_notification(format!("Failed to switch to next sheet: {e}"));
-            }
+            app_state.adjust_column_width(-1);
+        }
+        KeyCode::Char('>') => {
+            app_state.g_pressed = false;
+            app_state.adjust_column_width(1);
+        }
+        KeyCode::Char(bracket @ ('[' | ']')) => {
+            app_state.g_pressed = false;
+            app_state.start_bracket(bracket);
         }
         KeyCode::Char('g') => {
             if app_state.g_pressed {
@@ -159,6 +280,37 @@ fn handle_normal_mode(app_state: &mut AppState, key_code: KeyCode) {
                 app_state.g_pressed = true;
             }
         }
+        KeyCode::Char('s') if app_state.g_pressed => {
+            app_state.g_pressed = false;
+            app_state.open_sheet_picker();
+        }
+        KeyCode::Char('m') if app_state.g_pressed => {
+            app_state.g_pressed = false;
+            app_state.start_minimap_selection();
+        }
+        KeyCode::Char('e') if app_state.g_pressed => {
+            app_state.g_pressed = false;
+            app_state.start_external_edit();
+        }
+        KeyCode::Char(c @ ('m' | '\'' | '`')) => {
+            app_state.g_pressed = false;
+            app_state.pending_mark = Some(if c == 'm' { 'm' } else { '\'' });
+        }
+        KeyCode::Char('z') => {
+            app_state.g_pressed = false;
+            app_state.z_pressed = true;
+        }
+        KeyCode::Char('v') => {
+            app_state.g_pressed = false;
+            app_state.toggle_selection();
+        }
+        KeyCode::Esc => {
+            app_state.g_pressed = false;
+            // Without a selection to end, Esc stops a running save
+            if app_state.selection_anchor.is_some() || !app_state.cancel_save() {
+                app_state.clear_selection();
+            }
+        }
         KeyCode::Char('G') => {
             app_state.g_pressed = false;
             app_state.jump_to_last_row();
@@ -177,6 +329,7 @@ fn handle_normal_mode(app_state: &mut AppState, key_code: KeyCode) {
         }
         KeyCode::Char('y') => {
             app_state.g_pressed = false;
+            app_state.y_pressed = app_state.selection_anchor.is_none();
             app_state.copy_cell();
         }
         KeyCode::Char('d') => {
@@ -191,6 +344,12 @@ fn handle_normal_mode(app_state: &mut AppState, key_code: KeyCode) {
                 app_state.add_notification(format!("Paste failed: {e}"));
             }
         }
+        KeyCode::Char('.') => {
+            app_state.g_pressed = false;
+            if let Err(e) = app_state.repeat_last_change() {
+                app_state.add_notification(format!("Repeat failed: {e}"));
+            }
+        }
         KeyCode::Char(':') => {
             app_state.g_pressed = false;
             app_state.start_command_mode();
@@ -229,6 +388,11 @@ fn handle_normal_mode(app_state: &mut AppState, key_code: KeyCode) {
             }
         }
 
+        // Terminals send Ctrl-i as Tab
+        KeyCode::Tab => {
+            app_state.g_pressed = false;
+            app_state.jump_forward();
+        }
         KeyCode::Left => {
             app_state.g_pressed = false;
             app_state.move_cursor(0, -1);
@@ -245,13 +409,82 @@ fn handle_normal_mode(app_state: &mut AppState, key_code: KeyCode) {
             app_state.g_pressed = false;
             app_state.move_cursor(1, 0);
         }
+        KeyCode::PageDown => {
+            app_state.g_pressed = false;
+            app_state.scroll_page(true);
+        }
+        KeyCode::PageUp => {
+            app_state.g_pressed = false;
+            app_state.scroll_page(false);
+        }
         _ => {
             app_state.g_pressed = false;
         }
     }
 }
 
+/// Clicks, drags and the wheel, matched to what the last frame drew under the pointer
+pub fn handle_mouse_event(app_state: &mut AppState, mouse: MouseEvent) {
+    // Clicks act like keys in normal mode, sheets still loading can be left by their tabs
+    let clickable = match app_state.input_mode {
+        InputMode::Normal => true,
+        InputMode::LazyLoading => app_state
+            .mouse_areas
+            .tab_at(mouse.column, mouse.row)
+            .is_some(),
+        _ => false,
+    };
+    // Shift turns the wheel sideways, for mice without a horizontal wheel
+    let sideways = mouse.modifiers.contains(KeyModifiers::SHIFT);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) if clickable => {
+            app_state.flush_pending_bracket(true);
+            app_state.click_at(mouse.column, mouse.row);
+        }
+        MouseEventKind::Drag(MouseButton::Left)
+            if matches!(app_state.input_mode, InputMode::Normal) =>
+        {
+            app_state.drag_to(mouse.column, mouse.row);
+        }
+        MouseEventKind::Up(MouseButton::Left) => app_state.end_drag(),
+        MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+            let down = mouse.kind == MouseEventKind::ScrollDown;
+            app_state.wheel_at(mouse.column, mouse.row, down, sideways);
+        }
+        MouseEventKind::ScrollRight | MouseEventKind::ScrollLeft => {
+            let right = mouse.kind == MouseEventKind::ScrollRight;
+            app_state.wheel_at(mouse.column, mouse.row, right, true);
+        }
+        _ => {}
+    }
+}
+
+/// Text pasted into the terminal arrives in one piece with bracketed paste. In normal mode a
+/// block fills a range of cells at the cursor, anywhere else it is typed in as before
+pub fn handle_paste_event(app_state: &mut AppState, text: &str) {
+    if let InputMode::Normal = app_state.input_mode {
+        if let Err(e) = app_state.paste_text(text, "terminal paste") {
+            app_state.add_notification(format!("Paste failed: {e}"));
+        }
+        return;
+    }
+
+    for c in text.chars() {
+        let code = match c {
+            '\r' | '\n' => KeyCode::Enter,
+            '\t' => KeyCode::Tab,
+            c => KeyCode::Char(c),
+        };
+        handle_key_event(app_state, KeyEvent::new(code, KeyModifiers::NONE));
+    }
+}
+
 fn handle_editing_mode(app_state: &mut AppState, key: KeyEvent) {
+    if key.code == KeyCode::Char('e') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app_state.start_external_edit_of_edit_box();
+        return;
+    }
+
     // Convert KeyEvent to Input for tui-textarea
     let input = Input {
         key: key_code_to_tui_key(key.code),
@@ -268,11 +501,7 @@ fn handle_editing_mode(app_state: &mut AppState, key: KeyEvent) {
 fn handle_search_mode(app_state: &mut AppState, key_code: KeyCode) {
     match key_code {
         KeyCode::Enter => app_state.execute_search(),
-        KeyCode::Esc => {
-            app_state.input_mode = InputMode::Normal;
-            app_state.input_buffer = String::new();
-            app_state.text_area = TextArea::default();
-        }
+        KeyCode::Esc => app_state.cancel_search(),
         _ => {
             let input = Input {
                 key: key_code_to_tui_key(key_code),
@@ -280,7 +509,9 @@ fn handle_search_mode(app_state: &mut AppState, key_code: KeyCode) {
                 alt: false,
                 shift: false,
             };
-            app_state.text_area.input(input);
+            if app_state.text_area.input(input) {
+                app_state.preview_search();
+            }
         }
     }
 }
@@ -311,17 +542,8 @@ fn key_code_to_tui_key(key_code: KeyCode) -> Key {
 
 fn handle_lazy_loading_mode(app_state: &mut AppState, key_code: KeyCode) {
     match key_code {
-        KeyCode::Enter => {
-            let index = app_state.workbook.get_current_sheet_index();
-            let sheet_name = app_state.workbook.get_current_sheet_name();
-
-            // Load the sheet
-            if let Err(e) = app_state.workbook.ensure_sheet_loaded(index, &sheet_name) {
-                app_state.add_notification(format!("Failed to load sheet: {e}"));
-            }
-
-            app_state.input_mode = InputMode::Normal;
-        }
+        // The sheet loads in the background, the mode turns Normal once it is in
+        KeyCode::Enter => app_state.load_current_sheet(),
         KeyCode::Char('[') => {
             // Switch to previous sheet
             let current_index = app_state.workbook.get_current_sheet_index();
@@ -353,20 +575,169 @@ fn handle_lazy_loading_mode(app_state: &mut AppState, key_code: KeyCode) {
             // Allow entering command mode from lazy loading mode
             app_state.start_command_in_lazy_loading_mode();
         }
+        KeyCode::Esc => app_state.cancel_sheet_load(),
         _ => {
-            app_state.add_notification(
-                "Press Enter to load the sheet data, or use [ and ] to switch sheets".to_string(),
-            );
+            let current_name = app_state.workbook.get_current_sheet_name();
+            let message = match app_state.workbook.loading_sheet() {
+                Some((loading, _)) if loading == current_name => {
+                    "The sheet is loading, use [ and ] to switch sheets meanwhile"
+                }
+                _ => "Press Enter to load the sheet data, or use [ and ] to switch sheets",
+            };
+            app_state.add_notification(message.to_string());
         }
     }
 }
 
+fn handle_minimap_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Char('j') | KeyCode::Down => app_state.move_minimap_target(1, 0),
+        KeyCode::Char('k') | KeyCode::Up => app_state.move_minimap_target(-1, 0),
+        KeyCode::Char('l') | KeyCode::Right => app_state.move_minimap_target(0, 1),
+        KeyCode::Char('h') | KeyCode::Left => app_state.move_minimap_target(0, -1),
+        KeyCode::PageDown => app_state.move_minimap_target(10, 0),
+        KeyCode::PageUp => app_state.move_minimap_target(-10, 0),
+        KeyCode::Enter => app_state.confirm_minimap_selection(),
+        KeyCode::Esc => app_state.cancel_minimap_selection(),
+        _ => {}
+    }
+}
+
+fn handle_column_mapping_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Enter => app_state.confirm_column_mapping(),
+        KeyCode::Esc => app_state.cancel_column_mapping(),
+        KeyCode::Up | KeyCode::Char('k') => app_state.move_mapping_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app_state.move_mapping_selection(1),
+        KeyCode::Left | KeyCode::Char('h') => app_state.cycle_mapping_target(-1),
+        KeyCode::Right | KeyCode::Char('l') => app_state.cycle_mapping_target(1),
+        KeyCode::Char('s') => app_state.set_mapping_target(MappingTarget::Skip),
+        KeyCode::Char('n') => app_state.set_mapping_target(MappingTarget::New),
+        _ => {}
+    }
+}
+
+fn handle_column_manager_mode(app_state: &mut AppState, key: KeyEvent) {
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    match key.code {
+        KeyCode::Enter => app_state.apply_column_manager(),
+        KeyCode::Esc => app_state.cancel_column_manager(),
+        KeyCode::Up if shift => app_state.move_managed_column(-1),
+        KeyCode::Down if shift => app_state.move_managed_column(1),
+        KeyCode::Char('K') => app_state.move_managed_column(-1),
+        KeyCode::Char('J') => app_state.move_managed_column(1),
+        KeyCode::Up | KeyCode::Char('k') => app_state.move_column_manager_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app_state.move_column_manager_selection(1),
+        KeyCode::Left | KeyCode::Char('h' | '<') => app_state.adjust_managed_column_width(-1),
+        KeyCode::Right | KeyCode::Char('l' | '>') => app_state.adjust_managed_column_width(1),
+        KeyCode::Char(' ') => app_state.toggle_managed_column_hidden(),
+        KeyCode::Char('p') => app_state.toggle_managed_column_pin(),
+        _ => {}
+    }
+}
+
+fn handle_distinct_picker_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Enter => app_state.confirm_distinct_selection(),
+        KeyCode::Esc => app_state.close_distinct_picker(),
+        KeyCode::Up => app_state.move_distinct_selection(-1),
+        KeyCode::Down => app_state.move_distinct_selection(1),
+        KeyCode::PageUp => app_state.move_distinct_selection(-10),
+        KeyCode::PageDown => app_state.move_distinct_selection(10),
+        KeyCode::Backspace => app_state.update_distinct_query(None),
+        KeyCode::Char(c) => app_state.update_distinct_query(Some(c)),
+        _ => {}
+    }
+}
+
+fn handle_sheet_picker_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Enter => app_state.confirm_sheet_picker(),
+        KeyCode::Esc => app_state.close_sheet_picker(),
+        KeyCode::Up => app_state.move_sheet_picker_selection(-1),
+        KeyCode::Down => app_state.move_sheet_picker_selection(1),
+        KeyCode::PageUp => app_state.move_sheet_picker_selection(-10),
+        KeyCode::PageDown => app_state.move_sheet_picker_selection(10),
+        KeyCode::Backspace => app_state.update_sheet_picker_query(None),
+        KeyCode::Char(c) => app_state.update_sheet_picker_query(Some(c)),
+        _ => {}
+    }
+}
+
+fn handle_undo_list_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Enter => {
+            if let Err(e) = app_state.confirm_undo_list() {
+                app_state.add_notification(format!("Undo failed: {e}"));
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => app_state.close_undo_list(),
+        KeyCode::Up | KeyCode::Char('k') => app_state.move_undo_list_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app_state.move_undo_list_selection(1),
+        KeyCode::PageUp => app_state.move_undo_list_selection(-10),
+        KeyCode::PageDown => app_state.move_undo_list_selection(10),
+        KeyCode::Char('g') => app_state.move_undo_list_selection(isize::MIN),
+        KeyCode::Char('G') => app_state.move_undo_list_selection(isize::MAX),
+        _ => {}
+    }
+}
+
+fn handle_diff_list_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Enter => app_state.confirm_diff_list(),
+        KeyCode::Esc | KeyCode::Char('q') => app_state.close_diff_list(),
+        KeyCode::Up | KeyCode::Char('k') => app_state.move_diff_list_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app_state.move_diff_list_selection(1),
+        KeyCode::PageUp => app_state.move_diff_list_selection(-10),
+        KeyCode::PageDown => app_state.move_diff_list_selection(10),
+        KeyCode::Char('g') => app_state.move_diff_list_selection(isize::MIN),
+        KeyCode::Char('G') => app_state.move_diff_list_selection(isize::MAX),
+        _ => {}
+    }
+}
+
+// Only an explicit y deletes, anything else but n and Esc is ignored
+fn handle_confirm_delete_sheet_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Char('y' | 'Y') => app_state.confirm_sheet_delete(),
+        KeyCode::Char('n' | 'N') | KeyCode::Esc => app_state.cancel_sheet_delete(),
+        _ => {}
+    }
+}
+
+fn handle_confirm_reload_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Char('r' | 'R') => app_state.confirm_reload(),
+        KeyCode::Char('k' | 'K') | KeyCode::Esc => app_state.keep_local_workbook(),
+        _ => {}
+    }
+}
+
+fn handle_cell_popup_mode(app_state: &mut AppState, key_code: KeyCode) {
+    match key_code {
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q' | 'K' | ' ') => {
+            app_state.input_mode = InputMode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => app_state.scroll_cell_popup(1),
+        KeyCode::Char('k') | KeyCode::Up => app_state.scroll_cell_popup(-1),
+        KeyCode::PageDown => {
+            app_state.scroll_cell_popup(app_state.cell_popup_visible_lines as isize);
+        }
+        KeyCode::PageUp => {
+            app_state.scroll_cell_popup(-(app_state.cell_popup_visible_lines as isize));
+        }
+        KeyCode::Home => app_state.cell_popup_scroll = 0,
+        KeyCode::End => app_state.scroll_cell_popup(isize::MAX / 2),
+        _ => {}
+    }
+}
+
 fn handle_help_mode(app_state: &mut AppState, key_code: KeyCode) {
     let line_count = app_state.help_text.lines().count();
 
     let visible_lines = app_state.help_visible_lines;
 
-    let max_scroll = line_count.saturating_sub(visible_lines).max(0);
+    let max_scroll = line_count.saturating_sub(visible_lines);
 
     match key_code {
         KeyCode::Enter | KeyCode::Esc => {
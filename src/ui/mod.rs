@@ -1,4 +1,6 @@
 mod handlers;
 mod render;
+mod stream_view;
 
 pub use crate::ui::render::run_app;
+pub use crate::ui::stream_view::run_stream_viewer;
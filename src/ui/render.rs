@@ -1,50 +1,122 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyEventKind,
+    },
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table},
+    text::{Line, Span, Text},
+    widgets::block::{Position, Title},
+    widgets::{Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Table},
     Frame, Terminal,
 };
-use std::{io, time::Duration};
+use std::{collections::HashSet, io, time::Duration};
 
 use crate::app::AppState;
+use crate::app::AutosaveResult;
+use crate::app::CellDiff;
 use crate::app::InputMode;
-use crate::ui::handlers::handle_key_event;
+use crate::app::MappingTarget;
+use crate::app::SelectionStats;
+use crate::app::{command_history_path, edit_in_external_editor, pretty_json};
+use crate::app::{Activity, ActivityKind};
+use crate::excel::{CellStyle, HorizontalAlignment, Rgb};
+use crate::ui::handlers::{handle_key_event, handle_mouse_event, handle_paste_event};
 use crate::utils::cell_reference;
+use crate::utils::format_bytes;
 use crate::utils::index_to_col_name;
+use crate::utils::wrap_text;
+
+// Maximum number of screen lines the selected row can grow to when wrapping
+const MAX_WRAPPED_ROW_HEIGHT: usize = 10;
+
+// Long values beyond this many lines are cut off in the formula bar
+const MAX_FORMULA_BAR_HEIGHT: usize = 3;
+
+// Frames of the spinner shown while a sheet loads or the file is saved in the background
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
 pub fn run_app(mut app_state: AppState) -> Result<()> {
     // Setup terminal
     let mut terminal = setup_terminal()?;
+    // Pasted text comes as one event instead of keystrokes, so blocks can fill ranges
+    terminal.backend_mut().execute(EnableBracketedPaste)?;
+    terminal.backend_mut().execute(EnableMouseCapture)?;
 
     // Main event loop
     while !app_state.should_quit {
         terminal.draw(|f| ui(f, &mut app_state))?;
 
-        if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+        // Don't wait for input while a replace or search is running, only check for Esc
+        let timeout = if app_state.replace_job.is_some() || app_state.search_job.is_some() {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(50)
+        };
+
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
                     handle_key_event(&mut app_state, key);
                 }
+                Event::Paste(text) => handle_paste_event(&mut app_state, &text),
+                Event::Mouse(mouse) => handle_mouse_event(&mut app_state, mouse),
+                _ => {}
             }
         }
+
+        // The editor gets the terminal until it exits, then the UI is set up again
+        if let Some(target) = app_state.external_edit.take() {
+            let (text, name) = app_state.external_edit_text(target);
+            terminal.backend_mut().execute(DisableBracketedPaste)?;
+            terminal.backend_mut().execute(DisableMouseCapture)?;
+            restore_terminal(&mut terminal)?;
+            let edited = edit_in_external_editor(&text, &name);
+            terminal = setup_terminal()?;
+            terminal.backend_mut().execute(EnableBracketedPaste)?;
+            terminal.backend_mut().execute(EnableMouseCapture)?;
+            app_state.finish_external_edit(target, edited);
+        }
+
+        app_state.continue_replace();
+        app_state.continue_search();
+        app_state.continue_autosave();
+        app_state.continue_save();
+        app_state.continue_sheet_load();
+        app_state.check_file_changed();
+        app_state.flush_pending_bracket(false);
     }
 
     // Restore terminal
+    terminal.backend_mut().execute(DisableBracketedPaste)?;
+    terminal.backend_mut().execute(DisableMouseCapture)?;
     restore_terminal(&mut terminal)?;
+    app_state.finish_autosave();
+
+    if let Some(path) = &app_state.recovery_path {
+        println!("Unsaved changes were written to {}", path.display());
+    }
+
+    if let Some(history_path) = command_history_path() {
+        if let Err(e) = app_state.command_history.save(&history_path) {
+            eprintln!(
+                "Command history not saved to {}: {e}",
+                history_path.display()
+            );
+        }
+    }
 
     Ok(())
 }
 
 /// Setup the terminal for the application
-fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+pub(super) fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     stdout.execute(EnterAlternateScreen)?;
@@ -56,7 +128,9 @@ fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
 }
 
 /// Restore the terminal to its original state
-fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+pub(super) fn restore_terminal(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+) -> Result<()> {
     disable_raw_mode()?;
     terminal.backend_mut().execute(LeaveAlternateScreen)?;
     terminal.show_cursor()?;
@@ -69,6 +143,12 @@ fn update_visible_area(app_state: &mut AppState, area: Rect) {
     // Calculate visible rows based on available height (subtract header and borders)
     app_state.visible_rows = (area.height as usize).saturating_sub(3);
 
+    // The type row of :set typerow stays pinned under the header
+    if app_state.type_row {
+        app_state.visible_rows = app_state.visible_rows.saturating_sub(1).max(1);
+        app_state.handle_scrolling();
+    }
+
     // Ensure the selected column is visible
     app_state.ensure_column_visible(app_state.selected_cell.1);
 
@@ -78,15 +158,22 @@ fn update_visible_area(app_state: &mut AppState, area: Rect) {
     // Calculate available width for columns (subtract row numbers and borders)
     let available_width = (area.width as usize).saturating_sub(app_state.row_number_width + 2); // row_number_width + 2 for borders
 
+    // Frozen columns are always drawn first, so they take space from the scrollable area
+    let frozen_width: usize = (1..=app_state.frozen_cols)
+        .map(|col| app_state.get_column_width(col))
+        .sum();
+    let available_width = available_width.saturating_sub(frozen_width);
+
     // Calculate how many columns can fit in the available width
     let mut visible_cols = 0;
     let mut width_used = 0;
 
     // Iterate through columns starting from the leftmost visible column
-    for col_idx in app_state.start_col.. {
+    let start_col = app_state.start_col.max(app_state.frozen_cols + 1);
+    for col_idx in start_col.. {
         let col_width = app_state.get_column_width(col_idx);
 
-        if col_idx == app_state.start_col {
+        if col_idx == start_col {
             // Always include the first column even if it's wider than available space
             width_used += col_width;
             visible_cols += 1;
@@ -110,39 +197,258 @@ fn update_visible_area(app_state: &mut AppState, area: Rect) {
 
     // Ensure at least one column is visible
     app_state.visible_cols = visible_cols.max(1);
+
+    // A wrapped selected row takes extra lines from the rows below it
+    let extra_lines = selected_row_height(app_state).saturating_sub(1);
+    if extra_lines > 0 {
+        app_state.visible_rows = app_state
+            .visible_rows
+            .saturating_sub(extra_lines)
+            .max(app_state.frozen_rows + 1);
+        app_state.handle_scrolling();
+    }
+}
+
+/// Visible columns in display order, frozen columns first, without hidden columns
+fn visible_columns(app_state: &AppState) -> Vec<usize> {
+    let start_col = app_state.start_col.max(app_state.frozen_cols + 1);
+    let end_col = start_col + app_state.visible_cols - 1;
+    (1..=app_state.frozen_cols)
+        .chain(start_col..=end_col)
+        .filter(|&col| !app_state.is_column_hidden(col))
+        .collect()
+}
+
+/// Rows shown in display order, frozen rows first
+fn visible_rows(app_state: &AppState) -> Vec<usize> {
+    let start_row = app_state.start_row.max(app_state.frozen_rows + 1);
+    let end_row = start_row + app_state.scrollable_rows() - 1;
+    (1..=app_state.frozen_rows)
+        .chain(start_row..=end_row)
+        .collect()
+}
+
+// Keeps where the columns and rows of the grid in `area` are for the mouse, laid out
+// the way the table of draw_spreadsheet lays them out
+fn record_grid_areas(app_state: &mut AppState, area: Rect) {
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let visible_cols = visible_columns(app_state);
+
+    // Like the table: a selection symbol of no width, then columns one space apart. The
+    // table leaves the rest of the line empty, the filler at the end takes it here
+    let mut constraints = vec![
+        Constraint::Length(0),
+        Constraint::Length(app_state.row_number_width as u16),
+    ];
+    for &col in &visible_cols {
+        constraints.push(Constraint::Length(1));
+        constraints.push(Constraint::Length(app_state.get_column_width(col) as u16));
+    }
+    constraints.push(Constraint::Min(0));
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(constraints)
+        .split(inner);
+    let columns = visible_cols
+        .iter()
+        .zip(chunks.iter().skip(3).step_by(2))
+        .filter(|(_, chunk)| chunk.width > 0)
+        .map(|(&col, chunk)| (chunk.x, chunk.width, col))
+        .collect();
+
+    // The header and the type row come before the data rows, rows that don't fit whole
+    // aren't drawn
+    let wrapped_height = selected_row_height(app_state) as u16;
+    let mut y = inner.y + 1 + u16::from(app_state.type_row);
+    let mut rows = Vec::new();
+    for row in visible_rows(app_state) {
+        let height = if row == app_state.selected_cell.0 {
+            wrapped_height
+        } else {
+            1
+        };
+        if y + height > inner.bottom() {
+            break;
+        }
+        rows.push((y, height, row));
+        y += height;
+    }
+
+    app_state.mouse_areas.grid = area;
+    app_state.mouse_areas.header = inner.y;
+    app_state.mouse_areas.columns = columns;
+    app_state.mouse_areas.rows = rows;
+}
+
+/// Height of the selected row, which grows to fit its wrapped content when wrapping is enabled
+fn selected_row_height(app_state: &AppState) -> usize {
+    if !app_state.wrap_enabled {
+        return 1;
+    }
+
+    let row = app_state.selected_cell.0;
+    let max_height = (app_state.visible_rows.saturating_sub(app_state.frozen_rows))
+        .clamp(1, MAX_WRAPPED_ROW_HEIGHT);
+
+    visible_columns(app_state)
+        .into_iter()
+        .map(|col| {
+            let content = app_state.get_cell_content(row, col);
+            wrap_text(&content, app_state.get_column_width(col)).len()
+        })
+        .max()
+        .unwrap_or(1)
+        .min(max_height)
 }
 
 fn ui(f: &mut Frame, app_state: &mut AppState) {
+    let formula_bar_lines = formula_bar_lines(app_state, f.size().width);
+
     // Create the main layout
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1), // Combined title bar and sheet tabs
+            Constraint::Length(formula_bar_lines.len() as u16), // Formula bar
             Constraint::Min(1),    // Spreadsheet
             Constraint::Length(app_state.info_panel_height as u16), // Info panel
             Constraint::Length(1), // Status bar
         ])
         .split(f.size());
 
-    draw_title_with_tabs(f, app_state, chunks[0]);
+    app_state.mouse_areas.tabs = draw_title_with_tabs(f, app_state, chunks[0]);
+    draw_formula_bar(f, app_state, formula_bar_lines, chunks[1]);
+
+    // The structure map takes a column on the right and a line below the grid
+    let (grid_area, minimap_areas) = if app_state.minimap.is_some() {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(chunks[2]);
+        let lines = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(columns[0]);
+        (lines[0], Some((columns[1], lines[1])))
+    } else {
+        (chunks[2], None)
+    };
+
+    // With :split or :vsplit the pane without the focus is drawn by trading places with it
+    // for a moment
+    let grid_area = match app_state.split.take() {
+        Some(mut pane) => {
+            let direction = if pane.vertical {
+                Direction::Horizontal
+            } else {
+                Direction::Vertical
+            };
+            let panes = Layout::default()
+                .direction(direction)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(grid_area);
+            let (other_area, focused_area) = if pane.first {
+                (panes[0], panes[1])
+            } else {
+                (panes[1], panes[0])
+            };
+
+            app_state.exchange_pane(&mut pane);
+            update_visible_area(app_state, other_area);
+            draw_spreadsheet(f, app_state, other_area, false);
+            app_state.exchange_pane(&mut pane);
+            app_state.split = Some(pane);
+            focused_area
+        }
+        None => grid_area,
+    };
+
+    update_visible_area(app_state, grid_area);
+    draw_spreadsheet(f, app_state, grid_area, true);
+    record_grid_areas(app_state, grid_area);
+
+    if let Some((right, bottom)) = minimap_areas {
+        // Line the strips up with the table body, inside its borders and headers
+        let row_strip = Rect {
+            y: grid_area.y + 2,
+            height: grid_area.height.saturating_sub(3),
+            ..right
+        };
+        let label_width = (app_state.row_number_width + 1) as u16;
+        let col_strip = Rect {
+            x: bottom.x + label_width,
+            width: bottom.width.saturating_sub(label_width + 1),
+            ..bottom
+        };
 
-    update_visible_area(app_state, chunks[1]);
-    draw_spreadsheet(f, app_state, chunks[1]);
+        app_state.refresh_minimap();
+        if let Some(map) = &mut app_state.minimap {
+            map.rows_per_cell = map
+                .max_rows()
+                .div_ceil(row_strip.height.max(1) as usize)
+                .max(1);
+            map.cols_per_cell = map
+                .max_cols()
+                .div_ceil(col_strip.width.max(1) as usize)
+                .max(1);
+        }
+        draw_minimap(f, app_state, row_strip, col_strip);
+    }
 
-    draw_info_panel(f, app_state, chunks[2]);
-    draw_status_bar(f, app_state, chunks[3]);
+    draw_info_panel(f, app_state, chunks[3]);
+    if app_state.memory_status.is_some() {
+        app_state.refresh_memory_status();
+    }
+    app_state.refresh_selection_stats();
+    draw_status_bar(f, app_state, chunks[4]);
 
     // If in help mode, draw the help popup over everything else
     if let InputMode::Help = app_state.input_mode {
         draw_help_popup(f, app_state, f.size());
     }
 
+    if let InputMode::DistinctPicker = app_state.input_mode {
+        draw_distinct_picker(f, app_state, f.size());
+    }
+
+    if let InputMode::CellPopup | InputMode::Messages = app_state.input_mode {
+        draw_cell_popup(f, app_state, f.size());
+    }
+
+    if let InputMode::ColumnMapping = app_state.input_mode {
+        draw_column_mapping(f, app_state, f.size());
+    }
+
+    if let InputMode::ColumnManager = app_state.input_mode {
+        draw_column_manager(f, app_state, f.size());
+    }
+
+    if let InputMode::SheetPicker = app_state.input_mode {
+        draw_sheet_picker(f, app_state, f.size());
+    }
+
+    if let InputMode::UndoList = app_state.input_mode {
+        draw_undo_list(f, app_state, f.size());
+    }
+
+    if let InputMode::DiffList = app_state.input_mode {
+        draw_diff_list(f, app_state, f.size());
+    }
+
+    if let InputMode::ConfirmDeleteSheet = app_state.input_mode {
+        draw_confirm_delete_sheet(f, app_state, f.size());
+    }
+
+    if let InputMode::ConfirmReload = app_state.input_mode {
+        draw_confirm_reload(f, app_state, f.size());
+    }
+
     // If in lazy loading mode or CommandInLazyLoading mode and the current sheet is not loaded, draw the lazy loading overlay
     match app_state.input_mode {
         InputMode::LazyLoading | InputMode::CommandInLazyLoading => {
             let current_index = app_state.workbook.get_current_sheet_index();
             if !app_state.workbook.is_sheet_loaded(current_index) {
-                draw_lazy_loading_overlay(f, app_state, chunks[1]);
+                draw_lazy_loading_overlay(f, app_state, chunks[2]);
             } else if matches!(app_state.input_mode, InputMode::LazyLoading) {
                 // If the sheet is loaded, switch back to Normal mode
                 app_state.input_mode = crate::app::InputMode::Normal;
@@ -152,62 +458,222 @@ fn ui(f: &mut Frame, app_state: &mut AppState) {
     }
 }
 
-fn draw_spreadsheet(f: &mut Frame, app_state: &AppState, area: Rect) {
-    // Calculate visible row and column ranges
-    let start_row = app_state.start_row;
-    let end_row = start_row + app_state.visible_rows - 1;
-    let start_col = app_state.start_col;
-    let end_col = start_col + app_state.visible_cols - 1;
+// Width of the cell reference label in front of the formula bar content, e.g. " AB123 "
+fn formula_bar_label_width(app_state: &AppState) -> usize {
+    cell_reference(app_state.selected_cell).len() + 3
+}
 
-    let mut constraints = Vec::with_capacity(app_state.visible_cols + 1);
-    constraints.push(Constraint::Length(app_state.row_number_width as u16)); // Dynamic row header width
+// Wrap the raw content of the selected cell into at most MAX_FORMULA_BAR_HEIGHT lines
+fn formula_bar_lines(app_state: &AppState, width: u16) -> Vec<String> {
+    let (row, col) = app_state.selected_cell;
+    let content = &app_state.workbook.get_current_sheet().cell(row, col).value;
 
-    for col in start_col..=end_col {
-        constraints.push(Constraint::Length(app_state.get_column_width(col) as u16));
+    let content_width = (width as usize).saturating_sub(formula_bar_label_width(app_state));
+    let mut lines = wrap_text(content, content_width);
+
+    if lines.is_empty() {
+        lines.push(String::new());
     }
 
-    // Set table style based on current mode
-    let (table_block, header_style, cell_style) =
-        if matches!(app_state.input_mode, InputMode::Normal) {
-            // In Normal mode, add color to the border of the data display area to indicate current focus
+    if lines.len() > MAX_FORMULA_BAR_HEIGHT {
+        lines.truncate(MAX_FORMULA_BAR_HEIGHT);
+        if let Some(last) = lines.last_mut() {
+            last.pop();
+            last.push('…');
+        }
+    }
+
+    lines
+}
+
+fn draw_formula_bar(f: &mut Frame, app_state: &AppState, lines: Vec<String>, area: Rect) {
+    let label_width = formula_bar_label_width(app_state);
+    let label_style = if matches!(app_state.input_mode, InputMode::Editing) {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default()
+            .bg(Color::DarkGray)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD)
+    };
+
+    let text: Vec<Line> = lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let label = if i == 0 {
+                Span::styled(
+                    format!(" {} ", cell_reference(app_state.selected_cell)),
+                    label_style,
+                )
+            } else {
+                Span::raw(" ".repeat(label_width - 1))
+            };
+            Line::from(vec![label, Span::raw(" "), Span::raw(line)])
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(text), area);
+}
+
+// Translate the stored formatting of a cell into a terminal style
+fn terminal_style(cell_style: &CellStyle) -> Style {
+    let mut style = Style::default();
+
+    if let Some(Rgb(r, g, b)) = cell_style.fg {
+        style = style.fg(Color::Rgb(r, g, b));
+    }
+    if let Some(Rgb(r, g, b)) = cell_style.bg {
+        style = style.bg(Color::Rgb(r, g, b));
+    }
+    if cell_style.bold {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if cell_style.italic {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+
+    style
+}
+
+fn terminal_alignment(alignment: HorizontalAlignment) -> Option<Alignment> {
+    match alignment {
+        HorizontalAlignment::General => None,
+        HorizontalAlignment::Left => Some(Alignment::Left),
+        HorizontalAlignment::Center => Some(Alignment::Center),
+        HorizontalAlignment::Right => Some(Alignment::Right),
+    }
+}
+
+// Colors of the cells that differ from the :diff file, like the lines of a diff
+fn diff_style(kind: CellDiff) -> Style {
+    match kind {
+        CellDiff::Added => Style::default().bg(Color::Green).fg(Color::Black),
+        CellDiff::Removed => Style::default().bg(Color::Red).fg(Color::White),
+        CellDiff::Changed => Style::default().bg(Color::Magenta).fg(Color::White),
+    }
+}
+
+fn draw_spreadsheet(f: &mut Frame, app_state: &AppState, area: Rect, focused: bool) {
+    // Calculate visible row and column ranges, frozen rows and columns come first
+    let visible_rows = visible_rows(app_state);
+    let visible_cols = visible_columns(app_state);
+    let wrapped_height = selected_row_height(app_state);
+    let sheet = app_state.workbook.get_current_sheet();
+    let diff = app_state.diff.as_ref();
+
+    // Every match is highlighted while search highlighting is on, the one n/N jumped to
+    // stands out as long as the cursor stays on it
+    let (search_matches, current_match): (HashSet<&(usize, usize)>, _) =
+        if app_state.highlight_enabled && focused {
             (
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::LightCyan)),
-                Style::default().bg(Color::DarkGray).fg(Color::Gray),
-                Style::default(),
+                app_state.search_results.iter().collect(),
+                app_state
+                    .current_search_idx
+                    .and_then(|idx| app_state.search_results.get(idx))
+                    .filter(|&&pos| pos == app_state.selected_cell),
             )
         } else {
-            // In editing mode, dim the data display area
-            (
-                Block::default().borders(Borders::ALL),
-                Style::default().fg(Color::DarkGray),
-                Style::default().fg(Color::DarkGray), // Dimmed cell content
-            )
+            (HashSet::new(), None)
         };
 
+    let mut constraints = Vec::with_capacity(visible_cols.len() + 1);
+    constraints.push(Constraint::Length(app_state.row_number_width as u16)); // Dynamic row header width
+
+    for &col in &visible_cols {
+        constraints.push(Constraint::Length(app_state.get_column_width(col) as u16));
+    }
+
+    // Set table style based on current mode
+    let (table_block, header_style, cell_style) = if !focused {
+        // The pane of a split without the focus keeps a plain border
+        (
+            Block::default().borders(Borders::ALL),
+            Style::default().bg(Color::DarkGray).fg(Color::Gray),
+            Style::default(),
+        )
+    } else if matches!(app_state.input_mode, InputMode::Normal) {
+        // In Normal mode, add color to the border of the data display area to indicate current focus
+        (
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::LightCyan)),
+            Style::default().bg(Color::DarkGray).fg(Color::Gray),
+            Style::default(),
+        )
+    } else {
+        // In editing mode, dim the data display area
+        (
+            Block::default().borders(Borders::ALL),
+            Style::default().fg(Color::DarkGray),
+            Style::default().fg(Color::DarkGray), // Dimmed cell content
+        )
+    };
+
+    // The panes of a split are told apart by their sheet names
+    let table_block = if !focused || app_state.split.is_some() {
+        table_block.title(format!(" {} ", sheet.name))
+    } else {
+        table_block
+    };
+
+    // Frozen headers are underlined to mark the edge of the frozen panes
+    let frozen_header_style = header_style.add_modifier(Modifier::UNDERLINED);
+
     // Create header row
-    let mut header_cells = Vec::with_capacity(app_state.visible_cols + 1);
+    let mut header_cells = Vec::with_capacity(visible_cols.len() + 1);
     header_cells.push(Cell::from("").style(header_style));
 
     // Add column headers
-    for col in start_col..=end_col {
+    for &col in &visible_cols {
         let col_name = index_to_col_name(col);
-        header_cells.push(Cell::from(col_name).style(header_style));
+        let style = if col <= app_state.frozen_cols {
+            frozen_header_style
+        } else {
+            header_style
+        };
+        header_cells.push(Cell::from(col_name).style(style));
     }
 
     let header = Row::new(header_cells).height(1);
 
+    // Dominant type and share of conforming cells per column, greener the cleaner
+    let type_row = app_state.type_row.then(|| {
+        let mut cells = Vec::with_capacity(visible_cols.len() + 1);
+        cells.push(Cell::from("type").style(header_style));
+        for &col in &visible_cols {
+            let cell = match app_state.column_type_summary(col) {
+                Some(summary) => {
+                    let color = match summary.percent() {
+                        100 => Color::Green,
+                        90.. => Color::Yellow,
+                        _ => Color::LightRed,
+                    };
+                    Cell::from(summary.label()).style(header_style.fg(color))
+                }
+                None => Cell::from("").style(header_style),
+            };
+            cells.push(cell);
+        }
+        Row::new(cells)
+    });
+
     // Create data rows
-    let rows = (start_row..=end_row).map(|row| {
-        let mut cells = Vec::with_capacity(app_state.visible_cols + 1);
+    let rows = visible_rows.iter().map(|&row| {
+        let mut cells = Vec::with_capacity(visible_cols.len() + 1);
 
         // Add row header
-        cells.push(Cell::from(row.to_string()).style(header_style));
+        let row_header_style = if row <= app_state.frozen_rows {
+            frozen_header_style
+        } else {
+            header_style
+        };
+        cells.push(Cell::from(row.to_string()).style(row_header_style));
 
         // Add cells for this row
-        for col in start_col..=end_col {
-            let content = if app_state.selected_cell == (row, col)
+        for &col in &visible_cols {
+            let content = if focused
+                && app_state.selected_cell == (row, col)
                 && matches!(app_state.input_mode, InputMode::Editing)
             {
                 // Handle editing mode content
@@ -242,59 +708,106 @@ fn draw_spreadsheet(f: &mut Frame, app_state: &AppState, area: Rect) {
                 }
             } else {
                 // Handle normal cell content
-                let content = app_state.get_cell_content(row, col);
                 let col_width = app_state.get_column_width(col);
+                let content = app_state.get_display_content(row, col, col_width);
 
-                // Calculate display width
-                let display_width = content
-                    .chars()
-                    .fold(0, |acc, c| acc + if c.is_ascii() { 1 } else { 2 });
-
-                if display_width > col_width {
-                    // Truncate content if it's too wide
-                    let mut result = String::with_capacity(col_width);
-                    let mut current_width = 0;
+                if wrapped_height > 1 && row == app_state.selected_cell.0 {
+                    // Wrap the selected row instead of truncating it
+                    let mut lines = wrap_text(&content, col_width);
+                    lines.truncate(wrapped_height);
+                    lines.join("\n")
+                } else {
+                    // Calculate display width
+                    let display_width = content
+                        .chars()
+                        .fold(0, |acc, c| acc + if c.is_ascii() { 1 } else { 2 });
+
+                    if display_width > col_width {
+                        // Truncate content if it's too wide
+                        let mut result = String::with_capacity(col_width);
+                        let mut current_width = 0;
+
+                        for c in content.chars() {
+                            let char_width = if c.is_ascii() { 1 } else { 2 };
+                            if current_width + char_width < col_width {
+                                result.push(c);
+                                current_width += char_width;
+                            } else {
+                                break;
+                            }
+                        }
 
-                    for c in content.chars() {
-                        let char_width = if c.is_ascii() { 1 } else { 2 };
-                        if current_width + char_width < col_width {
-                            result.push(c);
-                            current_width += char_width;
-                        } else {
-                            break;
+                        if !content.is_empty() && result.len() < content.len() {
+                            result.push('…');
                         }
-                    }
 
-                    if !content.is_empty() && result.len() < content.len() {
-                        result.push('…');
+                        result
+                    } else {
+                        content
                     }
-
-                    result
-                } else {
-                    content
                 }
             };
 
-            // Determine cell style
-            let style = if app_state.selected_cell == (row, col) {
-                Style::default().bg(Color::White).fg(Color::Black)
-            } else if app_state.highlight_enabled && app_state.search_results.contains(&(row, col))
-            {
-                Style::default().bg(Color::Yellow).fg(Color::Black)
+            // Determine cell style, selection and search highlights override the cell colors
+            let stored_style = sheet.cell_style(row, col);
+            let mut base_style = terminal_style(stored_style);
+            // Formula cells stand out from typed-in values, keeping a color of their own
+            if sheet.cell(row, col).is_formula {
+                base_style = base_style.add_modifier(Modifier::ITALIC);
+                if stored_style.fg.is_none() {
+                    base_style = base_style.fg(Color::Cyan);
+                }
+            }
+            let style = if current_match == Some(&(row, col)) {
+                base_style.patch(
+                    Style::default()
+                        .bg(Color::LightRed)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else if focused && app_state.is_flashing(row, col) {
+                base_style.patch(Style::default().bg(Color::LightYellow).fg(Color::Black))
+            } else if app_state.selected_cell == (row, col) {
+                // The cursor of the pane without the focus is dimmed
+                let background = if focused { Color::White } else { Color::Gray };
+                base_style.patch(Style::default().bg(background).fg(Color::Black))
+            } else if focused && app_state.is_selected(row, col) {
+                base_style.patch(Style::default().bg(Color::Blue).fg(Color::White))
+            } else if search_matches.contains(&(row, col)) {
+                base_style.patch(Style::default().bg(Color::Yellow).fg(Color::Black))
+            } else if let Some(kind) = diff.and_then(|diff| diff.cell_diff(sheet, row, col)) {
+                base_style.patch(diff_style(kind))
+            } else if app_state.violates_rule(row, col) {
+                base_style.patch(
+                    Style::default()
+                        .fg(Color::LightRed)
+                        .add_modifier(Modifier::UNDERLINED),
+                )
             } else {
-                Style::default()
+                base_style
             };
 
-            cells.push(Cell::from(content).style(style));
+            let mut text = Text::from(content);
+            if let Some(alignment) = terminal_alignment(stored_style.alignment) {
+                for line in &mut text.lines {
+                    line.alignment = Some(alignment);
+                }
+            }
+
+            cells.push(Cell::from(text).style(style));
         }
 
-        Row::new(cells)
+        if row == app_state.selected_cell.0 {
+            Row::new(cells).height(wrapped_height as u16)
+        } else {
+            Row::new(cells)
+        }
     });
 
     // Create table with header and rows
     let table = Table::new(
         // Combine header and data rows
-        std::iter::once(header).chain(rows),
+        std::iter::once(header).chain(type_row).chain(rows),
     )
     .block(table_block)
     .style(cell_style)
@@ -304,36 +817,146 @@ fn draw_spreadsheet(f: &mut Frame, app_state: &AppState, area: Rect) {
 }
 
 // Parse command input and identify keywords and parameters for highlighting
-fn parse_command(input: &str) -> Vec<Span> {
+fn parse_command(input: &str) -> Vec<Span<'_>> {
     if input.is_empty() {
         return vec![Span::raw("")];
     }
 
     let known_commands = [
         "w",
+        "w!",
         "wq",
+        "wq!",
         "q",
         "q!",
         "x",
+        "x!",
         "y",
         "d",
         "put",
         "pu",
         "nohlsearch",
+        "nohl",
         "noh",
         "help",
         "delsheet",
+        "sheetdelete",
+        "sheetcopy",
+        "freeze",
+        "unfreeze",
+        "distinct",
+        "autofit",
+        "load",
+        "marks",
+        "messages",
+        "mes",
+        "formulas",
+        "mem",
+        "columns",
+        "undo",
+        "undolist",
+        "undol",
+        "profile",
+        "ec",
     ];
 
-    let commands_with_params = ["cw", "ej", "eja", "sheet", "dr", "dc"];
+    let commands_with_params = [
+        "cw",
+        "ej",
+        "eja",
+        "ec",
+        "csearch",
+        "goto",
+        "profile",
+        "sheet",
+        "sheetnew",
+        "sheetins",
+        "sheetrename",
+        "sheetcopy",
+        "sheetmove",
+        "dr",
+        "dc",
+        "freeze",
+        "distinct",
+        "append",
+        "autofit",
+        "undo",
+        "yank",
+    ];
 
-    let special_keywords = ["fit", "min", "all", "h", "v", "horizontal", "vertical"];
+    let special_keywords = [
+        "fit",
+        "min",
+        "all",
+        "h",
+        "v",
+        "horizontal",
+        "vertical",
+        "wrap",
+        "nowrap",
+        "--with-header",
+        "ignorecase",
+        "noignorecase",
+        "ic",
+        "noic",
+        "smartcase",
+        "nosmartcase",
+        "scs",
+        "noscs",
+        "recovery",
+        "norecovery",
+        "save",
+        "incsearch",
+        "noincsearch",
+        "is",
+        "nois",
+        "minimap",
+        "nominimap",
+        "memstatus",
+        "nomemstatus",
+        "noflash",
+        "autosave",
+        "noautosave",
+        "smartnumbers",
+        "nosmartnumbers",
+        "undofile",
+        "noundofile",
+        "udf",
+        "noudf",
+        "undolevels",
+        "ul",
+        "typerow",
+        "notyperow",
+        "renderers",
+        "norenderers",
+        "relativetime",
+        "norelativetime",
+        "col",
+        "column",
+        "row",
+        "md",
+        "markdown",
+        "csv",
+        "tsv",
+    ];
 
     // Check if input is a simple command without parameters
     if known_commands.contains(&input) {
         return vec![Span::styled(input, Style::default().fg(Color::Yellow))];
     }
 
+    // Substitute commands keep their pattern in one piece, e.g. s/foo bar/baz/g
+    for prefix in ["%s", "s"] {
+        if let Some(rest) = input.strip_prefix(prefix) {
+            if rest.starts_with(|c: char| !c.is_alphanumeric() && !c.is_whitespace()) {
+                return vec![
+                    Span::styled(prefix, Style::default().fg(Color::Yellow)),
+                    Span::styled(rest, Style::default().fg(Color::LightCyan)),
+                ];
+            }
+        }
+    }
+
     // Extract command and parameters
     let parts: Vec<&str> = input.split_whitespace().collect();
     if parts.is_empty() {
@@ -377,6 +1000,115 @@ fn parse_command(input: &str) -> Vec<Span> {
     vec![Span::raw(input)]
 }
 
+// Shade of a strip character for the share of non-empty cells it stands for
+fn density_symbol(density: f64) -> &'static str {
+    match density {
+        d if d <= 0.0 => " ",
+        d if d < 0.25 => "░",
+        d if d < 0.5 => "▒",
+        d if d < 0.75 => "▓",
+        _ => "█",
+    }
+}
+
+// One strip of the structure map, `marks` are (search match, changed) flags per character
+fn minimap_strip<'a>(
+    densities: impl Iterator<Item = f64>,
+    marks: &[(bool, bool)],
+    in_view: impl Fn(usize) -> bool,
+    marker: usize,
+) -> Vec<Span<'a>> {
+    densities
+        .enumerate()
+        .map(|(i, density)| {
+            let (matched, changed) = marks[i];
+            let mut style = if matched {
+                Style::default().fg(Color::Yellow)
+            } else if changed {
+                Style::default().fg(Color::LightMagenta)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            if in_view(i) {
+                style = style.bg(Color::DarkGray);
+            }
+            if i == marker {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+
+            // An empty spot still needs a visible character to show the marker
+            let symbol = match density_symbol(density) {
+                " " if i == marker => "·",
+                symbol => symbol,
+            };
+            Span::styled(symbol, style)
+        })
+        .collect()
+}
+
+fn draw_minimap(f: &mut Frame, app_state: &AppState, row_area: Rect, col_area: Rect) {
+    let Some(map) = &app_state.minimap else {
+        return;
+    };
+    let (rows_per_cell, cols_per_cell) = (map.rows_per_cell, map.cols_per_cell);
+    let row_cells = map.max_rows().div_ceil(rows_per_cell);
+    let col_cells = map.max_cols().div_ceil(cols_per_cell);
+
+    let mut row_marks = vec![(false, false); row_cells];
+    let mut col_marks = vec![(false, false); col_cells];
+    let mut mark = |(row, col): (usize, usize), matched: bool| {
+        let (Some(row_mark), Some(col_mark)) = (
+            row_marks.get_mut(row.saturating_sub(1) / rows_per_cell),
+            col_marks.get_mut(col.saturating_sub(1) / cols_per_cell),
+        ) else {
+            return;
+        };
+        for (is_match, is_changed) in [row_mark, col_mark] {
+            if matched {
+                *is_match = true;
+            } else {
+                *is_changed = true;
+            }
+        }
+    };
+    if app_state.highlight_enabled {
+        for &pos in &app_state.search_results {
+            mark(pos, true);
+        }
+    }
+    let sheet_name = app_state.workbook.get_current_sheet_name();
+    for pos in app_state.undo_history.changed_cells(&sheet_name) {
+        mark(pos, false);
+    }
+
+    let (marker_row, marker_col) = map.target.unwrap_or(app_state.selected_cell);
+    let start_row = app_state.start_row.max(app_state.frozen_rows + 1);
+    let end_row = start_row + app_state.scrollable_rows();
+    let start_col = app_state.start_col.max(app_state.frozen_cols + 1);
+    let end_col = start_col + app_state.visible_cols;
+    let overlaps = |i: usize, per_cell: usize, start: usize, end: usize| {
+        let first = i * per_cell + 1;
+        first < end && first + per_cell > start
+    };
+
+    let rows = minimap_strip(
+        (0..row_cells).map(|i| map.row_density(i * rows_per_cell + 1..(i + 1) * rows_per_cell + 1)),
+        &row_marks,
+        |i| overlaps(i, rows_per_cell, start_row, end_row),
+        marker_row.saturating_sub(1) / rows_per_cell,
+    );
+    let cols = minimap_strip(
+        (0..col_cells).map(|i| map.col_density(i * cols_per_cell + 1..(i + 1) * cols_per_cell + 1)),
+        &col_marks,
+        |i| overlaps(i, cols_per_cell, start_col, end_col),
+        marker_col.saturating_sub(1) / cols_per_cell,
+    );
+
+    let row_lines: Vec<Line> = rows.into_iter().map(Line::from).collect();
+    f.render_widget(Paragraph::new(row_lines), row_area);
+    f.render_widget(Paragraph::new(Line::from(cols)), col_area);
+}
+
 fn draw_info_panel(f: &mut Frame, app_state: &mut AppState, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -445,10 +1177,25 @@ fn draw_info_panel(f: &mut Frame, app_state: &mut AppState, area: Rect) {
         let title = format!(" Cell {cell_ref} Content ");
         let cell_block = Block::default().borders(Borders::ALL).title(title);
 
-        // Create paragraph with cell content
-        let cell_paragraph = Paragraph::new(content)
-            .block(cell_block)
-            .wrap(ratatui::widgets::Wrap { trim: false });
+        // Content longer than the panel scrolls with the mouse wheel, back at the top for
+        // another cell
+        let inner_area = cell_block.inner(chunks[0]);
+        let lines = wrap_text(&content, inner_area.width as usize);
+        if app_state.info_scroll_cell != (row, col) {
+            app_state.info_scroll_cell = (row, col);
+            app_state.info_scroll = 0;
+        }
+        let max_scroll = lines.len().saturating_sub(inner_area.height as usize);
+        app_state.info_scroll = app_state.info_scroll.min(max_scroll);
+        app_state.mouse_areas.content = chunks[0];
+
+        let visible_text = lines
+            .into_iter()
+            .skip(app_state.info_scroll)
+            .take(inner_area.height as usize)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let cell_paragraph = Paragraph::new(visible_text).block(cell_block);
 
         f.render_widget(cell_paragraph, chunks[0]);
     }
@@ -497,7 +1244,94 @@ fn draw_info_panel(f: &mut Frame, app_state: &mut AppState, area: Rect) {
 fn draw_status_bar(f: &mut Frame, app_state: &AppState, area: Rect) {
     match app_state.input_mode {
         InputMode::Normal => {
-            let status = "Input :help for operating instructions | hjkl=move [ ]=prev/next-sheet Enter=edit y=copy d=cut p=paste /=search N/n=prev/next-search-result :=command ";
+            let status = match app_state.selection_label() {
+                Some(range) => format!("-- VISUAL -- {range} | hjkl/Ctrl+arrows=extend v/Esc=end"),
+                None => "Input :help for operating instructions | hjkl=move [ ]=prev/next-sheet Enter=edit y=copy d=cut p=paste /=search N/n=prev/next-search-result :=command ".to_string(),
+            };
+
+            // Running saves and sheet loads, the last autosave and the memory in use are
+            // noted on the right
+            let mut area = area;
+            for activity in app_state.activities() {
+                // Esc stops a save, only while no selection is active
+                let hint = if activity.kind == ActivityKind::Save
+                    && app_state.selection_anchor.is_none()
+                {
+                    " Esc=cancel"
+                } else {
+                    ""
+                };
+                let width =
+                    ((activity.label.chars().count() + hint.len()) as u16 + 10).min(area.width / 2);
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Min(0), Constraint::Length(width)])
+                    .split(area);
+                draw_activity(f, &activity, hint, chunks[1]);
+                area = chunks[0];
+            }
+
+            let mut notes = Vec::new();
+            if let Some(label) = app_state
+                .selection_stats
+                .as_ref()
+                .filter(|_| app_state.stats_enabled)
+                .and_then(SelectionStats::label)
+            {
+                notes.push((format!(" {label} "), Style::default().fg(Color::Cyan)));
+            }
+            if let Some(result) = &app_state.autosave.last_result {
+                notes.push(match result {
+                    AutosaveResult::Saved(time) => (
+                        format!(" autosaved {time} "),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    AutosaveResult::Failed => (
+                        " autosave failed ".to_string(),
+                        Style::default().fg(Color::LightRed),
+                    ),
+                });
+            }
+            if let Some(usage) = &app_state.memory_status {
+                notes.push((
+                    format!(" mem {} ", format_bytes(usage.total())),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            if app_state.file_changed {
+                notes.push((
+                    " changed on disk ".to_string(),
+                    Style::default().fg(Color::LightYellow),
+                ));
+            }
+            // What the cell under the cursor was in the :diff file
+            if let Some(diff) = &app_state.diff {
+                let (row, col) = app_state.selected_cell;
+                let sheet = app_state.workbook.get_current_sheet();
+                if let Some(old) = diff.old_value(sheet, row, col) {
+                    let old: String = old.chars().take(40).collect();
+                    notes.push((
+                        format!(" {}: {old} ", diff.other_label(sheet)),
+                        diff_style(CellDiff::Changed),
+                    ));
+                }
+                notes.push((
+                    format!(" diff {} ", diff.file_name),
+                    Style::default().fg(Color::LightMagenta),
+                ));
+            }
+
+            for (text, style) in notes {
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([
+                        Constraint::Min(0),
+                        Constraint::Length(text.chars().count() as u16),
+                    ])
+                    .split(area);
+                f.render_widget(Paragraph::new(text).style(style), chunks[1]);
+                area = chunks[0];
+            }
 
             let status_widget = Paragraph::new(status)
                 .style(Style::default())
@@ -536,12 +1370,28 @@ fn draw_status_bar(f: &mut Frame, app_state: &AppState, area: Rect) {
                 "?"
             };
 
-            // Split the area for search prefix and search input
+            // Match count of the incremental preview, e.g. [2/15]
+            let query_typed = app_state
+                .text_area
+                .lines()
+                .iter()
+                .any(|line| !line.is_empty());
+            let match_count = if app_state.inc_search && query_typed {
+                match app_state.current_search_idx {
+                    Some(idx) => format!("[{}/{}]", idx + 1, app_state.search_results.len()),
+                    None => "no match".to_string(),
+                }
+            } else {
+                String::new()
+            };
+
+            // Split the area for search prefix, search input and match count
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
-                    Constraint::Length(1), // Search prefix
-                    Constraint::Min(1),    // Search input
+                    Constraint::Length(1),                        // Search prefix
+                    Constraint::Min(1),                           // Search input
+                    Constraint::Length(match_count.len() as u16), // Match count
                 ])
                 .split(area);
 
@@ -558,39 +1408,211 @@ fn draw_status_bar(f: &mut Frame, app_state: &AppState, area: Rect) {
             text_area.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED));
 
             f.render_widget(text_area.widget(), chunks[1]);
+
+            let count_style = if app_state.search_results.is_empty() {
+                Style::default().fg(Color::LightRed)
+            } else {
+                Style::default().fg(Color::LightYellow)
+            };
+            f.render_widget(Paragraph::new(match_count).style(count_style), chunks[2]);
         }
 
         InputMode::Help => {
             // No status bar in help mode
         }
 
-        InputMode::LazyLoading => {
-            // Show a status message for lazy loading mode
+        InputMode::CellPopup | InputMode::Messages => {
+            let status_widget = Paragraph::new("j/k=scroll Home/End=top/bottom Esc=close")
+                .style(Style::default())
+                .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::Replacing | InputMode::Searching => {
+            if let Some(activity) = app_state.activities().first() {
+                draw_activity(f, activity, " | Esc=cancel", area);
+            }
+        }
+
+        InputMode::Minimap => {
+            let target = app_state
+                .minimap
+                .as_ref()
+                .and_then(|map| map.target)
+                .unwrap_or(app_state.selected_cell);
+            let status_widget = Paragraph::new(format!(
+                "Structure map: {} | hjkl=move Enter=jump Esc=cancel",
+                cell_reference(target)
+            ))
+            .style(Style::default())
+            .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::SheetPicker => {
             let status_widget = Paragraph::new(
-                "Sheet data not loaded... Press Enter to load, [ and ] to switch sheets, :delsheet to delete current sheet, :q to quit, :q! to quit without saving",
+                "Type to filter | ↑↓=select Enter=switch to sheet Esc=close | ●=loaded ○=not loaded",
             )
-            .style(Style::default().fg(Color::LightYellow))
+            .style(Style::default())
             .alignment(ratatui::layout::Alignment::Left);
 
             f.render_widget(status_widget, area);
         }
-    }
-}
 
-fn draw_lazy_loading_overlay(f: &mut Frame, _app_state: &AppState, area: Rect) {
-    // Create a semi-transparent overlay
-    let overlay = Block::default()
-        .style(Style::default().bg(Color::Black).fg(Color::White))
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::LightCyan));
+        InputMode::UndoList => {
+            let status_widget = Paragraph::new(
+                "↑↓/jk=select Enter=undo or redo up to the change Esc=close | ●=in effect ○=undone",
+            )
+            .style(Style::default())
+            .alignment(ratatui::layout::Alignment::Left);
 
-    f.render_widget(Clear, area);
-    f.render_widget(overlay, area);
+            f.render_widget(status_widget, area);
+        }
 
-    // Calculate center position for the message
-    let message = "Press Enter to load the sheet, [ and ] to switch sheets";
-    let width = message.len() as u16;
-    let x = area.x + (area.width.saturating_sub(width)) / 2;
+        InputMode::DiffList => {
+            let status_widget = Paragraph::new(
+                "↑↓/jk=select Enter=go to the cell in the first sheet Esc=close | ]c/[c=next/previous difference",
+            )
+            .style(Style::default())
+            .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::ConfirmDeleteSheet => {
+            let status_widget = Paragraph::new("y=delete the sheet n/Esc=keep it")
+                .style(Style::default())
+                .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::ConfirmReload => {
+            let status_widget =
+                Paragraph::new("r=reload the file k/Esc=keep the workbook as it is")
+                    .style(Style::default())
+                    .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::ColumnManager => {
+            let status_widget = Paragraph::new(
+                "↑↓=select J/K=move Space=show/hide p=pin ←→=width Enter=apply Esc=cancel",
+            )
+            .style(Style::default())
+            .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::ColumnMapping => {
+            let status_widget = Paragraph::new(
+                "↑↓=select column ←→=change target s=skip n=new column Enter=append Esc=cancel",
+            )
+            .style(Style::default())
+            .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::DistinctPicker => {
+            let status_widget = Paragraph::new(
+                "Type to filter | ↑↓=select Enter=jump to first occurrence Esc=close",
+            )
+            .style(Style::default())
+            .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+
+        InputMode::LazyLoading => {
+            if current_sheet_loading(app_state).is_some() {
+                let activities = app_state.activities();
+                if let Some(activity) = activities
+                    .iter()
+                    .find(|activity| activity.kind == ActivityKind::SheetLoad)
+                {
+                    draw_activity(
+                        f,
+                        activity,
+                        " | Esc=cancel, [ and ] to switch sheets, :q to quit, :q! to quit without saving",
+                        area,
+                    );
+                    return;
+                }
+            }
+
+            // Show a status message for lazy loading mode
+            let message = "Sheet data not loaded... Press Enter or :load to load, [ and ] to switch sheets, :sheetdelete to delete current sheet, :q to quit, :q! to quit without saving";
+            let status_widget = Paragraph::new(message)
+                .style(Style::default().fg(Color::LightYellow))
+                .alignment(ratatui::layout::Alignment::Left);
+
+            f.render_widget(status_widget, area);
+        }
+    }
+}
+
+// A progress bar for a running operation, or a spinner when how much is left isn't known
+fn draw_activity(f: &mut Frame, activity: &Activity, hint: &str, area: Rect) {
+    match activity.ratio {
+        Some(ratio) => {
+            let ratio = ratio.clamp(0.0, 1.0);
+            let gauge = Gauge::default()
+                .gauge_style(Style::default().fg(Color::LightCyan).bg(Color::DarkGray))
+                .ratio(ratio)
+                .label(format!("{} {:.0}%{hint}", activity.label, ratio * 100.0));
+            f.render_widget(gauge, area);
+        }
+        None => {
+            let elapsed = activity.elapsed;
+            let frame = SPINNER_FRAMES[(elapsed.as_millis() / 100) as usize % SPINNER_FRAMES.len()];
+            let text = format!(" {frame} {}… {}s{hint}", activity.label, elapsed.as_secs());
+            f.render_widget(
+                Paragraph::new(text).style(Style::default().fg(Color::LightYellow)),
+                area,
+            );
+        }
+    }
+}
+
+// How long the current sheet has been loading in the background, if it is
+fn current_sheet_loading(app_state: &AppState) -> Option<Duration> {
+    let (name, elapsed) = app_state.workbook.loading_sheet()?;
+    (name == app_state.workbook.get_current_sheet_name()).then_some(elapsed)
+}
+
+fn draw_lazy_loading_overlay(f: &mut Frame, app_state: &AppState, area: Rect) {
+    // Create a semi-transparent overlay
+    let overlay = Block::default()
+        .style(Style::default().bg(Color::Black).fg(Color::White))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+
+    f.render_widget(Clear, area);
+    f.render_widget(overlay, area);
+
+    // Calculate center position for the message
+    let message = match current_sheet_loading(app_state) {
+        Some(elapsed) => {
+            let frame = SPINNER_FRAMES[(elapsed.as_millis() / 100) as usize % SPINNER_FRAMES.len()];
+            let done = app_state
+                .workbook
+                .sheet_load_progress()
+                .map(|ratio| format!(" {:.0}%", ratio * 100.0))
+                .unwrap_or_default();
+            format!(
+                "{frame} Loading the sheet...{done} {}s, [ and ] to switch sheets",
+                elapsed.as_secs()
+            )
+        }
+        None => "Press Enter to load the sheet, [ and ] to switch sheets".to_string(),
+    };
+    let width = message.chars().count() as u16;
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
     let y = area.y + area.height / 2;
 
     if x < area.width && y < area.height {
@@ -643,7 +1665,7 @@ fn draw_help_popup(f: &mut Frame, app_state: &mut AppState, area: Rect) {
     app_state.help_visible_lines = visible_lines;
 
     let line_count = app_state.help_text.lines().count();
-    let max_scroll = line_count.saturating_sub(visible_lines).max(0);
+    let max_scroll = line_count.saturating_sub(visible_lines);
 
     app_state.help_scroll = app_state.help_scroll.min(max_scroll);
 
@@ -680,7 +1702,637 @@ fn draw_help_popup(f: &mut Frame, app_state: &mut AppState, area: Rect) {
     f.render_widget(help_paragraph, popup_area);
 }
 
-fn draw_title_with_tabs(f: &mut Frame, app_state: &AppState, area: Rect) {
+fn draw_cell_popup(f: &mut Frame, app_state: &mut AppState, area: Rect) {
+    // :messages reuses the popup for the notification history
+    let showing_messages = matches!(app_state.input_mode, InputMode::Messages);
+    let (row, col) = app_state.selected_cell;
+    let content = if showing_messages {
+        app_state.message_history.join("\n")
+    } else {
+        // JSON is easier to read indented
+        let content = app_state.get_cell_content(row, col);
+        pretty_json(&content).unwrap_or(content)
+    };
+
+    // Popup covers most of the screen, centered
+    let popup_width = (area.width * 4 / 5).max(20).min(area.width);
+    let popup_height = (area.height * 3 / 4).max(5).min(area.height);
+    let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    // Track scrolling limits based on the wrapped content
+    let inner_width = popup_width.saturating_sub(4) as usize; // Borders and padding
+    let lines = wrap_text(&content, inner_width);
+    app_state.cell_popup_lines = lines.len();
+    app_state.cell_popup_visible_lines = popup_height.saturating_sub(2) as usize;
+    let max_scroll = lines
+        .len()
+        .saturating_sub(app_state.cell_popup_visible_lines);
+    app_state.cell_popup_scroll = app_state.cell_popup_scroll.min(max_scroll);
+
+    let title = if showing_messages {
+        format!(
+            " Messages ({}) [ESC to close] ",
+            app_state.message_history.len()
+        )
+    } else {
+        format!(
+            " Cell {} ({} lines) [ESC to close] ",
+            cell_reference(app_state.selected_cell),
+            lines.len()
+        )
+    };
+
+    let block = Block::default()
+        .title(title)
+        .title_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+
+    let inner_area = block.inner(popup_area);
+    let padded_area = Rect {
+        x: inner_area.x + 1,
+        y: inner_area.y,
+        width: inner_area.width.saturating_sub(2),
+        height: inner_area.height,
+    };
+
+    let visible_text = lines
+        .into_iter()
+        .skip(app_state.cell_popup_scroll)
+        .take(app_state.cell_popup_visible_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(block, popup_area);
+    f.render_widget(Paragraph::new(visible_text), padded_area);
+}
+
+fn draw_distinct_picker(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let Some(picker) = &app_state.distinct_picker else {
+        return;
+    };
+
+    let filtered = picker.filtered();
+
+    // Popup takes up to 60 columns and 20 rows, centered on screen
+    let popup_width = 60.min(area.width.saturating_sub(4));
+    let popup_height = 20.min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let title = format!(
+        " Distinct values in column {} ({}/{}) ",
+        index_to_col_name(picker.col),
+        filtered.len(),
+        picker.values.len()
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+
+    let inner_area = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    if inner_area.height == 0 {
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner_area);
+
+    let query_line = Line::from(vec![
+        Span::styled("Filter: ", Style::default().fg(Color::Yellow)),
+        Span::raw(picker.query.as_str()),
+        Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+    ]);
+    f.render_widget(Paragraph::new(query_line), chunks[0]);
+
+    // Keep the selected entry inside the visible part of the list
+    let list_height = chunks[1].height as usize;
+    let offset = if list_height == 0 {
+        0
+    } else {
+        picker.selected.saturating_sub(list_height - 1)
+    };
+
+    let count_width = filtered
+        .iter()
+        .map(|(_, count)| count.to_string().len())
+        .max()
+        .unwrap_or(1);
+
+    let lines: Vec<Line> = filtered
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+        .map(|(i, (value, count))| {
+            let text = format!("{count:>count_width$}  {}", value.replace('\n', " "));
+            if i == picker.selected {
+                Line::styled(text, Style::default().bg(Color::White).fg(Color::Black))
+            } else {
+                Line::raw(text)
+            }
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), chunks[1]);
+}
+
+fn draw_sheet_picker(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let Some(picker) = &app_state.sheet_picker else {
+        return;
+    };
+
+    let sheet_names = app_state.workbook.get_sheet_names();
+    let filtered = picker.filtered(&sheet_names);
+    let current_index = app_state.workbook.get_current_sheet_index();
+
+    let popup_width = 60.min(area.width.saturating_sub(4));
+    let popup_height = 20.min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let title = format!(" Sheets ({}/{}) ", filtered.len(), sheet_names.len());
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+
+    let inner_area = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    if inner_area.height == 0 {
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner_area);
+
+    let query_line = Line::from(vec![
+        Span::styled("Filter: ", Style::default().fg(Color::Yellow)),
+        Span::raw(picker.query.as_str()),
+        Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+    ]);
+    f.render_widget(Paragraph::new(query_line), chunks[0]);
+
+    let list_height = chunks[1].height as usize;
+    let offset = if list_height == 0 {
+        0
+    } else {
+        picker.selected.saturating_sub(list_height - 1)
+    };
+    let number_width = sheet_names.len().to_string().len();
+
+    let lines: Vec<Line> = filtered
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+        .map(|(i, (index, name))| {
+            // ● loaded, ○ not loaded yet with lazy loading, * the current sheet
+            let loaded = if app_state.workbook.is_sheet_loaded(*index) {
+                '●'
+            } else {
+                '○'
+            };
+            let current = if *index == current_index { '*' } else { ' ' };
+            let text = format!("{:>number_width$} {loaded}{current} {name}", index + 1);
+
+            if i == picker.selected {
+                Line::styled(text, Style::default().bg(Color::White).fg(Color::Black))
+            } else if *index == current_index {
+                Line::styled(text, Style::default().fg(Color::LightCyan))
+            } else {
+                Line::raw(text)
+            }
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), chunks[1]);
+}
+
+fn draw_undo_list(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let Some(list) = &app_state.undo_list else {
+        return;
+    };
+
+    let entries = app_state.undo_list_entries();
+    let current = app_state.undo_list_current();
+
+    let popup_width = 80.min(area.width.saturating_sub(4));
+    let popup_height = 20.min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let title = format!(
+        " Undo history ({} in effect, {} undone, {}, limit {}) ",
+        entries.len() - current - 1,
+        current,
+        format_bytes(app_state.undo_history.memory_used()),
+        app_state.undo_history.levels()
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+
+    let inner_area = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let list_height = inner_area.height as usize;
+    let offset = if list_height == 0 {
+        0
+    } else {
+        list.selected.saturating_sub(list_height - 1)
+    };
+
+    let lines: Vec<Line> = entries
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+        .map(|(i, entry)| {
+            // ● in effect, ○ undone, * the current state
+            let applied = if entry.applied { '●' } else { '○' };
+            let marker = if i == current { '*' } else { ' ' };
+            let text = format!("{applied}{marker} {}", entry.summary);
+
+            if i == list.selected {
+                Line::styled(text, Style::default().bg(Color::White).fg(Color::Black))
+            } else if i == current {
+                Line::styled(text, Style::default().fg(Color::LightCyan))
+            } else if !entry.applied {
+                Line::styled(text, Style::default().fg(Color::DarkGray))
+            } else {
+                Line::raw(text)
+            }
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), inner_area);
+}
+
+fn draw_diff_list(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let Some(list) = &app_state.diff_list else {
+        return;
+    };
+
+    let popup_width = 80.min(area.width.saturating_sub(4));
+    let popup_height = 20.min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let title = format!(
+        " {} vs {}: {} cell(s) differ ",
+        list.first,
+        list.second,
+        list.entries.len()
+    );
+    let mut block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+    if let Some(note) = &list.size_note {
+        block = block.title(Title::from(format!(" {note} ")).position(Position::Bottom));
+    }
+
+    let inner_area = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let list_height = inner_area.height as usize;
+    let offset = if list_height == 0 {
+        0
+    } else {
+        list.selected.saturating_sub(list_height - 1)
+    };
+
+    // Values are cut short so both fit on the line
+    let value_width = (inner_area.width as usize).saturating_sub(22) / 2;
+    let shorten = |value: &str| -> String {
+        if value.chars().count() > value_width {
+            let mut short: String = value.chars().take(value_width.saturating_sub(1)).collect();
+            short.push('…');
+            short
+        } else {
+            value.to_string()
+        }
+    };
+
+    let lines: Vec<Line> = list
+        .entries
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+        .map(|(i, entry)| {
+            let kind = match entry.kind {
+                CellDiff::Added => "only 1st",
+                CellDiff::Removed => "only 2nd",
+                CellDiff::Changed => "changed",
+            };
+            let text = format!(
+                "{:<8} {kind:<9} {} → {}",
+                cell_reference(entry.cell),
+                shorten(&entry.first),
+                shorten(&entry.second)
+            );
+
+            if i == list.selected {
+                Line::styled(text, Style::default().bg(Color::White).fg(Color::Black))
+            } else {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<8} ", cell_reference(entry.cell)),
+                        Style::default(),
+                    ),
+                    Span::styled(format!("{kind:<9}"), diff_style(entry.kind)),
+                    Span::raw(format!(
+                        " {} → {}",
+                        shorten(&entry.first),
+                        shorten(&entry.second)
+                    )),
+                ])
+            }
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), inner_area);
+}
+
+fn draw_confirm_delete_sheet(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let sheet_name = app_state.workbook.get_current_sheet_name();
+    let current_index = app_state.workbook.get_current_sheet_index();
+
+    let size = if app_state.workbook.is_sheet_loaded(current_index) {
+        let sheet = app_state.workbook.get_current_sheet();
+        format!("{} rows x {} columns", sheet.max_rows, sheet.max_cols)
+    } else {
+        "not loaded yet".to_string()
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Delete sheet "),
+            Span::styled(sheet_name, Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!(" ({size})?")),
+        ]),
+        Line::raw(""),
+        Line::raw("u undoes the deletion until the file is saved"),
+        Line::raw(""),
+        Line::from(vec![
+            Span::styled("y", Style::default().fg(Color::LightRed)),
+            Span::raw(" delete   "),
+            Span::styled("n", Style::default().fg(Color::LightGreen)),
+            Span::raw("/"),
+            Span::styled("Esc", Style::default().fg(Color::LightGreen)),
+            Span::raw(" keep"),
+        ]),
+    ];
+
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+    let popup_width = (content_width + 4).min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" Delete sheet ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightRed));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(paragraph, popup_area);
+}
+
+fn draw_confirm_reload(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let file_name = app_state
+        .file_path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+    let local = if app_state.workbook.is_modified() {
+        "Reloading drops the changes you haven't saved"
+    } else {
+        "You have no unsaved changes"
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(file_name, Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" was changed by another program"),
+        ]),
+        Line::raw(""),
+        Line::raw(local),
+        Line::raw(""),
+        Line::from(vec![
+            Span::styled("r", Style::default().fg(Color::LightRed)),
+            Span::raw(" reload   "),
+            Span::styled("k", Style::default().fg(Color::LightGreen)),
+            Span::raw("/"),
+            Span::styled("Esc", Style::default().fg(Color::LightGreen)),
+            Span::raw(" keep"),
+        ]),
+    ];
+
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+    let popup_width = (content_width + 4).min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height);
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" File changed on disk ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightYellow));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(paragraph, popup_area);
+}
+
+fn draw_column_mapping(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let Some(mapping) = &app_state.column_mapping else {
+        return;
+    };
+
+    let popup_width = 70.min(area.width.saturating_sub(4));
+    let popup_height = 20.min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let file_name = mapping
+        .path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let block = Block::default()
+        .title(format!(" Map columns of {file_name} "))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+
+    let inner_area = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let list_height = inner_area.height as usize;
+    if list_height == 0 {
+        return;
+    }
+    let offset = mapping.selected.saturating_sub(list_height - 1);
+
+    let source_labels: Vec<String> = mapping
+        .source_headers
+        .iter()
+        .enumerate()
+        .map(|(idx, header)| {
+            if header.is_empty() {
+                index_to_col_name(idx + 1)
+            } else {
+                format!("{}: {}", index_to_col_name(idx + 1), header)
+            }
+        })
+        .collect();
+    let label_width = source_labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let duplicates = mapping.duplicate_targets();
+
+    let lines: Vec<Line> = mapping
+        .targets
+        .iter()
+        .zip(&source_labels)
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+        .map(|(i, (target, label))| {
+            let text = format!(
+                "{label:<label_width$}  →  {}",
+                mapping.target_label(*target)
+            );
+
+            let style = match target {
+                _ if i == mapping.selected => Style::default().bg(Color::White).fg(Color::Black),
+                MappingTarget::Column(col) if duplicates.contains(col) => {
+                    Style::default().fg(Color::LightRed)
+                }
+                MappingTarget::Skip => Style::default().fg(Color::DarkGray),
+                MappingTarget::New => Style::default().fg(Color::LightGreen),
+                MappingTarget::Column(_) => Style::default(),
+            };
+            Line::styled(text, style)
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), inner_area);
+}
+
+fn draw_column_manager(f: &mut Frame, app_state: &AppState, area: Rect) {
+    let Some(manager) = &app_state.column_manager else {
+        return;
+    };
+
+    let popup_width = 70.min(area.width.saturating_sub(4));
+    let popup_height = 20.min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(format!(
+            " Columns of {} ",
+            app_state.workbook.get_current_sheet_name()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan));
+
+    let inner_area = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let list_height = inner_area.height as usize;
+    if list_height == 0 {
+        return;
+    }
+    let offset = manager.selected.saturating_sub(list_height - 1);
+
+    let lines: Vec<Line> = manager
+        .columns
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(list_height)
+        .map(|(i, column)| {
+            let position = index_to_col_name(i + 1);
+            let moved_from = if column.col == i + 1 {
+                String::new()
+            } else {
+                format!(" (was {})", index_to_col_name(column.col))
+            };
+            let text = format!(
+                "{} {} {position:>3}  width {:>2}  {}{moved_from}",
+                if column.hidden { "[ ]" } else { "[x]" },
+                if i < manager.pinned { "pin" } else { "   " },
+                column.width,
+                column.header,
+            );
+
+            let style = if i == manager.selected {
+                Style::default().bg(Color::White).fg(Color::Black)
+            } else if column.hidden {
+                Style::default().fg(Color::DarkGray)
+            } else if i < manager.pinned {
+                Style::default().fg(Color::LightYellow)
+            } else {
+                Style::default()
+            };
+            Line::styled(text, style)
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), inner_area);
+}
+
+// Returns where each sheet tab shown was drawn, with the index of its sheet
+fn draw_title_with_tabs(f: &mut Frame, app_state: &AppState, area: Rect) -> Vec<(Rect, usize)> {
     let is_editing = matches!(app_state.input_mode, InputMode::Editing);
     let sheet_names = app_state.workbook.get_sheet_names();
     let current_index = app_state.workbook.get_current_sheet_index();
@@ -691,7 +2343,11 @@ fn draw_title_with_tabs(f: &mut Frame, app_state: &AppState, area: Rect) {
         .and_then(|n| n.to_str())
         .unwrap_or("Untitled");
 
-    let title_content = format!(" {file_name} ");
+    // The buffer number only shows once several workbooks are open
+    let title_content = match app_state.buffer_position() {
+        (_, 1) => format!(" {file_name} "),
+        (number, count) => format!(" {file_name} [{number}/{count}] "),
+    };
 
     let title_width = title_content
         .chars()
@@ -806,4 +2462,10 @@ fn draw_title_with_tabs(f: &mut Frame, app_state: &AppState, area: Rect) {
         let indicator_widget = Paragraph::new(more_indicator).style(indicator_style);
         f.render_widget(indicator_widget, indicator_rect);
     }
+
+    visible_tabs
+        .iter()
+        .zip(tab_layout.iter())
+        .map(|(&sheet_idx, &rect)| (rect, sheet_idx))
+        .collect()
 }
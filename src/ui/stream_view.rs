@@ -0,0 +1,327 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+use std::time::Duration;
+use unicode_width::UnicodeWidthChar;
+
+use crate::app::{StreamMode, StreamViewer};
+use crate::ui::render::{restore_terminal, setup_terminal};
+use crate::utils::{cell_reference, index_to_col_name};
+
+// How long indexing and search may hold the UI before the next redraw and key check
+const STREAM_SLICE: Duration = Duration::from_millis(30);
+
+/// Runs the read-only viewer of --stream until it is quit
+pub fn run_stream_viewer(mut viewer: StreamViewer) -> Result<()> {
+    let mut terminal = setup_terminal()?;
+
+    while !viewer.should_quit {
+        terminal.draw(|f| draw(f, &mut viewer))?;
+
+        // Don't wait for input while indexing or searching, only check for keys
+        let timeout = if viewer.is_busy() {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(50)
+        };
+
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    handle_key(&mut viewer, key);
+                }
+            }
+        }
+
+        viewer.tick(STREAM_SLICE);
+    }
+
+    restore_terminal(&mut terminal)?;
+    Ok(())
+}
+
+fn handle_key(viewer: &mut StreamViewer, key: KeyEvent) {
+    match viewer.mode {
+        StreamMode::Command | StreamMode::Search => match key.code {
+            KeyCode::Enter => viewer.submit_input(),
+            KeyCode::Esc => viewer.cancel_input(),
+            KeyCode::Backspace if viewer.input_buffer.is_empty() => viewer.cancel_input(),
+            KeyCode::Backspace => {
+                viewer.input_buffer.pop();
+            }
+            KeyCode::Char(c) => viewer.input_buffer.push(c),
+            _ => {}
+        },
+        StreamMode::Normal => handle_normal_key(viewer, key),
+    }
+}
+
+fn handle_normal_key(viewer: &mut StreamViewer, key: KeyEvent) {
+    let g_pressed = std::mem::take(&mut viewer.g_pressed);
+    viewer.message = None;
+
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        match key.code {
+            KeyCode::Char('f') => viewer.scroll_page(true),
+            KeyCode::Char('b') => viewer.scroll_page(false),
+            KeyCode::Char('d') => viewer.scroll_half_page(true),
+            KeyCode::Char('u') => viewer.scroll_half_page(false),
+            KeyCode::Char('c') => viewer.should_quit = true,
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('h') | KeyCode::Left => viewer.move_cursor(0, -1),
+        KeyCode::Char('j') | KeyCode::Down => viewer.move_cursor(1, 0),
+        KeyCode::Char('k') | KeyCode::Up => viewer.move_cursor(-1, 0),
+        KeyCode::Char('l') | KeyCode::Right => viewer.move_cursor(0, 1),
+        KeyCode::PageDown => viewer.scroll_page(true),
+        KeyCode::PageUp => viewer.scroll_page(false),
+        KeyCode::Char('g') if g_pressed => viewer.jump_to_first_row(),
+        KeyCode::Char('g') => viewer.g_pressed = true,
+        KeyCode::Char('G') => viewer.jump_to_last_row(),
+        KeyCode::Char('0') | KeyCode::Home => viewer.jump_to_first_col(),
+        KeyCode::Char('$') | KeyCode::End => viewer.jump_to_last_col(),
+        KeyCode::Char('[') => viewer.switch_sheet(false),
+        KeyCode::Char(']') => viewer.switch_sheet(true),
+        KeyCode::Char(':') => viewer.start_input(StreamMode::Command),
+        KeyCode::Char('/') => viewer.start_input(StreamMode::Search),
+        KeyCode::Char('n') => viewer.search_next(),
+        KeyCode::Char('N' | '?') => {
+            viewer.message = Some("Streaming mode only searches forward, use / and n".to_string());
+        }
+        KeyCode::Char('q') => viewer.should_quit = true,
+        KeyCode::Esc => viewer.cancel_search(),
+        _ => {}
+    }
+}
+
+// Cuts `content` to `width` display columns, marking the cut with an ellipsis
+fn fit_to_width(content: &str, width: usize) -> String {
+    let mut result = String::new();
+    let mut used = 0;
+
+    for c in content
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+    {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width {
+            result.pop();
+            result.push('…');
+            break;
+        }
+        result.push(c);
+        used += char_width;
+    }
+    result
+}
+
+fn draw(f: &mut Frame, viewer: &mut StreamViewer) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Title
+            Constraint::Length(1), // Content of the selected cell
+            Constraint::Min(3),    // Grid
+            Constraint::Length(1), // Status or prompt
+        ])
+        .split(f.size());
+
+    // Rows are read before drawing so the grid knows its columns
+    let row_number_width = viewer.stream.rows().max(1).to_string().len() + 1;
+    viewer.visible_rows = chunks[2].height.saturating_sub(3).max(1) as usize;
+    viewer.grid_width = (chunks[2].width as usize).saturating_sub(row_number_width + 3);
+    viewer.handle_scrolling();
+
+    let mut rows = Vec::with_capacity(viewer.visible_rows);
+    for row in viewer.start_row..viewer.start_row + viewer.visible_rows {
+        let cells = match viewer.stream.row(row) {
+            Ok(cells) => cells.map(<[String]>::to_vec),
+            Err(e) => {
+                viewer.message = Some(format!("Failed to read row {row}: {e}"));
+                break;
+            }
+        };
+        match cells {
+            Some(cells) => rows.push(cells),
+            // Rows still being read are shown as placeholders until the next tick fills them
+            None if viewer.stream.is_reading() => {
+                let last_row = viewer
+                    .stream
+                    .rows()
+                    .min(viewer.start_row + viewer.visible_rows - 1);
+                rows.extend((row..=last_row).map(|_| vec!["…".to_string()]));
+                break;
+            }
+            None => break,
+        }
+    }
+    viewer.fit_columns(&rows);
+
+    draw_title(f, viewer, chunks[0]);
+    draw_cell_bar(f, viewer, &rows, chunks[1]);
+    draw_grid(f, viewer, &rows, chunks[2]);
+    draw_status(f, viewer, chunks[3]);
+}
+
+fn draw_title(f: &mut Frame, viewer: &StreamViewer, area: Rect) {
+    let file_name = viewer
+        .stream
+        .path()
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let progress = match viewer.stream.index_progress() {
+        _ if viewer.stream.is_indexed() => format!("{} rows", viewer.stream.rows()),
+        Some(progress) => format!(
+            "{} rows so far, indexing {:.0}%",
+            viewer.stream.rows(),
+            progress * 100.0
+        ),
+        None => format!("{} rows so far, indexing", viewer.stream.rows()),
+    };
+    let progress = if viewer.stream.is_reading() {
+        format!("{progress}, reading rows")
+    } else {
+        progress
+    };
+    let file_name = match viewer.stream.sheet_name() {
+        Some(sheet) => format!("{file_name} [{sheet}]"),
+        None => file_name,
+    };
+
+    let title = Line::from(vec![
+        Span::styled(
+            format!(" {file_name} "),
+            Style::default()
+                .bg(Color::LightCyan)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!(" read-only streaming | {progress}"),
+            Style::default().fg(Color::Gray),
+        ),
+    ]);
+    f.render_widget(Paragraph::new(title), area);
+}
+
+fn draw_cell_bar(f: &mut Frame, viewer: &StreamViewer, rows: &[Vec<String>], area: Rect) {
+    let (row, col) = viewer.selected_cell;
+    let value = rows
+        .get(row.wrapping_sub(viewer.start_row))
+        .and_then(|cells| cells.get(col - 1))
+        .map_or("", String::as_str);
+
+    let label = format!(" {} ", cell_reference(viewer.selected_cell));
+    let width = (area.width as usize).saturating_sub(label.len() + 1);
+    let line = Line::from(vec![
+        Span::styled(
+            label,
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" "),
+        Span::raw(fit_to_width(value, width)),
+    ]);
+    f.render_widget(Paragraph::new(line), area);
+}
+
+fn draw_grid(f: &mut Frame, viewer: &StreamViewer, rows: &[Vec<String>], area: Rect) {
+    let row_number_width = viewer.stream.rows().max(1).to_string().len() + 1;
+    let header_style = Style::default().bg(Color::DarkGray).fg(Color::Gray);
+
+    // Columns from start_col that fit, at least one
+    let mut visible_cols = Vec::new();
+    let mut used = 0;
+    for col in viewer.start_col..=viewer.stream.max_cols().max(1) {
+        let width = viewer.column_width(col);
+        if !visible_cols.is_empty() && used + width > viewer.grid_width {
+            break;
+        }
+        visible_cols.push(col);
+        used += width;
+    }
+
+    let mut constraints = vec![Constraint::Length(row_number_width as u16)];
+    constraints.extend(
+        visible_cols
+            .iter()
+            .map(|&col| Constraint::Length(viewer.column_width(col) as u16)),
+    );
+
+    let header = Row::new(
+        std::iter::once(Cell::from("").style(header_style)).chain(
+            visible_cols
+                .iter()
+                .map(|&col| Cell::from(index_to_col_name(col)).style(header_style)),
+        ),
+    );
+
+    let table_rows = rows.iter().enumerate().map(|(offset, cells)| {
+        let row = viewer.start_row + offset;
+        let row_cells = visible_cols.iter().map(|&col| {
+            let width = viewer.column_width(col).saturating_sub(1);
+            let value = cells.get(col - 1).map_or("", String::as_str);
+            let cell = Cell::from(fit_to_width(value, width));
+
+            if viewer.selected_cell == (row, col) {
+                cell.style(Style::default().bg(Color::White).fg(Color::Black))
+            } else {
+                cell
+            }
+        });
+
+        Row::new(std::iter::once(Cell::from(row.to_string()).style(header_style)).chain(row_cells))
+    });
+
+    let table = Table::new(table_rows)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::LightCyan)),
+        )
+        .widths(&constraints)
+        .column_spacing(0);
+
+    f.render_widget(table, area);
+}
+
+fn draw_status(f: &mut Frame, viewer: &StreamViewer, area: Rect) {
+    let line = match viewer.mode {
+        StreamMode::Command => Line::from(format!(":{}", viewer.input_buffer)),
+        StreamMode::Search => Line::from(format!("/{}", viewer.input_buffer)),
+        StreamMode::Normal => {
+            if let Some(search) = &viewer.search {
+                Line::styled(
+                    format!(
+                        "Searching for {}... {} rows scanned | Esc=cancel",
+                        search.query(),
+                        search.rows_scanned()
+                    ),
+                    Style::default().fg(Color::Yellow),
+                )
+            } else if let Some(message) = &viewer.message {
+                Line::from(message.as_str())
+            } else {
+                Line::from(
+                    "Read-only | hjkl=move gg/G=first/last row 0/$=first/last column [ ]=sheet /=search n=next :N=go to row :q=quit",
+                )
+            }
+        }
+    };
+    f.render_widget(Paragraph::new(line), area);
+}
@@ -9,9 +9,11 @@ pub enum Direction {
     Down,
 }
 
-/// Find non-empty cell in specified direction
+/// Find the cell Ctrl+arrow jumps to in Excel: inside a block of filled cells
+/// the last cell of the block, otherwise the first filled cell of the next
+/// block, or the edge of the sheet when there is none
 ///
-/// Returns the position of found cell, or None if already at boundary
+/// Returns None if already at boundary
 #[must_use]
 pub fn find_non_empty_cell(
     sheet: &Sheet,
@@ -19,142 +21,31 @@ pub fn find_non_empty_cell(
     direction: Direction,
     max_bounds: (usize, usize),
 ) -> Option<(usize, usize)> {
-    let (row, col) = current_pos;
     let (max_row, max_col) = max_bounds;
-
-    // Check if already at boundary
-    match direction {
-        Direction::Left if col <= 1 => return None,
-        Direction::Right if col >= max_col => return None,
-        Direction::Up if row <= 1 => return None,
-        Direction::Down if row >= max_row => return None,
-        _ => {}
-    }
-
-    // Check if current cell is empty
-    let current_cell_is_empty = row >= sheet.data.len()
-        || col >= sheet.data[row].len()
-        || sheet.data[row][col].value.is_empty();
-
-    if current_cell_is_empty {
-        // Current cell is empty, find first non-empty cell
-        match direction {
-            Direction::Left => {
-                for c in (1..col).rev() {
-                    if row < sheet.data.len()
-                        && c < sheet.data[row].len()
-                        && !sheet.data[row][c].value.is_empty()
-                    {
-                        return Some((row, c));
-                    }
-                }
-                // Return boundary if no non-empty cell found
-                Some((row, 1))
-            }
-            Direction::Right => {
-                for c in (col + 1)..=max_col {
-                    if row < sheet.data.len()
-                        && c < sheet.data[row].len()
-                        && !sheet.data[row][c].value.is_empty()
-                    {
-                        return Some((row, c));
-                    }
-                }
-                // Return boundary if no non-empty cell found
-                Some((row, max_col))
-            }
-            Direction::Up => {
-                for r in (1..row).rev() {
-                    if r < sheet.data.len()
-                        && col < sheet.data[r].len()
-                        && !sheet.data[r][col].value.is_empty()
-                    {
-                        return Some((r, col));
-                    }
-                }
-                // Return boundary if no non-empty cell found
-                Some((1, col))
-            }
-            Direction::Down => {
-                for r in (row + 1)..=max_row {
-                    if r < sheet.data.len()
-                        && col < sheet.data[r].len()
-                        && !sheet.data[r][col].value.is_empty()
-                    {
-                        return Some((r, col));
-                    }
-                }
-                // Return boundary if no non-empty cell found
-                Some((max_row, col))
-            }
+    let step = |(row, col): (usize, usize)| match direction {
+        Direction::Left => (col > 1).then(|| (row, col - 1)),
+        Direction::Right => (col < max_col).then(|| (row, col + 1)),
+        Direction::Up => (row > 1).then(|| (row - 1, col)),
+        Direction::Down => (row < max_row).then(|| (row + 1, col)),
+    };
+    let is_filled = |(row, col): (usize, usize)| !sheet.cell(row, col).value.is_empty();
+
+    let mut pos = step(current_pos)?;
+
+    if is_filled(current_pos) && is_filled(pos) {
+        // Inside a block, stop on its last cell
+        while let Some(next) = step(pos).filter(|&next| is_filled(next)) {
+            pos = next;
         }
-    } else {
-        // Current cell is non-empty, find boundary
-        match direction {
-            Direction::Left => {
-                let mut last_non_empty = col;
-
-                for c in (1..col).rev() {
-                    if row < sheet.data.len() && c < sheet.data[row].len() {
-                        if sheet.data[row][c].value.is_empty() {
-                            return Some((row, c + 1));
-                        }
-                        last_non_empty = c;
-                    } else {
-                        return Some((row, c + 1));
-                    }
-                }
-
-                Some((row, last_non_empty))
-            }
-            Direction::Right => {
-                let mut last_non_empty = col;
-
-                for c in (col + 1)..=max_col {
-                    if row < sheet.data.len() && c < sheet.data[row].len() {
-                        if sheet.data[row][c].value.is_empty() {
-                            return Some((row, c - 1));
-                        }
-                        last_non_empty = c;
-                    } else {
-                        return Some((row, c - 1));
-                    }
-                }
-
-                Some((row, last_non_empty))
-            }
-            Direction::Up => {
-                let mut last_non_empty = row;
-
-                for r in (1..row).rev() {
-                    if r < sheet.data.len() && col < sheet.data[r].len() {
-                        if sheet.data[r][col].value.is_empty() {
-                            return Some((r + 1, col));
-                        }
-                        last_non_empty = r;
-                    } else {
-                        return Some((r + 1, col));
-                    }
-                }
-
-                Some((last_non_empty, col))
-            }
-            Direction::Down => {
-                let mut last_non_empty = row;
-
-                for r in (row + 1)..=max_row {
-                    if r < sheet.data.len() && col < sheet.data[r].len() {
-                        if sheet.data[r][col].value.is_empty() {
-                            return Some((r - 1, col));
-                        }
-                        last_non_empty = r;
-                    } else {
-                        return Some((r - 1, col));
-                    }
-                }
+        return Some(pos);
+    }
 
-                Some((last_non_empty, col))
-            }
+    // On an empty cell or the edge of a block, skip the gap to the next block
+    while !is_filled(pos) {
+        match step(pos) {
+            Some(next) => pos = next,
+            None => break,
         }
     }
+    Some(pos)
 }
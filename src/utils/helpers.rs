@@ -1,3 +1,11 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Rows of an Excel sheet, the last row is 1048576
+pub const MAX_ROWS: usize = 1_048_576;
+
+/// Columns of an Excel sheet, the last column is XFD
+pub const MAX_COLS: usize = 16_384;
+
 #[must_use]
 pub fn index_to_col_name(index: usize) -> String {
     let mut col_name = String::new();
@@ -18,7 +26,7 @@ pub fn index_to_col_name(index: usize) -> String {
 
 #[must_use]
 pub fn col_name_to_index(name: &str) -> Option<usize> {
-    let mut result = 0;
+    let mut result: usize = 0;
 
     for c in name.chars() {
         if !c.is_ascii_alphabetic() {
@@ -26,14 +34,135 @@ pub fn col_name_to_index(name: &str) -> Option<usize> {
         }
 
         let val = (c.to_ascii_uppercase() as u8 - b'A' + 1) as usize;
-        result = result * 26 + val;
+        // Names too long for a usize are no column
+        result = result.checked_mul(26)?.checked_add(val)?;
     }
 
     Some(result)
 }
 
+// Parse a cell reference like "A1", "B10" or "$C$5"
+#[must_use]
+pub fn parse_cell_reference(input: &str) -> Option<(usize, usize)> {
+    let input = input.replace('$', "");
+
+    // Cell references should have at least 2 characters (e.g., A1)
+    if input.len() < 2 {
+        return None;
+    }
+
+    // Find the first digit to separate column and row parts
+    let mut col_end = 0;
+    for (i, c) in input.char_indices() {
+        if c.is_ascii_digit() {
+            col_end = i;
+            break;
+        }
+    }
+
+    if col_end == 0 {
+        return None; // No digits found
+    }
+
+    let col_part = &input[0..col_end];
+    let row_part = &input[col_end..];
+
+    // Convert column letters to index
+    let col = col_name_to_index(&col_part.to_uppercase())?;
+
+    // Parse row number, rows start at 1
+    let row = row_part.parse::<usize>().ok().filter(|&row| row > 0)?;
+
+    Some((row, col))
+}
+
+// Parse a range like "A1:D100", a single cell being a range of one. The corners come back
+// as top-left and bottom-right whichever way round they were written
+#[must_use]
+pub fn parse_cell_range(input: &str) -> Option<((usize, usize), (usize, usize))> {
+    let (start, end) = input.split_once(':').unwrap_or((input, input));
+    let (start_row, start_col) = parse_cell_reference(start.trim())?;
+    let (end_row, end_col) = parse_cell_reference(end.trim())?;
+    Some((
+        (start_row.min(end_row), start_col.min(end_col)),
+        (start_row.max(end_row), start_col.max(end_col)),
+    ))
+}
+
 // Format cell reference (e.g., A1, B2)
 #[must_use]
 pub fn cell_reference(cell: (usize, usize)) -> String {
     format!("{}{}", index_to_col_name(cell.1), cell.0)
 }
+
+// Wrap text into lines no wider than `width` display columns, keeping explicit line breaks
+#[must_use]
+pub fn wrap_text(content: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for line in content.lines() {
+        let mut current = String::new();
+        let mut current_width = 0;
+
+        for c in line.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if current_width + char_width > width && !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+                current_width = 0;
+            }
+            current.push(c);
+            current_width += char_width;
+        }
+
+        lines.push(current);
+    }
+
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    lines
+}
+
+// Split command arguments on spaces, quotes keep a name with spaces together:
+// "Q1 sales" Q2 gives [Q1 sales, Q2]
+#[must_use]
+pub fn split_arguments(text: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut chars = text.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let argument: String = if c == '"' || c == '\'' {
+            chars.next();
+            chars.by_ref().take_while(|&ch| ch != c).collect()
+        } else {
+            chars
+                .by_ref()
+                .take_while(|ch| !ch.is_whitespace())
+                .collect()
+        };
+        arguments.push(argument);
+    }
+    arguments
+}
+
+// Format a byte count for people, e.g. 512 B, 3.4 KB, 12.0 MB
+#[must_use]
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
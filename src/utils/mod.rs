@@ -1,5 +1,13 @@
 mod cell_navigation;
 mod helpers;
+mod number_format;
+mod pattern;
+mod smart_number;
 
 pub use cell_navigation::*;
 pub use helpers::*;
+pub use number_format::{
+    datetime_to_serial, format_duration, format_serial_date, serial_to_datetime, NumberFormat,
+};
+pub use pattern::Pattern;
+pub use smart_number::*;
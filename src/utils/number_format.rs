@@ -0,0 +1,512 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+// How one section of a number format shows a value, e.g. `$#,##0.00` or `0.0%`
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Section {
+    prefix: String,
+    suffix: String,
+    integer_digits: usize, // Zeros before the point, the integer part is padded to them
+    min_decimals: usize,   // Zeros after the point
+    max_decimals: usize,   // Zeros and #s after the point
+    thousands: bool,
+    scale: i32, // Commas after the last digit divide by 1000 each
+    percent: bool,
+}
+
+impl Section {
+    fn parse(code: &str) -> Result<Self, String> {
+        let mut section = Self::default();
+        let mut chars = code.chars().peekable();
+        let mut seen_digit = false;
+        let mut after_point = false;
+        let mut trailing_commas = 0;
+
+        while let Some(c) = chars.next() {
+            let literal = match c {
+                '0' | '#' | '?' => {
+                    seen_digit = true;
+                    if trailing_commas > 0 {
+                        section.thousands = true;
+                        trailing_commas = 0;
+                    }
+                    if after_point {
+                        section.max_decimals += 1;
+                        if c == '0' {
+                            section.min_decimals = section.max_decimals;
+                        }
+                    } else if c == '0' {
+                        section.integer_digits += 1;
+                    }
+                    continue;
+                }
+                '.' if !after_point => {
+                    after_point = true;
+                    seen_digit = true;
+                    continue;
+                }
+                ',' if seen_digit => {
+                    trailing_commas += 1;
+                    continue;
+                }
+                '%' => {
+                    section.percent = true;
+                    "%".to_string()
+                }
+                '"' => chars.by_ref().take_while(|&c| c != '"').collect(),
+                '\\' => chars.next().map(String::from).unwrap_or_default(),
+                // _x leaves the room of x, *x repeats x to fill the cell
+                '_' => {
+                    chars.next();
+                    " ".to_string()
+                }
+                '*' => {
+                    chars.next();
+                    String::new()
+                }
+                // Colors and conditions such as [Red] or [>100] are left out
+                '[' => {
+                    chars.by_ref().find(|&c| c == ']');
+                    String::new()
+                }
+                'E' | 'e' if matches!(chars.peek(), Some('+' | '-')) => {
+                    return Err("scientific formats aren't supported".to_string())
+                }
+                c if c.is_alphabetic() => return Err(format!("{code} isn't a number format")),
+                c => c.to_string(),
+            };
+            if seen_digit {
+                section.scale += trailing_commas;
+                trailing_commas = 0;
+                section.suffix.push_str(&literal);
+            } else {
+                section.prefix.push_str(&literal);
+            }
+        }
+        section.scale += trailing_commas;
+
+        if !seen_digit {
+            return Err(format!("{code} has no digit placeholders"));
+        }
+        Ok(section)
+    }
+
+    fn format(&self, value: f64) -> String {
+        let mut value = value;
+        if self.percent {
+            value *= 100.0;
+        }
+        value /= 1000f64.powi(self.scale);
+
+        let rounded = format!("{:.*}", self.max_decimals, value.abs());
+        let (integer, decimals) = rounded.split_once('.').unwrap_or((&rounded, ""));
+        let decimals = decimals.trim_end_matches('0');
+        let decimals = format!("{decimals:0<width$}", width = self.min_decimals);
+
+        let integer = integer.trim_start_matches('0');
+        let integer = format!("{integer:0>width$}", width = self.integer_digits);
+        let integer = if self.thousands {
+            group_thousands(&integer)
+        } else {
+            integer
+        };
+
+        let mut text = self.prefix.clone();
+        text.push_str(&integer);
+        if !decimals.is_empty() {
+            text.push('.');
+            text.push_str(&decimals);
+        }
+        text.push_str(&self.suffix);
+        text
+    }
+}
+
+// 1234567 as 1,234,567
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+// One piece of a date format such as `dd/mm/yyyy hh:mm`
+#[derive(Clone, Debug, PartialEq)]
+enum DatePart {
+    Literal(String),
+    Year { digits: usize },
+    Month { digits: usize }, // 1 or 2 digits, 3 for Jan and 4 for January
+    Day { digits: usize },   // Likewise, 3 for Mon and 4 for Monday
+    Hour { digits: usize },
+    Minute { digits: usize },
+    Second { digits: usize },
+    Fraction { digits: usize }, // Tenths, hundredths... of a second after ss.
+    Elapsed { unit: u32, digits: usize }, // [h], [mm] or [ss], counting past the day
+    AmPm { short: bool },
+}
+
+impl DatePart {
+    fn is_hour(&self) -> bool {
+        matches!(self, Self::Hour { .. } | Self::Elapsed { unit: 3600, .. })
+    }
+
+    fn is_second(&self) -> bool {
+        matches!(self, Self::Second { .. } | Self::Elapsed { unit: 1, .. })
+    }
+}
+
+// Letters that make a format a date format once quoted text and brackets are left out
+fn is_date_code(code: &str) -> bool {
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                chars.by_ref().find(|&c| c == '"');
+            }
+            '\\' | '_' | '*' => {
+                chars.next();
+            }
+            '[' => {
+                let inside: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if matches!(inside.to_lowercase().chars().next(), Some('h' | 'm' | 's')) {
+                    return true;
+                }
+            }
+            c if matches!(c.to_ascii_lowercase(), 'y' | 'm' | 'd' | 'h' | 's') => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn parse_date(code: &str) -> Vec<DatePart> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let lower = c.to_ascii_lowercase();
+        let run = chars[i..]
+            .iter()
+            .take_while(|d| d.to_ascii_lowercase() == lower)
+            .count();
+        let rest: String = chars[i..].iter().collect::<String>().to_lowercase();
+        let (part, len) = match lower {
+            '"' => {
+                let text: String = chars[i + 1..].iter().take_while(|&&c| c != '"').collect();
+                let len = text.chars().count() + 2;
+                (DatePart::Literal(text), len)
+            }
+            '\\' => (
+                DatePart::Literal(chars.get(i + 1).map(|c| c.to_string()).unwrap_or_default()),
+                2,
+            ),
+            '_' => (DatePart::Literal(" ".to_string()), 2),
+            '*' => (DatePart::Literal(String::new()), 2),
+            '[' => {
+                let inside: String = chars[i + 1..].iter().take_while(|&&c| c != ']').collect();
+                let len = inside.chars().count() + 2;
+                let digits = inside.chars().count();
+                let unit = match inside.to_lowercase().chars().next() {
+                    Some('h') => Some(3600),
+                    Some('m') => Some(60),
+                    Some('s') => Some(1),
+                    _ => None,
+                };
+                match unit {
+                    Some(unit) => (DatePart::Elapsed { unit, digits }, len),
+                    // Colors and locales such as [Red] or [$-409] are left out
+                    None => (DatePart::Literal(String::new()), len),
+                }
+            }
+            'y' => (DatePart::Year { digits: run }, run),
+            'm' => (DatePart::Month { digits: run }, run),
+            'd' => (DatePart::Day { digits: run }, run),
+            'h' => (DatePart::Hour { digits: run }, run),
+            's' => (DatePart::Second { digits: run }, run),
+            '.' if parts.last().is_some_and(DatePart::is_second)
+                && chars.get(i + 1) == Some(&'0') =>
+            {
+                let digits = chars[i + 1..].iter().take_while(|&&c| c == '0').count();
+                (DatePart::Fraction { digits }, digits + 1)
+            }
+            'a' if rest.starts_with("am/pm") => (DatePart::AmPm { short: false }, 5),
+            'a' if rest.starts_with("a/p") => (DatePart::AmPm { short: true }, 3),
+            _ => (DatePart::Literal(c.to_string()), 1),
+        };
+        parts.push(part);
+        i += len;
+    }
+
+    // m and mm are minutes right after hours or right before seconds
+    let fields: Vec<usize> = (0..parts.len())
+        .filter(|&i| !matches!(parts[i], DatePart::Literal(_)))
+        .collect();
+    for (n, &i) in fields.iter().enumerate() {
+        if let DatePart::Month { digits } = parts[i] {
+            let after_hour = n > 0 && parts[fields[n - 1]].is_hour();
+            let before_second = fields.get(n + 1).is_some_and(|&j| parts[j].is_second());
+            if digits <= 2 && (after_hour || before_second) {
+                parts[i] = DatePart::Minute { digits };
+            }
+        }
+    }
+    parts
+}
+
+// The parts of a date format filled in from a serial number
+fn format_date(parts: &[DatePart], serial: f64) -> Option<String> {
+    let fraction_digits = parts
+        .iter()
+        .map(|part| match part {
+            DatePart::Fraction { digits } => *digits,
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0)
+        .min(3);
+    // Rounded to what is shown, so 0.99999 doesn't show as 23:59:59
+    let steps = 86_400.0 * 10f64.powi(fraction_digits as i32);
+    let serial = (serial * steps).round() / steps;
+    let datetime = serial_to_datetime(serial)?;
+    let twelve_hours = parts
+        .iter()
+        .any(|part| matches!(part, DatePart::AmPm { .. }));
+    let seconds = (serial * 86_400.0).floor();
+
+    let mut text = String::new();
+    for part in parts {
+        let field = match *part {
+            DatePart::Literal(ref literal) => literal.clone(),
+            DatePart::Year { digits } if digits <= 2 => datetime.format("%y").to_string(),
+            DatePart::Year { .. } => datetime.format("%Y").to_string(),
+            DatePart::Month { digits } => datetime
+                .format(match digits {
+                    1 => "%-m",
+                    2 => "%m",
+                    3 => "%b",
+                    _ => "%B",
+                })
+                .to_string(),
+            DatePart::Day { digits } => datetime
+                .format(match digits {
+                    1 => "%-d",
+                    2 => "%d",
+                    3 => "%a",
+                    _ => "%A",
+                })
+                .to_string(),
+            DatePart::Hour { digits } => {
+                let format = match (twelve_hours, digits) {
+                    (true, 1) => "%-I",
+                    (true, _) => "%I",
+                    (false, 1) => "%-H",
+                    (false, _) => "%H",
+                };
+                datetime.format(format).to_string()
+            }
+            DatePart::Minute { digits } => datetime
+                .format(if digits == 1 { "%-M" } else { "%M" })
+                .to_string(),
+            DatePart::Second { digits } => datetime
+                .format(if digits == 1 { "%-S" } else { "%S" })
+                .to_string(),
+            DatePart::Fraction { digits } => {
+                let fraction = serial * 86_400.0 - seconds;
+                let shown = format!("{fraction:.*}", digits.min(3));
+                shown.trim_start_matches('0').to_string()
+            }
+            // Only the largest unit counts past the day, [h]:mm leaves minutes under 60
+            DatePart::Elapsed { unit, digits } => {
+                let count = seconds as i64 / i64::from(unit);
+                format!("{count:0>digits$}")
+            }
+            DatePart::AmPm { short } => {
+                let am = datetime.format("%P").to_string() == "am";
+                match (short, am) {
+                    (false, true) => "AM",
+                    (false, false) => "PM",
+                    (true, true) => "A",
+                    (true, false) => "P",
+                }
+                .to_string()
+            }
+        };
+        text.push_str(&field);
+    }
+    Some(text)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    Number {
+        positive: Section,
+        negative: Option<Section>,
+    },
+    Date(Vec<DatePart>),
+}
+
+/// A display format for numbers in Excel's notation, e.g. `#,##0.00`, `0.0%` or
+/// `$#,##0_);($#,##0)` with a second section for negative numbers, or a date format
+/// such as `dd/mm/yyyy hh:mm` for serial dates. Only changes how a value looks, never
+/// the value
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberFormat {
+    code: String,
+    kind: Kind,
+}
+
+impl NumberFormat {
+    pub fn parse(code: &str) -> Result<Self, String> {
+        let mut sections = code.split(';');
+        let first = sections.next().unwrap_or_default();
+        let kind = if is_date_code(first) {
+            Kind::Date(parse_date(first))
+        } else {
+            let positive = Section::parse(first)?;
+            let negative = match sections.next() {
+                Some(negative) if !negative.is_empty() => Some(Section::parse(negative)?),
+                _ => None,
+            };
+            Kind::Number { positive, negative }
+        };
+        Ok(Self {
+            code: code.to_string(),
+            kind,
+        })
+    }
+
+    /// One of Excel's built-in formats by id, `None` for General, text and the formats
+    /// that can't be shown such as fractions and scientific notation. Dates are shown the
+    /// ISO way rather than in the locale of whoever wrote the file
+    #[must_use]
+    pub fn builtin(id: u8) -> Option<Self> {
+        let code = match id {
+            1 => "0",
+            2 => "0.00",
+            3 => "#,##0",
+            4 => "#,##0.00",
+            5 | 37 => "#,##0_);(#,##0)",
+            6 | 38 => "#,##0_);[Red](#,##0)",
+            7 | 39 => "#,##0.00_);(#,##0.00)",
+            8 | 40 => "#,##0.00_);[Red](#,##0.00)",
+            9 => "0%",
+            10 => "0.00%",
+            14 => "yyyy-mm-dd",
+            15 => "d-mmm-yy",
+            16 => "d-mmm",
+            17 => "mmm-yy",
+            18 => "h:mm AM/PM",
+            19 => "h:mm:ss AM/PM",
+            20 => "h:mm",
+            21 => "h:mm:ss",
+            22 => "yyyy-mm-dd h:mm",
+            45 => "mm:ss",
+            46 => "[h]:mm:ss",
+            47 => "mm:ss.0",
+            _ => return None,
+        };
+        Self::parse(code).ok()
+    }
+
+    #[must_use]
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Whether values are shown as dates or times
+    #[must_use]
+    pub fn is_date(&self) -> bool {
+        matches!(self.kind, Kind::Date(_))
+    }
+
+    #[must_use]
+    pub fn format(&self, value: f64) -> String {
+        let (positive, negative) = match &self.kind {
+            Kind::Number { positive, negative } => (positive, negative),
+            // Excel has no dates before 1900, those are shown as the number
+            Kind::Date(parts) => {
+                return format_date(parts, value)
+                    .filter(|_| value >= 0.0)
+                    .unwrap_or_else(|| value.to_string())
+            }
+        };
+        if value < 0.0 {
+            if let Some(negative) = negative {
+                return negative.format(-value);
+            }
+        }
+        let text = positive.format(value.abs());
+        // -0.001 shown as 0.00 has no sign
+        if value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            format!("-{text}")
+        } else {
+            text
+        }
+    }
+}
+
+// Day 0 of Excel's 1900 date system, which takes 1900 for a leap year
+fn excel_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(1899, 12, 30)
+        .unwrap_or_default()
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+}
+
+/// The date and time of an Excel serial number in the 1900 date system, e.g. 44927.5
+/// is 2023-01-01 12:00
+#[must_use]
+pub fn serial_to_datetime(serial: f64) -> Option<NaiveDateTime> {
+    // Serials before the 29th of February 1900 that never was are a day off
+    let days = if serial >= 60.0 { serial } else { serial + 1.0 };
+    let milliseconds = (days * 86_400_000.0).round();
+    if !milliseconds.is_finite() || milliseconds.abs() > 1e15 {
+        return None;
+    }
+    excel_epoch().checked_add_signed(Duration::milliseconds(milliseconds as i64))
+}
+
+/// The Excel serial number of a date and time in the 1900 date system
+#[must_use]
+pub fn datetime_to_serial(datetime: NaiveDateTime) -> f64 {
+    let days = datetime
+        .signed_duration_since(excel_epoch())
+        .num_milliseconds() as f64
+        / 86_400_000.0;
+    if days > 60.0 {
+        days
+    } else {
+        days - 1.0
+    }
+}
+
+/// A serial date as 2023-01-01, with the time of day when it has one
+#[must_use]
+pub fn format_serial_date(serial: f64) -> Option<String> {
+    let datetime = serial_to_datetime(serial)?;
+    let format = if serial.fract() == 0.0 {
+        "%Y-%m-%d"
+    } else if datetime.format("%S").to_string() == "00" {
+        "%Y-%m-%d %H:%M"
+    } else {
+        "%Y-%m-%d %H:%M:%S"
+    };
+    Some(datetime.format(format).to_string())
+}
+
+/// A duration in days as 36:15:00, hours going past a day
+#[must_use]
+pub fn format_duration(days: f64) -> String {
+    let seconds = (days.abs() * 86_400.0).round() as u64;
+    let sign = if days < 0.0 && seconds > 0 { "-" } else { "" };
+    format!(
+        "{sign}{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,296 @@
+#[derive(Clone, Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// A small regular expression for validation rules: literals, `.`, `[...]` classes,
+/// `\d \w \s` and their negations, `^ $`, `* + ? {n,m}`, groups and `|`. It is found
+/// anywhere in the text unless anchored. Matching backtracks, plenty for a cell
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    source: String,
+    alternatives: Vec<Vec<Node>>,
+}
+
+const DIGITS: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+const SPACE: &[(char, char)] = &[(' ', ' '), ('\t', '\r')];
+
+// The ranges of \d, \w and \s, for the letter after the backslash
+fn shorthand(c: char) -> Option<&'static [(char, char)]> {
+    match c.to_ascii_lowercase() {
+        'd' => Some(DIGITS),
+        'w' => Some(WORD),
+        's' => Some(SPACE),
+        _ => None,
+    }
+}
+
+fn escaped(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        c => c,
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let node = self.atom()?;
+            nodes.push(self.quantified(node)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let Some(c) = self.next() else {
+            return Err("Pattern ends too early".to_string());
+        };
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                // (?:...) groups the same way, nothing is captured anyway
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let alternatives = self.alternatives()?;
+                if self.next() != Some(')') {
+                    return Err("Missing ) in pattern".to_string());
+                }
+                Node::Group(alternatives)
+            }
+            '[' => self.class()?,
+            '\\' => {
+                let Some(c) = self.next() else {
+                    return Err("Pattern ends with \\".to_string());
+                };
+                match shorthand(c) {
+                    Some(ranges) => Node::Class {
+                        ranges: ranges.to_vec(),
+                        negated: c.is_ascii_uppercase(),
+                    },
+                    None => Node::Char(escaped(c)),
+                }
+            }
+            '*' | '+' | '?' | '{' => return Err(format!("Nothing to repeat before {c}")),
+            c => Node::Char(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let Some(c) = self.next() else {
+                return Err("Missing ] in pattern".to_string());
+            };
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let start = if c == '\\' {
+                let Some(c) = self.next() else {
+                    return Err("Missing ] in pattern".to_string());
+                };
+                if let Some(shorthand) = shorthand(c).filter(|_| c.is_ascii_lowercase()) {
+                    ranges.extend_from_slice(shorthand);
+                    continue;
+                }
+                escaped(c)
+            } else {
+                c
+            };
+            // a-z, while a - before the closing ] is just a dash
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let end = match self.next() {
+                    Some('\\') => self.next().map(escaped),
+                    end => end,
+                };
+                let Some(end) = end.filter(|&end| end >= start) else {
+                    return Err(format!("Bad range {start}- in pattern"));
+                };
+                ranges.push((start, end));
+            } else {
+                ranges.push((start, start));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn quantified(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let close = self.chars[self.pos..]
+                    .iter()
+                    .position(|&c| c == '}')
+                    .ok_or("Missing } in pattern")?;
+                let inside: String = self.chars[self.pos + 1..self.pos + close].iter().collect();
+                let number = |text: &str| {
+                    text.trim()
+                        .parse::<usize>()
+                        .map_err(|_| format!("Bad repeat count {{{inside}}}"))
+                };
+                let bounds = match inside.split_once(',') {
+                    None => (number(&inside)?, Some(number(&inside)?)),
+                    Some((min, "")) => (number(min)?, None),
+                    Some((min, max)) => (number(min)?, Some(number(max)?)),
+                };
+                self.pos += close;
+                bounds
+            }
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+        if matches!(node, Node::Start | Node::End) {
+            return Err("Nothing to repeat after an anchor".to_string());
+        }
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+        })
+    }
+}
+
+fn match_sequence(
+    nodes: &[Node],
+    text: &[char],
+    pos: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    match nodes.split_first() {
+        None => next(pos),
+        Some((node, rest)) => match_node(node, text, pos, &mut |after| {
+            match_sequence(rest, text, after, next)
+        }),
+    }
+}
+
+fn match_node(node: &Node, text: &[char], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+    let current = text.get(pos).copied();
+    match node {
+        Node::Char(c) => current == Some(*c) && next(pos + 1),
+        Node::Any => current.is_some_and(|c| c != '\n') && next(pos + 1),
+        Node::Class { ranges, negated } => {
+            current.is_some_and(|c| {
+                ranges
+                    .iter()
+                    .any(|&(start, end)| (start..=end).contains(&c))
+                    != *negated
+            }) && next(pos + 1)
+        }
+        Node::Start => pos == 0 && next(pos),
+        Node::End => pos == text.len() && next(pos),
+        Node::Group(alternatives) => alternatives
+            .iter()
+            .any(|sequence| match_sequence(sequence, text, pos, next)),
+        Node::Repeat { node, min, max } => match_repeat(node, *min, *max, 0, text, pos, next),
+    }
+}
+
+// Greedy: takes one more repetition while it can, then backs off
+fn match_repeat(
+    node: &Node,
+    min: usize,
+    max: Option<usize>,
+    count: usize,
+    text: &[char],
+    pos: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    if max.map_or(true, |max| count < max)
+        && match_node(node, text, pos, &mut |after| {
+            // A repetition that matched nothing would loop forever
+            (after != pos || count < min)
+                && match_repeat(node, min, max, count + 1, text, after, next)
+        })
+    {
+        return true;
+    }
+    count >= min && next(pos)
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return Err("Unmatched ) in pattern".to_string());
+        }
+        Ok(Self {
+            source: source.to_string(),
+            alternatives,
+        })
+    }
+
+    #[must_use]
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        (0..=text.len()).any(|start| {
+            self.alternatives
+                .iter()
+                .any(|sequence| match_sequence(sequence, &text, start, &mut |_| true))
+        })
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
@@ -0,0 +1,167 @@
+// Built-in Excel number formats given to smart numbers
+const FORMAT_THOUSANDS: u8 = 3; // #,##0
+const FORMAT_THOUSANDS_DECIMALS: u8 = 4; // #,##0.00
+const FORMAT_CURRENCY: u8 = 5; // $#,##0_);($#,##0)
+const FORMAT_CURRENCY_DECIMALS: u8 = 7; // $#,##0.00_);($#,##0.00)
+const FORMAT_PERCENT: u8 = 9; // 0%
+const FORMAT_PERCENT_DECIMALS: u8 = 10; // 0.00%
+
+const CURRENCY_SYMBOLS: [char; 4] = ['$', '€', '£', '¥'];
+
+/// A number typed with units or symbols, e.g. `$1,234.56`, `42%` or `3.5k`
+#[derive(Debug, PartialEq, Eq)]
+pub struct SmartNumber {
+    pub value: String,     // Plain decimal, e.g. 1234.56
+    pub number_format: u8, // Built-in Excel number format matching how it was typed
+}
+
+// Strips a leading `-` or `+`, returning whether it was a minus
+fn strip_sign(text: &str) -> (bool, &str) {
+    if let Some(rest) = text.strip_prefix('-') {
+        (true, rest.trim_start())
+    } else if let Some(rest) = text.strip_prefix('+') {
+        (false, rest.trim_start())
+    } else {
+        (false, text)
+    }
+}
+
+// Splits `1,234.56` into `1234` and `56`, thousands separators must group by three
+fn split_digits(text: &str) -> Option<(String, &str, bool)> {
+    let (int_part, frac_part) = text.split_once('.').unwrap_or((text, ""));
+    if !frac_part.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let groups: Vec<&str> = int_part.split(',').collect();
+    let grouped = groups.len() > 1;
+    let valid_groups = groups.iter().enumerate().all(|(idx, group)| {
+        let valid_len = match (grouped, idx) {
+            (false, _) => true,
+            (true, 0) => (1..=3).contains(&group.len()),
+            (true, _) => group.len() == 3,
+        };
+        valid_len && group.chars().all(|c| c.is_ascii_digit())
+    });
+    if !valid_groups || (int_part.is_empty() && frac_part.is_empty()) {
+        return None;
+    }
+
+    Some((groups.concat(), frac_part, grouped))
+}
+
+// Moves the decimal point of `int_part.frac_part` by `shift` places, without the rounding of f64 math
+fn shift_decimal(int_part: &str, frac_part: &str, shift: isize) -> String {
+    let digits = format!("{int_part}{frac_part}");
+    let point = int_part.len() as isize + shift;
+
+    let (int, frac) = if point <= 0 {
+        (
+            String::new(),
+            format!("{}{digits}", "0".repeat(point.unsigned_abs())),
+        )
+    } else if point as usize >= digits.len() {
+        (
+            format!("{digits}{}", "0".repeat(point as usize - digits.len())),
+            String::new(),
+        )
+    } else {
+        let (int, frac) = digits.split_at(point as usize);
+        (int.to_string(), frac.to_string())
+    };
+
+    let int = match int.trim_start_matches('0') {
+        "" => "0",
+        int => int,
+    };
+    match frac.trim_end_matches('0') {
+        "" => int.to_string(),
+        frac => format!("{int}.{frac}"),
+    }
+}
+
+/// Reads `$1,234.56`, `-12.5%`, `3.5k`, `(1,200)` and the like as a plain number and
+/// the number format that shows it the way it was typed. Plain numbers and anything
+/// else that isn't a number give `None`.
+#[must_use]
+pub fn parse_smart_number(input: &str) -> Option<SmartNumber> {
+    let text = input.trim();
+    if text.is_empty() || text.parse::<f64>().is_ok() {
+        return None;
+    }
+
+    // Accounting style (1,234) is negative
+    let (parenthesized, text) = match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(inner) => (true, inner.trim()),
+        None => (false, text),
+    };
+
+    // The sign may come before or after a leading currency symbol: -$5 or $-5
+    let (mut negative, mut text) = strip_sign(text);
+    let mut currency = false;
+    if let Some(rest) = text.strip_prefix(CURRENCY_SYMBOLS) {
+        currency = true;
+        text = rest.trim_start();
+        if !negative {
+            (negative, text) = strip_sign(text);
+        }
+    }
+    if !currency {
+        if let Some(rest) = text.strip_suffix(CURRENCY_SYMBOLS) {
+            currency = true;
+            text = rest.trim_end();
+        }
+    }
+
+    let mut percent = false;
+    let mut shift = 0;
+    if let Some(rest) = text.strip_suffix('%') {
+        percent = true;
+        shift = -2;
+        text = rest.trim_end();
+    } else if let Some(rest) = text.strip_suffix(['k', 'K']) {
+        shift = 3;
+        text = rest;
+    } else if let Some(rest) = text.strip_suffix('M') {
+        shift = 6;
+        text = rest;
+    } else if let Some(rest) = text.strip_suffix('B') {
+        shift = 9;
+        text = rest;
+    }
+    if currency && percent {
+        return None;
+    }
+
+    let (int_part, frac_part, grouped) = split_digits(text)?;
+    if !(parenthesized || currency || grouped || shift != 0) {
+        return None;
+    }
+
+    let value = shift_decimal(&int_part, frac_part, shift);
+    let has_decimals = if shift > 0 {
+        value.contains('.')
+    } else {
+        !frac_part.is_empty()
+    };
+
+    let number_format = match (currency, percent, has_decimals) {
+        (true, _, false) => FORMAT_CURRENCY,
+        (true, _, true) => FORMAT_CURRENCY_DECIMALS,
+        (_, true, false) => FORMAT_PERCENT,
+        (_, true, true) => FORMAT_PERCENT_DECIMALS,
+        (_, _, false) => FORMAT_THOUSANDS,
+        (_, _, true) => FORMAT_THOUSANDS_DECIMALS,
+    };
+
+    let value = if (negative ^ parenthesized) && value != "0" {
+        format!("-{value}")
+    } else {
+        value
+    };
+
+    Some(SmartNumber {
+        value,
+        number_format,
+    })
+}